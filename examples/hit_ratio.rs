@@ -1,4 +1,4 @@
-use caches::{AdaptiveCache, Cache, LRUCache, TwoQueueCache, WTinyLFUCache, SegmentedCache};
+use caches::{AdaptiveCache, Cache, ClockProCache, LRUCache, S3FifoCache, TwoQueueCache, WTinyLFUCache, SegmentedCache};
 use rand::{thread_rng, Rng};
 use rand::seq::SliceRandom;
 
@@ -158,6 +158,66 @@ fn segmented_cache(cases: Vec<(usize, Vec<u64>)>) -> Vec<(usize, f64)> {
     result
 }
 
+fn clock_pro_cache(cases: Vec<(usize, Vec<u64>)>) -> Vec<(usize, f64)> {
+    let mut result: Vec<(usize, f64)> = Vec::with_capacity(cases.len());
+
+    cases.iter().for_each(|total| {
+        let mut l = ClockProCache::new(8192).unwrap();
+
+        let mut hit = 0u64;
+        let mut miss = 0u64;
+
+        (0..(*total).0).for_each(|v| {
+            let k = (*total).1[v];
+            let _ = l.put(k, k);
+        });
+
+        (0..(*total).0).for_each(|v| {
+            let k = (*total).1[v];
+            if let Some(_) = l.get(&k) {
+                hit += 1;
+            } else {
+                miss += 1;
+            }
+        });
+
+        let hit_ratio = ((hit as f64) / ((*total).0 as f64)) * 100.0;
+        result.push(((*total).0, hit_ratio));
+    });
+
+    result
+}
+
+fn s3fifo_cache(cases: Vec<(usize, Vec<u64>)>) -> Vec<(usize, f64)> {
+    let mut result: Vec<(usize, f64)> = Vec::with_capacity(cases.len());
+
+    cases.iter().for_each(|total| {
+        let mut l = S3FifoCache::new(8192).unwrap();
+
+        let mut hit = 0u64;
+        let mut miss = 0u64;
+
+        (0..(*total).0).for_each(|v| {
+            let k = (*total).1[v];
+            let _ = l.put(k, k);
+        });
+
+        (0..(*total).0).for_each(|v| {
+            let k = (*total).1[v];
+            if let Some(_) = l.get(&k) {
+                hit += 1;
+            } else {
+                miss += 1;
+            }
+        });
+
+        let hit_ratio = ((hit as f64) / ((*total).0 as f64)) * 100.0;
+        result.push(((*total).0, hit_ratio));
+    });
+
+    result
+}
+
 fn wtinylfu_cache(cases: Vec<(usize, Vec<u64>)>) -> Vec<(usize, f64)> {
     let mut result: Vec<(usize, f64)> = Vec::with_capacity(cases.len());
 
@@ -234,6 +294,16 @@ fn main() {
         segmented_cache(random_numbers.clone())
     );
 
+    println!(
+        "ClockProCache Hit Ratio: {:?}",
+        clock_pro_cache(random_numbers.clone())
+    );
+
+    println!(
+        "S3FifoCache Hit Ratio: {:?}",
+        s3fifo_cache(random_numbers.clone())
+    );
+
     println!(
         "WTinyLFUCache Hit Ratio: {:?}",
         wtinylfu_cache(random_numbers.clone())
@@ -1,6 +1,8 @@
 //! The basic APIs for Cache implementation.
-use crate::{KeyRef, PutResult};
+use crate::entry::sealed::EntryCache;
+use crate::{Entry, KeyRef, PutResult};
 use core::borrow::Borrow;
+use core::convert::Infallible;
 use core::hash::Hash;
 
 /// Cache contains the basic APIs for a cache.
@@ -13,14 +15,57 @@ pub trait Cache<K: Hash + Eq, V> {
 
     /// Returns a reference to the value of the key in the cache or `None` if it
     /// is not present in the cache. Update the cache if it exists.
-    fn get<'a, Q>(&mut self, k: &'a Q) -> Option<&'a V>
+    ///
+    /// The returned reference borrows the cache, so the cache cannot be modified
+    /// while the reference is alive, e.g. the value cannot be evicted by a `put`:
+    ///
+    /// ```compile_fail,E0499
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    /// cache.put(1, String::from("a"));
+    ///
+    /// let v = cache.get(&1).unwrap();
+    /// // evicts 1 and drops the value `v` points to
+    /// cache.put(2, String::from("b"));
+    /// assert_eq!(v, "a");
+    /// ```
+    ///
+    /// But it does not borrow the key, the key can be a temporary value:
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    /// cache.put(String::from("a"), 1);
+    ///
+    /// let v = cache.get(&String::from("a")).unwrap();
+    /// assert_eq!(*v, 1);
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Eq + Hash + ?Sized;
 
     /// Returns a mutable reference to the value of the key in the cache or `None` if it
     /// is not present in the cache. Update the cache if it exists.
-    fn get_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    ///
+    /// The returned reference borrows the cache, like [`get`].
+    ///
+    /// ```compile_fail,E0499
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    /// cache.put(1, String::from("a"));
+    ///
+    /// let v = cache.get_mut(&1).unwrap();
+    /// // evicts 1 and drops the value `v` points to
+    /// cache.put(2, String::from("b"));
+    /// v.push('b');
+    /// ```
+    ///
+    /// [`get`]: trait.Cache.html#tymethod.get
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Eq + Hash + ?Sized;
@@ -28,7 +73,23 @@ pub trait Cache<K: Hash + Eq, V> {
     /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
     /// not present in the cache. Unlike `get`, `peek` does not update the cache so the key's
     /// position will be unchanged.
-    fn peek<'a, Q>(&self, k: &'a Q) -> Option<&'a V>
+    ///
+    /// The returned reference borrows the cache, like [`get`].
+    ///
+    /// ```compile_fail,E0502
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    /// cache.put(1, String::from("a"));
+    ///
+    /// let v = cache.peek(&1).unwrap();
+    /// // evicts 1 and drops the value `v` points to
+    /// cache.put(2, String::from("b"));
+    /// assert_eq!(v, "a");
+    /// ```
+    ///
+    /// [`get`]: trait.Cache.html#tymethod.get
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Eq + Hash + ?Sized;
@@ -36,7 +97,23 @@ pub trait Cache<K: Hash + Eq, V> {
     /// Returns a mutable reference to the value corresponding to the key in the cache or `None` if it is
     /// not present in the cache. Unlike `get_mut`, `peek_mut` does not update the cache so the key's
     /// position will be unchanged.
-    fn peek_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    ///
+    /// The returned reference borrows the cache, like [`get`].
+    ///
+    /// ```compile_fail,E0499
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    /// cache.put(1, String::from("a"));
+    ///
+    /// let v = cache.peek_mut(&1).unwrap();
+    /// // evicts 1 and drops the value `v` points to
+    /// cache.put(2, String::from("b"));
+    /// v.push('b');
+    /// ```
+    ///
+    /// [`get`]: trait.Cache.html#tymethod.get
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Eq + Hash + ?Sized;
@@ -66,6 +143,77 @@ pub trait Cache<K: Hash + Eq, V> {
 
     /// Returns a bool indicating whether the cache is empty or not.
     fn is_empty(&self) -> bool;
+
+    /// Returns a reference to the value of the key in the cache, if the key is not present,
+    /// calls `f` to build the value and puts it into the cache. `f` is only called on a miss,
+    /// so the value is never constructed on a hit. Updates the cache like [`get`].
+    ///
+    /// Returns `None` if the cache does not keep the built value, e.g. a weighted cache
+    /// rejects an entry heavier than its capacity.
+    ///
+    /// The key is looked up once like [`get`], a miss puts the key like [`put`] without looking
+    /// up the value again. The loaders are provided for the caches of this crate.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// let mut calls = 0;
+    ///
+    /// for _ in 0..3 {
+    ///     let v = cache.get_or_insert_with(1, || {
+    ///         calls += 1;
+    ///         "a"
+    ///     });
+    ///     assert_eq!(v, Some(&"a"));
+    /// }
+    /// assert_eq!(calls, 1);
+    /// ```
+    ///
+    /// [`get`]: trait.Cache.html#tymethod.get
+    /// [`put`]: trait.Cache.html#tymethod.put
+    fn get_or_insert_with<F>(&mut self, k: K, f: F) -> Option<&V>
+    where
+        Self: Sized + EntryCache<K, V>,
+        F: FnOnce() -> V,
+    {
+        match self.try_get_or_insert_with(k, || Ok::<_, Infallible>(f())) {
+            Ok(v) => v,
+            Err(e) => match e {},
+        }
+    }
+
+    /// Like [`get_or_insert_with`], but `f` may fail. If `f` returns an error, nothing is put
+    /// into the cache and the error is returned.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// assert!(cache.try_get_or_insert_with(1, || "a".parse::<u64>()).is_err());
+    /// assert!(!cache.contains(&1));
+    ///
+    /// assert_eq!(cache.try_get_or_insert_with(1, || "1".parse::<u64>()), Ok(Some(&1)));
+    /// // the value is not parsed again on a hit
+    /// assert_eq!(cache.try_get_or_insert_with(1, || "a".parse::<u64>()), Ok(Some(&1)));
+    /// ```
+    ///
+    /// [`get_or_insert_with`]: trait.Cache.html#method.get_or_insert_with
+    fn try_get_or_insert_with<F, E>(&mut self, k: K, f: F) -> Result<Option<&V>, E>
+    where
+        Self: Sized + EntryCache<K, V>,
+        F: FnOnce() -> Result<V, E>,
+    {
+        Ok(match Entry::new(self, k) {
+            Entry::Occupied(ent) => Some(&*ent.into_mut()),
+            Entry::Vacant(ent) => ent.insert_in(f()?).0.map(|v| &*v),
+        })
+    }
 }
 
 /// Implement this trait for Cache to support resize.
@@ -76,3 +224,16 @@ pub trait ResizableCache {
     /// Returns the number of discarded entries
     fn resize(&mut self, cap: usize) -> u64;
 }
+
+/// Implement this trait for Cache to tell which entry it would evict next,
+/// the admission policies use it to compare a new entry with the victim, e.g. [`TinyLFUAdmission`].
+///
+/// [`TinyLFUAdmission`]: struct.TinyLFUAdmission.html
+pub trait PeekVictim<K: Hash + Eq, V>: Cache<K, V> {
+    /// Returns the key of the entry which would be evicted if `k` was put into the cache now,
+    /// or `None` if putting `k` does not evict any entry. Does not update the cache.
+    fn peek_victim<Q>(&self, k: &Q) -> Option<&K>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized;
+}
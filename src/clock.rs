@@ -0,0 +1,220 @@
+//! Time sources for the time based caches.
+//!
+//! A [`Clock`] returns a monotonic duration since an arbitrary, fixed origin.
+//! Caches only compare the values returned by the same clock, so the origin does not matter.
+//!
+//! - [`StdClock`] is backed by [`std::time::Instant`] (requires `std`).
+//! - [`ManualClock`] only moves when it is advanced by hand. It can be used in deterministic
+//!   tests, or on targets without a time source by advancing it from a timer interrupt.
+//!
+//! [`Clock`]: trait.Clock.html
+//! [`StdClock`]: struct.StdClock.html
+//! [`ManualClock`]: struct.ManualClock.html
+//! [`std::time::Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+use crate::cfg_std;
+use alloc::sync::Arc;
+use core::sync::atomic::Ordering;
+use core::time::Duration;
+
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::AtomicU64;
+#[cfg(not(target_has_atomic = "64"))]
+use fallback::AtomicU64;
+
+/// `Clock` is a monotonic time source.
+pub trait Clock {
+    /// Returns the duration elapsed since the origin of the clock.
+    /// The returned value must never decrease.
+    fn now(&self) -> Duration;
+}
+
+cfg_std!(
+    use std::time::Instant;
+
+    /// `StdClock` is a [`Clock`] backed by [`std::time::Instant`], its origin is
+    /// the time it was created at.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Clock, StdClock};
+    ///
+    /// let clock = StdClock::new();
+    /// let a = clock.now();
+    /// let b = clock.now();
+    /// assert!(a <= b);
+    /// ```
+    ///
+    /// [`Clock`]: trait.Clock.html
+    /// [`std::time::Instant`]: https://doc.rust-lang.org/std/time/struct.Instant.html
+    #[derive(Debug, Copy, Clone)]
+    pub struct StdClock {
+        origin: Instant,
+    }
+
+    impl StdClock {
+        /// Creates a `StdClock` whose origin is now.
+        pub fn new() -> Self {
+            Self {
+                origin: Instant::now(),
+            }
+        }
+    }
+
+    impl Default for StdClock {
+        fn default() -> Self {
+            Self::new()
+        }
+    }
+
+    impl Clock for StdClock {
+        #[inline]
+        fn now(&self) -> Duration {
+            self.origin.elapsed()
+        }
+    }
+);
+
+/// `ManualClock` is a [`Clock`] which only moves when it is advanced.
+///
+/// Clones share the same time, so a clone can be kept outside of a cache to drive the cache's clock.
+/// On targets without 64-bit atomics the time is kept behind a spin lock instead of an `AtomicU64`.
+///
+/// # Example
+/// ```rust
+/// use caches::{Clock, ManualClock};
+/// use core::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let handle = clock.clone();
+/// assert_eq!(clock.now(), Duration::ZERO);
+///
+/// handle.advance(Duration::from_secs(1));
+/// assert_eq!(clock.now(), Duration::from_secs(1));
+/// ```
+///
+/// [`Clock`]: trait.Clock.html
+#[derive(Debug, Clone, Default)]
+pub struct ManualClock {
+    nanos: Arc<AtomicU64>,
+}
+
+impl ManualClock {
+    /// Creates a `ManualClock` at zero.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Moves the clock forward by `d`. The clock saturates at `u64::MAX` nanoseconds.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Clock, ManualClock};
+    /// use core::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// clock.advance(Duration::from_millis(10));
+    /// clock.advance(Duration::from_millis(10));
+    /// assert_eq!(clock.now(), Duration::from_millis(20));
+    ///
+    /// clock.advance(Duration::MAX);
+    /// assert_eq!(clock.now(), Duration::from_nanos(u64::MAX));
+    /// ```
+    pub fn advance(&self, d: Duration) {
+        let d = nanos(d);
+        let _ = self
+            .nanos
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |now| {
+                Some(now.saturating_add(d))
+            });
+    }
+
+    /// Sets the time of the clock. The time is not changed if `now` is before
+    /// the current time of the clock, so the clock stays monotonic.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Clock, ManualClock};
+    /// use core::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// clock.set(Duration::from_secs(2));
+    /// clock.set(Duration::from_secs(1));
+    /// assert_eq!(clock.now(), Duration::from_secs(2));
+    /// ```
+    pub fn set(&self, now: Duration) {
+        self.nanos.fetch_max(nanos(now), Ordering::SeqCst);
+    }
+}
+
+impl Clock for ManualClock {
+    #[inline]
+    fn now(&self) -> Duration {
+        Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+// Converts `d` to nanoseconds, saturating at `u64::MAX`.
+#[inline]
+fn nanos(d: Duration) -> u64 {
+    d.as_nanos().min(u64::MAX as u128) as u64
+}
+
+#[cfg(not(target_has_atomic = "64"))]
+mod fallback {
+    use core::cell::UnsafeCell;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    /// `AtomicU64` is a `u64` behind a spin lock, for the targets without 64-bit atomics.
+    /// It has the subset of the `core::sync::atomic::AtomicU64` API used by `ManualClock`.
+    #[derive(Debug, Default)]
+    pub(super) struct AtomicU64 {
+        locked: AtomicBool,
+        val: UnsafeCell<u64>,
+    }
+
+    // `val` is only accessed while `locked` is held.
+    unsafe impl Sync for AtomicU64 {}
+
+    impl AtomicU64 {
+        fn with<R>(&self, f: impl FnOnce(&mut u64) -> R) -> R {
+            while self
+                .locked
+                .compare_exchange_weak(false, true, Ordering::Acquire, Ordering::Relaxed)
+                .is_err()
+            {
+                core::hint::spin_loop();
+            }
+            let rst = f(unsafe { &mut *self.val.get() });
+            self.locked.store(false, Ordering::Release);
+            rst
+        }
+
+        pub(super) fn load(&self, _: Ordering) -> u64 {
+            self.with(|val| *val)
+        }
+
+        pub(super) fn fetch_update<F>(&self, _: Ordering, _: Ordering, mut f: F) -> Result<u64, u64>
+        where
+            F: FnMut(u64) -> Option<u64>,
+        {
+            self.with(|val| {
+                let old = *val;
+                match f(old) {
+                    Some(new) => {
+                        *val = new;
+                        Ok(old)
+                    }
+                    None => Err(old),
+                }
+            })
+        }
+
+        pub(super) fn fetch_max(&self, n: u64, _: Ordering) -> u64 {
+            self.with(|val| {
+                let old = *val;
+                *val = old.max(n);
+                old
+            })
+        }
+    }
+}
@@ -0,0 +1,521 @@
+//! The entry API of the caches, which is used to insert or modify an entry in place.
+//!
+//! The value of an occupied entry is found by the lookup of the entry, and it is read,
+//! modified or replaced without looking up the key again. Inserting into a vacant entry
+//! puts the key into the cache, which hashes the key again.
+use crate::{KeyRef, PutResult};
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+pub(crate) mod sealed {
+    use crate::{Cache, PutResult};
+    use core::hash::Hash;
+    use core::ptr::NonNull;
+
+    /// Implemented by the caches which support the entry API and the loaders of [`Cache`].
+    pub trait EntryCache<K: Hash + Eq, V>: Cache<K, V> {
+        /// Puts a key which is not in the cache, returns the [`PutResult`] and a pointer to
+        /// the inserted value, or `None` if the cache does not keep the value.
+        fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>);
+    }
+}
+
+use sealed::EntryCache;
+
+/// A view into a single entry in a cache, which may either be vacant or occupied.
+///
+/// This enum is constructed from the `entry` method of the caches, e.g. [`RawLRU::entry`].
+/// Looking up the entry counts as a `get` of the key, so an occupied entry is moved
+/// like it is returned by `get`.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, RawLRU};
+///
+/// let mut cache = RawLRU::new(2).unwrap();
+///
+/// // count the words
+/// for w in ["a", "b", "a"] {
+///     cache.entry(w).and_modify(|c| *c += 1).or_insert(1);
+/// }
+///
+/// assert_eq!(cache.peek(&"a"), Some(&2));
+/// assert_eq!(cache.peek(&"b"), Some(&1));
+/// ```
+///
+/// [`RawLRU::entry`]: struct.RawLRU.html#method.entry
+pub enum Entry<'a, K, V, C> {
+    /// An occupied entry.
+    Occupied(OccupiedEntry<'a, K, V, C>),
+    /// A vacant entry.
+    Vacant(VacantEntry<'a, K, V, C>),
+}
+
+/// A view into an occupied entry in a cache. It is part of the [`Entry`] enum.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct OccupiedEntry<'a, K, V, C> {
+    cache: &'a mut C,
+    key: K,
+    val: NonNull<V>,
+    marker: PhantomData<&'a mut V>,
+}
+
+/// A view into a vacant entry in a cache. It is part of the [`Entry`] enum.
+///
+/// [`Entry`]: enum.Entry.html
+pub struct VacantEntry<'a, K, V, C> {
+    cache: &'a mut C,
+    key: K,
+    marker: PhantomData<&'a mut V>,
+}
+
+impl<'a, K: Hash + Eq, V, C: EntryCache<K, V>> Entry<'a, K, V, C> {
+    pub(crate) fn new(cache: &'a mut C, key: K) -> Self {
+        match cache.get_mut(&KeyRef { k: &key }).map(NonNull::from) {
+            Some(val) => Entry::Occupied(OccupiedEntry {
+                cache,
+                key,
+                val,
+                marker: PhantomData,
+            }),
+            None => Entry::Vacant(VacantEntry {
+                cache,
+                key,
+                marker: PhantomData,
+            }),
+        }
+    }
+
+    /// Returns a reference to the key of this entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::RawLRU;
+    ///
+    /// let mut cache: RawLRU<&str, u64> = RawLRU::new(2).unwrap();
+    /// assert_eq!(cache.entry("a").key(), &"a");
+    /// ```
+    pub fn key(&self) -> &K {
+        match self {
+            Entry::Occupied(ent) => ent.key(),
+            Entry::Vacant(ent) => ent.key(),
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the default if empty, and returns
+    /// a mutable reference to the value in the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// *cache.entry("a").or_insert(1) += 10;
+    /// assert_eq!(cache.peek(&"a"), Some(&11));
+    ///
+    /// *cache.entry("a").or_insert(1) += 10;
+    /// assert_eq!(cache.peek(&"a"), Some(&21));
+    /// ```
+    pub fn or_insert(self, default: V) -> &'a mut V {
+        match self {
+            Entry::Occupied(ent) => ent.into_mut(),
+            Entry::Vacant(ent) => ent.insert(default).0,
+        }
+    }
+
+    /// Ensures a value is in the entry by inserting the result of the default function if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// cache.entry("a").or_insert_with(Vec::new).push(1);
+    /// cache.entry("a").or_insert_with(Vec::new).push(2);
+    ///
+    /// assert_eq!(cache.peek(&"a"), Some(&vec![1, 2]));
+    /// ```
+    pub fn or_insert_with<F: FnOnce() -> V>(self, default: F) -> &'a mut V {
+        match self {
+            Entry::Occupied(ent) => ent.into_mut(),
+            Entry::Vacant(ent) => ent.insert(default()).0,
+        }
+    }
+
+    /// Provides in-place mutable access to an occupied entry before any
+    /// potential inserts into the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// cache.entry("a").and_modify(|v| *v += 1).or_insert(42);
+    /// assert_eq!(cache.peek(&"a"), Some(&42));
+    ///
+    /// cache.entry("a").and_modify(|v| *v += 1).or_insert(42);
+    /// assert_eq!(cache.peek(&"a"), Some(&43));
+    /// ```
+    pub fn and_modify<F: FnOnce(&mut V)>(self, f: F) -> Self {
+        match self {
+            Entry::Occupied(mut ent) => {
+                f(ent.get_mut());
+                Entry::Occupied(ent)
+            }
+            Entry::Vacant(ent) => Entry::Vacant(ent),
+        }
+    }
+}
+
+impl<'a, K: Hash + Eq, V: Default, C: EntryCache<K, V>> Entry<'a, K, V, C> {
+    /// Ensures a value is in the entry by inserting the default value if empty,
+    /// and returns a mutable reference to the value in the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache: RawLRU<&str, Option<u64>> = RawLRU::new(2).unwrap();
+    /// cache.entry("a").or_default();
+    ///
+    /// assert_eq!(cache.peek(&"a"), Some(&None));
+    /// ```
+    pub fn or_default(self) -> &'a mut V {
+        self.or_insert_with(Default::default)
+    }
+}
+
+impl<'a, K: Hash + Eq, V, C: EntryCache<K, V>> OccupiedEntry<'a, K, V, C> {
+    /// Gets a reference to the key in the entry.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Gets a reference to the value in the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// cache.put("a", 1);
+    ///
+    /// if let Entry::Occupied(ent) = cache.entry("a") {
+    ///     assert_eq!(ent.get(), &1);
+    /// }
+    /// ```
+    pub fn get(&self) -> &V {
+        unsafe { self.val.as_ref() }
+    }
+
+    /// Gets a mutable reference to the value in the entry.
+    ///
+    /// If you need a reference to the `OccupiedEntry` which may outlive the
+    /// destruction of the `Entry` value, see [`into_mut`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// cache.put("a", 1);
+    ///
+    /// if let Entry::Occupied(mut ent) = cache.entry("a") {
+    ///     *ent.get_mut() += 10;
+    /// }
+    /// assert_eq!(cache.peek(&"a"), Some(&11));
+    /// ```
+    ///
+    /// [`into_mut`]: struct.OccupiedEntry.html#method.into_mut
+    pub fn get_mut(&mut self) -> &mut V {
+        unsafe { self.val.as_mut() }
+    }
+
+    /// Converts the entry into a mutable reference to the value in the entry
+    /// with a lifetime bound to the cache itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// cache.put("a", 1);
+    ///
+    /// if let Entry::Occupied(ent) = cache.entry("a") {
+    ///     *ent.into_mut() += 10;
+    /// }
+    /// assert_eq!(cache.peek(&"a"), Some(&11));
+    /// ```
+    pub fn into_mut(self) -> &'a mut V {
+        unsafe { &mut *self.val.as_ptr() }
+    }
+
+    /// Sets the value of the entry, and returns the entry's old value.
+    ///
+    /// Like assigning to the value returned by `get_mut`, the value is replaced in place,
+    /// the `on_evict` callback is not invoked.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// cache.put("a", 1);
+    ///
+    /// if let Entry::Occupied(mut ent) = cache.entry("a") {
+    ///     assert_eq!(ent.insert(2), 1);
+    /// }
+    /// assert_eq!(cache.peek(&"a"), Some(&2));
+    /// ```
+    pub fn insert(&mut self, v: V) -> V {
+        core::mem::replace(self.get_mut(), v)
+    }
+
+    /// Takes the value out of the entry, and returns it.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(2).unwrap();
+    /// cache.put("a", 1);
+    ///
+    /// if let Entry::Occupied(ent) = cache.entry("a") {
+    ///     assert_eq!(ent.remove(), 1);
+    /// }
+    /// assert!(!cache.contains(&"a"));
+    /// ```
+    pub fn remove(self) -> V {
+        self.cache.remove(&KeyRef { k: &self.key }).unwrap()
+    }
+}
+
+impl<'a, K: Hash + Eq, V, C: EntryCache<K, V>> VacantEntry<'a, K, V, C> {
+    /// Gets a reference to the key that would be used when inserting a value
+    /// through the `VacantEntry`.
+    pub fn key(&self) -> &K {
+        &self.key
+    }
+
+    /// Take ownership of the key.
+    pub fn into_key(self) -> K {
+        self.key
+    }
+
+    /// Puts the value into the cache with the key of the `VacantEntry`, returns a mutable
+    /// reference to the value and the [`PutResult`] of the put, which contains the entry
+    /// evicted to make room for the new one.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Entry, PutResult, RawLRU};
+    ///
+    /// let mut cache = RawLRU::new(1).unwrap();
+    ///
+    /// if let Entry::Vacant(ent) = cache.entry("a") {
+    ///     assert_eq!(ent.insert(1), (&mut 1, PutResult::Put));
+    /// }
+    ///
+    /// if let Entry::Vacant(ent) = cache.entry("b") {
+    ///     let (v, rst) = ent.insert(2);
+    ///     *v += 1;
+    ///     assert_eq!(rst, PutResult::Evicted { key: "a", value: 1 });
+    /// }
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    pub fn insert(self, v: V) -> (&'a mut V, PutResult<K, V>) {
+        let (val, rst) = self.insert_in(v);
+        // the caches with an entry method always keep the inserted value
+        (val.unwrap(), rst)
+    }
+
+    // Puts the value into the cache, the value is `None` if the cache does not keep it.
+    pub(crate) fn insert_in(self, v: V) -> (Option<&'a mut V>, PutResult<K, V>) {
+        let (rst, val) = self.cache.put_vacant(self.key, v);
+        (val.map(|val| unsafe { &mut *val.as_ptr() }), rst)
+    }
+}
+
+impl<'a, K: fmt::Debug, V: fmt::Debug, C> fmt::Debug for Entry<'a, K, V, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Entry::Occupied(ent) => f.debug_tuple("Entry").field(ent).finish(),
+            Entry::Vacant(ent) => f.debug_tuple("Entry").field(ent).finish(),
+        }
+    }
+}
+
+impl<'a, K: fmt::Debug, V: fmt::Debug, C> fmt::Debug for OccupiedEntry<'a, K, V, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("OccupiedEntry")
+            .field("key", &self.key)
+            .field("value", unsafe { self.val.as_ref() })
+            .finish()
+    }
+}
+
+impl<'a, K: fmt::Debug, V, C> fmt::Debug for VacantEntry<'a, K, V, C> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_tuple("VacantEntry").field(&self.key).finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lfu::{CostCache, TinyLFUAdmission, WTinyLFUCache, WTinyLFUCacheBuilder};
+    use crate::lru::{
+        AdaptiveCache, ArenaAdaptiveCache, ArenaLRU, ArenaTwoQueueCache, CarCache, ClockCache,
+        ClockProCache, DLIRSCache, ExpiringLRU, LIRSCache, S3FifoCache, SegmentedCache, SieveCache,
+        TwoQueueCache, WeightedLRU,
+    };
+    use crate::{Cache, Entry, RawLRU};
+    use alloc::format;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    // Looking up the entry and inserting into a vacant entry behaves like `get` and `put`.
+    macro_rules! assert_entry_same_as_get_and_put {
+        ($entry_cache:expr, $cache:expr) => {{
+            let mut entry_cache = $entry_cache;
+            let mut cache = $cache;
+            let mut rng = StdRng::seed_from_u64(0);
+
+            (0..10_000).for_each(|_| {
+                let k = rng.gen::<u64>() % 64;
+                match entry_cache.entry(k) {
+                    Entry::Occupied(mut ent) => {
+                        *ent.get_mut() += 1;
+                        *cache.get_mut(&k).unwrap() += 1;
+                    }
+                    Entry::Vacant(ent) => {
+                        assert!(cache.get(&k).is_none());
+                        let (v, rst) = ent.insert(k);
+                        assert_eq!(*v, k);
+                        assert_eq!(rst, cache.put(k, k));
+                    }
+                }
+                assert_eq!(entry_cache.len(), cache.len());
+            });
+
+            (0..64).for_each(|k| assert_eq!(entry_cache.peek(&k), cache.peek(&k)));
+        }};
+    }
+
+    #[test]
+    fn test_entry_same_as_get_and_put() {
+        assert_entry_same_as_get_and_put!(RawLRU::new(16).unwrap(), RawLRU::new(16).unwrap());
+        assert_entry_same_as_get_and_put!(
+            SegmentedCache::new(4, 12).unwrap(),
+            SegmentedCache::new(4, 12).unwrap()
+        );
+    }
+
+    #[test]
+    fn test_entry_or_default_and_into_key() {
+        let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
+
+        *cache.entry(1).or_default() += 1;
+        *cache.entry(1).or_default() += 1;
+        assert_eq!(cache.peek(&1), Some(&2));
+
+        match cache.entry(2) {
+            Entry::Occupied(_) => panic!("2 should not be in the cache"),
+            Entry::Vacant(ent) => assert_eq!(ent.into_key(), 2),
+        }
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.entry(2).key(), &2);
+    }
+
+    #[test]
+    fn test_entry_debug() {
+        let mut cache = RawLRU::new(2).unwrap();
+        cache.put(1, "a");
+
+        assert_eq!(
+            format!("{:?}", cache.entry(1)),
+            "Entry(OccupiedEntry { key: 1, value: \"a\" })"
+        );
+        assert_eq!(format!("{:?}", cache.entry(2)), "Entry(VacantEntry(2))");
+    }
+
+    // The loaders return the value of the key in the cache, the built value is only returned
+    // if the cache keeps it.
+    macro_rules! assert_loader_returns_cached_value {
+        ($($cache:expr),+ $(,)?) => {{
+            $(
+                let mut cache = $cache;
+                let mut rng = StdRng::seed_from_u64(0);
+
+                (0..10_000).for_each(|_| {
+                    let k = rng.gen::<u64>() % 64;
+                    let v = cache.get_or_insert_with(k, || k + 1).copied();
+                    assert!(v.is_none() || v == Some(k + 1));
+                    assert_eq!(v, cache.peek(&k).copied());
+                });
+            )+
+        }};
+    }
+
+    #[test]
+    fn test_loaders() {
+        let weigher = |k: &u64, _: &u64| (*k % 20) as usize;
+        assert_loader_returns_cached_value!(
+            RawLRU::new(16).unwrap(),
+            ArenaLRU::new(16).unwrap(),
+            SegmentedCache::new(4, 12).unwrap(),
+            TwoQueueCache::new(16).unwrap(),
+            ArenaTwoQueueCache::new(16).unwrap(),
+            AdaptiveCache::new(16).unwrap(),
+            ArenaAdaptiveCache::new(16).unwrap(),
+            LIRSCache::new(16).unwrap(),
+            DLIRSCache::new(16).unwrap(),
+            ClockCache::new(16).unwrap(),
+            ClockProCache::new(16).unwrap(),
+            CarCache::new(16).unwrap(),
+            SieveCache::new(16).unwrap(),
+            S3FifoCache::new(16).unwrap(),
+            ExpiringLRU::new(16).unwrap(),
+            WeightedLRU::new(16, weigher).unwrap(),
+            WTinyLFUCache::with_sizes(2, 8, 6, 100).unwrap(),
+            WTinyLFUCacheBuilder::new(2, 8, 6, 100)
+                .finalize_weighted(16, weigher)
+                .unwrap(),
+            CostCache::new(16, 64).unwrap(),
+            TinyLFUAdmission::new(TwoQueueCache::new(16).unwrap(), 100).unwrap(),
+        );
+    }
+
+    #[test]
+    fn test_loaders_do_not_clone_the_key() {
+        #[derive(Debug, Hash, Eq, PartialEq)]
+        struct Key(u64);
+
+        let mut cache = RawLRU::new(2).unwrap();
+        assert_eq!(cache.get_or_insert_with(Key(1), || "a"), Some(&"a"));
+        assert_eq!(
+            cache.get_or_insert_with(Key(1), || unreachable!()),
+            Some(&"a")
+        );
+        assert_eq!(cache.try_get_or_insert_with(Key(2), || Err(())), Err(()));
+        assert!(!cache.contains(&Key(2)));
+    }
+}
@@ -1,12 +1,18 @@
 //! LFU based caches implementation.
 //!
-//! This module contains LFU based caches, [`WTinyLFUCache`], [`TinyLFU`] and [`SampledLFU`].
+//! This module contains LFU based caches, [`WTinyLFUCache`], [`CostCache`], [`TinyLFU`] and
+//! [`SampledLFU`], and the [`TinyLFUAdmission`] wrapper which puts a TinyLFU in front of the
+//! other caches.
 //!
+mod admission;
+mod cost;
 pub mod sampled;
 pub mod tinylfu;
 mod wtinylfu;
 
-pub use wtinylfu::{WTinyLFUCache, WTinyLFUCacheBuilder};
+pub use admission::TinyLFUAdmission;
+pub use cost::{CostCache, CostCacheError};
+pub use wtinylfu::{WTinyLFUCache, WTinyLFUCacheBuilder, WeightedWTinyLFUCache};
 
 use crate::{DefaultHashBuilder, KeyRef};
 use core::borrow::Borrow;
@@ -23,12 +29,21 @@ pub trait KeyHasher<K: Hash + Eq> {
 }
 
 /// `DefaultKeyHasher` uses the same hasher as the Hashmap's default hasher
-#[derive(Clone)]
 pub struct DefaultKeyHasher<K: Hash + Eq> {
     marker: PhantomData<K>,
     hasher: DefaultHashBuilder,
 }
 
+// derived `Clone` would require `K: Clone`, the clone hashes the keys the same as the original.
+impl<K: Hash + Eq> Clone for DefaultKeyHasher<K> {
+    fn clone(&self) -> Self {
+        Self {
+            marker: PhantomData,
+            hasher: self.hasher.clone(),
+        }
+    }
+}
+
 impl<K: Hash + Eq> Default for DefaultKeyHasher<K> {
     fn default() -> Self {
         Self {
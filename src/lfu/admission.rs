@@ -0,0 +1,405 @@
+use crate::entry::sealed::EntryCache;
+use crate::lfu::{
+    tinylfu::{TinyLFU, TinyLFUError, DEFAULT_FALSE_POSITIVE_RATIO},
+    DefaultKeyHasher, KeyHasher,
+};
+use crate::{Cache, KeyRef, PeekVictim, PutResult, ResizableCache};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::Hash;
+use core::marker::PhantomData;
+use core::ptr::NonNull;
+
+/// `TinyLFUAdmission` puts a [`TinyLFU`] admission policy in front of any cache which
+/// implements [`PeekVictim`], e.g. [`LRUCache`], [`TwoQueueCache`] or [`AdaptiveCache`].
+///
+/// The accesses of `get`, `get_mut` and `put` are recorded by the TinyLFU sketch. When putting
+/// a new key would evict an entry of the inner cache, the new key is only admitted if its estimated
+/// frequency is not less than the victim's, otherwise the inner cache is not changed and
+/// `put` returns [`PutResult::Rejected`].
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, PutResult, RawLRU, TinyLFUAdmission};
+/// use caches::lfu::tinylfu::TinyLFU;
+///
+/// let tinylfu = TinyLFU::new(100, 1000, 0.01).unwrap();
+/// let mut cache = TinyLFUAdmission::with_tinylfu(RawLRU::new(2).unwrap(), tinylfu);
+///
+/// cache.put(1, "a");
+/// cache.put(2, "b");
+///
+/// // 1 and 2 are hot
+/// for _ in 0..3 {
+///     cache.get(&1);
+///     cache.get(&2);
+/// }
+///
+/// // 3 is only seen by its put, it is rejected instead of evicting 1
+/// assert_eq!(cache.put(3, "c"), PutResult::Rejected { key: 3, value: "c" });
+/// assert!(cache.contains(&1));
+/// assert!(cache.contains(&2));
+/// ```
+///
+/// [`TinyLFU`]: tinylfu/struct.TinyLFU.html
+/// [`PeekVictim`]: ../trait.PeekVictim.html
+/// [`LRUCache`]: ../lru/type.LRUCache.html
+/// [`TwoQueueCache`]: ../lru/struct.TwoQueueCache.html
+/// [`AdaptiveCache`]: ../lru/struct.AdaptiveCache.html
+/// [`PutResult::Rejected`]: ../enum.PutResult.html#variant.Rejected
+pub struct TinyLFUAdmission<K, V, C, KH = DefaultKeyHasher<K>> {
+    cache: C,
+    tinylfu: TinyLFU<K, KH>,
+    marker: PhantomData<V>,
+}
+
+impl<K: Hash + Eq, V, C: PeekVictim<K, V>> TinyLFUAdmission<K, V, C> {
+    /// Wraps the cache with a TinyLFU sized by the capacity of the cache.
+    /// `samples` is the number of accesses after which the counters of the TinyLFU are halved.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{Cache, TinyLFUAdmission, TwoQueueCache};
+    ///
+    /// let cache = TinyLFUAdmission::new(TwoQueueCache::<u64, u64>::new(10).unwrap(), 100).unwrap();
+    /// assert_eq!(cache.cap(), 10);
+    /// ```
+    pub fn new(cache: C, samples: usize) -> Result<Self, TinyLFUError> {
+        let tinylfu = TinyLFU::new(cache.cap(), samples, DEFAULT_FALSE_POSITIVE_RATIO)?;
+        Ok(Self::with_tinylfu(cache, tinylfu))
+    }
+}
+
+impl<K: Hash + Eq, V, C: PeekVictim<K, V>, KH: KeyHasher<K>> TinyLFUAdmission<K, V, C, KH> {
+    /// Wraps the cache with the given TinyLFU.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{AdaptiveCache, Cache, TinyLFUAdmission};
+    /// use caches::lfu::tinylfu::TinyLFU;
+    ///
+    /// let tinylfu = TinyLFU::new(100, 1000, 0.01).unwrap();
+    /// let cache = TinyLFUAdmission::with_tinylfu(AdaptiveCache::<u64, u64>::new(10).unwrap(), tinylfu);
+    /// assert_eq!(cache.cap(), 10);
+    /// ```
+    pub fn with_tinylfu(cache: C, tinylfu: TinyLFU<K, KH>) -> Self {
+        Self {
+            cache,
+            tinylfu,
+            marker: PhantomData,
+        }
+    }
+
+    /// Returns a reference to the inner cache.
+    pub fn inner(&self) -> &C {
+        &self.cache
+    }
+
+    /// Returns a mutable reference to the inner cache. The accesses and puts on the inner cache
+    /// bypass the admission policy.
+    pub fn inner_mut(&mut self) -> &mut C {
+        &mut self.cache
+    }
+
+    /// Unwraps the inner cache.
+    pub fn into_inner(self) -> C {
+        self.cache
+    }
+
+    /// Returns a reference to the TinyLFU of the admission policy.
+    pub fn tinylfu(&self) -> &TinyLFU<K, KH> {
+        &self.tinylfu
+    }
+
+    // Returns true if putting the key would evict an entry which is used more frequently.
+    fn rejects(&self, k: &K) -> bool {
+        let key_ref = KeyRef { k };
+        match self.cache.peek_victim(&key_ref) {
+            Some(victim) => self.tinylfu.lt(&key_ref, &KeyRef { k: victim }),
+            None => false,
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, C: PeekVictim<K, V>, KH: KeyHasher<K>> Cache<K, V>
+    for TinyLFUAdmission<K, V, C, KH>
+{
+    /// Records the access of the key, puts the key-value pair into the inner cache if it is
+    /// admitted, returns a [`PutResult`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PutResult, RawLRU, TinyLFUAdmission};
+    /// use caches::lfu::tinylfu::TinyLFU;
+    ///
+    /// let tinylfu = TinyLFU::new(100, 1000, 0.01).unwrap();
+    /// let mut cache = TinyLFUAdmission::with_tinylfu(RawLRU::new(1).unwrap(), tinylfu);
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(1, "b"), PutResult::Update("a"));
+    ///
+    /// // 1 has been put twice, 2 only once
+    /// assert_eq!(cache.put(2, "c"), PutResult::Rejected { key: 2, value: "c" });
+    ///
+    /// // 2 is as frequent as 1 now, so it is admitted
+    /// assert_eq!(cache.put(2, "d"), PutResult::Evicted { key: 1, value: "b" });
+    /// ```
+    ///
+    /// [`PutResult`]: struct.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        self.tinylfu.increment(&KeyRef { k: &k });
+        if self.rejects(&k) {
+            return PutResult::Rejected { key: k, value: v };
+        }
+        self.cache.put(k, v)
+    }
+
+    /// Records the access of the key, returns a reference to the value of the key in
+    /// the inner cache or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU, TinyLFUAdmission};
+    /// let mut cache = TinyLFUAdmission::new(RawLRU::new(2).unwrap(), 100).unwrap();
+    ///
+    /// cache.put(1, "a");
+    ///
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// assert_eq!(cache.get(&2), None);
+    /// assert_eq!(cache.tinylfu().estimate(&2), 1);
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tinylfu.increment(k);
+        self.cache.get(k)
+    }
+
+    /// Records the access of the key, returns a mutable reference to the value of the key in
+    /// the inner cache or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, RawLRU, TinyLFUAdmission};
+    /// let mut cache = TinyLFUAdmission::new(RawLRU::new(2).unwrap(), 100).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// *cache.get_mut(&1).unwrap() += 1;
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tinylfu.increment(k);
+        self.cache.get_mut(k)
+    }
+
+    /// Returns a reference to the value of the key in the inner cache or `None`.
+    /// Unlike `get`, `peek` does not record the access or update the inner cache.
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.peek(k)
+    }
+
+    /// Returns a mutable reference to the value of the key in the inner cache or `None`.
+    /// Unlike `get_mut`, `peek_mut` does not record the access or update the inner cache.
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.peek_mut(k)
+    }
+
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.contains(k)
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.cache.remove(k)
+    }
+
+    /// Clears the inner cache and the TinyLFU.
+    fn purge(&mut self) {
+        self.cache.purge();
+        self.tinylfu.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.cache.len()
+    }
+
+    fn cap(&self) -> usize {
+        self.cache.cap()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.cache.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, C: PeekVictim<K, V> + EntryCache<K, V>, KH: KeyHasher<K>> EntryCache<K, V>
+    for TinyLFUAdmission<K, V, C, KH>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the access of the key is already recorded by the lookup of the entry
+        if self.rejects(&k) {
+            return (PutResult::Rejected { key: k, value: v }, None);
+        }
+        self.cache.put_vacant(k, v)
+    }
+}
+
+impl<K, V, C: ResizableCache, KH> ResizableCache for TinyLFUAdmission<K, V, C, KH> {
+    fn resize(&mut self, cap: usize) -> u64 {
+        self.cache.resize(cap)
+    }
+}
+
+impl<K, V, C: fmt::Debug, KH> fmt::Debug for TinyLFUAdmission<K, V, C, KH> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("TinyLFUAdmission")
+            .field("cache", &self.cache)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lfu::tinylfu::TinyLFU;
+    use crate::lfu::TinyLFUAdmission;
+    use crate::lru::{AdaptiveCache, TwoQueueCache};
+    use crate::{Cache, PeekVictim, PutResult, RawLRU};
+    use alloc::vec::Vec;
+    use rand::{thread_rng, Rng};
+
+    fn assert_scan_resistant<C: PeekVictim<u64, u64>>(cache: C) {
+        // a large sketch keeps the estimations of the test exact
+        let tinylfu = TinyLFU::new(1024, 1000, 0.01).unwrap();
+        let mut cache = TinyLFUAdmission::with_tinylfu(cache, tinylfu);
+
+        for k in 0..4 {
+            cache.put(k, k);
+        }
+        for _ in 0..5 {
+            for k in 0..4 {
+                assert_eq!(cache.get(&k), Some(&k));
+            }
+        }
+
+        // a scan of the keys seen once can not evict the hot keys
+        for k in 100..200 {
+            cache.get(&k);
+            assert_eq!(cache.put(k, k), PutResult::Rejected { key: k, value: k });
+        }
+        for k in 0..4 {
+            assert!(cache.contains(&k));
+        }
+
+        // a key becoming hot is admitted
+        for _ in 0..10 {
+            cache.get(&500);
+        }
+        assert!(!matches!(cache.put(500, 500), PutResult::Rejected { .. }));
+        assert!(cache.contains(&500));
+        assert_eq!(cache.len(), 4);
+    }
+
+    fn assert_peek_victim<C: PeekVictim<u64, u64>>(mut cache: C) {
+        let mut rng = thread_rng();
+        for _ in 0..10000 {
+            let k = rng.gen::<u64>() % 32;
+            if rng.gen::<bool>() {
+                cache.get(&k);
+                continue;
+            }
+
+            let victim = cache.peek_victim(&k).copied();
+            let before: Vec<u64> = (0..32).filter(|k| cache.contains(k)).collect();
+            if before.len() > cache.cap() {
+                // the ARC may go over its capacity when the LRU it evicts from is empty,
+                // the victim is only checked for a cache within its capacity.
+                cache.put(k, k);
+                continue;
+            }
+            cache.put(k, k);
+            let evicted: Vec<u64> = before.into_iter().filter(|k| !cache.contains(k)).collect();
+            assert!(evicted.len() <= 1);
+            assert_eq!(victim, evicted.first().copied());
+        }
+    }
+
+    #[test]
+    fn test_admission_lru() {
+        assert_scan_resistant(RawLRU::new(4).unwrap());
+        assert_peek_victim(RawLRU::new(8).unwrap());
+    }
+
+    #[test]
+    fn test_admission_two_queue() {
+        assert_scan_resistant(TwoQueueCache::new(4).unwrap());
+        assert_peek_victim(TwoQueueCache::new(8).unwrap());
+    }
+
+    #[test]
+    fn test_admission_adaptive() {
+        assert_scan_resistant(AdaptiveCache::new(4).unwrap());
+        assert_peek_victim(AdaptiveCache::new(8).unwrap());
+    }
+
+    #[test]
+    fn test_admission_records_put() {
+        let tinylfu = TinyLFU::new(1024, 1000, 0.01).unwrap();
+        let mut cache = TinyLFUAdmission::with_tinylfu(RawLRU::new(1).unwrap(), tinylfu);
+
+        cache.put(1, 1);
+        cache.put(1, 1);
+        assert_eq!(cache.tinylfu().estimate(&1), 2);
+
+        // the put of 2 is recorded even if 2 is rejected
+        assert_eq!(cache.put(2, 2), PutResult::Rejected { key: 2, value: 2 });
+        assert_eq!(cache.tinylfu().estimate(&2), 1);
+
+        // the lookup of the entry is the only access recorded for a vacant insert
+        assert_eq!(cache.get_or_insert_with(2, || 2), Some(&2));
+        assert_eq!(cache.tinylfu().estimate(&2), 2);
+        assert!(!cache.contains(&1));
+    }
+
+    #[test]
+    fn test_admission_cap_1() {
+        let mut cache = TinyLFUAdmission::new(RawLRU::new(1).unwrap(), 100).unwrap();
+        let mut rng = thread_rng();
+        for _ in 0..1000 {
+            let k = rng.gen::<u64>() % 4;
+            if rng.gen::<bool>() {
+                cache.get(&k);
+            } else {
+                cache.put(k, k);
+            }
+            assert!(cache.len() <= 1);
+        }
+    }
+}
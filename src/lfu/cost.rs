@@ -0,0 +1,787 @@
+mod error;
+pub use error::CostCacheError;
+
+use crate::entry::sealed::EntryCache;
+use crate::lfu::{
+    sampled::{SampledLFU, DEFAULT_SAMPLES},
+    tinylfu::{TinyLFU, TinyLFUBuilder, TinyLFUError, DEFAULT_FALSE_POSITIVE_RATIO},
+    DefaultKeyHasher, KeyHasher,
+};
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder,
+    EvictionReason, KeyRef, OnEvictCallback, PutResult,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
+use rand::rngs::SmallRng;
+use rand::SeedableRng;
+
+import_hashbrown!(HashMap);
+import_std!(HashMap);
+
+// The counters of the TinyLFU are halved after this multiple of the expected entries.
+const SAMPLES_PER_ENTRY: usize = 10;
+
+/// `CostCache` is a cache bounded by the total cost of its entries, like [Dgraph's ristretto].
+///
+/// The cost of each entry is tracked by a [`SampledLFU`], and the accesses of `get`, `get_mut`
+/// and `put` are recorded by a [`TinyLFU`]. When a new entry does not fit, the cache samples
+/// some entries at random and chooses the one with the lowest TinyLFU estimate as a victim,
+/// until the entry fits.
+/// The new entry is rejected, and none of the victims is evicted, as soon as it is less
+/// frequently used than every sampled entry. Entries whose cost exceeds the max cost of the
+/// cache are always rejected.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, CostCache, PutResult};
+///
+/// let mut cache = CostCache::new(10, 100).unwrap();
+///
+/// assert_eq!(cache.put_with_cost(1, "a", 4), PutResult::Put);
+/// assert_eq!(cache.put_with_cost(2, "b", 4), PutResult::Put);
+/// assert_eq!(cache.cost(), 8);
+///
+/// // 1 and 2 are hot
+/// for _ in 0..3 {
+///     cache.get(&1);
+///     cache.get(&2);
+/// }
+///
+/// // 3 has been seen only once, it is rejected instead of evicting 1 or 2
+/// assert_eq!(cache.put_with_cost(3, "c", 4), PutResult::Rejected { key: 3, value: "c" });
+///
+/// // 11 is more than the max cost
+/// assert_eq!(cache.put_with_cost(4, "d", 11), PutResult::Rejected { key: 4, value: "d" });
+/// ```
+///
+/// [Dgraph's ristretto]: https://github.com/dgraph-io/ristretto
+/// [`SampledLFU`]: sampled/struct.SampledLFU.html
+/// [`TinyLFU`]: tinylfu/struct.TinyLFU.html
+pub struct CostCache<
+    K,
+    V,
+    KH = DefaultKeyHasher<K>,
+    S = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    // the entries are stored by the hashes of their keys, which are shared by the TinyLFU
+    // and the SampledLFU
+    store: HashMap<u64, (K, V), S>,
+    tinylfu: TinyLFU<K, KH>,
+    lfu: SampledLFU<K, KH, S>,
+    // samples the victims
+    rng: SmallRng,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> CostCache<K, V> {
+    /// Creates a `CostCache` whose entries cost at most `max_cost` in total,
+    /// `entries` is the expected number of entries in the cache, which sizes the TinyLFU.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache};
+    ///
+    /// let cache: CostCache<u64, u64> = CostCache::new(1000, 100).unwrap();
+    /// assert_eq!(cache.cap(), 1000);
+    /// assert!(CostCache::<u64, u64>::new(0, 100).is_err());
+    /// ```
+    pub fn new(max_cost: i64, entries: usize) -> Result<Self, CostCacheError> {
+        Self::with_on_evict_cb_and_hashers_in(
+            max_cost,
+            entries,
+            None,
+            DefaultKeyHasher::default(),
+            DefaultHashBuilder::default(),
+        )
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback>
+    CostCache<K, V, DefaultKeyHasher<K>, DefaultHashBuilder, E>
+{
+    /// Creates a `CostCache` whose entries cost at most `max_cost` in total
+    /// and uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = CostCache::with_on_evict_cb(1, 10, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(max_cost: i64, entries: usize, cb: E) -> Result<Self, CostCacheError> {
+        Self::with_on_evict_cb_and_hashers_in(
+            max_cost,
+            entries,
+            Some(cb),
+            DefaultKeyHasher::default(),
+            DefaultHashBuilder::default(),
+        )
+    }
+}
+
+impl<K: Hash + Eq, V, KH: KeyHasher<K> + Clone, S: BuildHasher + Clone, E: OnEvictCallback>
+    CostCache<K, V, KH, S, E>
+{
+    /// Creates a `CostCache` whose entries cost at most `max_cost` in total, uses the provided
+    /// evict callback, the provided key hasher for the TinyLFU and the SampledLFU, and the
+    /// provided hash builder for the maps.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache, DefaultEvictCallback, DefaultHashBuilder};
+    /// use caches::lfu::DefaultKeyHasher;
+    ///
+    /// let cache: CostCache<u64, u64> = CostCache::with_on_evict_cb_and_hashers(
+    ///     1000,
+    ///     100,
+    ///     DefaultEvictCallback,
+    ///     DefaultKeyHasher::default(),
+    ///     DefaultHashBuilder::default(),
+    /// )
+    /// .unwrap();
+    /// assert_eq!(cache.cap(), 1000);
+    /// ```
+    pub fn with_on_evict_cb_and_hashers(
+        max_cost: i64,
+        entries: usize,
+        cb: E,
+        key_hasher: KH,
+        hasher: S,
+    ) -> Result<Self, CostCacheError> {
+        Self::with_on_evict_cb_and_hashers_in(max_cost, entries, Some(cb), key_hasher, hasher)
+    }
+
+    fn with_on_evict_cb_and_hashers_in(
+        max_cost: i64,
+        entries: usize,
+        cb: Option<E>,
+        key_hasher: KH,
+        hasher: S,
+    ) -> Result<Self, CostCacheError> {
+        if max_cost <= 0 {
+            return Err(CostCacheError::InvalidMaxCost(max_cost));
+        }
+
+        let tinylfu = TinyLFUBuilder::new(entries, entries * SAMPLES_PER_ENTRY)
+            .set_key_hasher(key_hasher.clone())
+            .set_false_positive_ratio(DEFAULT_FALSE_POSITIVE_RATIO)
+            .finalize()
+            .map_err(|e| match e {
+                TinyLFUError::InvalidCountMinWidth(v) => CostCacheError::InvalidCountMinWidth(v),
+                TinyLFUError::InvalidSamples(v) => CostCacheError::InvalidSamples(v),
+                TinyLFUError::InvalidFalsePositiveRatio(v) => {
+                    CostCacheError::InvalidFalsePositiveRatio(v)
+                }
+            })?;
+
+        Ok(Self {
+            store: HashMap::with_hasher(hasher.clone()),
+            tinylfu,
+            lfu: SampledLFU::with_samples_and_key_hasher_and_hasher(
+                max_cost,
+                DEFAULT_SAMPLES,
+                key_hasher,
+                hasher,
+            ),
+            rng: SmallRng::seed_from_u64(DefaultHashBuilder::default().build_hasher().finish()),
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+impl<K: Hash + Eq, V, KH: KeyHasher<K>, S: BuildHasher, E: OnEvictCallback>
+    CostCache<K, V, KH, S, E>
+{
+    /// Puts a key-value pair which costs `cost` into the cache, returns a [`PutResult`].
+    ///
+    /// All the entries evicted to make room for the entry are returned. If the entry is rejected
+    /// by the admission, no entry is evicted and the entry is returned in
+    /// [`PutResult::Rejected`]. The evict callback is not invoked for a rejected entry, which
+    /// never was in the cache.
+    ///
+    /// An entry which costs more than the max cost is rejected as well, and the old entry of the
+    /// key, if any, is removed from the cache.
+    ///
+    /// The cost of an updated entry may grow, the cache then evicts the sampled entries with
+    /// the lowest estimates, but never the updated entry itself.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache, PutResult};
+    /// let mut cache = CostCache::new(10, 100).unwrap();
+    ///
+    /// assert_eq!(cache.put_with_cost(1, "a", 6), PutResult::Put);
+    /// assert_eq!(cache.put_with_cost(2, "b", 4), PutResult::Put);
+    ///
+    /// // 2 is used more frequently than 1
+    /// cache.get(&2);
+    /// assert_eq!(cache.put_with_cost(2, "bc", 5), PutResult::EvictedAndUpdate {
+    ///     evicted: (1, "a"),
+    ///     update: "b",
+    /// });
+    /// assert_eq!(cache.cost(), 5);
+    /// ```
+    ///
+    /// [`PutResult`]: ../enum.PutResult.html
+    /// [`PutResult::Rejected`]: ../enum.PutResult.html#variant.Rejected
+    pub fn put_with_cost(&mut self, k: K, v: V, cost: i64) -> PutResult<K, V> {
+        self.record(&KeyRef { k: &k });
+        let rst = self.put_in(k, v, cost);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns the total cost of the entries in the cache.
+    pub fn cost(&self) -> i64 {
+        self.lfu.used()
+    }
+
+    /// Returns the cost of the entry of the key in the cache or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache};
+    /// let mut cache = CostCache::new(10, 100).unwrap();
+    ///
+    /// cache.put_with_cost(1, "a", 6);
+    /// assert_eq!(cache.cost_of(&1), Some(6));
+    /// assert_eq!(cache.cost_of(&2), None);
+    /// ```
+    pub fn cost_of<Q>(&self, k: &Q) -> Option<i64>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kh = self.find(k)?;
+        self.lfu.cost_of_hashed_key(kh)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CostCache};
+        /// let mut cache = CostCache::new(10, 100).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(1, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 1);
+        /// assert_eq!(stats.updates(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, CostCache};
+        /// let mut cache = CostCache::new(10, 100).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    /// Records an access of the key in the TinyLFU.
+    #[inline]
+    fn record<Q>(&mut self, k: &Q)
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tinylfu.try_reset();
+        self.tinylfu.increment(k);
+    }
+
+    /// Returns the hash of the key if the key is in the cache.
+    fn find<Q>(&self, k: &Q) -> Option<u64>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kh = self.lfu.hash_key(k);
+        let (key, _) = self.store.get(&kh)?;
+        let key_ref = KeyRef { k: key };
+        if Borrow::<Q>::borrow(&key_ref) == k {
+            Some(kh)
+        } else {
+            None
+        }
+    }
+
+    fn put_in(&mut self, k: K, v: V, cost: i64) -> PutResult<K, V> {
+        let kh = self.lfu.hash_key(&KeyRef { k: &k });
+        let old = self.store.get(&kh).map(|(old_key, _)| *old_key == k);
+        if cost > self.lfu.get_max_cost() {
+            // the old value is replaced even if the new one is rejected, so the cache never
+            // keeps a stale value of the key.
+            if old == Some(true) {
+                let (old_key, old_val) = self.store.remove(&kh).unwrap();
+                self.lfu.remove_hashed_key(kh);
+                self.cb(&old_key, &old_val, EvictionReason::Replaced);
+            }
+            return PutResult::Rejected { key: k, value: v };
+        }
+
+        let mut evicted = Vec::new();
+        if old == Some(true) {
+            let (_, old_val) = self.store.remove(&kh).unwrap();
+            self.cb(&k, &old_val, EvictionReason::Replaced);
+            self.lfu.update_hashed_key(kh, cost);
+            self.store.insert(kh, (k, v));
+            let need = -self.lfu.room_left(0);
+            let victims = self.select_victims(kh, need, None).unwrap();
+            self.evict_victims(victims, &mut evicted);
+            return PutResult::from_evicted(evicted, Some(old_val));
+        }
+
+        // a different key with the same hash leaves its room to the entry if it is admitted
+        let shared = match old {
+            Some(_) => self.lfu.cost_of_hashed_key(kh).unwrap_or(0),
+            None => 0,
+        };
+        let need = -self.lfu.room_left(cost) - shared;
+        let estimate = self.tinylfu.estimate_hashed_key(kh);
+        let victims = match self.select_victims(kh, need, Some(estimate)) {
+            Some(victims) => victims,
+            None => return PutResult::Rejected { key: k, value: v },
+        };
+
+        if let Some((old_key, old_val)) = self.store.remove(&kh) {
+            self.lfu.remove_hashed_key(kh);
+            self.cb(&old_key, &old_val, EvictionReason::Capacity);
+            evicted.push((old_key, old_val));
+        }
+        self.evict_victims(victims, &mut evicted);
+        self.lfu.increment_hashed_key(kh, cost);
+        self.store.insert(kh, (k, v));
+        PutResult::from_evicted(evicted, None)
+    }
+
+    /// Chooses the sampled entries with the lowest estimates until `need` more cost is freed,
+    /// the entry of `kh` is never chosen. Returns `None` if the entry with the `estimate` is less
+    /// frequently used than every sampled entry, or there is nothing left to evict, in which case
+    /// nothing is evicted.
+    fn select_victims(
+        &mut self,
+        kh: u64,
+        mut need: i64,
+        estimate: Option<u64>,
+    ) -> Option<Vec<u64>> {
+        let mut victims = Vec::new();
+        while need > 0 {
+            let victim = self
+                .lfu
+                .sample_victims(&mut self.rng)
+                .into_iter()
+                .filter(|(h, _)| *h != kh && !victims.contains(h))
+                .map(|(h, cost)| (h, cost, self.tinylfu.estimate_hashed_key(h)))
+                .min_by_key(|(_, _, hits)| *hits);
+            let (victim, cost, hits) = match victim {
+                Some(victim) => victim,
+                None => {
+                    // the sample may only hold the chosen entries, sample again unless
+                    // every other entry is chosen
+                    let left = self.store.len() - victims.len();
+                    if left > usize::from(self.store.contains_key(&kh)) {
+                        continue;
+                    }
+                    return None;
+                }
+            };
+            if let Some(estimate) = estimate {
+                if estimate < hits {
+                    return None;
+                }
+            }
+            victims.push(victim);
+            need -= cost;
+        }
+        Some(victims)
+    }
+
+    fn evict_victims(&mut self, victims: Vec<u64>, evicted: &mut Vec<(K, V)>) {
+        for victim in victims {
+            self.lfu.remove_hashed_key(victim);
+            if let Some((k, v)) = self.store.remove(&victim) {
+                self.cb(&k, &v, EvictionReason::Capacity);
+                evicted.push((k, v));
+            }
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, KH: KeyHasher<K>, S: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for CostCache<K, V, KH, S, E>
+{
+    /// Puts a key-value pair which costs `1` into the cache, returns a [`PutResult`].
+    /// See [`put_with_cost`] for the details.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache, PutResult};
+    /// let mut cache = CostCache::new(2, 100).unwrap();
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(2, "b"), PutResult::Put);
+    /// assert_eq!(cache.put(2, "beta"), PutResult::Update("b"));
+    /// assert_eq!(cache.cost(), 2);
+    /// ```
+    ///
+    /// [`PutResult`]: ../enum.PutResult.html
+    /// [`put_with_cost`]: struct.CostCache.html#method.put_with_cost
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        self.put_with_cost(k, v, 1)
+    }
+
+    /// Records the access of the key, returns a reference to the value of the key in the cache
+    /// or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache};
+    /// let mut cache = CostCache::new(2, 100).unwrap();
+    ///
+    /// cache.put(1, "a");
+    ///
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// assert_eq!(cache.get(&2), None);
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.record(k);
+        let v = match self.find(k) {
+            Some(kh) => self.store.get(&kh).map(|(_, v)| v),
+            None => None,
+        };
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Records the access of the key, returns a mutable reference to the value of the key in
+    /// the cache or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CostCache};
+    /// let mut cache = CostCache::new(2, 100).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// *cache.get_mut(&1).unwrap() += 1;
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.record(k);
+        let v = match self.find(k) {
+            Some(kh) => self.store.get_mut(&kh).map(|(_, v)| v),
+            None => None,
+        };
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None`.
+    /// Unlike `get`, `peek` does not record the access.
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kh = self.find(k)?;
+        self.store.get(&kh).map(|(_, v)| v)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None`.
+    /// Unlike `get_mut`, `peek_mut` does not record the access.
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let kh = self.find(k)?;
+        self.store.get_mut(&kh).map(|(_, v)| v)
+    }
+
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.find(k).is_some()
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let kh = self.find(k)?;
+        self.lfu.remove_hashed_key(kh);
+        let (key, val) = self.store.remove(&kh)?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    fn purge(&mut self) {
+        let ents: Vec<(K, V)> = self.store.drain().map(|(_, ent)| ent).collect();
+        for (k, v) in ents {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        self.lfu.clear();
+        self.tinylfu.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.store.len()
+    }
+
+    /// Returns the max cost of the cache.
+    fn cap(&self) -> usize {
+        self.lfu.get_max_cost() as usize
+    }
+
+    fn is_empty(&self) -> bool {
+        self.store.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, KH: KeyHasher<K>, S: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for CostCache<K, V, KH, S, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the access of the key is already recorded by the lookup of the entry
+        let kh = self.lfu.hash_key(&KeyRef { k: &k });
+        let rst = self.put_in(k, v, 1);
+        self.stats.record_put(&rst);
+        if let PutResult::Rejected { .. } = rst {
+            return (rst, None);
+        }
+        let v = self.store.get_mut(&kh).map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lfu::CostCache;
+    use crate::{Cache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_cost_cache_admission() {
+        let mut cache = CostCache::new(10, 100).unwrap();
+
+        // 1 to 5 fill the cache, and the odd keys are hot
+        for k in 1..=5u64 {
+            assert_eq!(cache.put_with_cost(k, k, 2), PutResult::Put);
+        }
+        for _ in 0..5 {
+            for k in [1, 3, 5] {
+                cache.get(&k);
+            }
+        }
+        assert_eq!(cache.cost(), 10);
+
+        // 6 is as frequent as the cold keys, it takes the room of one of them
+        match cache.put_with_cost(6, 6, 2) {
+            PutResult::Evicted { key, .. } => assert!(key == 2 || key == 4),
+            rst => panic!("unexpected put result: {:?}", rst),
+        }
+        assert_eq!(cache.len(), 5);
+
+        // 7 needs the room of all the others, but it is colder than the hot keys,
+        // nothing is evicted for it
+        assert_eq!(
+            cache.put_with_cost(7, 7, 10),
+            PutResult::Rejected { key: 7, value: 7 }
+        );
+        assert!(!cache.contains(&7));
+        assert!([1, 3, 5].iter().all(|k| cache.contains(k)));
+        assert_eq!(cache.len(), 5);
+        assert_eq!(cache.cost(), 10);
+    }
+
+    #[test]
+    fn test_cost_cache_rejected_not_evicted() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache =
+            CostCache::with_on_evict_cb(4, 100, EvictedCounter { ctr: ctr.clone() }).unwrap();
+        cache.put_with_cost(1, 1, 2);
+        cache.put_with_cost(2, 2, 2);
+        cache.get(&1);
+        cache.get(&2);
+
+        // neither a too costly entry, nor an entry rejected by the admission was in the cache
+        assert_eq!(
+            cache.put_with_cost(3, 3, 5),
+            PutResult::Rejected { key: 3, value: 3 }
+        );
+        assert_eq!(
+            cache.put_with_cost(4, 4, 2),
+            PutResult::Rejected { key: 4, value: 4 }
+        );
+        assert_eq!(ctr.load(Ordering::SeqCst), 0);
+        assert_eq!(cache.len(), 2);
+
+        // a newcomer as frequent as the victims is admitted, only the victims are returned
+        cache.get(&4);
+        cache.get(&4);
+        match cache.put_with_cost(4, 4, 4) {
+            PutResult::EvictedMany { evicted, update } => {
+                assert_eq!(evicted.len(), 2);
+                assert!(evicted.iter().all(|(k, _)| *k == 1 || *k == 2));
+                assert_eq!(update, None);
+            }
+            rst => panic!("unexpected put result: {:?}", rst),
+        }
+        assert_eq!(ctr.load(Ordering::SeqCst), 2);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_cost_cache_update() {
+        let mut cache = CostCache::new(10, 100).unwrap();
+        cache.put_with_cost(1, 1, 4);
+        cache.put_with_cost(2, 2, 4);
+        assert_eq!(cache.cost_of(&1), Some(4));
+
+        assert_eq!(cache.put_with_cost(1, 11, 2), PutResult::Update(1));
+        assert_eq!(cache.cost(), 6);
+
+        // the updated entry is never evicted by its own update
+        assert_eq!(
+            cache.put_with_cost(1, 111, 10),
+            PutResult::EvictedAndUpdate {
+                evicted: (2, 2),
+                update: 11
+            }
+        );
+        assert_eq!(cache.cost(), 10);
+        assert_eq!(cache.peek(&1), Some(&111));
+
+        // a too costly update removes the old entry instead of keeping a stale value
+        assert_eq!(
+            cache.put_with_cost(1, 1111, 11),
+            PutResult::Rejected {
+                key: 1,
+                value: 1111
+            }
+        );
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.cost(), 0);
+    }
+
+    #[test]
+    fn test_cost_cache_random_ops() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache =
+            CostCache::with_on_evict_cb(256, 64, EvictedCounter { ctr: ctr.clone() }).unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => {
+                    let updated = cache.contains(&k);
+                    let rst = cache.put_with_cost(k, k, (k % 16) as i64 + 1);
+                    if !updated && !matches!(rst, PutResult::Rejected { .. }) {
+                        inserted += 1;
+                    }
+                }
+                1 => {
+                    cache.get(&k);
+                }
+                _ => {
+                    cache.remove(&k);
+                }
+            }
+            assert!(cache.cost() <= 256);
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert!(cache.is_empty());
+        assert_eq!(cache.cost(), 0);
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,47 @@
+use core::fmt::{Debug, Display, Formatter};
+
+/// CostCacheError contains the error of [`CostCache`]
+///
+/// [`CostCache`]: struct.CostCache.html
+pub enum CostCacheError {
+    /// The max cost is not positive
+    InvalidMaxCost(i64),
+    /// Count Min sketch with wrong width
+    InvalidCountMinWidth(u64),
+    /// Invalid Samples value for TinyLFU
+    InvalidSamples(usize),
+    /// Invalid false positive ratio for TinyLFU
+    InvalidFalsePositiveRatio(f64),
+}
+
+impl CostCacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        match self {
+            CostCacheError::InvalidMaxCost(v) => write!(f, "invalid max cost: {}", *v),
+            CostCacheError::InvalidCountMinWidth(v) => {
+                write!(f, "invalid count main sketch width: {}", *v)
+            }
+            CostCacheError::InvalidSamples(v) => write!(f, "invalid number of samples: {}", *v),
+            CostCacheError::InvalidFalsePositiveRatio(v) => write!(
+                f,
+                "invalid false positive ratio: {}, which should be in range (0.0, 1.0)",
+                *v
+            ),
+        }
+    }
+}
+
+impl Display for CostCacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.fmt(f)
+    }
+}
+
+impl Debug for CostCacheError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> core::fmt::Result {
+        self.fmt(f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for CostCacheError {}
@@ -9,20 +9,24 @@ use core::borrow::Borrow;
 use core::hash::{BuildHasher, Hash};
 use core::marker::PhantomData;
 use core::sync::atomic::{AtomicI64, Ordering};
+use rand::Rng;
 
 import_hashbrown!(HashMap);
 import_std!(HashMap);
 
 /// DEFAULT_SAMPLES is the number of items to sample when looking at eviction
 /// candidates. 5 seems to be the most optimal number [citation needed].
-const DEFAULT_SAMPLES: usize = 5;
+pub(crate) const DEFAULT_SAMPLES: usize = 5;
 
 /// SampledLFU stores key-costs paris
 pub struct SampledLFU<K, KH = DefaultKeyHasher<K>, S = DefaultHashBuilder> {
     samples: usize,
     max_cost: AtomicI64,
     used: i64,
-    key_costs: HashMap<u64, i64, S>,
+    // the cost of each hashed key and its index in `keys`
+    key_costs: HashMap<u64, (i64, usize), S>,
+    // the hashed keys, for the random sampling
+    keys: Vec<u64>,
     kh: KH,
     marker: PhantomData<K>,
 }
@@ -35,6 +39,7 @@ impl<K: Hash + Eq> SampledLFU<K> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(DefaultHashBuilder::default()),
+            keys: Vec::new(),
             kh: DefaultKeyHasher::default(),
             marker: Default::default(),
         }
@@ -48,6 +53,7 @@ impl<K: Hash + Eq> SampledLFU<K> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(DefaultHashBuilder::default()),
+            keys: Vec::new(),
             kh: DefaultKeyHasher::default(),
             marker: Default::default(),
         }
@@ -63,6 +69,7 @@ impl<K: Hash + Eq, S: BuildHasher> SampledLFU<K, DefaultKeyHasher<K>, S> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(hasher),
+            keys: Vec::new(),
             kh: DefaultKeyHasher::default(),
             marker: Default::default(),
         }
@@ -76,6 +83,7 @@ impl<K: Hash + Eq, S: BuildHasher> SampledLFU<K, DefaultKeyHasher<K>, S> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(hasher),
+            keys: Vec::new(),
             kh: DefaultKeyHasher::default(),
             marker: Default::default(),
         }
@@ -91,6 +99,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>> SampledLFU<K, KH> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(DefaultHashBuilder::default()),
+            keys: Vec::new(),
             kh,
             marker: Default::default(),
         }
@@ -104,6 +113,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>> SampledLFU<K, KH> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(DefaultHashBuilder::default()),
+            keys: Vec::new(),
             kh,
             marker: Default::default(),
         }
@@ -124,6 +134,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
             max_cost: AtomicI64::new(max_cost),
             used: 0,
             key_costs: HashMap::with_hasher(hasher),
+            keys: Vec::new(),
             kh,
             marker: Default::default(),
         }
@@ -141,6 +152,12 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
         self.max_cost.load(Ordering::SeqCst)
     }
 
+    /// get the total cost of the entries in SampledLFU
+    #[inline]
+    pub fn used(&self) -> i64 {
+        self.used
+    }
+
     /// get the remain space of SampledLRU
     #[inline]
     pub fn room_left(&self, cost: i64) -> i64 {
@@ -148,11 +165,16 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
     }
 
     /// try to fill the SampledLFU by the given pairs.
+    ///
+    /// The pairs are taken in the order of the inner map, which is the same every time,
+    /// see [`sample_victims`] for the random sampling.
+    ///
+    /// [`sample_victims`]: struct.SampledLFU.html#method.sample_victims
     pub fn fill_sample(&mut self, mut pairs: Vec<(u64, i64)>) -> Vec<(u64, i64)> {
         if pairs.len() >= self.samples {
             pairs
         } else {
-            for (k, v) in &self.key_costs {
+            for (k, (v, _)) in &self.key_costs {
                 pairs.push((*k, *v));
                 if pairs.len() >= self.samples {
                     return pairs;
@@ -173,20 +195,114 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
         self.increment_hashed_key(kh, cost)
     }
 
+    /// Returns the cost of an entry by hashed key
+    #[inline]
+    pub fn cost_of_hashed_key(&self, kh: u64) -> Option<i64> {
+        self.key_costs.get(&kh).map(|(cost, _)| *cost)
+    }
+
     /// Put a hashed key and cost to SampledLFU
     #[inline]
     pub fn increment_hashed_key(&mut self, key: u64, cost: i64) {
-        self.key_costs.insert(key, cost);
-        self.used += cost
+        if !self.update_hashed_key(key, cost) {
+            self.key_costs.insert(key, (cost, self.keys.len()));
+            self.keys.push(key);
+            self.used += cost
+        }
     }
 
     /// Remove an entry from SampledLFU by hashed key
     #[inline]
     pub fn remove_hashed_key(&mut self, kh: u64) -> Option<i64> {
-        self.key_costs.remove(&kh).map(|cost| {
-            self.used -= cost;
-            cost
-        })
+        let (cost, idx) = self.key_costs.remove(&kh)?;
+        self.keys.swap_remove(idx);
+        if let Some(moved) = self.keys.get(idx) {
+            if let Some((_, moved_idx)) = self.key_costs.get_mut(moved) {
+                *moved_idx = idx;
+            }
+        }
+        self.used -= cost;
+        Some(cost)
+    }
+
+    /// Returns up to `samples` distinct entries chosen at random by `rng`,
+    /// as (hashed key, cost) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::lfu::sampled::SampledLFU;
+    /// use rand::thread_rng;
+    ///
+    /// let mut lfu = SampledLFU::<u64>::with_samples(100, 3);
+    /// (0..10).for_each(|kh| lfu.increment_hashed_key(kh, 1));
+    ///
+    /// let victims = lfu.sample_victims(&mut thread_rng());
+    /// assert_eq!(victims.len(), 3);
+    /// assert!(victims.iter().all(|(kh, cost)| *kh < 10 && *cost == 1));
+    /// ```
+    pub fn sample_victims<R: Rng + ?Sized>(&self, rng: &mut R) -> Vec<(u64, i64)> {
+        if self.keys.len() <= self.samples {
+            return self
+                .keys
+                .iter()
+                .map(|kh| (*kh, self.key_costs[kh].0))
+                .collect();
+        }
+
+        let mut victims = Vec::with_capacity(self.samples);
+        while victims.len() < self.samples {
+            let kh = self.keys[rng.gen_range(0..self.keys.len())];
+            if victims.iter().all(|(v, _)| *v != kh) {
+                victims.push((kh, self.key_costs[&kh].0));
+            }
+        }
+        victims
+    }
+
+    /// Removes the sampled entries with the lowest frequencies given by `estimator`, until
+    /// an entry of `cost` fits, returns the removed (hashed key, cost) pairs.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::lfu::sampled::SampledLFU;
+    /// use rand::thread_rng;
+    ///
+    /// let mut lfu = SampledLFU::<u64>::with_samples(10, 5);
+    /// (0..5).for_each(|kh| lfu.increment_hashed_key(kh, 2));
+    ///
+    /// // the frequency of a hashed key is the key itself, so 0 and 1 are the coldest
+    /// let evicted = lfu.evict_until_fits(4, &mut thread_rng(), |kh| kh);
+    /// assert_eq!(evicted, vec![(0, 2), (1, 2)]);
+    /// assert_eq!(lfu.room_left(4), 0);
+    /// ```
+    pub fn evict_until_fits<R, F>(
+        &mut self,
+        cost: i64,
+        rng: &mut R,
+        mut estimator: F,
+    ) -> Vec<(u64, i64)>
+    where
+        R: Rng + ?Sized,
+        F: FnMut(u64) -> u64,
+    {
+        let mut evicted = Vec::new();
+        while self.room_left(cost) < 0 {
+            let victim = self
+                .sample_victims(rng)
+                .into_iter()
+                .min_by_key(|(kh, _)| estimator(*kh));
+            match victim {
+                Some((kh, _)) => {
+                    if let Some(cost) = self.remove_hashed_key(kh) {
+                        evicted.push((kh, cost));
+                    }
+                }
+                None => break,
+            }
+        }
+        evicted
     }
 
     /// Remove an entry from SampledLFU by key
@@ -205,6 +321,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
     pub fn clear(&mut self) {
         self.used = 0;
         self.key_costs.clear();
+        self.keys.clear();
     }
 
     /// Update the cost by key. If the provided key in SampledLFU, then update it and return true, otherwise false.
@@ -223,7 +340,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
         // Evictions will be handled the next time a new item is added
         match self.key_costs.get_mut(&k) {
             None => false,
-            Some(prev) => {
+            Some((prev, _)) => {
                 let prev_val = *prev;
                 self.used += cost - prev_val;
                 *prev = cost;
@@ -246,7 +363,9 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, S: BuildHasher> SampledLFU<K, KH, S> {
 #[cfg(test)]
 mod test {
     use crate::lfu::sampled::SampledLFU;
+    use rand::thread_rng;
     use std::vec;
+    use std::vec::Vec;
 
     #[test]
     fn test_remove() {
@@ -308,4 +427,58 @@ mod test {
         let sample = l.fill_sample(sample[0..(sample.len() - 2)].to_vec());
         assert_eq!(4, sample.len())
     }
+
+    #[test]
+    fn test_sample_victims() {
+        let mut rng = thread_rng();
+        let mut l = SampledLFU::<u64>::new(100);
+        l.increment_hashed_key(1, 1);
+        l.increment_hashed_key(2, 2);
+        let mut sample = l.sample_victims(&mut rng);
+        sample.sort_unstable();
+        assert_eq!(sample, vec![(1, 1), (2, 2)]);
+
+        (3..=20).for_each(|kh| l.increment_hashed_key(kh, 1));
+        assert_eq!(l.remove_hashed_key(2), Some(2));
+        (2..=10).for_each(|kh| assert_eq!(l.remove_hashed_key(kh * 2), Some(1)));
+        assert_eq!(l.used, 10);
+
+        // every remaining key is sampled sooner or later, and never twice at once
+        let mut seen = Vec::new();
+        (0..1000).for_each(|_| {
+            let mut sample = l.sample_victims(&mut rng);
+            assert_eq!(sample.len(), 5);
+            sample.sort_unstable();
+            sample.dedup();
+            assert_eq!(sample.len(), 5);
+            sample.into_iter().for_each(|(kh, cost)| {
+                assert_eq!(kh % 2, 1);
+                assert_eq!(cost, 1);
+                seen.push(kh);
+            });
+        });
+        seen.sort_unstable();
+        seen.dedup();
+        assert_eq!(seen, (0..10).map(|i| i * 2 + 1).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_evict_until_fits() {
+        let mut rng = thread_rng();
+        let mut l = SampledLFU::<u64>::with_samples(64, 3);
+        (0..32).for_each(|kh| l.increment_hashed_key(kh, 2));
+        assert_eq!(l.room_left(0), 0);
+
+        let evicted = l.evict_until_fits(5, &mut rng, |kh| kh % 7);
+        assert_eq!(evicted.len(), 3);
+        assert!(evicted.iter().all(|(kh, cost)| *kh < 32 && *cost == 2));
+        assert_eq!(l.room_left(5), 1);
+        assert_eq!(l.keys.len(), 29);
+
+        // more than the max cost, all the keys are evicted
+        let evicted = l.evict_until_fits(65, &mut rng, |kh| kh);
+        assert_eq!(evicted.len(), 29);
+        assert_eq!(l.used, 0);
+        assert!(l.key_costs.is_empty());
+    }
 }
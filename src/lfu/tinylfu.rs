@@ -404,8 +404,43 @@ impl<K: Hash + Eq, KH: KeyHasher<K>> TinyLFU<K, KH> {
 }
 
 #[cfg(test)]
-mod test {
-    use crate::lfu::tinylfu::TinyLFU;
+pub(crate) mod test {
+    #[cfg(feature = "std")]
+    use crate::lfu::tinylfu::sketch::CountMinSketch;
+    use crate::lfu::tinylfu::{TinyLFU, TinyLFUBuilder};
+    use crate::lfu::KeyHasher;
+    use crate::KeyRef;
+    use core::borrow::Borrow;
+    use core::hash::{Hash, Hasher};
+    #[cfg(feature = "std")]
+    use rand::{rngs::StdRng, SeedableRng};
+    use rustc_hash::FxHasher;
+
+    // hashes the keys the same on every run, unlike the randomly seeded default hasher.
+    struct FixedKeyHasher;
+
+    impl KeyHasher<u64> for FixedKeyHasher {
+        fn hash_key<Q>(&self, key: &Q) -> u64
+        where
+            KeyRef<u64>: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let mut s = FxHasher::default();
+            key.hash(&mut s);
+            s.finish()
+        }
+    }
+
+    // the std sketch draws the seeds of its rows at random, seed it so that the keys collide
+    // the same way on every run.
+    #[cfg(feature = "std")]
+    pub(crate) fn seed_sketch<KH>(l: &mut TinyLFU<u64, KH>, size: u64) {
+        l.ctr = CountMinSketch::with_rng(size, &mut StdRng::seed_from_u64(0)).unwrap();
+    }
+
+    // the core sketch has no seeds.
+    #[cfg(not(feature = "std"))]
+    pub(crate) fn seed_sketch<KH>(_l: &mut TinyLFU<u64, KH>, _size: u64) {}
 
     #[test]
     fn test_increment() {
@@ -439,7 +474,12 @@ mod test {
 
     #[test]
     fn test_estimate() {
-        let mut l: TinyLFU<u64> = TinyLFU::new(8, 8, 0.01).unwrap();
+        let mut l = TinyLFUBuilder::new(8, 8)
+            .set_false_positive_ratio(0.01)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut l, 8);
         l.increment(&1);
         l.increment(&1);
         l.increment(&1);
@@ -452,6 +492,7 @@ mod test {
     #[test]
     fn test_estimate_hashed_keyed_key() {
         let mut l: TinyLFU<u64> = TinyLFU::new(8, 8, 0.01).unwrap();
+        seed_sketch(&mut l, 8);
         l.increment_hashed_key(1);
         l.increment_hashed_key(1);
         l.increment_hashed_key(1);
@@ -462,7 +503,12 @@ mod test {
 
     #[test]
     fn test_increment_keys() {
-        let mut l: TinyLFU<u64> = TinyLFU::new(16, 16, 0.01).unwrap();
+        let mut l = TinyLFUBuilder::new(16, 16)
+            .set_false_positive_ratio(0.01)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut l, 16);
 
         assert_eq!(l.samples, 16);
         l.increment_keys(&[&1, &2, &2, &3, &3, &3]);
@@ -475,6 +521,7 @@ mod test {
     #[test]
     fn test_increment_hashed_keys() {
         let mut l: TinyLFU<u64> = TinyLFU::new(16, 16, 0.01).unwrap();
+        seed_sketch(&mut l, 16);
 
         assert_eq!(l.samples, 16);
         l.increment_hashed_keys(&[1, 2, 2, 3, 3, 3]);
@@ -5,7 +5,6 @@
 //! I claim no additional copyright over the original implementation.
 use alloc::vec;
 use alloc::vec::Vec;
-use core::ptr::addr_of;
 
 const MASK: [u8; 8] = [1, 2, 4, 8, 16, 32, 64, 128];
 const LN_2: f64 = 0.69314718056;
@@ -92,23 +91,14 @@ impl Bloom {
 
     /// `set` sets the bit[idx] of bitset
     pub fn set(&mut self, idx: usize) {
-        let raw = (addr_of!(self.bitset[idx >> 6]) as *const u64) as u64;
-        let offset = ((idx % 64) >> 3) as u64;
-        let ptr = (raw + offset) as *mut u64;
-        unsafe {
-            *ptr |= MASK[idx % 8] as u64;
-        }
+        // The reference implementation sets `MASK[idx % 8]` on the byte at offset
+        // `(idx % 64) >> 3` of the word, which is bit `idx % 64` of the word.
+        self.bitset[idx >> 6] |= (MASK[idx % 8] as u64) << (((idx % 64) >> 3) << 3);
     }
 
     /// `is_set` checks if bit[idx] of bitset is set, returns true/false.
     pub fn is_set(&self, idx: usize) -> bool {
-        let raw = (addr_of!(self.bitset[idx >> 6]) as *const u64) as u64;
-        let offset = ((idx % 64) >> 3) as u64;
-        let ptr = (raw + offset) as *mut u64;
-        unsafe {
-            let r = (*ptr >> (idx % 8)) & 1;
-            r == 1
-        }
+        (self.bitset[idx >> 6] >> (idx % 64)) & 1 == 1
     }
 
     /// `add` adds hash of a key to the bloom filter
@@ -134,6 +124,29 @@ impl Bloom {
         true
     }
 
+    /// `size` makes Bloom filter with as bitset of size sz.
+    #[cfg(test)]
+    pub fn size(&mut self, sz: usize) {
+        let size = get_size(sz as u64);
+        self.bitset = vec![0; (size.size >> 6) as usize];
+        self.shift = 64 - size.exp;
+        self.size = size.size - 1;
+    }
+
+    /// `size_exp` returns the exponent of the size of the bitset.
+    #[cfg(test)]
+    pub fn size_exp(&self) -> u64 {
+        64 - self.shift
+    }
+
+    /// `total_size` returns the total size of the bloom filter.
+    #[cfg(test)]
+    pub fn total_size(&self) -> usize {
+        // The bl struct has 5 members and each one is 8 byte. The bitset is a
+        // uint64 byte slice.
+        self.bitset.len() * 8 + 5 * 8
+    }
+
     /// `contains_or_add` only Adds hash, if it's not present in the bloomfilter.
     /// Returns true if hash was added.
     /// Returns false if hash was already registered in the bloomfilter.
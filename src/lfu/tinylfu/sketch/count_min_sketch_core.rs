@@ -24,7 +24,8 @@ impl CountMinSketch {
             return Err(TinyLFUError::InvalidCountMinWidth(ctrs));
         }
 
-        let ctrs = next_power_of_2(ctrs);
+        // a row packs two counters in a byte, so it has two counters at least
+        let ctrs = next_power_of_2(ctrs).max(2);
         let hctrs = ctrs / 2;
 
         let this = Self {
@@ -89,4 +90,12 @@ mod test {
 
         assert_eq!(cm.estimate(hash), 2);
     }
+
+    #[test]
+    fn test_count_min_sketch_one_counter() {
+        let mut cm = CountMinSketch::new(1).unwrap();
+        cm.increment(1);
+
+        assert_eq!(cm.estimate(1), 1);
+    }
 }
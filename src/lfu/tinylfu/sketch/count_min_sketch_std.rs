@@ -3,12 +3,10 @@
 //! This file is a mechanical translation of the reference Golang code, available at https://github.com/dgraph-io/ristretto/blob/master/sketch.go
 //!
 //! I claim no additional copyright over the original implementation.
-use std::time::{SystemTime, UNIX_EPOCH};
-
 use crate::lfu::tinylfu::error::TinyLFUError;
 use crate::lfu::tinylfu::sketch::{next_power_of_2, CountMinRow, DEPTH};
 use alloc::vec::Vec;
-use rand::{rngs::StdRng, Rng, SeedableRng};
+use rand::{thread_rng, Rng};
 
 /// `CountMinSketch` is a small conservative-update count-min sketch
 /// implementation with 4-bit counters
@@ -20,18 +18,22 @@ pub(crate) struct CountMinSketch {
 
 impl CountMinSketch {
     pub(crate) fn new(ctrs: u64) -> Result<Self, TinyLFUError> {
+        Self::with_rng(ctrs, &mut thread_rng())
+    }
+
+    /// `with_rng` creates a sketch whose row seeds are drawn from `source`.
+    pub(crate) fn with_rng<R: Rng + ?Sized>(
+        ctrs: u64,
+        source: &mut R,
+    ) -> Result<Self, TinyLFUError> {
         if ctrs < 1 {
             return Err(TinyLFUError::InvalidCountMinWidth(ctrs));
         }
 
-        let ctrs = next_power_of_2(ctrs);
+        // a row packs two counters in a byte, so it has two counters at least
+        let ctrs = next_power_of_2(ctrs).max(2);
         let hctrs = ctrs / 2;
 
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .expect("system time before Unix epoch");
-        let mut source = StdRng::seed_from_u64(timestamp.as_nanos() as u64);
-
         let seeds: Vec<u64> = {
             (0..DEPTH).map(|_| {
                 source.gen::<u64>()
@@ -52,7 +54,7 @@ impl CountMinSketch {
         let mask = self.mask;
         (0..DEPTH).for_each(|i| {
             let seed = self.seeds[i];
-            self.rows[i].increment((hashed ^ seed) & mask);
+            self.rows[i].increment(index(hashed, seed, mask));
         })
     }
 
@@ -62,7 +64,7 @@ impl CountMinSketch {
         let mut min = 255u8;
         (0..DEPTH).for_each(|i| {
             let seed = self.seeds[i];
-            let val = self.rows[i].get((hashed ^ seed) & mask);
+            let val = self.rows[i].get(index(hashed, seed, mask));
             if val < min {
                 min = val;
             }
@@ -82,15 +84,30 @@ impl CountMinSketch {
     }
 }
 
+/// `index` returns the counter position of the hash in the row with the given seed.
+///
+/// The seed must be mixed into the hash before masking, otherwise two hashes which collide
+/// in one row would collide in every row and taking the minimum would not help.
+#[inline]
+fn index(hashed: u64, seed: u64, mask: u64) -> u64 {
+    ((hashed ^ seed).wrapping_mul(0x9E37_79B9_7F4A_7C15) >> 32) & mask
+}
+
 #[cfg(test)]
 mod test {
     use super::*;
+    use rand::{rngs::StdRng, SeedableRng};
     use std::format;
 
     #[test]
     fn test_count_min_sketch() {
         let s = CountMinSketch::new(5).unwrap();
         assert_eq!(7u64, s.mask);
+
+        let mut s = CountMinSketch::new(1).unwrap();
+        assert_eq!(1u64, s.mask);
+        s.increment(1);
+        assert_eq!(s.estimate(1), 1);
     }
 
     #[test]
@@ -109,7 +126,7 @@ mod test {
 
     #[test]
     fn test_count_min_sketch_estimate() {
-        let mut s = CountMinSketch::new(16).unwrap();
+        let mut s = CountMinSketch::with_rng(16, &mut StdRng::seed_from_u64(0)).unwrap();
         s.increment(1);
         s.increment(1);
 
@@ -1,15 +1,27 @@
 mod error;
 pub use error::WTinyLFUError;
+mod weighted;
+pub use weighted::WeightedWTinyLFUCache;
 
 use crate::lfu::{
-    tinylfu::{TinyLFUError, TinyLFU, TinyLFUBuilder, DEFAULT_FALSE_POSITIVE_RATIO},
+    tinylfu::{TinyLFU, TinyLFUBuilder, TinyLFUError, DEFAULT_FALSE_POSITIVE_RATIO},
     DefaultKeyHasher, KeyHasher,
 };
 use crate::lru::{SegmentedCache, SegmentedCacheBuilder};
-use crate::{Cache, DefaultHashBuilder, KeyRef, LRUCache, PutResult};
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::entry::sealed::EntryCache;
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, Entry, EvictionReason, KeyRef,
+    LRUCache, OnEvictCallback, PutResult,
+};
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::hash::{BuildHasher, Hash};
 use core::marker::PhantomData;
+use core::ptr::NonNull;
 
 const DEFAULT_WINDOW_CACHE_SIZE_RATIO: f64 = 0.01;
 const DEFAULT_HOT_ITEMS_CACHE_SIZE_RATIO: f64 = 0.80;
@@ -23,6 +35,7 @@ pub struct WTinyLFUCacheBuilder<
     FH = DefaultHashBuilder,
     RH = DefaultHashBuilder,
     WH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     samples: usize,
     window_cache_size: usize,
@@ -33,6 +46,10 @@ pub struct WTinyLFUCacheBuilder<
     main_cache_protected_hasher: Option<FH>,
     main_cache_probationary_hasher: Option<RH>,
     false_positive_ratio: Option<f64>,
+    on_evict: Option<E>,
+    adaptive_window: bool,
+    record_on_put: bool,
+    record_on_peek: bool,
     marker: PhantomData<K>,
 }
 
@@ -48,6 +65,10 @@ impl<K: Hash + Eq> Default for WTinyLFUCacheBuilder<K> {
             main_cache_probationary_hasher: Some(DefaultHashBuilder::default()),
             key_hasher: Some(DefaultKeyHasher::default()),
             false_positive_ratio: Some(DEFAULT_FALSE_POSITIVE_RATIO),
+            on_evict: None,
+            adaptive_window: false,
+            record_on_put: true,
+            record_on_peek: false,
             marker: Default::default(),
         }
     }
@@ -69,8 +90,14 @@ impl<K: Hash + Eq> WTinyLFUCacheBuilder<K> {
     }
 }
 
-impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: BuildHasher>
-    WTinyLFUCacheBuilder<K, KH, FH, RH, WH>
+impl<
+        K: Hash + Eq,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > WTinyLFUCacheBuilder<K, KH, FH, RH, WH, E>
 {
     /// Set the samples of TinyLFU
     pub fn set_samples(self, samples: usize) -> Self {
@@ -84,6 +111,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_protected_hasher: self.main_cache_protected_hasher,
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -100,6 +131,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -116,6 +151,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -132,6 +171,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -148,6 +191,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: Some(fpr),
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -156,7 +203,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
     pub fn set_window_hasher<NWH: BuildHasher>(
         self,
         hasher: NWH,
-    ) -> WTinyLFUCacheBuilder<K, KH, FH, RH, NWH> {
+    ) -> WTinyLFUCacheBuilder<K, KH, FH, RH, NWH, E> {
         WTinyLFUCacheBuilder {
             samples: self.samples,
             window_cache_size: self.window_cache_size,
@@ -167,6 +214,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -175,7 +226,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
     pub fn set_protected_hasher<NFH: BuildHasher>(
         self,
         hasher: NFH,
-    ) -> WTinyLFUCacheBuilder<K, KH, NFH, RH, WH> {
+    ) -> WTinyLFUCacheBuilder<K, KH, NFH, RH, WH, E> {
         WTinyLFUCacheBuilder {
             samples: self.samples,
             window_cache_size: self.window_cache_size,
@@ -186,6 +237,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             key_hasher: self.key_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -194,7 +249,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
     pub fn set_probationary_hasher<NRH: BuildHasher>(
         self,
         hasher: NRH,
-    ) -> WTinyLFUCacheBuilder<K, KH, FH, NRH, WH> {
+    ) -> WTinyLFUCacheBuilder<K, KH, FH, NRH, WH, E> {
         WTinyLFUCacheBuilder {
             samples: self.samples,
             window_cache_size: self.window_cache_size,
@@ -205,6 +260,10 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             key_hasher: self.key_hasher,
             main_cache_probationary_hasher: Some(hasher),
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
             marker: self.marker,
         }
     }
@@ -213,7 +272,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
     pub fn set_key_hasher<NKH: KeyHasher<K>>(
         self,
         hasher: NKH,
-    ) -> WTinyLFUCacheBuilder<K, NKH, FH, RH, WH> {
+    ) -> WTinyLFUCacheBuilder<K, NKH, FH, RH, WH, E> {
         WTinyLFUCacheBuilder {
             samples: self.samples,
             window_cache_size: self.window_cache_size,
@@ -224,6 +283,181 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
             key_hasher: Some(hasher),
             main_cache_probationary_hasher: self.main_cache_probationary_hasher,
             false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
+            marker: self.marker,
+        }
+    }
+
+    /// Set the callback which is invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry moves between the window cache and the main
+    /// cache, it is invoked once the entry is evicted, rejected by the admission policy,
+    /// removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, OnEvictCallback, WTinyLFUCache, WTinyLFUCacheBuilder};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = WTinyLFUCacheBuilder::<u64>::new(1, 2, 2, 5)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize::<u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// // 1 is moved from the window cache to the main cache, the callback is not invoked
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 0);
+    ///
+    /// cache.remove(&1);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    ///
+    /// cache.purge();
+    /// assert_eq!(counter.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(
+        self,
+        cb: NE,
+    ) -> WTinyLFUCacheBuilder<K, KH, FH, RH, WH, NE> {
+        WTinyLFUCacheBuilder {
+            samples: self.samples,
+            window_cache_size: self.window_cache_size,
+            main_cache_protected_size: self.main_cache_protected_size,
+            main_cache_probationary_size: self.main_cache_probationary_size,
+            window_cache_hasher: self.window_cache_hasher,
+            main_cache_protected_hasher: self.main_cache_protected_hasher,
+            main_cache_probationary_hasher: self.main_cache_probationary_hasher,
+            key_hasher: self.key_hasher,
+            false_positive_ratio: self.false_positive_ratio,
+            on_evict: Some(cb),
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
+            marker: self.marker,
+        }
+    }
+
+    /// Set whether the size of the window cache adapts to the workload. Default is `false`.
+    ///
+    /// Like the adaptive W-TinyLFU of [Caffeine], the cache samples its hit rate over periods of
+    /// ten times its capacity, and climbs the hill of the hit rate: the capacity keeps moving
+    /// between the window cache and the main cache in the same direction while the hit rate
+    /// improves, and turns back when it gets worse. The step decays, so the sizes settle down
+    /// for a stable workload, and restarts when the hit rate changes sharply while the sizes
+    /// are not moving.
+    ///
+    /// The sum of the capacities is not changed, the entries are moved between the window cache
+    /// and the main cache instead of being evicted. The current size of the window cache is
+    /// returned by [`window_cache_cap`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, WTinyLFUCacheBuilder};
+    ///
+    /// let mut cache = WTinyLFUCacheBuilder::<u64>::new(1, 80, 19, 1000)
+    ///     .set_adaptive_window(true)
+    ///     .finalize::<u64>()
+    ///     .unwrap();
+    ///
+    /// // a recency-heavy workload, every key is read again shortly after it is written
+    /// let mut lookup = |k: u64| {
+    ///     for key in [k, k.saturating_sub(k * 37 % 60)] {
+    ///         if cache.get(&key).is_none() {
+    ///             cache.put(key, key);
+    ///         }
+    ///     }
+    ///     cache.window_cache_cap()
+    /// };
+    /// for k in 0..100_000u64 {
+    ///     lookup(k);
+    /// }
+    ///
+    /// // the window cache grows until each LRU of the main cache is left one slot, and settles
+    /// for k in 100_000..120_000u64 {
+    ///     assert_eq!(lookup(k), 98);
+    /// }
+    /// assert_eq!(cache.cap(), 100);
+    /// ```
+    ///
+    /// [Caffeine]: https://github.com/ben-manes/caffeine/wiki/Efficiency
+    /// [`window_cache_cap`]: struct.WTinyLFUCache.html#method.window_cache_cap
+    pub fn set_adaptive_window(self, adaptive: bool) -> Self {
+        Self {
+            samples: self.samples,
+            window_cache_size: self.window_cache_size,
+            main_cache_protected_size: self.main_cache_protected_size,
+            main_cache_probationary_size: self.main_cache_probationary_size,
+            window_cache_hasher: self.window_cache_hasher,
+            main_cache_protected_hasher: self.main_cache_protected_hasher,
+            main_cache_probationary_hasher: self.main_cache_probationary_hasher,
+            key_hasher: self.key_hasher,
+            false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: adaptive,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
+            marker: self.marker,
+        }
+    }
+
+    /// Set whether `put` records the key in the TinyLFU. Default is `true`.
+    ///
+    /// The admission of the main cache compares the frequencies of the keys, so if the writes
+    /// are not recorded, a key which is written many times but never read keeps being rejected.
+    /// Disable it to only count the reads, e.g. when most writes are one-off fills.
+    pub fn set_record_on_put(self, record: bool) -> Self {
+        Self {
+            samples: self.samples,
+            window_cache_size: self.window_cache_size,
+            main_cache_protected_size: self.main_cache_protected_size,
+            main_cache_probationary_size: self.main_cache_probationary_size,
+            window_cache_hasher: self.window_cache_hasher,
+            main_cache_protected_hasher: self.main_cache_protected_hasher,
+            main_cache_probationary_hasher: self.main_cache_probationary_hasher,
+            key_hasher: self.key_hasher,
+            false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: record,
+            record_on_peek: self.record_on_peek,
+            marker: self.marker,
+        }
+    }
+
+    /// Set whether `peek_mut` and `peek_recorded` record the key in the TinyLFU. Default is
+    /// `false`.
+    ///
+    /// The recorded peeks count for the admission, but still do not change the order of the LRUs.
+    /// `peek` never records the key, as it only borrows the cache immutably, use `peek_recorded`
+    /// instead.
+    pub fn set_record_on_peek(self, record: bool) -> Self {
+        Self {
+            samples: self.samples,
+            window_cache_size: self.window_cache_size,
+            main_cache_protected_size: self.main_cache_protected_size,
+            main_cache_probationary_size: self.main_cache_probationary_size,
+            window_cache_hasher: self.window_cache_hasher,
+            main_cache_protected_hasher: self.main_cache_protected_hasher,
+            main_cache_probationary_hasher: self.main_cache_probationary_hasher,
+            key_hasher: self.key_hasher,
+            false_positive_ratio: self.false_positive_ratio,
+            on_evict: self.on_evict,
+            adaptive_window: self.adaptive_window,
+            record_on_put: self.record_on_put,
+            record_on_peek: record,
             marker: self.marker,
         }
     }
@@ -231,10 +465,59 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
     /// Finalize the builder to [`TinyLFUCache`]
     ///
     /// [`TinyLFUCache`]: struct.TinyLFUCache.html
-    pub fn finalize<V>(self) -> Result<WTinyLFUCache<K, V, KH, FH, RH, WH>, WTinyLFUError>
+    #[allow(clippy::type_complexity)]
+    pub fn finalize<V>(self) -> Result<WTinyLFUCache<K, V, KH, FH, RH, WH, E>, WTinyLFUError>
     where
         K: Eq,
     {
+        self.validate()?;
+
+        let lru = LRUCache::with_hasher(self.window_cache_size, self.window_cache_hasher.unwrap())
+            .unwrap();
+
+        let slru = SegmentedCacheBuilder::new(
+            self.main_cache_probationary_size,
+            self.main_cache_protected_size,
+        )
+        .set_probationary_hasher(self.main_cache_probationary_hasher.unwrap())
+        .set_protected_hasher(self.main_cache_protected_hasher.unwrap())
+        .finalize()
+        .unwrap();
+
+        let size = self.window_cache_size
+            + self.main_cache_protected_size
+            + self.main_cache_probationary_size;
+
+        let tinylfu = Self::build_tinylfu(
+            self.key_hasher.unwrap(),
+            size,
+            self.samples,
+            self.false_positive_ratio.unwrap(),
+        )?;
+
+        let climber = if self.adaptive_window {
+            Some(HillClimber::new(
+                size,
+                self.main_cache_protected_size as f64
+                    / (self.main_cache_protected_size + self.main_cache_probationary_size) as f64,
+            ))
+        } else {
+            None
+        };
+
+        Ok(WTinyLFUCache {
+            tinylfu,
+            lru,
+            slru,
+            climber,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    fn validate(&self) -> Result<(), WTinyLFUError> {
         if self.window_cache_size == 0 {
             return Err(WTinyLFUError::InvalidWindowCacheSize(0));
         }
@@ -255,25 +538,17 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
         if fp_ratio <= 0.0 || fp_ratio >= 1.0 {
             return Err(WTinyLFUError::InvalidFalsePositiveRatio(fp_ratio));
         }
+        Ok(())
+    }
 
-        let lru = LRUCache::with_hasher(self.window_cache_size, self.window_cache_hasher.unwrap())
-            .unwrap();
-
-        let slru = SegmentedCacheBuilder::new(
-            self.main_cache_probationary_size,
-            self.main_cache_protected_size,
-        )
-        .set_probationary_hasher(self.main_cache_probationary_hasher.unwrap())
-        .set_protected_hasher(self.main_cache_protected_hasher.unwrap())
-        .finalize()
-        .unwrap();
-
-        let size = self.window_cache_size
-            + self.main_cache_protected_size
-            + self.main_cache_probationary_size;
-
-        let tinylfu = TinyLFUBuilder::new(size, self.samples)
-            .set_key_hasher(self.key_hasher.unwrap())
+    fn build_tinylfu(
+        key_hasher: KH,
+        size: usize,
+        samples: usize,
+        fp_ratio: f64,
+    ) -> Result<TinyLFU<K, KH>, WTinyLFUError> {
+        TinyLFUBuilder::new(size, samples)
+            .set_key_hasher(key_hasher)
             .set_false_positive_ratio(fp_ratio)
             .finalize()
             .map_err(|e| match e {
@@ -282,9 +557,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
                 TinyLFUError::InvalidFalsePositiveRatio(v) => {
                     WTinyLFUError::InvalidFalsePositiveRatio(v)
                 }
-            })?;
-
-        Ok(WTinyLFUCache { tinylfu, lru, slru })
+            })
     }
 }
 
@@ -294,7 +567,7 @@ impl<K: Hash + Eq, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Build
 /// # Example
 /// ```rust
 /// use caches::{WTinyLFUCache, PutResult, Cache};
-/// 
+///
 /// let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
 /// assert_eq!(cache.cap(), 5);
 /// assert_eq!(cache.window_cache_cap(), 1);
@@ -361,10 +634,95 @@ pub struct WTinyLFUCache<
     FH = DefaultHashBuilder,
     RH = DefaultHashBuilder,
     WH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     tinylfu: TinyLFU<K, KH>,
     lru: LRUCache<K, V, WH>,
     slru: SegmentedCache<K, V, FH, RH>,
+    // only used by the adaptive window mode
+    climber: Option<HillClimber>,
+    record_on_put: bool,
+    record_on_peek: bool,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+// The hit rate changes more than this restart the climbing with the full step.
+const HILL_CLIMBER_RESTART_THRESHOLD: f64 = 0.05;
+// The full step is this ratio of the capacity of the cache.
+const HILL_CLIMBER_STEP_RATIO: f64 = 0.0625;
+// The step decays by this ratio after every sample period.
+const HILL_CLIMBER_STEP_DECAY_RATE: f64 = 0.98;
+// The sample period is this multiple of the capacity of the cache.
+const HILL_CLIMBER_SAMPLE_RATIO: usize = 10;
+
+// Samples the hit rate of the cache, and tells how much capacity should move between
+// the window cache and the main cache, the same as the hill climber of Caffeine.
+struct HillClimber {
+    sample_size: usize,
+    hits: usize,
+    misses: usize,
+    prev_hit_rate: f64,
+    // the next adjustment, the positive step grows the window cache.
+    step: f64,
+    full_step: f64,
+    // whether the last adjustment changed the window cache capacity
+    moved: bool,
+    // the share of the protected LRU in the main cache
+    protected_ratio: f64,
+}
+
+impl HillClimber {
+    fn new(cap: usize, protected_ratio: f64) -> Self {
+        let full_step = cap as f64 * HILL_CLIMBER_STEP_RATIO;
+        Self {
+            sample_size: cap * HILL_CLIMBER_SAMPLE_RATIO,
+            hits: 0,
+            misses: 0,
+            prev_hit_rate: 0.0,
+            // the window cache starts small, so the climbing starts by growing it
+            step: full_step,
+            full_step,
+            moved: false,
+            protected_ratio,
+        }
+    }
+
+    // Records a lookup, returns the adjustment of the window cache capacity at the end of
+    // a sample period.
+    fn record(&mut self, hit: bool) -> Option<isize> {
+        if hit {
+            self.hits += 1;
+        } else {
+            self.misses += 1;
+        }
+
+        let requests = self.hits + self.misses;
+        if requests < self.sample_size {
+            return None;
+        }
+
+        let hit_rate = self.hits as f64 / requests as f64;
+        let change = hit_rate - self.prev_hit_rate;
+        let amount = if change >= 0.0 { self.step } else { -self.step };
+        // `f64::abs` is not available without std
+        let sharp =
+            !(-HILL_CLIMBER_RESTART_THRESHOLD..HILL_CLIMBER_RESTART_THRESHOLD).contains(&change);
+        // a sharp change right after an adjustment is caused by the adjustment itself,
+        // restarting on it keeps the window swinging instead of settling.
+        self.step = if sharp && !self.moved && amount >= 0.0 {
+            self.full_step
+        } else if sharp && !self.moved {
+            -self.full_step
+        } else {
+            amount * HILL_CLIMBER_STEP_DECAY_RATE
+        };
+
+        self.prev_hit_rate = hit_rate;
+        self.hits = 0;
+        self.misses = 0;
+        Some(amount as isize)
+    }
 }
 
 impl<K: Hash + Eq, V> WTinyLFUCache<K, V, DefaultKeyHasher<K>> {
@@ -407,67 +765,241 @@ impl<K: Hash + Eq, V> WTinyLFUCache<K, V, DefaultKeyHasher<K>> {
     pub fn builder() -> WTinyLFUCacheBuilder<K> {
         WTinyLFUCacheBuilder::default()
     }
+}
 
+impl<
+        K: Hash + Eq,
+        V,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > WTinyLFUCache<K, V, KH, FH, RH, WH, E>
+{
+    /// Creates a WTinyLFUCache according to [`WTinyLFUCacheBuilder`]
     ///
+    /// [`WTinyLFUCacheBuilder`]: struct.WTinyLFUCacheBuilder.html
+    pub fn from_builder(
+        builder: WTinyLFUCacheBuilder<K, KH, FH, RH, WH, E>,
+    ) -> Result<Self, WTinyLFUError> {
+        builder.finalize()
+    }
+
+    /// Gets the given key's corresponding [`Entry`] in the cache for in-place manipulation.
+    /// Looking up the entry counts as a `get` of the key, and a vacant entry is inserted
+    /// into the window cache just like `put`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, PutResult, WTinyLFUCache};
+    /// let mut cache = WTinyLFUCache::with_sizes(1, 1, 1, 5).unwrap();
+    ///
+    /// cache.entry(1).or_insert(1);
+    /// cache.entry(1).and_modify(|v| *v += 1).or_insert(1);
+    /// assert_eq!(cache.peek(&1), Some(&2));
+    ///
+    /// if let Entry::Vacant(ent) = cache.entry(2) {
+    ///     let (v, rst) = ent.insert(1);
+    ///     assert_eq!(*v, 1);
+    ///     // 1 is moved from the window cache to the main cache
+    ///     assert_eq!(rst, PutResult::Put);
+    /// }
+    /// assert_eq!(cache.window_cache_len(), 1);
+    /// assert_eq!(cache.main_cache_len(), 1);
+    /// ```
+    ///
+    /// [`Entry`]: ../enum.Entry.html
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, V, Self> {
+        Entry::new(self, k)
+    }
+
+    /// Returns a reference to the value of the key like `peek`, without updating the LRU lists,
+    /// but records the key in the TinyLFU when [`set_record_on_peek`] is enabled.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, WTinyLFUCacheBuilder};
+    ///
+    /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+    ///     .set_record_on_peek(true)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.peek_recorded(&1), Some(&"a"));
+    /// assert_eq!(cache.peek_recorded(&2), None);
+    /// ```
+    ///
+    /// [`set_record_on_peek`]: struct.WTinyLFUCacheBuilder.html#method.set_record_on_peek
+    pub fn peek_recorded<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.record_on_peek {
+            self.record(k);
+        }
+        self.peek(k)
+    }
+
+    /// Returns the number of entries in the window cache.
     pub fn window_cache_len(&self) -> usize {
         self.lru.len()
     }
 
-    ///
+    /// Returns the capacity of the window cache, it changes in the adaptive window mode.
     pub fn window_cache_cap(&self) -> usize {
         self.lru.cap()
     }
 
-    ///
+    /// Returns the number of entries in the main cache.
     pub fn main_cache_len(&self) -> usize {
         self.slru.len()
     }
 
-    ///
+    /// Returns the capacity of the main cache.
     pub fn main_cache_cap(&self) -> usize {
         self.slru.cap()
     }
-}
 
-impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: BuildHasher>
-    WTinyLFUCache<K, V, KH, FH, RH, WH>
-{
-    /// Creates a WTinyLFUCache according to [`WTinyLFUCacheBuilder`]
-    ///
-    /// [`WTinyLFUCacheBuilder`]: struct.WTinyLFUCacheBuilder.html
-    pub fn from_builder(
-        builder: WTinyLFUCacheBuilder<K, KH, FH, RH, WH>,
-    ) -> Result<Self, WTinyLFUError> {
-        builder.finalize()
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, WTinyLFUCache};
+        /// let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(1, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 1);
+        /// assert_eq!(stats.updates(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, WTinyLFUCache};
+        /// let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
     }
-}
 
-impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: BuildHasher>
-    Cache<K, V> for WTinyLFUCache<K, V, KH, FH, RH, WH>
-{
-    /// Puts a key-value pair into cache, returns a [`PutResult`].
-    ///
-    /// # Example
-    ///
-    /// ```
-    /// use caches::{Cache, WTinyLFUCache};
-    /// use caches::PutResult;
-    /// let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
-    ///
-    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
-    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
-    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
-    /// assert_eq!(PutResult::Put, cache.put(3, "c"));
-    ///
-    /// assert_eq!(cache.get(&1), Some(&"a"));
-    /// assert_eq!(cache.get(&2), Some(&"beta"));
-    /// ```
-    ///
-    /// [`PutResult`]: struct.PutResult.html
-    fn put(&mut self, k: K, v: V) -> PutResult<K, V>
+    /// Records an access of the key in the TinyLFU.
+    #[inline]
+    fn record<Q>(&mut self, k: &Q)
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
     {
+        self.tinylfu.try_reset();
+        self.tinylfu.increment(k);
+    }
+
+    /// Puts an entry which passed the admission into the main cache.
+    fn admit(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.slru.put(k, v);
+        if let PutResult::Evicted { key, value } = &rst {
+            self.cb(key, value, EvictionReason::Capacity);
+        }
+        rst
+    }
+
+    /// Records a lookup for the adaptive window mode, and moves the capacity between the window
+    /// cache and the main cache at the end of a sample period.
+    fn climb(&mut self, hit: bool) {
+        let adjustment = match self.climber.as_mut().and_then(|c| c.record(hit)) {
+            Some(adjustment) => adjustment,
+            None => return,
+        };
+
+        let window_cap = self.lru.cap();
+        if adjustment > 0 {
+            self.grow_window(adjustment as usize);
+        } else if adjustment < 0 {
+            self.shrink_window(adjustment.unsigned_abs());
+        }
+
+        if let Some(climber) = self.climber.as_mut() {
+            climber.moved = self.lru.cap() != window_cap;
+        }
+    }
+
+    /// Moves `delta` of the capacity of the main cache to the window cache, the entries out of
+    /// the new probationary capacity are moved to the window cache.
+    fn grow_window(&mut self, delta: usize) {
+        // both of the LRUs of the main cache keep at least one slot
+        let delta = delta.min(self.slru.cap() - 2);
+        if delta == 0 {
+            return;
+        }
+
+        let protected_cap = self.main_protected_cap(self.slru.cap() - delta);
+        self.slru
+            .shift_cap_to_probationary(self.slru.protected_cap().saturating_sub(protected_cap));
+
+        let ents = self.slru.shrink_probationary(delta);
+        self.lru.set_cap(self.lru.cap() + delta);
+        for ent in ents {
+            self.lru.put_box(ent);
+        }
+    }
+
+    /// Moves `delta` of the capacity of the window cache to the main cache, the entries out of
+    /// the new window capacity are moved to the probationary LRU of the main cache.
+    fn shrink_window(&mut self, delta: usize) {
+        let window_cap = self.lru.cap();
+        let delta = delta.min(window_cap - 1);
+        if delta == 0 {
+            return;
+        }
+
+        let mut ents = Vec::new();
+        while self.lru.len() > window_cap - delta {
+            ents.push(self.lru.remove_lru_in().unwrap());
+        }
+        self.lru.set_cap(window_cap - delta);
+        self.slru.grow_probationary_with(delta, ents);
+
+        let protected_cap = self.main_protected_cap(self.slru.cap());
+        self.slru
+            .shift_cap_to_protected(protected_cap.saturating_sub(self.slru.protected_cap()));
+    }
+
+    /// Puts the entry into the cache without recording the key in the TinyLFU.
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
         #[cfg(any(feature = "nightly", feature = "nightly-core"))]
-        let new_key_ref = &KeyRef {k: &k};
+        let new_key_ref = &KeyRef { k: &k };
 
         #[cfg(not(any(feature = "nightly", feature = "nightly-core")))]
         let new_key_ref = &k;
@@ -475,36 +1007,44 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
         match self.lru.remove(new_key_ref) {
             None => {
                 if self.slru.contains(new_key_ref) {
+                    if let Some(ref cb) = self.on_evict {
+                        if let Some(old) = self.slru.peek(new_key_ref) {
+                            cb.on_evict_with_reason(&k, old, EvictionReason::Replaced);
+                        }
+                    }
+                    self.stats.record_update();
                     return self.slru.put(k, v);
                 }
 
+                self.stats.record_insert();
                 match self.lru.put(k, v) {
                     PutResult::Put => PutResult::Put,
                     PutResult::Update(v) => PutResult::Update(v),
                     PutResult::Evicted { key, value } => {
                         #[cfg(any(feature = "nightly", feature = "nightly-core"))]
-                        let evicted_key_ref = &KeyRef{k: &key};
+                        let evicted_key_ref = &KeyRef { k: &key };
 
                         #[cfg(not(any(feature = "nightly", feature = "nightly-core")))]
                         let evicted_key_ref = &key;
 
                         if self.slru.len() < self.slru.cap() {
-                            return self.slru.put(key, value);
+                            return self.admit(key, value);
                         }
 
                         match self.slru.peek_lru_from_probationary() {
-                            None => self.slru.put(key, value),
+                            None => self.admit(key, value),
                             Some((lruk, _)) => {
                                 #[cfg(any(feature = "nightly", feature = "nightly-core"))]
-                                    let lru_key_ref = &KeyRef{k: lruk};
+                                let lru_key_ref = &KeyRef { k: lruk };
 
                                 #[cfg(not(any(feature = "nightly", feature = "nightly-core")))]
-                                    let lru_key_ref = lruk;
+                                let lru_key_ref = lruk;
 
                                 if self.tinylfu.lt(evicted_key_ref, lru_key_ref) {
+                                    self.cb(&key, &value, EvictionReason::Rejected);
                                     PutResult::Evicted { key, value }
                                 } else {
-                                    self.slru.put(key, value)
+                                    self.admit(key, value)
                                 }
                             }
                         }
@@ -515,9 +1055,15 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
                 }
             }
             Some(old) => {
+                self.cb(&k, &old, EvictionReason::Replaced);
+                self.stats.record_update();
                 if self.slru.protected_len() >= self.slru.protected_cap() {
                     let ent = self.slru.remove_lru_from_protected().unwrap();
-                    self.lru.put(ent.0, ent.1);
+                    // the window cache has room for the entry because the key was just
+                    // removed from it, we still keep this for good measure.
+                    if let PutResult::Evicted { key, value } = self.lru.put(ent.0, ent.1) {
+                        self.cb(&key, &value, EvictionReason::Capacity);
+                    }
                 }
 
                 self.slru.put_protected(k, v);
@@ -526,6 +1072,52 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
         }
     }
 
+    /// Returns the capacity of the protected LRU for the main cache of the given capacity,
+    /// keeping the ratio between the protected LRU and the probationary LRU.
+    fn main_protected_cap(&self, main_cap: usize) -> usize {
+        let ratio = self.climber.as_ref().map_or(0.0, |c| c.protected_ratio);
+        ((main_cap as f64 * ratio) as usize)
+            .max(1)
+            .min(main_cap - 1)
+    }
+}
+
+impl<
+        K: Hash + Eq,
+        V,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > Cache<K, V> for WTinyLFUCache<K, V, KH, FH, RH, WH, E>
+{
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, WTinyLFUCache};
+    /// use caches::PutResult;
+    /// let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
+    /// assert_eq!(PutResult::Put, cache.put(3, "c"));
+    ///
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// assert_eq!(cache.get(&2), Some(&"beta"));
+    /// ```
+    ///
+    /// [`PutResult`]: struct.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if self.record_on_put {
+            self.record(&KeyRef { k: &k });
+        }
+        self.put_in(k, v)
+    }
+
     /// Returns a reference to the value of the key in the cache or `None`.
     ///
     /// # Example
@@ -541,15 +1133,24 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
     ///
     /// assert_eq!(cache.get(&"banana"), Some(&6));
     /// ```
-    fn get<'a, Q>(&mut self, k: &'a Q) -> Option<&'a V>
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.tinylfu.try_reset();
-        self.tinylfu.increment(k);
+        if self.climber.is_some() {
+            let hit = self.lru.contains(k) || self.slru.contains(k);
+            self.climb(hit);
+        }
+
+        self.record(k);
 
-        self.lru.get(k).or_else(|| self.slru.get(k))
+        let v = match self.lru.get(k) {
+            Some(v) => Some(v),
+            None => self.slru.get(k),
+        };
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a mutable reference to the value of the key in the cache or `None`.
@@ -567,20 +1168,32 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
     ///
     /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
     /// ```
-    fn get_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.tinylfu.try_reset();
-        self.tinylfu.increment(k);
-        self.lru.get_mut(k).or_else(|| self.slru.get_mut(k))
+        if self.climber.is_some() {
+            let hit = self.lru.contains(k) || self.slru.contains(k);
+            self.climb(hit);
+        }
+
+        self.record(k);
+        let v = match self.lru.get_mut(k) {
+            Some(v) => Some(v),
+            None => self.slru.get_mut(k),
+        };
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
     /// not present in the cache. Unlike `get`, `peek` does not update the LRU list so the key's
     /// position will be unchanged.
     ///
+    /// `peek` never records the key in the TinyLFU, use [`peek_recorded`] to count the peek
+    /// for the admission.
+    ///
     /// # Example
     ///
     /// ```
@@ -594,7 +1207,9 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
     /// assert_eq!(cache.peek(&1), Some(&"a"));
     /// assert_eq!(cache.peek(&2), Some(&"b"));
     /// ```
-    fn peek<'a, Q>(&self, k: &'a Q) -> Option<&'a V>
+    ///
+    /// [`peek_recorded`]: struct.WTinyLFUCache.html#method.peek_recorded
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -619,12 +1234,18 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
     /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
     /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
     /// ```
-    fn peek_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.lru.peek_mut(k).or_else(|| self.slru.peek_mut(k))
+        if self.record_on_peek {
+            self.record(k);
+        }
+        match self.lru.peek_mut(k) {
+            Some(v) => Some(v),
+            None => self.slru.peek_mut(k),
+        }
     }
 
     fn contains<Q>(&self, k: &Q) -> bool
@@ -640,12 +1261,24 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
         KeyRef<K>: Borrow<Q>,
         Q: Eq + Hash + ?Sized,
     {
-        self.lru.remove(k).or_else(|| self.slru.remove(k))
+        let (key, val) = self
+            .lru
+            .remove_entry(k)
+            .or_else(|| self.slru.remove_entry(k))?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
     }
 
     fn purge(&mut self) {
-        self.lru.purge();
-        self.slru.purge();
+        while let Some((k, v)) = self.lru.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.slru.remove_lru_from_probationary() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.slru.remove_lru_from_protected() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
         self.tinylfu.clear();
     }
 
@@ -662,10 +1295,63 @@ impl<K: Hash + Eq, V, KH: KeyHasher<K>, FH: BuildHasher, RH: BuildHasher, WH: Bu
     }
 }
 
+impl<
+        K: Hash + Eq,
+        V,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > EntryCache<K, V> for WTinyLFUCache<K, V, KH, FH, RH, WH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the key was already recorded by the lookup of the entry
+        let rst = self.put_in(k, v);
+        let v = self.lru.peek_mru_mut().map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::lfu::WTinyLFUCache;
-    use crate::{Cache, PutResult};
+    use crate::lfu::tinylfu::test::seed_sketch;
+    use crate::lfu::{KeyHasher, WTinyLFUCache, WTinyLFUCacheBuilder};
+    use crate::{Cache, Entry, EvictionReason, KeyRef, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::borrow::Borrow;
+    use core::hash::{Hash, Hasher};
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+    use rustc_hash::FxHasher;
+    use std::sync::Mutex;
+
+    // hashes the keys the same on every run, so the TinyLFU estimates do not depend on the
+    // collisions of a randomly seeded hasher.
+    pub(super) struct FixedKeyHasher;
+
+    impl KeyHasher<u64> for FixedKeyHasher {
+        fn hash_key<Q>(&self, key: &Q) -> u64
+        where
+            KeyRef<u64>: Borrow<Q>,
+            Q: Hash + Eq + ?Sized,
+        {
+            let mut s = FxHasher::default();
+            key.hash(&mut s);
+            s.finish()
+        }
+    }
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
 
     #[test]
     fn test_wtinylfu() {
@@ -750,4 +1436,369 @@ mod test {
         assert_eq!(cache.remove(&3), Some(33));
         assert_eq!(cache.remove(&2), Some(22));
     }
+
+    #[test]
+    fn test_wtinylfu_on_evict() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = WTinyLFUCacheBuilder::<u64>::new(4, 48, 12, 64)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64>()
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 256;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Put | PutResult::Evicted { .. } => inserted += 1,
+                    _ => {}
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_wtinylfu_stats() {
+        let mut cache = WTinyLFUCache::<u64, u64>::with_sizes(1, 2, 2, 100).unwrap();
+
+        // make the keys in the main cache frequently used, so it rejects the new keys.
+        for k in 1..=5 {
+            cache.put(k, k);
+        }
+        let mut hits = 0;
+        for _ in 0..4 {
+            for k in 1..=5 {
+                if cache.get(&k).is_some() {
+                    hits += 1;
+                }
+            }
+        }
+
+        assert_eq!(cache.stats().hits(), hits);
+        assert_eq!(cache.stats().misses(), 20 - hits);
+        cache.reset_stats();
+
+        let mut evicted = 0;
+        for k in 100..110 {
+            if let PutResult::Evicted { .. } = cache.put(k, k) {
+                evicted += 1;
+            }
+        }
+
+        let stats = cache.stats();
+        assert_eq!(stats.inserts(), 10);
+        assert!(stats.rejections() > 0);
+        assert_eq!(stats.rejections() + stats.evictions(), evicted);
+    }
+
+    struct EvictionReasons(Arc<Mutex<Vec<EvictionReason>>>);
+
+    impl OnEvictCallback for EvictionReasons {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {}
+
+        fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+            self.0.lock().unwrap().push(reason);
+        }
+    }
+
+    #[test]
+    fn test_wtinylfu_on_evict_with_reason() {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = WTinyLFUCacheBuilder::<u64>::new(1, 2, 2, 100)
+            .set_on_evict(EvictionReasons(reasons.clone()))
+            .finalize::<u64>()
+            .unwrap();
+
+        // make the keys in the main cache frequently used, so it rejects the new keys.
+        for k in 1..=5 {
+            cache.put(k, k);
+        }
+        for _ in 0..4 {
+            for k in 1..=5 {
+                cache.get(&k);
+            }
+        }
+        reasons.lock().unwrap().clear();
+
+        cache.put(5, 50);
+        assert_eq!(
+            reasons.lock().unwrap().pop(),
+            Some(EvictionReason::Replaced)
+        );
+
+        let mut evicted = 0;
+        for k in 100..110 {
+            if let PutResult::Evicted { .. } = cache.put(k, k) {
+                evicted += 1;
+            }
+        }
+        {
+            let reasons = reasons.lock().unwrap();
+            assert!(reasons.contains(&EvictionReason::Rejected));
+            assert!(reasons
+                .iter()
+                .all(|r| *r == EvictionReason::Rejected || *r == EvictionReason::Capacity));
+            assert_eq!(reasons.len(), evicted);
+        }
+
+        reasons.lock().unwrap().clear();
+        let len = cache.len();
+        cache.purge();
+        assert_eq!(
+            *reasons.lock().unwrap(),
+            alloc::vec![EvictionReason::Purged; len]
+        );
+    }
+
+    #[test]
+    fn test_wtinylfu_entry() {
+        let mut cache = WTinyLFUCache::with_sizes(1, 2, 2, 5).unwrap();
+
+        for k in 0..3 {
+            cache.entry(k).or_insert(k);
+        }
+        assert_eq!(cache.window_cache_len(), 1);
+        assert_eq!(cache.main_cache_len(), 2);
+
+        // the occupied entries are found in every segment
+        for k in 0..3 {
+            *cache.entry(k).or_insert(0) += 10;
+        }
+        for k in 0..3 {
+            assert_eq!(cache.peek(&k), Some(&(k + 10)));
+        }
+
+        // the evicted entries are reported by the vacant insert
+        let mut evicted = 0;
+        for k in 3..10 {
+            match cache.entry(k) {
+                Entry::Occupied(_) => panic!("{} should not be in the cache", k),
+                Entry::Vacant(ent) => {
+                    let (v, rst) = ent.insert(k);
+                    assert_eq!(*v, k);
+                    if let PutResult::Evicted { .. } = rst {
+                        evicted += 1;
+                    }
+                }
+            }
+            assert_eq!(cache.peek(&k), Some(&k));
+        }
+        assert_eq!(cache.len(), 10 - evicted);
+    }
+
+    #[test]
+    fn test_wtinylfu_entry_records_once() {
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+
+        // the lookup of the entry is the only access recorded for a vacant insert
+        cache.entry(1).or_insert(1);
+        assert_eq!(cache.tinylfu.estimate(&1), 1);
+
+        cache.put(2, 2);
+        assert_eq!(cache.tinylfu.estimate(&2), 1);
+
+        cache.entry(1).and_modify(|v| *v += 1).or_insert(0);
+        assert_eq!(cache.tinylfu.estimate(&1), 2);
+    }
+
+    #[test]
+    fn test_wtinylfu_loader_records_once() {
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+
+        // the lookup of the key is the only access recorded for a miss
+        assert_eq!(cache.get_or_insert_with(1, || 1), Some(&1));
+        assert_eq!(cache.tinylfu.estimate(&1), 1);
+
+        assert_eq!(cache.get_or_insert_with(1, || unreachable!()), Some(&1));
+        assert_eq!(cache.tinylfu.estimate(&1), 2);
+    }
+
+    #[test]
+    fn test_wtinylfu_adaptive_window() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = WTinyLFUCacheBuilder::<u64>::new(1, 80, 19, 1000)
+            .set_adaptive_window(true)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64>()
+            .unwrap();
+        let mut inserted = 0;
+        let mut check = |k: u64| {
+            if cache.get(&k).is_none() {
+                if let PutResult::Put | PutResult::Evicted { .. } = cache.put(k, k) {
+                    inserted += 1;
+                }
+            }
+            // the capacity moves between the window cache and the main cache,
+            // and no entry is lost by the moving.
+            assert_eq!(cache.window_cache_cap() + cache.main_cache_cap(), 100);
+            assert!(cache.window_cache_len() <= cache.window_cache_cap());
+            assert!(cache.main_cache_len() <= cache.main_cache_cap());
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+            cache.window_cache_cap()
+        };
+
+        let mut periods = Vec::new();
+
+        // every key is read again shortly after it is written, the window cache grows
+        // until each LRU of the main cache is left one slot
+        for k in 0..100_000u64 {
+            periods.push(check(k));
+            periods.push(check(k.saturating_sub(k * 37 % 60)));
+        }
+        let recency = settled_window_cap(periods.drain(..));
+        assert_eq!(recency, 98);
+
+        // the frequently used keys are hot and the others are scanned, the capacity moves back
+        let mut rng = StdRng::seed_from_u64(0);
+        for k in 0..300_000u64 {
+            if k % 2 == 0 {
+                periods.push(check(rng.gen::<u64>() % 80));
+            } else {
+                periods.push(check(100_000 + k));
+            }
+        }
+        let frequency = settled_window_cap(periods.drain(..));
+        assert!(frequency < recency);
+    }
+
+    // Returns the window cache capacity the adaptive window settles at, the capacity
+    // does not change in the last 50 sample periods of the 1000 lookups.
+    fn settled_window_cap(lookups: impl Iterator<Item = usize>) -> usize {
+        let periods: Vec<usize> = lookups.skip(999).step_by(1000).collect();
+        let settled = periods[periods.len() - 1];
+        assert!(periods[periods.len() - 50..]
+            .iter()
+            .all(|cap| *cap == settled));
+        settled
+    }
+
+    // Fills the main cache of a `WTinyLFUCache::with_sizes(1, 2, 2, _)` with the keys
+    // 1 to 4 which are read twice, 5 is left in the window cache.
+    fn fill_read_keys<C: Cache<u64, u64>>(cache: &mut C) {
+        for k in 1..=3 {
+            cache.put(k, k);
+        }
+        for k in [1, 2, 1, 2] {
+            cache.get(&k);
+        }
+        for k in 4..=5 {
+            cache.put(k, k);
+        }
+        for k in [3, 4, 3, 4] {
+            cache.get(&k);
+        }
+    }
+
+    #[test]
+    fn test_wtinylfu_record_on_put() {
+        // the writes are recorded by default
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+        fill_read_keys(&mut cache);
+        assert_eq!(cache.main_cache_len(), 4);
+
+        // 100 is only written, each write is followed by a new key which pushes it out of the
+        // window cache, it is admitted once it is written more often than the others are read.
+        for v in 0..5 {
+            cache.put(100, v);
+            cache.put(1000 + v, v);
+        }
+        assert_eq!(cache.peek(&100), Some(&4));
+        // the keys written once are rejected, only the last one is left in the window cache
+        assert!((1000..1004).all(|k| !cache.contains(&k)));
+
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .set_record_on_put(false)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+        fill_read_keys(&mut cache);
+
+        // the writes are not recorded, so 100 is never admitted
+        for v in 0..5 {
+            cache.put(100, v);
+            assert_eq!(
+                cache.put(1000 + v, v),
+                PutResult::Evicted { key: 100, value: v }
+            );
+        }
+        assert!(!cache.contains(&100));
+    }
+
+    #[test]
+    fn test_wtinylfu_record_on_peek() {
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .set_record_on_put(false)
+            .set_record_on_peek(true)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+        fill_read_keys(&mut cache);
+
+        cache.put(100, 100);
+        for _ in 0..2 {
+            assert_eq!(cache.peek_mut(&100), Some(&mut 100));
+        }
+        assert_eq!(cache.peek_recorded(&100), Some(&100));
+        // `peek` is never recorded
+        assert_eq!(cache.peek(&100), Some(&100));
+
+        // the peeks are recorded, so 100 is admitted when it leaves the window cache
+        cache.put(1000, 1000);
+        assert!(cache.contains(&100));
+        assert_eq!(cache.main_cache_len(), 4);
+
+        let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .set_record_on_put(false)
+            .finalize()
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 5);
+        fill_read_keys(&mut cache);
+
+        cache.put(100, 100);
+        for _ in 0..2 {
+            cache.peek_mut(&100);
+        }
+        cache.peek_recorded(&100);
+        assert_eq!(
+            cache.put(1000, 1000),
+            PutResult::Evicted {
+                key: 100,
+                value: 100
+            }
+        );
+    }
+
+    #[test]
+    fn test_wtinylfu_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<WTinyLFUCache<u64, u64>>();
+    }
 }
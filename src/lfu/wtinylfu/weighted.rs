@@ -0,0 +1,892 @@
+use super::{WTinyLFUCacheBuilder, WTinyLFUError};
+use crate::entry::sealed::EntryCache;
+use crate::lfu::{tinylfu::TinyLFU, DefaultKeyHasher, KeyHasher};
+use crate::lru::RawLRU;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult, Weigher,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+/// `WeightedWTinyLFUCache` is a [`WTinyLFUCache`] whose capacity is a total weight
+/// instead of an entry count.
+///
+/// It is built by [`WTinyLFUCacheBuilder::finalize_weighted`], the window, protected and
+/// probationary sizes of the builder are the maximum weights of the segments, and the weight
+/// of each entry is given by a [`Weigher`]. An entry evicted from the window cache is admitted
+/// into the main cache if it fits, otherwise it is compared with the LRU entries of the main
+/// cache one by one until it fits, and it is rejected as soon as it is used less frequently
+/// than one of them. Entries heavier than the whole cache are rejected by `put`.
+/// All the evicted and rejected entries are returned in the [`PutResult`].
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, PutResult, WTinyLFUCacheBuilder};
+///
+/// let mut cache = WTinyLFUCacheBuilder::new(4, 8, 4, 100)
+///     .finalize_weighted(16, |_: &u64, v: &Vec<u8>| v.len())
+///     .unwrap();
+///
+/// assert_eq!(cache.put(1, vec![0; 4]), PutResult::Put);
+/// // 1 is moved from the window cache to the main cache
+/// assert_eq!(cache.put(2, vec![0; 4]), PutResult::Put);
+/// assert_eq!(cache.weight(), 8);
+///
+/// // 17 is heavier than the whole cache
+/// assert_eq!(cache.put(3, vec![0; 17]), PutResult::Rejected { key: 3, value: vec![0; 17] });
+/// ```
+///
+/// [`WTinyLFUCache`]: struct.WTinyLFUCache.html
+/// [`WTinyLFUCacheBuilder::finalize_weighted`]: struct.WTinyLFUCacheBuilder.html#method.finalize_weighted
+/// [`Weigher`]: trait.Weigher.html
+/// [`PutResult`]: enum.PutResult.html
+pub struct WeightedWTinyLFUCache<
+    K: Hash,
+    V,
+    W,
+    KH = DefaultKeyHasher<K>,
+    FH = DefaultHashBuilder,
+    RH = DefaultHashBuilder,
+    WH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    tinylfu: TinyLFU<K, KH>,
+    window: RawLRU<K, V, DefaultEvictCallback, WH>,
+    probationary: RawLRU<K, V, DefaultEvictCallback, RH>,
+    protected: RawLRU<K, V, DefaultEvictCallback, FH>,
+    weigher: W,
+    window_weight: usize,
+    probationary_weight: usize,
+    protected_weight: usize,
+    window_cap: usize,
+    probationary_cap: usize,
+    protected_cap: usize,
+    record_on_put: bool,
+    record_on_peek: bool,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<
+        K: Hash + Eq,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > WTinyLFUCacheBuilder<K, KH, FH, RH, WH, E>
+{
+    /// Finalize the builder to [`WeightedWTinyLFUCache`]. The sizes of the builder are used as
+    /// the maximum weights of the segments, and `entries` is the expected number of entries in
+    /// the cache, which sizes the frequency sketch.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{UnitWeigher, WeightedWTinyLFUCache, WTinyLFUCacheBuilder};
+    ///
+    /// let cache: WeightedWTinyLFUCache<u64, u64, _> = WTinyLFUCacheBuilder::new(1, 8, 2, 100)
+    ///     .finalize_weighted(11, UnitWeigher)
+    ///     .unwrap();
+    /// ```
+    ///
+    /// [`WeightedWTinyLFUCache`]: struct.WeightedWTinyLFUCache.html
+    #[allow(clippy::type_complexity)]
+    pub fn finalize_weighted<V, W: Weigher<K, V>>(
+        self,
+        entries: usize,
+        weigher: W,
+    ) -> Result<WeightedWTinyLFUCache<K, V, W, KH, FH, RH, WH, E>, WTinyLFUError> {
+        self.validate()?;
+
+        let tinylfu = Self::build_tinylfu(
+            self.key_hasher.unwrap(),
+            entries,
+            self.samples,
+            self.false_positive_ratio.unwrap(),
+        )?;
+
+        Ok(WeightedWTinyLFUCache {
+            tinylfu,
+            window: RawLRU::unbounded_with_hasher(self.window_cache_hasher.unwrap()),
+            probationary: RawLRU::unbounded_with_hasher(
+                self.main_cache_probationary_hasher.unwrap(),
+            ),
+            protected: RawLRU::unbounded_with_hasher(self.main_cache_protected_hasher.unwrap()),
+            weigher,
+            window_weight: 0,
+            probationary_weight: 0,
+            protected_weight: 0,
+            window_cap: self.window_cache_size,
+            probationary_cap: self.main_cache_probationary_size,
+            protected_cap: self.main_cache_protected_size,
+            record_on_put: self.record_on_put,
+            record_on_peek: self.record_on_peek,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+impl<
+        K: Hash + Eq,
+        V,
+        W: Weigher<K, V>,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > WeightedWTinyLFUCache<K, V, W, KH, FH, RH, WH, E>
+{
+    /// Returns the total weight of the entries in the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, WTinyLFUCacheBuilder};
+    ///
+    /// let mut cache = WTinyLFUCacheBuilder::new(4, 8, 4, 100)
+    ///     .finalize_weighted(16, |_: &u64, v: &&str| v.len())
+    ///     .unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "bcd");
+    /// assert_eq!(cache.weight(), 4);
+    /// ```
+    pub fn weight(&self) -> usize {
+        self.window_weight + self.probationary_weight + self.protected_weight
+    }
+
+    /// Returns a reference to the value of the key like `peek`, without updating the LRU lists,
+    /// but records the key in the TinyLFU when [`set_record_on_peek`] is enabled.
+    ///
+    /// [`set_record_on_peek`]: struct.WTinyLFUCacheBuilder.html#method.set_record_on_peek
+    pub fn peek_recorded<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.record_on_peek {
+            self.record(k);
+        }
+        self.peek(k)
+    }
+
+    /// Returns the total weight of the entries in the window cache.
+    pub fn window_cache_weight(&self) -> usize {
+        self.window_weight
+    }
+
+    /// Returns the total weight of the entries in the main cache.
+    pub fn main_cache_weight(&self) -> usize {
+        self.probationary_weight + self.protected_weight
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, UnitWeigher, WTinyLFUCacheBuilder};
+        /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+        ///     .finalize_weighted(5, UnitWeigher)
+        ///     .unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(1, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 1);
+        /// assert_eq!(stats.updates(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, UnitWeigher, WTinyLFUCacheBuilder};
+        /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+        ///     .finalize_weighted(5, UnitWeigher)
+        ///     .unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    /// Records an access of the key in the TinyLFU.
+    #[inline]
+    fn record<Q>(&mut self, k: &Q)
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.tinylfu.try_reset();
+        self.tinylfu.increment(k);
+    }
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if self.weigher.weight(&k, &v) > self.cap() {
+            // the old value is replaced even if the new one is rejected
+            if let Some((old_k, old, _)) = self.take(&KeyRef { k: &k }) {
+                self.cb(&old_k, &old, EvictionReason::Replaced);
+            }
+            return PutResult::Rejected { key: k, value: v };
+        }
+
+        let mut evicted = Vec::new();
+        let update = match self.take(&KeyRef { k: &k }) {
+            Some((_, old, in_window)) => {
+                self.cb(&k, &old, EvictionReason::Replaced);
+                if in_window {
+                    self.put_window(k, v, &mut evicted);
+                } else {
+                    self.put_protected(k, v, &mut evicted);
+                }
+                Some(old)
+            }
+            None => {
+                self.put_window(k, v, &mut evicted);
+                None
+            }
+        };
+        PutResult::from_evicted(evicted, update)
+    }
+
+    /// Puts an entry into the window cache, the entries which overflow the window cache
+    /// go through the admission of the main cache. Returns false if the entry itself
+    /// overflows the window cache and it is rejected.
+    fn put_window(&mut self, k: K, v: V, evicted: &mut Vec<(K, V)>) -> bool {
+        self.window_weight += self.weigher.weight(&k, &v);
+        self.window.put(k, v);
+        while self.window_weight > self.window_cap {
+            match self.window.remove_lru() {
+                Some((k, v)) => {
+                    self.window_weight -= self.weigher.weight(&k, &v);
+                    let admitted = self.admit(k, v, evicted);
+                    // the entry is the most recently used one, so it is the last to overflow
+                    if self.window.is_empty() {
+                        return admitted;
+                    }
+                }
+                None => break,
+            }
+        }
+        true
+    }
+
+    /// Puts an entry into the probationary cache if it fits. Otherwise the LRU entries of the main
+    /// cache are evicted until it fits, as long as they are not used more frequently than the entry.
+    /// Returns whether the entry is admitted.
+    fn admit(&mut self, k: K, v: V, evicted: &mut Vec<(K, V)>) -> bool {
+        let weight = self.weigher.weight(&k, &v);
+        let main_cap = self.probationary_cap + self.protected_cap;
+        loop {
+            if self.main_cache_weight() + weight <= main_cap {
+                self.probationary_weight += weight;
+                self.probationary.put(k, v);
+                return true;
+            }
+
+            let rejected = weight > main_cap
+                || match self
+                    .probationary
+                    .peek_lru()
+                    .or_else(|| self.protected.peek_lru())
+                {
+                    Some((victim, _)) => {
+                        #[cfg(any(feature = "nightly", feature = "nightly-core"))]
+                        let (candidate, victim) = (&KeyRef { k: &k }, &KeyRef { k: victim });
+
+                        #[cfg(not(any(feature = "nightly", feature = "nightly-core")))]
+                        let candidate = &k;
+
+                        self.tinylfu.lt(candidate, victim)
+                    }
+                    None => true,
+                };
+
+            if rejected {
+                self.cb(&k, &v, EvictionReason::Rejected);
+                evicted.push((k, v));
+                return false;
+            }
+
+            if let Some(ent) = self.evict_main(EvictionReason::Capacity) {
+                evicted.push(ent);
+            }
+        }
+    }
+
+    /// Puts an entry into the protected cache, the entries which overflow the protected cache
+    /// are moved to the probationary cache, and the entries which overflow the main cache are evicted.
+    fn put_protected(&mut self, k: K, v: V, evicted: &mut Vec<(K, V)>) {
+        self.protected_weight += self.weigher.weight(&k, &v);
+        self.protected.put(k, v);
+        while self.protected_weight > self.protected_cap {
+            match self.protected.remove_lru() {
+                Some((k, v)) => {
+                    let weight = self.weigher.weight(&k, &v);
+                    self.protected_weight -= weight;
+                    self.probationary_weight += weight;
+                    self.probationary.put(k, v);
+                }
+                None => break,
+            }
+        }
+
+        while self.main_cache_weight() > self.probationary_cap + self.protected_cap {
+            match self.evict_main(EvictionReason::Capacity) {
+                Some(ent) => evicted.push(ent),
+                None => break,
+            }
+        }
+    }
+
+    /// Moves an entry from the probationary cache to the protected cache.
+    fn promote<Q>(&mut self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        match self.probationary.remove_entry(k) {
+            Some((key, val)) => {
+                self.probationary_weight -= self.weigher.weight(&key, &val);
+                // moving an entry inside the main cache never overflows it, nothing is evicted.
+                let mut evicted = Vec::new();
+                self.put_protected(key, val, &mut evicted);
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn evict_main(&mut self, reason: EvictionReason) -> Option<(K, V)> {
+        let (k, v) = match self.probationary.remove_lru() {
+            Some((k, v)) => {
+                self.probationary_weight -= self.weigher.weight(&k, &v);
+                (k, v)
+            }
+            None => {
+                let (k, v) = self.protected.remove_lru()?;
+                self.protected_weight -= self.weigher.weight(&k, &v);
+                (k, v)
+            }
+        };
+        self.cb(&k, &v, reason);
+        Some((k, v))
+    }
+
+    /// Removes the entry from the segment it is in, returns the entry
+    /// and whether it was in the window cache.
+    fn take<Q>(&mut self, k: &Q) -> Option<(K, V, bool)>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some((key, val)) = self.window.remove_entry(k) {
+            self.window_weight -= self.weigher.weight(&key, &val);
+            return Some((key, val, true));
+        }
+        if let Some((key, val)) = self.probationary.remove_entry(k) {
+            self.probationary_weight -= self.weigher.weight(&key, &val);
+            return Some((key, val, false));
+        }
+        let (key, val) = self.protected.remove_entry(k)?;
+        self.protected_weight -= self.weigher.weight(&key, &val);
+        Some((key, val, false))
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<
+        K: Hash + Eq,
+        V,
+        W: Weigher<K, V>,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > Cache<K, V> for WeightedWTinyLFUCache<K, V, W, KH, FH, RH, WH, E>
+{
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// All the entries evicted from the main cache and the entries rejected by the admission
+    /// are returned, more than one of them in [`PutResult::EvictedMany`]. The entry is rejected
+    /// and returned in [`PutResult::Rejected`] if it is heavier than the whole cache, and the
+    /// old entry of the key, if any, is removed from the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PutResult, WTinyLFUCacheBuilder};
+    /// let mut cache = WTinyLFUCacheBuilder::new(2, 2, 2, 5)
+    ///     .finalize_weighted(6, |_: &u64, v: &&str| v.len())
+    ///     .unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "bc"));
+    /// assert_eq!(PutResult::Rejected { key: 3, value: "defghij" }, cache.put(3, "defghij"));
+    ///
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// assert_eq!(cache.get(&2), Some(&"bc"));
+    /// assert_eq!(cache.weight(), 3);
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    /// [`PutResult::EvictedMany`]: enum.PutResult.html#variant.EvictedMany
+    /// [`PutResult::Rejected`]: enum.PutResult.html#variant.Rejected
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if self.record_on_put {
+            self.record(&KeyRef { k: &k });
+        }
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WTinyLFUCacheBuilder};
+    /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+    ///     .finalize_weighted(5, UnitWeigher)
+    ///     .unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.record(k);
+
+        let v = if self.window.contains(k) {
+            self.window.get(k)
+        } else if self.promote(k) {
+            match self.protected.peek(k) {
+                Some(v) => Some(v),
+                None => self.probationary.peek(k),
+            }
+        } else {
+            self.protected.get(k)
+        };
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None`.
+    ///
+    /// The value must keep its weight, the cache does not weigh it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WTinyLFUCacheBuilder};
+    /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+    ///     .finalize_weighted(5, UnitWeigher)
+    ///     .unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.record(k);
+
+        let v = if self.window.contains(k) {
+            self.window.get_mut(k)
+        } else if self.promote(k) {
+            match self.protected.peek_mut(k) {
+                Some(v) => Some(v),
+                None => self.probationary.peek_mut(k),
+            }
+        } else {
+            self.protected.get_mut(k)
+        };
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LRU lists so the key's
+    /// position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WTinyLFUCacheBuilder};
+    /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+    ///     .finalize_weighted(5, UnitWeigher)
+    ///     .unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.window
+            .peek(k)
+            .or_else(|| self.probationary.peek(k))
+            .or_else(|| self.protected.peek(k))
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the LRU
+    /// lists so the key's position will be unchanged.
+    ///
+    /// The value must keep its weight, the cache does not weigh it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WTinyLFUCacheBuilder};
+    /// let mut cache = WTinyLFUCacheBuilder::new(1, 2, 2, 5)
+    ///     .finalize_weighted(5, UnitWeigher)
+    ///     .unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.record_on_peek {
+            self.record(k);
+        }
+        match self.window.peek_mut(k) {
+            Some(v) => Some(v),
+            None => match self.probationary.peek_mut(k) {
+                Some(v) => Some(v),
+                None => self.protected.peek_mut(k),
+            },
+        }
+    }
+
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        self.window.contains(k) || self.probationary.contains(k) || self.protected.contains(k)
+    }
+
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let (key, val, _) = self.take(k)?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    fn purge(&mut self) {
+        while let Some((k, v)) = self.window.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.probationary.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.protected.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        self.window_weight = 0;
+        self.probationary_weight = 0;
+        self.protected_weight = 0;
+        self.tinylfu.clear();
+    }
+
+    fn len(&self) -> usize {
+        self.window.len() + self.probationary.len() + self.protected.len()
+    }
+
+    /// Returns the maximum total weight of the entries the cache can hold.
+    fn cap(&self) -> usize {
+        self.window_cap + self.probationary_cap + self.protected_cap
+    }
+
+    fn is_empty(&self) -> bool {
+        self.window.is_empty() && self.probationary.is_empty() && self.protected.is_empty()
+    }
+}
+
+impl<
+        K: Hash + Eq,
+        V,
+        W: Weigher<K, V>,
+        KH: KeyHasher<K>,
+        FH: BuildHasher,
+        RH: BuildHasher,
+        WH: BuildHasher,
+        E: OnEvictCallback,
+    > EntryCache<K, V> for WeightedWTinyLFUCache<K, V, W, KH, FH, RH, WH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the key was already recorded by the lookup of the entry, and it is not in the cache
+        if self.weigher.weight(&k, &v) > self.cap() {
+            let rst = PutResult::Rejected { key: k, value: v };
+            self.stats.record_put(&rst);
+            return (rst, None);
+        }
+
+        let mut evicted = Vec::new();
+        let kept = self.put_window(k, v, &mut evicted);
+        let rst = PutResult::from_evicted(evicted, None);
+        self.stats.record_put(&rst);
+        if !kept {
+            return (rst, None);
+        }
+        // the entry is still in the window cache, or it has been admitted into the main cache
+        let v = if self.window.is_empty() {
+            self.probationary.peek_mru_mut()
+        } else {
+            self.window.peek_mru_mut()
+        };
+        (rst, v.map(|(_, v)| NonNull::from(v)))
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lfu::tinylfu::test::seed_sketch;
+    use crate::lfu::wtinylfu::test::FixedKeyHasher;
+    use crate::{
+        Cache, OnEvictCallback, PutResult, UnitWeigher, WTinyLFUCacheBuilder, WeightedWTinyLFUCache,
+    };
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_admission() {
+        let mut cache = WTinyLFUCacheBuilder::new(4, 4, 4, 100)
+            .finalize_weighted(16, |_: &u64, v: &Vec<u8>| v.len())
+            .unwrap();
+
+        // 1 and 2 fill the main cache and become frequently used
+        cache.put(1, vec![0; 4]);
+        cache.put(2, vec![0; 4]);
+        cache.put(3, vec![0; 4]);
+        (0..10).for_each(|_| {
+            cache.get(&1);
+            cache.get(&2);
+        });
+        assert_eq!(cache.main_cache_weight(), 8);
+        assert_eq!(cache.window_cache_weight(), 4);
+
+        // 3 is pushed out of the window cache and rejected by the admission
+        assert_eq!(
+            cache.put(4, vec![0; 4]),
+            PutResult::Evicted {
+                key: 3,
+                value: vec![0; 4]
+            }
+        );
+        assert!(cache.contains(&1));
+        assert!(cache.contains(&2));
+
+        // 4 is used frequently, so it evicts both 1 and 2 to make room for itself
+        (0..20).for_each(|_| {
+            cache.get(&4);
+        });
+        assert_eq!(
+            cache.put(4, vec![0; 8]),
+            PutResult::EvictedMany {
+                evicted: vec![(1, vec![0; 4]), (2, vec![0; 4])],
+                update: Some(vec![0; 4]),
+            }
+        );
+        assert_eq!(cache.main_cache_weight(), 8);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_reject_update() {
+        let mut cache = WTinyLFUCacheBuilder::new(4, 4, 4, 100)
+            .finalize_weighted(16, |_: &u64, v: &Vec<u8>| v.len())
+            .unwrap();
+
+        cache.put(1, vec![0; 4]);
+        cache.put(2, vec![0; 4]);
+        assert_eq!(
+            cache.put(1, vec![1; 13]),
+            PutResult::Rejected {
+                key: 1,
+                value: vec![1; 13]
+            }
+        );
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.weight(), 4);
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_record_on_put() {
+        for record in [true, false] {
+            let mut cache = WTinyLFUCacheBuilder::new(4, 4, 4, 100)
+                .set_key_hasher(FixedKeyHasher)
+                .set_record_on_put(record)
+                .finalize_weighted(16, |_: &u64, v: &Vec<u8>| v.len())
+                .unwrap();
+            seed_sketch(&mut cache.tinylfu, 16);
+
+            // 1 and 2 fill the main cache and are read twice
+            cache.put(1, vec![0; 4]);
+            cache.put(2, vec![0; 4]);
+            cache.put(3, vec![0; 4]);
+            (0..2).for_each(|_| {
+                cache.get(&1);
+                cache.get(&2);
+            });
+
+            // 3 is only written, and pushed out of the window cache after each write
+            (0..5).for_each(|k| {
+                cache.put(3, vec![0; 4]);
+                cache.put(100 + k, vec![0; 4]);
+            });
+            assert_eq!(cache.contains(&3), record);
+        }
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_loader() {
+        let mut cache = WTinyLFUCacheBuilder::new(4, 4, 4, 100)
+            .set_key_hasher(FixedKeyHasher)
+            .finalize_weighted(16, |_: &u64, v: &Vec<u8>| v.len())
+            .unwrap();
+        seed_sketch(&mut cache.tinylfu, 16);
+
+        // the lookup of the key is the only access recorded for a miss
+        assert_eq!(
+            cache.get_or_insert_with(1, || vec![1; 4]),
+            Some(&vec![1; 4])
+        );
+        assert_eq!(cache.tinylfu.estimate(&1), 1);
+
+        // 2 overflows the window cache, it is admitted into the main cache instead of 1
+        assert_eq!(
+            cache.get_or_insert_with(2, || vec![2; 6]),
+            Some(&vec![2; 6])
+        );
+        assert_eq!(cache.main_cache_weight(), 6);
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.get_or_insert_with(3, || vec![3; 17]), None);
+        assert!(!cache.contains(&3));
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_random_ops() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = WTinyLFUCacheBuilder::new(16, 192, 48, 1000)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize_weighted(64, |k: &u64, _: &u64| (*k % 16) as usize)
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Update(_) | PutResult::EvictedAndUpdate { .. } => {}
+                    PutResult::EvictedMany {
+                        update: Some(_), ..
+                    } => {}
+                    PutResult::Rejected { .. } => {}
+                    _ => inserted += 1,
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert!(cache.weight() <= cache.cap());
+            assert!(cache.window_cache_weight() <= 16);
+            assert!(cache.main_cache_weight() <= 240);
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(cache.weight(), 0);
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_weighted_wtinylfu_send_sync() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<WeightedWTinyLFUCache<u64, Vec<u8>, UnitWeigher>>();
+    }
+}
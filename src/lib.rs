@@ -20,23 +20,35 @@
 //! </div>
 //!
 //! ## Introduction
-//! The MSRV for this crate is 1.55.0.
+//! The MSRV for this crate is 1.60.0.
 //!
 //! - LRU
-//! - `LRUCache`, `SegmentedCache`, `TwoQueueCache` and `AdaptiveCache`.
+//! - `LRUCache`, `SegmentedCache`, `TwoQueueCache`, `AdaptiveCache`, `ExpiringLRU`, `LIRSCache`, `DLIRSCache`, `ClockCache`, `ClockProCache`, `CarCache`, `SieveCache` and `S3FifoCache`.
 //! - LFU
-//! - `TinyLFU`, `SampledLFU`, and `WTinyLFUCache`
+//! - `TinyLFU`, `SampledLFU`, `WTinyLFUCache` and `TinyLFUAdmission`, which puts a TinyLFU admission policy in front of `LRUCache`, `TwoQueueCache` or `AdaptiveCache`
+//! - Weighted
+//! - `WeightedLRU` and `WeightedWTinyLFUCache`, whose capacity is a total weight given by a `Weigher` instead of an entry count.
+//! - `CostCache`, which is bounded by the total cost of its entries and evicts the sampled entries with the lowest TinyLFU estimates, like Dgraph's ristretto.
+//! - Arena
+//! - `ArenaLRU`, `ArenaTwoQueueCache` and `ArenaAdaptiveCache`, which store their entries in a preallocated arena instead of boxing each entry.
+//! - Concurrent
+//! - `ShardedCache` (requires `std`), a thread-safe wrapper over any of the caches above.
 //!
 //! ## Installation
 //! - std
 //! ```toml
 //! [dependencies]
-//! caches = "0.2.0"
+//! caches = "0.3.0"
 //! ```
 //! - no_std
 //! ```toml
 //! [dependencies]
-//! caches = {version: "0.2.0", features: ["core"]}
+//! caches = {version: "0.3.0", features: ["core"]}
+//! ```
+//! - statistics (hits, misses, evictions, etc.), readable by `stats()` of the caches
+//! ```toml
+//! [dependencies]
+//! caches = {version: "0.3.0", features: ["stats"]}
 //! ```
 //!
 //! ## Usages
@@ -45,8 +57,7 @@
 //!
 //! ## Roadmap
 //! - [x] `0.2`: Support TinyLFU, SampledLFU, WTinyLFUCache
-//! - [ ] `0.3`: Support LIRS, DLIRS, DSLRU
-//! - [ ] `0.4`: Add ttl feature to support
+//! - [x] `0.3`: Support LIRS, DLIRS, DSLRU, add ttl feature
 //!
 //! ## Related
 //! If you want a high-performance thread-safe modern cache, please see https://crates.io/crates/stretto
@@ -99,20 +110,36 @@ extern crate hashbrown;
 #[cfg(any(test, feature = "std"))]
 extern crate std;
 
+use alloc::vec::Vec;
 use core::borrow::Borrow;
 use core::fmt::{Debug, Formatter};
 use core::hash::{Hash, Hasher};
 
 pub mod lru;
 pub use lru::{
-    AdaptiveCache, AdaptiveCacheBuilder, LRUCache, RawLRU, SegmentedCache, SegmentedCacheBuilder,
-    TwoQueueCache, TwoQueueCacheBuilder,
+    AdaptiveCache, AdaptiveCacheBuilder, ArenaAdaptiveCache, ArenaLRU, ArenaTwoQueueCache,
+    CarCache, ClockCache, ClockProCache, DLIRSCache, DLIRSCacheBuilder, ExpiringLRU,
+    ExpiringLRUBuilder, LIRSCache, LIRSCacheBuilder, LRUCache, RawLRU, S3FifoCache,
+    S3FifoCacheBuilder, SegmentedCache, SegmentedCacheBuilder, SieveCache, TwoQueueCache,
+    TwoQueueCacheBuilder, WeightedLRU,
 };
 
 mod cache_api;
-pub use cache_api::{Cache, ResizableCache};
+pub use cache_api::{Cache, PeekVictim, ResizableCache};
+mod entry;
+pub use entry::{Entry, OccupiedEntry, VacantEntry};
+mod clock;
+pub use clock::{Clock, ManualClock};
+mod weigher;
+pub use weigher::{UnitWeigher, Weigher};
+mod stats;
+cfg_stats!(
+    pub use stats::CacheStats;
+);
 pub mod lfu;
-pub use lfu::{WTinyLFUCache, WTinyLFUCacheBuilder};
+pub use lfu::{
+    CostCache, TinyLFUAdmission, WTinyLFUCache, WTinyLFUCacheBuilder, WeightedWTinyLFUCache,
+};
 
 #[macro_use]
 mod macros;
@@ -120,11 +147,23 @@ mod macros;
 cfg_not_std!(
     /// Re-export for DefaultHashBuilder
     pub type DefaultHashBuilder = hashbrown::hash_map::DefaultHashBuilder;
+
+    /// DefaultClock is the clock used by the time based caches,
+    /// there is no time source without std, so it has to be advanced by hand.
+    pub type DefaultClock = ManualClock;
 );
 
 cfg_std!(
     /// Re-export for DefaultHashBuilder
     pub type DefaultHashBuilder = std::collections::hash_map::RandomState;
+
+    pub use clock::StdClock;
+
+    mod sharded;
+    pub use sharded::ShardedCache;
+
+    /// DefaultClock is the clock used by the time based caches.
+    pub type DefaultClock = StdClock;
 );
 
 // Struct used to hold a reference to a key
@@ -215,7 +254,79 @@ impl OnEvictCallback for DefaultEvictCallback {
 /// ```
 pub trait OnEvictCallback {
     /// `on_evict` is a callback function will be invoked if an entry is evicted.
+    ///
+    /// It is not invoked for [`EvictionReason::Replaced`].
+    ///
+    /// [`EvictionReason::Replaced`]: enum.EvictionReason.html#variant.Replaced
     fn on_evict<K, V>(&self, key: &K, val: &V);
+
+    /// `on_evict_with_reason` is invoked with the reason why the entry left the cache.
+    /// The caches always call this method, the default implementation forwards to
+    /// [`on_evict`] for every reason except [`EvictionReason::Replaced`].
+    ///
+    /// # Example
+    /// ```
+    /// use caches::{RawLRU, OnEvictCallback, EvictionReason, Cache};
+    /// use std::sync::{Arc, Mutex};
+    ///
+    /// struct Reasons(Arc<Mutex<Vec<EvictionReason>>>);
+    ///
+    /// impl OnEvictCallback for Reasons {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {}
+    ///
+    ///     fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+    ///         self.0.lock().unwrap().push(reason);
+    ///     }
+    /// }
+    ///
+    /// let reasons = Arc::new(Mutex::new(Vec::new()));
+    /// let mut cache: RawLRU<u64, u64, Reasons> = RawLRU::with_on_evict_cb(2, Reasons(reasons.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(1, 2);
+    /// cache.put(2, 2);
+    /// cache.put(3, 3);
+    /// cache.remove(&2);
+    /// cache.purge();
+    ///
+    /// assert_eq!(
+    ///     *reasons.lock().unwrap(),
+    ///     vec![EvictionReason::Replaced, EvictionReason::Capacity, EvictionReason::Removed, EvictionReason::Purged],
+    /// );
+    /// ```
+    ///
+    /// [`on_evict`]: #tymethod.on_evict
+    /// [`EvictionReason::Replaced`]: enum.EvictionReason.html#variant.Replaced
+    fn on_evict_with_reason<K, V>(&self, key: &K, val: &V, reason: EvictionReason) {
+        if reason != EvictionReason::Replaced {
+            self.on_evict(key, val);
+        }
+    }
+}
+
+/// `EvictionReason` tells an [`OnEvictCallback`] why an entry left the cache.
+///
+/// [`OnEvictCallback`]: trait.OnEvictCallback.html
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EvictionReason {
+    /// The cache was full and the entry was chosen as the victim.
+    Capacity,
+    /// The entry was removed explicitly, e.g. by `remove` or `remove_lru`.
+    Removed,
+    /// The entry was removed by `purge`.
+    Purged,
+    /// The entry was removed because the cache was shrunk by `resize`.
+    Resized,
+    /// The entry was rejected by the TinyLFU admission policy of [`WTinyLFUCache`],
+    /// because it is less frequently used than the victim of the main cache.
+    ///
+    /// [`WTinyLFUCache`]: struct.WTinyLFUCache.html
+    Rejected,
+    /// The value of the entry was replaced by a `put` on the same key.
+    /// The callback receives the old value.
+    Replaced,
+    /// The entry was removed because its time-to-live expired.
+    Expired,
 }
 
 /// `PutResult` is returned when try to put a entry in cache.
@@ -229,10 +340,19 @@ pub trait OnEvictCallback {
 /// - **`PutResult::Evicted`** means that the the key is not in cache previously,
 /// but the cache is full, so the evict happens. The inner is the evicted entry `(Key, Value)`.
 ///
-/// - **`PutResult::EvictedAndUpdate`** is only possible to be returned by [`TwoQueueCache`] and [`AdaptiveCache`]. For more information, please see the related examples of [`TwoQueueCache`] and [`AdaptiveCache`]
+/// - **`PutResult::EvictedAndUpdate`** means that the key already exists in the cache, and one
+///   entry is evicted to make room for the new value. It is returned by the weighted caches and
+///   [`CostCache`], when the new value is heavier or costs more than the old one.
 ///
-/// [`TwoQueueCache`]: struct.TwoQueueCache.html
-/// [`AdaptiveCache`]: struct.AdaptiveCache.html
+/// - **`PutResult::EvictedMany`** is returned by the weighted caches and [`CostCache`], when more
+///   than one entry is evicted to make room for the new entry.
+///
+/// - **`PutResult::Rejected`** is returned by the weighted caches, when the entry is heavier
+///   than the capacity of the whole cache, and by [`TinyLFUAdmission`] and [`CostCache`], when
+///   the entry is less frequently used than the victim.
+///
+/// [`TinyLFUAdmission`]: struct.TinyLFUAdmission.html
+/// [`CostCache`]: struct.CostCache.html
 pub enum PutResult<K, V> {
     /// `Put` means that the key is not in cache previously, and the cache has enough
     /// capacity, no evict happens.
@@ -251,17 +371,71 @@ pub enum PutResult<K, V> {
         value: V,
     },
 
-    /// `EvictedAndUpdate` is only possible to be returned by [`TwoQueueCache`].
-    ///
-    /// For more information, please see the related examples of [`TwoQueueCache`].
+    /// `EvictedAndUpdate` means that the key already exists in the cache, and one entry is
+    /// evicted to make room for the new value. It is returned by the weighted caches,
+    /// [`WeightedLRU`] and [`WeightedWTinyLFUCache`], and by [`CostCache`], when the new value
+    /// is heavier or costs more than the old one.
     ///
-    /// [`TwoQueueCache`]: struct.TwoQueueCache.html
+    /// [`WeightedLRU`]: struct.WeightedLRU.html
+    /// [`WeightedWTinyLFUCache`]: struct.WeightedWTinyLFUCache.html
+    /// [`CostCache`]: struct.CostCache.html
     EvictedAndUpdate {
         /// The evicted entry.
         evicted: (K, V),
         /// The old value for the updated entry.
         update: V,
     },
+
+    /// `EvictedMany` is returned by the weighted caches, [`WeightedLRU`] and
+    /// [`WeightedWTinyLFUCache`], and by [`CostCache`], when more than one entry is evicted
+    /// to make room for the entry.
+    ///
+    /// [`WeightedLRU`]: struct.WeightedLRU.html
+    /// [`WeightedWTinyLFUCache`]: struct.WeightedWTinyLFUCache.html
+    /// [`CostCache`]: struct.CostCache.html
+    EvictedMany {
+        /// The evicted entries, in the order they were evicted.
+        evicted: Vec<(K, V)>,
+        /// The old value if the key was already in the cache.
+        update: Option<V>,
+    },
+
+    /// `Rejected` is returned by the weighted caches, [`WeightedLRU`] and
+    /// [`WeightedWTinyLFUCache`], when the entry is heavier than the capacity of the whole cache,
+    /// and by [`TinyLFUAdmission`] and [`CostCache`], when the entry is less frequently used
+    /// than the victim. The inner is the rejected entry, the old entry of the key is removed
+    /// from the cache.
+    ///
+    /// [`WeightedLRU`]: struct.WeightedLRU.html
+    /// [`WeightedWTinyLFUCache`]: struct.WeightedWTinyLFUCache.html
+    /// [`TinyLFUAdmission`]: struct.TinyLFUAdmission.html
+    /// [`CostCache`]: struct.CostCache.html
+    Rejected {
+        /// The key for the rejected entry.
+        key: K,
+        /// The value for the rejected entry.
+        value: V,
+    },
+}
+
+impl<K, V> PutResult<K, V> {
+    /// Builds the result of a put which evicted `evicted`, `update` is the old value if the key
+    /// was already in the cache.
+    pub(crate) fn from_evicted(mut evicted: Vec<(K, V)>, update: Option<V>) -> Self {
+        match (evicted.len(), update) {
+            (0, None) => PutResult::Put,
+            (0, Some(old)) => PutResult::Update(old),
+            (1, None) => {
+                let (key, value) = evicted.pop().unwrap();
+                PutResult::Evicted { key, value }
+            }
+            (1, Some(old)) => PutResult::EvictedAndUpdate {
+                evicted: evicted.pop().unwrap(),
+                update: old,
+            },
+            (_, update) => PutResult::EvictedMany { evicted, update },
+        }
+    }
 }
 
 impl<K: PartialEq, V: PartialEq> PartialEq for PutResult<K, V> {
@@ -290,6 +464,17 @@ impl<K: PartialEq, V: PartialEq> PartialEq for PutResult<K, V> {
                 }
                 _ => false,
             },
+            PutResult::EvictedMany { evicted, update } => match other {
+                PutResult::EvictedMany {
+                    evicted: other_evicted,
+                    update: other_update,
+                } => *evicted == *other_evicted && *update == *other_update,
+                _ => false,
+            },
+            PutResult::Rejected { key, value } => match other {
+                PutResult::Rejected { key: ok, value: ov } => *key == *ok && *value == *ov,
+                _ => false,
+            },
         }
     }
 }
@@ -305,6 +490,14 @@ impl<K: Debug, V: Debug> Debug for PutResult<K, V> {
                 write!(f, "PutResult::Evicted {{key: {:?}, val: {:?}}}", *k, *v)
             }
             PutResult::EvictedAndUpdate { evicted, update } => write!(f, "PutResult::EvictedAndUpdate {{ evicted: {{key: {:?}, value: {:?}}}, update: {:?} }}", (*evicted).0, (*evicted).1, *update),
+            PutResult::EvictedMany { evicted, update } => write!(
+                f,
+                "PutResult::EvictedMany {{ evicted: {:?}, update: {:?} }}",
+                *evicted, *update
+            ),
+            PutResult::Rejected { key: k, value: v } => {
+                write!(f, "PutResult::Rejected {{key: {:?}, val: {:?}}}", *k, *v)
+            }
         }
     }
 }
@@ -322,8 +515,14 @@ impl<K: Clone, V: Clone> Clone for PutResult<K, V> {
                 evicted: evicted.clone(),
                 update: update.clone(),
             },
+            PutResult::EvictedMany { evicted, update } => PutResult::EvictedMany {
+                evicted: evicted.clone(),
+                update: update.clone(),
+            },
+            PutResult::Rejected { key: k, value: v } => PutResult::Rejected {
+                key: k.clone(),
+                value: v.clone(),
+            },
         }
     }
 }
-
-impl<K: Copy, V: Copy> Copy for PutResult<K, V> {}
@@ -10,7 +10,8 @@
 //!
 //! - [`LRUCache`] or [`RawLRU`] is a fixed size LRU cache.
 //!
-//! - [`SegmentedCache`] is a fixed size Segmented LRU cache.
+//! - [`SegmentedCache`] is a fixed size Segmented LRU cache. It can also be built as a
+//!   dynamic segmented LRU (DSLRU) cache, which shifts capacity between its two segments at runtime.
 //!
 //! - [`AdaptiveCache`] is a fixed size Adaptive Replacement Cache (ARC).
 //! ARC is an enhancement over the standard LRU cache in that tracks both
@@ -22,6 +23,33 @@
 //! over the standard LRU cache in that it tracks both frequently
 //! and recently used entries separately.
 //!
+//! - [`ExpiringLRU`] is a fixed size LRU cache whose entries expire after a time-to-live.
+//!
+//! - [`LIRSCache`] is a fixed size Low Inter-reference Recency Set (LIRS) cache.
+//!   LIRS uses the reuse distance of the entries to decide which entries to keep,
+//!   so it is not polluted by scans of entries which are accessed only once.
+//!
+//! - [`DLIRSCache`] is a Dynamic LIRS cache. It works like [`LIRSCache`], but adapts
+//!   the share of the cache reserved for HIR entries at runtime, like [`AdaptiveCache`].
+//!
+//! - [`ArenaLRU`], [`ArenaTwoQueueCache`] and [`ArenaAdaptiveCache`] are the [`RawLRU`],
+//!   [`TwoQueueCache`] and [`AdaptiveCache`] backed by a preallocated arena, see [Trade-Off](#trade-off).
+//!
+//! - [`ClockCache`] is a fixed size CLOCK cache, which approximates LRU with a circular buffer
+//!   and a reference bit per entry, so a hit only sets a bit instead of relinking the entry.
+//!
+//! - [`ClockProCache`] is a fixed size CLOCK-Pro cache, which is scan resistant like [`LIRSCache`]
+//!   at the cost of a CLOCK. The hot, cold and test pages share a single clock.
+//!
+//! - [`CarCache`] is a fixed size CAR (Clock with Adaptive Replacement) cache. It adapts like
+//!   [`AdaptiveCache`], but its recent and frequent lists are clocks, so a hit only sets a bit.
+//!
+//! - [`SieveCache`] is a fixed size SIEVE cache, a FIFO queue with a visited bit per entry and
+//!   a hand which sifts out the entries not visited since it last passed them.
+//!
+//! - [`S3FifoCache`] is a fixed size S3-FIFO cache. A small FIFO filters out the new entries
+//!   which are not accessed again, the other entries live in a main FIFO.
+//!
 //! ## Trade-Off
 //! In theory, [`AdaptiveCache`] and [`TwoQueueCache`] add some additional
 //! tracking overhead to a [`LRUCache`] cache, computationally it is roughly **2x** the cost,
@@ -38,6 +66,19 @@
 //! than [`LRUCache`], even though I try my best to avoid boxing and re-boxing and
 //! use [`mem::swap`] to avoid memory allocating and deallocating.
 //!
+//! The arena based caches, [`ArenaLRU`], [`ArenaTwoQueueCache`] and [`ArenaAdaptiveCache`], avoid
+//! this cost: all the entries (and the ghost keys) live in one `Vec` which is allocated up front,
+//! the lists are linked by `u32` indices, a node is reused as soon as its entry is evicted,
+//! and moving an entry from one list to another only relinks it. The price is the memory of
+//! the whole capacity being allocated when the cache is created.
+//!
+//! [`ClockCache`] goes further for read-mostly workloads: a hit does not move the entry at all,
+//! it only sets the reference bit, which can also be done through a shared reference with
+//! `get_shared`. It evicts an entry not used since the hand last passed it, which is
+//! less precise than the least recently used one. [`SieveCache`] has the same cheap hits, and its
+//! hand does not move the retained entries back to the head, which keeps the new entries which
+//! are not used again close to the hand.
+//!
 //! [`SegmentedCache`] is computationally **1.2-1.5x** slower to [`LRUCache`] if you set the configurations reasonable, .
 //! 20% of the total size for probationary segment, and 80% of the total size for protected segment may suitable for most of situations.
 //!
@@ -110,22 +151,51 @@
 //! [`TwoQueueCacheExample`]: https://github.com/al8n/caches-rs/blob/main/examples/two_queue_cache.rs
 //! [`AdaptiveCache`]: struct.AdaptiveCache.html
 //! [`AdaptiveCacheExample`]: https://github.com/al8n/caches-rs/blob/main/examples/adaptive_cache.rs
+//! [`ExpiringLRU`]: struct.ExpiringLRU.html
+//! [`LIRSCache`]: struct.LIRSCache.html
+//! [`DLIRSCache`]: struct.DLIRSCache.html
+//! [`ClockCache`]: struct.ClockCache.html
+//! [`ClockProCache`]: struct.ClockProCache.html
+//! [`CarCache`]: struct.CarCache.html
+//! [`SieveCache`]: struct.SieveCache.html
+//! [`S3FifoCache`]: struct.S3FifoCache.html
 mod adaptive;
+mod arena;
+mod car;
+mod clock;
+mod clock_pro;
+mod dlirs;
 mod error;
+mod expiring;
+mod lirs;
 mod raw;
+mod s3fifo;
 mod segmented;
+mod sieve;
 mod two_queue;
+mod weighted;
 
-pub use adaptive::{AdaptiveCache, AdaptiveCacheBuilder};
+pub use adaptive::{AdaptiveCache, AdaptiveCacheBuilder, ArenaAdaptiveCache};
+pub use arena::{ArenaIter, ArenaKeys, ArenaLRU};
+pub use car::CarCache;
+pub use clock::ClockCache;
+pub use clock_pro::ClockProCache;
+pub use dlirs::{DLIRSCache, DLIRSCacheBuilder};
 pub use error::CacheError;
+pub use expiring::{ExpiringLRU, ExpiringLRUBuilder};
+pub use lirs::{LIRSCache, LIRSCacheBuilder, DEFAULT_LIRS_GHOST_RATIO, DEFAULT_LIRS_HIR_RATIO};
 pub use raw::{
     KeysLRUIter, KeysMRUIter, LRUIter, LRUIterMut, MRUIter, MRUIterMut, RawLRU, ValuesLRUIter,
     ValuesLRUIterMut, ValuesMRUIter, ValuesMRUIterMut,
 };
+pub use s3fifo::{S3FifoCache, S3FifoCacheBuilder, DEFAULT_S3_FIFO_SMALL_RATIO};
 pub use segmented::{SegmentedCache, SegmentedCacheBuilder};
+pub use sieve::SieveCache;
 pub use two_queue::{
-    TwoQueueCache, TwoQueueCacheBuilder, DEFAULT_2Q_GHOST_RATIO, DEFAULT_2Q_RECENT_RATIO,
+    ArenaTwoQueueCache, TwoQueueCache, TwoQueueCacheBuilder, DEFAULT_2Q_GHOST_RATIO,
+    DEFAULT_2Q_RECENT_RATIO,
 };
+pub use weighted::WeightedLRU;
 
 use crate::lru::raw::EntryNode;
 use crate::DefaultEvictCallback;
@@ -1,12 +1,24 @@
+use crate::entry::sealed::EntryCache;
 use crate::lru::raw::EntryNode;
 use crate::lru::raw::{
     KeysLRUIter, KeysMRUIter, LRUIter, LRUIterMut, MRUIter, MRUIterMut, RawLRU, ValuesLRUIter,
     ValuesLRUIterMut, ValuesMRUIter, ValuesMRUIterMut,
 };
 use crate::lru::{swap_value, CacheError};
-use crate::{Cache, DefaultEvictCallback, DefaultHashBuilder, KeyRef, PutResult};
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PeekVictim, PutResult,
+};
 use core::borrow::Borrow;
-use core::hash::{BuildHasher, Hash};
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
+
+mod arena;
+pub use arena::ArenaAdaptiveCache;
 
 /// `AdaptiveCacheBuilder` is used to help build a [`AdaptiveCache`] with custom configuration.
 ///
@@ -16,12 +28,14 @@ pub struct AdaptiveCacheBuilder<
     REH = DefaultHashBuilder,
     FH = DefaultHashBuilder,
     FEH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     size: usize,
     recent_hasher: Option<RH>,
     recent_evict_hasher: Option<REH>,
     freq_hasher: Option<FH>,
     freq_evict_hasher: Option<FEH>,
+    on_evict: Option<E>,
 }
 
 impl Default for AdaptiveCacheBuilder {
@@ -44,6 +58,7 @@ impl Default for AdaptiveCacheBuilder {
             recent_evict_hasher: Some(DefaultHashBuilder::default()),
             freq_hasher: Some(DefaultHashBuilder::default()),
             freq_evict_hasher: Some(DefaultHashBuilder::default()),
+            on_evict: None,
         }
     }
 }
@@ -75,8 +90,8 @@ impl AdaptiveCacheBuilder {
     }
 }
 
-impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
-    AdaptiveCacheBuilder<RH, REH, FH, FEH>
+impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher, E: OnEvictCallback>
+    AdaptiveCacheBuilder<RH, REH, FH, FEH, E>
 {
     /// Set the cache size
     pub fn set_size(self, size: usize) -> Self {
@@ -86,6 +101,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
             recent_evict_hasher: self.recent_evict_hasher,
             freq_hasher: self.freq_hasher,
             freq_evict_hasher: self.freq_evict_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -93,13 +109,14 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
     pub fn set_recent_hasher<NRH: BuildHasher>(
         self,
         hasher: NRH,
-    ) -> AdaptiveCacheBuilder<NRH, REH, FH, FEH> {
+    ) -> AdaptiveCacheBuilder<NRH, REH, FH, FEH, E> {
         AdaptiveCacheBuilder {
             size: self.size,
             recent_hasher: Some(hasher),
             recent_evict_hasher: self.recent_evict_hasher,
             freq_hasher: self.freq_hasher,
             freq_evict_hasher: self.freq_evict_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -107,13 +124,14 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
     pub fn set_frequent_hasher<NFH: BuildHasher>(
         self,
         hasher: NFH,
-    ) -> AdaptiveCacheBuilder<RH, REH, NFH, FEH> {
+    ) -> AdaptiveCacheBuilder<RH, REH, NFH, FEH, E> {
         AdaptiveCacheBuilder {
             size: self.size,
             recent_hasher: self.recent_hasher,
             recent_evict_hasher: self.recent_evict_hasher,
             freq_hasher: Some(hasher),
             freq_evict_hasher: self.freq_evict_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -121,13 +139,14 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
     pub fn set_recent_evict_hasher<NREH: BuildHasher>(
         self,
         hasher: NREH,
-    ) -> AdaptiveCacheBuilder<RH, NREH, FH, FEH> {
+    ) -> AdaptiveCacheBuilder<RH, NREH, FH, FEH, E> {
         AdaptiveCacheBuilder {
             size: self.size,
             recent_hasher: self.recent_hasher,
             recent_evict_hasher: Some(hasher),
             freq_hasher: self.freq_hasher,
             freq_evict_hasher: self.freq_evict_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -135,22 +154,74 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
     pub fn set_frequent_evict_hasher<NFEH: BuildHasher>(
         self,
         hasher: NFEH,
-    ) -> AdaptiveCacheBuilder<RH, REH, FH, NFEH> {
+    ) -> AdaptiveCacheBuilder<RH, REH, FH, NFEH, E> {
         AdaptiveCacheBuilder {
             size: self.size,
             recent_hasher: self.recent_hasher,
             recent_evict_hasher: self.recent_evict_hasher,
             freq_hasher: self.freq_hasher,
             freq_evict_hasher: Some(hasher),
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the callback which is invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry moves between the inner LRUs. It is invoked
+    /// when an entry is evicted to the recent evict LRU or the frequent evict LRU (those LRUs only
+    /// remember the key hash, the entry itself is returned by `put`), removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{AdaptiveCache, AdaptiveCacheBuilder, Cache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: AdaptiveCache<u64, u64, _, _, _, _, _> = AdaptiveCacheBuilder::new(2)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    ///
+    /// // 1 is evicted to the recent evict LRU, only the hash of its key is kept
+    /// cache.put(3, 3);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    ///
+    /// cache.purge();
+    /// assert_eq!(counter.load(Ordering::SeqCst), 3);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(
+        self,
+        cb: NE,
+    ) -> AdaptiveCacheBuilder<RH, REH, FH, FEH, NE> {
+        AdaptiveCacheBuilder {
+            size: self.size,
+            recent_hasher: self.recent_hasher,
+            recent_evict_hasher: self.recent_evict_hasher,
+            freq_hasher: self.freq_hasher,
+            freq_evict_hasher: self.freq_evict_hasher,
+            on_evict: Some(cb),
         }
     }
 
     /// Finalize the builder to [`TwoQueueCache`]
     ///
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
+    #[allow(clippy::type_complexity)]
     pub fn finalize<K: Hash + Eq, V>(
         self,
-    ) -> Result<AdaptiveCache<K, V, RH, REH, FH, FEH>, CacheError> {
+    ) -> Result<AdaptiveCache<K, V, RH, REH, FH, FEH, E>, CacheError> {
         let size = self.size;
         if size == 0 {
             return Err(CacheError::InvalidSize(0));
@@ -169,6 +240,8 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
             recent_evict,
             frequent: freq,
             frequent_evict: freq_evict,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
         })
     }
 }
@@ -186,7 +259,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 ///
 /// ```rust
 ///
-/// use caches::{Cache, AdaptiveCache};
+/// use caches::{Cache, AdaptiveCache, PutResult};
 ///
 /// let mut cache = AdaptiveCache::new(4).unwrap();
 ///
@@ -201,7 +274,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 /// assert_eq!(cache.frequent_len(), 2);
 ///
 /// // evict from recent
-/// cache.put(4, 4);
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
 /// assert_eq!(cache.recent_evict_len(), 1);
 ///
 /// // current state
@@ -211,7 +284,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 /// // frequent evict:  (MRU) [] (LRU)
 ///
 /// // Add 2, should cause hit on recent_evict
-/// cache.put(2, 2);
+/// assert_eq!(cache.put(2, 2), PutResult::Evicted { key: 3, value: 3 });
 /// assert_eq!(cache.recent_evict_len(), 1);
 /// assert_eq!(cache.partition(), 1);
 /// assert_eq!(cache.frequent_len(), 3);
@@ -223,7 +296,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 /// // frequent evict:  (MRU) [] (LRU)
 ///
 /// // Add 4, should migrate to frequent
-/// cache.put(4, 4);
+/// assert_eq!(cache.put(4, 4), PutResult::Update(4));
 /// assert_eq!(cache.recent_len(), 0);
 /// assert_eq!(cache.frequent_len(), 4);
 ///
@@ -234,7 +307,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 /// // frequent evict:  (MRU) [] (LRU)
 ///
 /// // Add 5, should evict to b2
-/// cache.put(5, 5);
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 0, value: 0 });
 /// assert_eq!(cache.recent_len(), 1);
 /// assert_eq!(cache.frequent_len(), 3);
 /// assert_eq!(cache.frequent_evict_len(), 1);
@@ -246,7 +319,7 @@ impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
 /// // frequent evict:  (MRU) [0] (LRU)
 ///
 /// // Add 0, should decrease p
-/// cache.put(0, 0);
+/// assert_eq!(cache.put(0, 0), PutResult::Evicted { key: 5, value: 5 });
 /// assert_eq!(cache.recent_len(), 0);
 /// assert_eq!(cache.frequent_len(), 4);
 /// assert_eq!(cache.recent_evict_len(), 2);
@@ -269,6 +342,7 @@ pub struct AdaptiveCache<
     REH = DefaultHashBuilder,
     FH = DefaultHashBuilder,
     FEH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     /// `size` is the total capacity of the cache
     size: usize,
@@ -279,14 +353,19 @@ pub struct AdaptiveCache<
     /// `recent` is the LRU for recently accessed items
     recent: RawLRU<K, V, DefaultEvictCallback, RH>,
 
-    /// `recent_evict` is the LRU for evictions from `recent`
-    recent_evict: RawLRU<K, V, DefaultEvictCallback, REH>,
+    /// `recent_evict` is the LRU for the key hashes of evictions from `recent`
+    recent_evict: RawLRU<u64, (), DefaultEvictCallback, REH>,
 
     /// `frequent` is the LRU for frequently accessed items
     frequent: RawLRU<K, V, DefaultEvictCallback, FH>,
 
-    /// `frequent_evict` is the LRU for evictions from `frequent`
-    frequent_evict: RawLRU<K, V, DefaultEvictCallback, FEH>,
+    /// `frequent_evict` is the LRU for the key hashes of evictions from `frequent`
+    frequent_evict: RawLRU<u64, (), DefaultEvictCallback, FEH>,
+
+    /// `on_evict` is invoked when an entry leaves the cache
+    on_evict: Option<E>,
+
+    stats: StatsRecorder,
 }
 
 impl<K: Hash + Eq, V> AdaptiveCache<K, V> {
@@ -321,128 +400,20 @@ impl<K: Hash + Eq, V> AdaptiveCache<K, V> {
     }
 }
 
-impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
-    Cache<K, V> for AdaptiveCache<K, V, RH, REH, FH, FEH>
+impl<
+        K: Hash + Eq,
+        V,
+        RH: BuildHasher,
+        REH: BuildHasher,
+        FH: BuildHasher,
+        FEH: BuildHasher,
+        E: OnEvictCallback,
+    > Cache<K, V> for AdaptiveCache<K, V, RH, REH, FH, FEH, E>
 {
-    fn put(&mut self, k: K, mut v: V) -> PutResult<K, V> {
-        let key_ref = KeyRef { k: &k };
-        // check if the value is contained in recent, and potentially
-        // promote it to frequent
-        if let Some(_) = self
-            .recent
-            // here we remove an entry from recent LRU if key exists
-            .remove_and_return_ent(&key_ref)
-            .map(|mut ent| {
-                unsafe {
-                    swap_value(&mut v, ent.as_mut());
-                }
-                // here we add the entry to frequent LRU,
-                // the result will always be PutResult::Put
-                // because we have removed this entry from recent LRU
-                self.frequent.put_box(ent);
-            })
-        {
-            return PutResult::Update(v);
-        }
-
-        // check if the value is already in frequent and update it
-        if let Some(ent_ptr) = self.frequent.map.get_mut(&key_ref).map(|node| {
-            let node_ptr: *mut EntryNode<K, V> = &mut **node;
-            node_ptr
-        }) {
-            self.frequent.update(&mut v, ent_ptr);
-            return PutResult::Update(v);
-        }
-
-        let recent_len = self.recent.len();
-        let freq_len = self.frequent.len();
-        let recent_evict_len = self.recent_evict.len();
-        let freq_evict_len = self.frequent_evict.len();
-
-        // check if this value was recently evicted as part of the
-        // recently used list
-        if self.recent_evict.contains(&key_ref) {
-            // freq set is too small, increase P appropriately
-            let mut delta = 1usize;
-
-            if freq_evict_len > recent_evict_len {
-                delta = freq_evict_len / recent_evict_len;
-            }
-
-            if self.p + delta >= self.size {
-                self.p = self.size;
-            } else {
-                self.p += delta;
-            }
-
-            // potentially need to make room in the cache
-            if self.recent.len() + self.frequent.len() >= self.size {
-                self.replace(false);
-            }
-
-            // remove from recent evict
-            let mut ent = self.recent_evict.map.remove(&key_ref).unwrap();
-            let ent_ptr = ent.as_mut();
-            self.recent_evict.detach(ent_ptr);
-            unsafe {
-                swap_value(&mut v, ent_ptr);
-            }
-
-            // add the key to the frequently used list
-            self.frequent.put_box(ent);
-            return PutResult::Update(v);
-        }
-
-        // Check if this value was recently evicted as part of the
-        // frequently used list
-        if self.frequent_evict.map.contains_key(&key_ref) {
-            // frequent set is too small, decrease P appropriately
-            let mut delta = 1usize;
-            if recent_evict_len > freq_evict_len {
-                delta = recent_evict_len / freq_evict_len;
-            }
-
-            if delta >= self.p {
-                self.p = 0;
-            } else {
-                self.p -= delta;
-            }
-
-            // Potentially need to make room in the cache
-            if recent_len + freq_len >= self.size {
-                self.replace(true);
-            }
-
-            // remove from frequent evict
-            let mut ent = self.frequent_evict.map.remove(&key_ref).unwrap();
-            let ent_ptr = ent.as_mut();
-            self.frequent_evict.detach(ent_ptr);
-
-            unsafe {
-                swap_value(&mut v, ent_ptr);
-            }
-
-            // add the key to the frequently used list
-            self.frequent.put_box(ent);
-            return PutResult::Update(v);
-        }
-
-        // Potentially need to make room in the cache
-        if recent_len + freq_len >= self.size {
-            self.replace(false);
-        }
-
-        // Keep the size of the ghost buffers trim
-        if recent_evict_len > self.size - self.p {
-            self.recent_evict.remove_lru();
-        }
-
-        if freq_evict_len > self.p {
-            self.frequent_evict.remove_lru();
-        }
-
-        // Add to the recently seen list
-        self.recent.put(k, v)
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
     }
 
     /// Returns a reference to the value of the key in the cache or `None` if it
@@ -461,17 +432,17 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     ///
     /// assert_eq!(cache.get(&"banana"), Some(&6));
     /// ```
-    fn get<'a, Q>(&mut self, k: &'a Q) -> Option<&'a V>
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
         // If the value is contained in recent, then
         // promote it to frequent
-        self.recent
-            .peek(k)
-            .and_then(|v| self.move_to_frequent(k, v))
-            .or_else(|| self.frequent.get(k))
+        self.move_to_frequent(k);
+        let v = self.frequent.get(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a mutable reference to the value of the key in the cache or `None` if it
@@ -492,17 +463,17 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
     /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
     /// ```
-    fn get_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
         // If the value is contained in recent, then
         // promote it to frequent
-        self.recent
-            .peek_mut(k)
-            .and_then(|v| self.move_to_frequent(k, v))
-            .or_else(|| self.frequent.get_mut(k))
+        self.move_to_frequent(k);
+        let v = self.frequent.get_mut(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
@@ -521,7 +492,7 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// assert_eq!(cache.peek(&1), Some(&"a"));
     /// assert_eq!(cache.peek(&2), Some(&"b"));
     /// ```
-    fn peek<'a, Q>(&'_ self, k: &'a Q) -> Option<&'a V>
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -545,14 +516,16 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
     /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
     /// ```
-    fn peek_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.recent
-            .peek_mut(k)
-            .or_else(|| self.frequent.peek_mut(k))
+        if self.recent.contains(k) {
+            self.recent.peek_mut(k)
+        } else {
+            self.frequent.peek_mut(k)
+        }
     }
 
     /// Returns a bool indicating whether the given key is in the cache.
@@ -601,11 +574,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.recent
-            .remove(k)
-            .or_else(|| self.frequent.remove(k))
-            .or_else(|| self.recent_evict.remove(k))
-            .or_else(|| self.frequent_evict.remove(k))
+        let hash = self.hash_key(k);
+        self.recent_evict.remove(&hash);
+        self.frequent_evict.remove(&hash);
+        let (key, val) = self
+            .recent
+            .remove_entry(k)
+            .or_else(|| self.frequent.remove_entry(k))?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
     }
 
     /// Clears the contents of the cache.
@@ -627,8 +604,12 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// assert_eq!(cache.len(), 0);
     /// ```
     fn purge(&mut self) {
-        self.recent.purge();
-        self.frequent.purge();
+        while let Some((k, v)) = self.recent.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.frequent.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
         self.recent_evict.purge();
         self.frequent_evict.purge();
     }
@@ -676,8 +657,86 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     }
 }
 
-impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher>
-    AdaptiveCache<K, V, RH, REH, FH, FEH>
+impl<
+        K: Hash + Eq,
+        V,
+        RH: BuildHasher,
+        REH: BuildHasher,
+        FH: BuildHasher,
+        FEH: BuildHasher,
+        E: OnEvictCallback,
+    > PeekVictim<K, V> for AdaptiveCache<K, V, RH, REH, FH, FEH, E>
+{
+    /// Returns the key of the entry which would be evicted if `k` was put into the cache now.
+    /// A key in the ghost LRUs moves the target size of the recent LRU first, just like `put`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PeekVictim, AdaptiveCache};
+    /// let mut cache = AdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.get(&2);
+    ///
+    /// assert_eq!(cache.peek_victim(&3), Some(&1));
+    /// assert_eq!(cache.peek_victim(&1), None);
+    /// ```
+    fn peek_victim<Q>(&self, k: &Q) -> Option<&K>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let recent_len = self.recent.len();
+        if recent_len + self.frequent.len() < self.size
+            || self.recent.contains(k)
+            || self.frequent.contains(k)
+        {
+            return None;
+        }
+
+        // keep consistent with `put_in` and `replace`
+        let recent_evict_len = self.recent_evict.len();
+        let freq_evict_len = self.frequent_evict.len();
+        let hash = self.hash_key(k);
+        let (p, freq_contains_key) = if self.recent_evict.contains(&hash) {
+            let delta = if freq_evict_len > recent_evict_len {
+                freq_evict_len / recent_evict_len
+            } else {
+                1
+            };
+            ((self.p + delta).min(self.size), false)
+        } else if self.frequent_evict.contains(&hash) {
+            let delta = if recent_evict_len > freq_evict_len {
+                recent_evict_len / freq_evict_len
+            } else {
+                1
+            };
+            (self.p.saturating_sub(delta), true)
+        } else {
+            (self.p, false)
+        };
+
+        let victim = if recent_len > 0 && (recent_len > p || (recent_len == p && freq_contains_key))
+        {
+            self.recent.peek_lru().or_else(|| self.frequent.peek_lru())
+        } else {
+            self.frequent.peek_lru().or_else(|| self.recent.peek_lru())
+        };
+        victim.map(|(k, _)| k)
+    }
+}
+
+impl<
+        K: Hash + Eq,
+        V,
+        RH: BuildHasher,
+        REH: BuildHasher,
+        FH: BuildHasher,
+        FEH: BuildHasher,
+        E: OnEvictCallback,
+    > AdaptiveCache<K, V, RH, REH, FH, FEH, E>
 {
     /// Create a [`AdaptiveCache`] from [`AdaptiveCacheBuilder`].
     ///
@@ -696,7 +755,7 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// [`AdaptiveCacheBuilder`]: struct.AdaptiveCacheBuilder.html
     /// [`AdaptiveCache`]: struct.AdaptiveCache.html
     pub fn from_builder(
-        builder: AdaptiveCacheBuilder<RH, REH, FH, FEH>,
+        builder: AdaptiveCacheBuilder<RH, REH, FH, FEH, E>,
     ) -> Result<Self, CacheError> {
         builder.finalize()
     }
@@ -716,16 +775,57 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
         self.frequent.len()
     }
 
-    /// Returns the number of key-value pairs that are currently in the recent evict LRU.
+    /// Returns the number of keys that are currently in the recent evict LRU.
     pub fn recent_evict_len(&self) -> usize {
         self.recent_evict.len()
     }
 
-    /// Returns the number of key-value pairs that are currently in the frequent evict LRU.
+    /// Returns the number of keys that are currently in the frequent evict LRU.
     pub fn frequent_evict_len(&self) -> usize {
         self.frequent_evict.len()
     }
 
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, AdaptiveCache};
+        /// let mut cache = AdaptiveCache::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// // 1 is in the ghost list of the recent LRU, and 2 is evicted to make room for it
+        /// cache.put(1, "a");
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 3);
+        /// assert_eq!(stats.evictions(), 2);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, AdaptiveCache};
+        /// let mut cache = AdaptiveCache::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
     /// An iterator visiting all keys of recent LRU in most-recently used order. The iterator element type is
     /// `&'a K`.
     ///
@@ -967,7 +1067,7 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
         self.recent.iter_lru_mut()
     }
 
-    /// An iterator visiting all keys of recent evict LRU in most-recently used order. The iterator element type is
+    /// An iterator visiting all keys of frequent LRU in most-recently used order. The iterator element type is
     /// `&'a K`.
     ///
     /// # Examples
@@ -980,15 +1080,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for key in cache.recent_evict_keys() {
+    /// for key in cache.frequent_keys() {
     ///     println!("key: {}", key);
     /// }
     /// ```
-    pub fn recent_evict_keys(&self) -> KeysMRUIter<'_, K, V> {
-        self.recent_evict.keys()
+    pub fn frequent_keys(&self) -> KeysMRUIter<'_, K, V> {
+        self.frequent.keys()
     }
 
-    /// An iterator visiting all keys of recent evict LRU in less-recently used order. The iterator element type is
+    /// An iterator visiting all keys of frequent LRU in less-recently used order. The iterator element type is
     /// `&'a K`.
     ///
     /// # Examples
@@ -1001,15 +1101,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for key in cache.recent_evict_keys_lru() {
+    /// for key in cache.frequent_keys_lru() {
     ///     println!("key: {}", key);
     /// }
     /// ```
-    pub fn recent_evict_keys_lru(&self) -> KeysLRUIter<'_, K, V> {
-        self.recent_evict.keys_lru()
+    pub fn frequent_keys_lru(&self) -> KeysLRUIter<'_, K, V> {
+        self.frequent.keys_lru()
     }
 
-    /// An iterator visiting all values of recent evict LRU in most-recently used order. The iterator element type is
+    /// An iterator visiting all values of frequent LRU in most-recently used order. The iterator element type is
     /// `&'a V`.
     ///
     /// # Examples
@@ -1022,15 +1122,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for val in cache.recent_evict_values() {
+    /// for val in cache.frequent_values() {
     ///     println!("val: {}",  val);
     /// }
     /// ```
-    pub fn recent_evict_values(&self) -> ValuesMRUIter<'_, K, V> {
-        self.recent_evict.values()
+    pub fn frequent_values(&self) -> ValuesMRUIter<'_, K, V> {
+        self.frequent.values()
     }
 
-    /// An iterator visiting all values of recent evict LRU in less-recently used order. The iterator element type is
+    /// An iterator visiting all values of frequent LRU in less-recently used order. The iterator element type is
     /// `&'a V`.
     ///
     /// # Examples
@@ -1043,15 +1143,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for val in cache.recent_evict_values_lru() {
+    /// for val in cache.frequent_values_lru() {
     ///     println!("val: {}", val);
     /// }
     /// ```
-    pub fn recent_evict_values_lru(&self) -> ValuesLRUIter<'_, K, V> {
-        self.recent_evict.values_lru()
+    pub fn frequent_values_lru(&self) -> ValuesLRUIter<'_, K, V> {
+        self.frequent.values_lru()
     }
 
-    /// An iterator visiting all values of recent evict in most-recently used order. The iterator element type is
+    /// An iterator visiting all values of frequent LRU in most-recently used order. The iterator element type is
     /// `&'a mut V`.
     ///
     /// # Examples
@@ -1064,15 +1164,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for val in cache.recent_evict_values_mut() {
+    /// for val in cache.frequent_values_mut() {
     ///     println!("val: {}", val);
     /// }
     /// ```
-    pub fn recent_evict_values_mut(&mut self) -> ValuesMRUIterMut<'_, K, V> {
-        self.recent_evict.values_mut()
+    pub fn frequent_values_mut(&mut self) -> ValuesMRUIterMut<'_, K, V> {
+        self.frequent.values_mut()
     }
 
-    /// An iterator visiting all values of recent evict LRU in less-recently used order. The iterator element type is
+    /// An iterator visiting all values of frequent LRU in less-recently used order. The iterator element type is
     /// `&'a mut V`.
     ///
     /// # Examples
@@ -1085,15 +1185,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for val in cache.recent_evict_values_lru_mut() {
+    /// for val in cache.frequent_values_lru_mut() {
     ///     println!("val: {}", val);
     /// }
     /// ```
-    pub fn recent_evict_values_lru_mut(&mut self) -> ValuesLRUIterMut<'_, K, V> {
-        self.recent_evict.values_lru_mut()
+    pub fn frequent_values_lru_mut(&mut self) -> ValuesLRUIterMut<'_, K, V> {
+        self.frequent.values_lru_mut()
     }
 
-    /// An iterator visiting all entries of recent evict LRU in most-recently used order. The iterator element type is
+    /// An iterator visiting all entries of frequent LRU in most-recently used order. The iterator element type is
     /// `(&'a K, &'a V)`.
     ///
     /// # Examples
@@ -1106,15 +1206,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for (key, val) in cache.recent_evict_iter() {
+    /// for (key, val) in cache.frequent_iter() {
     ///     println!("key: {} val: {}", key, val);
     /// }
     /// ```
-    pub fn recent_evict_iter(&self) -> MRUIter<'_, K, V> {
-        self.recent_evict.iter()
+    pub fn frequent_iter(&self) -> MRUIter<'_, K, V> {
+        self.frequent.iter()
     }
 
-    /// An iterator visiting all entries of recent evict LRU in less-recently used order. The iterator element type is
+    /// An iterator visiting all entries of frequent LRU in less-recently used order. The iterator element type is
     /// `(&'a K, &'a V)`.
     ///
     /// # Examples
@@ -1127,15 +1227,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put("b", 2);
     /// cache.put("c", 3);
     ///
-    /// for (key, val) in cache.recent_evict_iter_lru() {
+    /// for (key, val) in cache.frequent_iter_lru() {
     ///     println!("key: {} val: {}", key, val);
     /// }
     /// ```
-    pub fn recent_evict_iter_lru(&self) -> LRUIter<'_, K, V> {
-        self.recent_evict.iter_lru()
+    pub fn frequent_iter_lru(&self) -> LRUIter<'_, K, V> {
+        self.frequent.iter_lru()
     }
 
-    /// An iterator visiting all entries of recent evict LRU in most-recently-used order, giving a mutable reference on
+    /// An iterator visiting all entries of frequent LRU in most-recently-used order, giving a mutable reference on
     /// V.  The iterator element type is `(&'a K, &'a mut V)`.
     ///
     /// # Examples
@@ -1156,14 +1256,14 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
     /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
     ///
-    /// // evict [0, 1, 2] to recent evict list
-    /// cache.put(3, HddBlock { idx: 3, dirty: false, data: [0x00; 512]});
-    /// cache.put(4, HddBlock { idx: 4, dirty: true,  data: [0x55; 512]});
-    /// cache.put(5, HddBlock { idx: 5, dirty: true,  data: [0x77; 512]});
+    /// // upgrade to frequent list
+    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
+    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
+    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
     ///
     /// let mut ctr = 2i32;
     /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.recent_evict_iter_mut() {
+    /// for (block_id, block) in cache.frequent_iter_mut() {
     ///     if block.dirty {
     ///         // write block to disk
     ///         block.dirty = false
@@ -1172,11 +1272,11 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     ///     ctr -= 1;
     /// }
     /// ```
-    pub fn recent_evict_iter_mut(&mut self) -> MRUIterMut<'_, K, V> {
-        self.recent_evict.iter_mut()
+    pub fn frequent_iter_mut(&mut self) -> MRUIterMut<'_, K, V> {
+        self.frequent.iter_mut()
     }
 
-    /// An iterator visiting all entries of recent evict LRU in less-recently-used order, giving a mutable reference on
+    /// An iterator visiting all entries of frequent LRU in less-recently-used order, giving a mutable reference on
     /// V.  The iterator element type is `(&'a K, &'a mut V)`.
     ///
     /// # Examples
@@ -1197,15 +1297,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
     /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
     ///
-    /// // evict [0, 1, 2] to frequent list
-    /// cache.put(3, HddBlock { idx: 3, dirty: false, data: [0x00; 512]});
-    /// cache.put(4, HddBlock { idx: 4, dirty: true,  data: [0x55; 512]});
-    /// cache.put(5, HddBlock { idx: 5, dirty: true,  data: [0x77; 512]});
+    /// // upgrade to frequent list
+    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
+    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
+    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
     ///
     /// let mut ctr = 0i32;
     ///
     /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.recent_evict_iter_lru_mut() {
+    /// for (block_id, block) in cache.frequent_iter_lru_mut() {
     ///     if block.dirty {
     ///         // write block to disk
     ///         block.dirty = false
@@ -1214,550 +1314,227 @@ impl<K: Hash + Eq, V, RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: B
     ///     ctr += 1;
     /// }
     /// ```
-    pub fn recent_evict_iter_lru_mut(&mut self) -> LRUIterMut<'_, K, V> {
-        self.recent_evict.iter_lru_mut()
+    pub fn frequent_iter_lru_mut(&mut self) -> LRUIterMut<'_, K, V> {
+        self.frequent.iter_lru_mut()
     }
 
-    /// An iterator visiting all keys of frequent LRU in most-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.frequent_keys() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn frequent_keys(&self) -> KeysMRUIter<'_, K, V> {
-        self.frequent.keys()
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key<Q: Hash + ?Sized>(&self, k: &Q) -> u64 {
+        let mut hasher = self.recent.map.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
     }
 
-    /// An iterator visiting all keys of frequent LRU in less-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.frequent_keys_lru() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn frequent_keys_lru(&self) -> KeysLRUIter<'_, K, V> {
-        self.frequent.keys_lru()
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
     }
 
-    /// An iterator visiting all values of frequent LRU in most-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_values() {
-    ///     println!("val: {}",  val);
-    /// }
-    /// ```
-    pub fn frequent_values(&self) -> ValuesMRUIter<'_, K, V> {
-        self.frequent.values()
-    }
-
-    /// An iterator visiting all values of frequent LRU in less-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_values_lru() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_values_lru(&self) -> ValuesLRUIter<'_, K, V> {
-        self.frequent.values_lru()
-    }
-
-    /// An iterator visiting all values of frequent LRU in most-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_values_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_values_mut(&mut self) -> ValuesMRUIterMut<'_, K, V> {
-        self.frequent.values_mut()
-    }
-
-    /// An iterator visiting all values of frequent LRU in less-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_values_lru_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_values_lru_mut(&mut self) -> ValuesLRUIterMut<'_, K, V> {
-        self.frequent.values_lru_mut()
-    }
-
-    /// An iterator visiting all entries of frequent LRU in most-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.frequent_iter() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn frequent_iter(&self) -> MRUIter<'_, K, V> {
-        self.frequent.iter()
-    }
-
-    /// An iterator visiting all entries of frequent LRU in less-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.frequent_iter_lru() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn frequent_iter_lru(&self) -> LRUIter<'_, K, V> {
-        self.frequent.iter_lru()
-    }
-
-    /// An iterator visiting all entries of frequent LRU in most-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 2i32;
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.frequent_iter_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr -= 1;
-    /// }
-    /// ```
-    pub fn frequent_iter_mut(&mut self) -> MRUIterMut<'_, K, V> {
-        self.frequent.iter_mut()
-    }
-
-    /// An iterator visiting all entries of frequent LRU in less-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 0i32;
-    ///
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.frequent_iter_lru_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr += 1;
-    /// }
-    /// ```
-    pub fn frequent_iter_lru_mut(&mut self) -> LRUIterMut<'_, K, V> {
-        self.frequent.iter_lru_mut()
-    }
-
-    /// An iterator visiting all keys of frequent evict LRU in most-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.frequent_evict_keys() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn frequent_evict_keys(&self) -> KeysMRUIter<'_, K, V> {
-        self.frequent_evict.keys()
-    }
-
-    /// An iterator visiting all keys of frequent evict LRU in less-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.frequent_evict_keys_lru() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn frequent_evict_keys_lru(&self) -> KeysLRUIter<'_, K, V> {
-        self.frequent_evict.keys_lru()
-    }
-
-    /// An iterator visiting all values of frequent evict LRU in most-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_evict_values() {
-    ///     println!("val: {}",  val);
-    /// }
-    /// ```
-    pub fn frequent_evict_values(&self) -> ValuesMRUIter<'_, K, V> {
-        self.frequent_evict.values()
-    }
-
-    /// An iterator visiting all values of frequent evict LRU in less-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_evict_values_lru() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_evict_values_lru(&self) -> ValuesLRUIter<'_, K, V> {
-        self.frequent_evict.values_lru()
-    }
-
-    /// An iterator visiting all values of frequent evict LRU in most-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_evict_values_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_evict_values_mut(&mut self) -> ValuesMRUIterMut<'_, K, V> {
-        self.frequent_evict.values_mut()
+    fn move_to_frequent<Q>(&mut self, k: &Q)
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if let Some(ent) = self.recent.remove_and_return_ent(k) {
+            self.frequent.put_box(ent);
+        }
     }
+}
 
-    /// An iterator visiting all values of frequent evict LRU in less-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.frequent_evict_values_lru_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn frequent_evict_values_lru_mut(&mut self) -> ValuesLRUIterMut<'_, K, V> {
-        self.frequent_evict.values_lru_mut()
+impl<
+        K: Hash + Eq,
+        V,
+        RH: BuildHasher,
+        REH: BuildHasher,
+        FH: BuildHasher,
+        FEH: BuildHasher,
+        E: OnEvictCallback,
+    > AdaptiveCache<K, V, RH, REH, FH, FEH, E>
+{
+    /// replace is used to adaptively evict from either recent or frequent
+    /// based on the current learned value of P, the evicted entry is returned.
+    /// If the chosen LRU is empty, the other one is evicted from.
+    fn replace(&mut self, freq_contains_key: bool) -> Option<(K, V)> {
+        let recent_len = self.recent.len();
+        let from_recent = (recent_len > 0
+            && (recent_len > self.p || (recent_len == self.p && freq_contains_key)))
+            || self.frequent.is_empty();
+
+        // only the key hash is remembered, the entry is returned to the caller
+        let (k, v) = if from_recent {
+            let (k, v) = self.recent.remove_lru()?;
+            self.recent_evict.put(self.hash_key(&k), ());
+            (k, v)
+        } else {
+            let (k, v) = self.frequent.remove_lru()?;
+            self.frequent_evict.put(self.hash_key(&k), ());
+            (k, v)
+        };
+        self.cb(&k, &v, EvictionReason::Capacity);
+        Some((k, v))
     }
 
-    /// An iterator visiting all entries of frequent evict LRU in most-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.frequent_evict_iter() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn frequent_evict_iter(&self) -> MRUIter<'_, K, V> {
-        self.frequent_evict.iter()
-    }
+    fn put_in(&mut self, k: K, mut v: V) -> PutResult<K, V> {
+        let key_ref = KeyRef { k: &k };
+        // check if the value is contained in recent, and potentially
+        // promote it to frequent
+        if let Some(_) = self
+            .recent
+            // here we remove an entry from recent LRU if key exists
+            .remove_and_return_ent(&key_ref)
+            .map(|mut ent| {
+                unsafe {
+                    swap_value(&mut v, ent.as_mut());
+                }
+                // here we add the entry to frequent LRU,
+                // the result will always be PutResult::Put
+                // because we have removed this entry from recent LRU
+                self.frequent.put_box(ent);
+            })
+        {
+            self.cb(&k, &v, EvictionReason::Replaced);
+            return PutResult::Update(v);
+        }
 
-    /// An iterator visiting all entries of frequent evict LRU in less-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.frequent_evict_iter_lru() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn frequent_evict_iter_lru(&self) -> LRUIter<'_, K, V> {
-        self.frequent_evict.iter_lru()
-    }
+        // check if the value is already in frequent and update it
+        if let Some(ent_ptr) = self.frequent.map.get_mut(&key_ref).map(|node| {
+            let node_ptr: *mut EntryNode<K, V> = &mut **node;
+            node_ptr
+        }) {
+            self.frequent.update(&mut v, ent_ptr);
+            self.cb(&k, &v, EvictionReason::Replaced);
+            return PutResult::Update(v);
+        }
 
-    /// An iterator visiting all entries of frequent evict LRU in most-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 2i32;
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.frequent_evict_iter_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr -= 1;
-    /// }
-    /// ```
-    pub fn frequent_evict_iter_mut(&mut self) -> MRUIterMut<'_, K, V> {
-        self.frequent_evict.iter_mut()
-    }
+        let recent_len = self.recent.len();
+        let freq_len = self.frequent.len();
+        let recent_evict_len = self.recent_evict.len();
+        let freq_evict_len = self.frequent_evict.len();
 
-    /// An iterator visiting all entries of frequent evict LRU in less-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, AdaptiveCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = AdaptiveCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 0i32;
-    ///
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.frequent_evict_iter_lru_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr += 1;
-    /// }
-    /// ```
-    pub fn frequent_evict_iter_lru_mut(&mut self) -> LRUIterMut<'_, K, V> {
-        self.frequent_evict.iter_lru_mut()
-    }
+        // check if this value was recently evicted as part of the
+        // recently used list
+        let hash = self.hash_key(&k);
+        if self.recent_evict.remove(&hash).is_some() {
+            self.stats.record_ghost_hit();
+            // freq set is too small, increase P appropriately
+            let mut delta = 1usize;
 
-    /// replace is used to adaptively evict from either recent or frequent
-    /// based on the current learned value of P
-    fn replace(&mut self, freq_contains_key: bool) {
-        let recent_evict_len = self.recent.len();
-        if recent_evict_len > 0
-            && (recent_evict_len > self.p || (recent_evict_len == self.p && freq_contains_key))
-        {
-            match self.recent.remove_lru_in() {
-                None => None,
-                Some(ent) => Some(self.recent_evict.put_box(ent)),
+            if freq_evict_len > recent_evict_len {
+                delta = freq_evict_len / recent_evict_len;
+            }
+
+            if self.p + delta >= self.size {
+                self.p = self.size;
+            } else {
+                self.p += delta;
+            }
+
+            // potentially need to make room in the cache
+            let evicted = if recent_len + freq_len >= self.size {
+                self.replace(false)
+            } else {
+                None
             };
-        } else {
-            match self.frequent.remove_lru_in() {
-                None => None,
-                Some(ent) => Some(self.frequent_evict.put_box(ent)),
+
+            // add the key to the frequently used list
+            self.frequent.put(k, v);
+            return match evicted {
+                Some((key, value)) => PutResult::Evicted { key, value },
+                None => PutResult::Put,
             };
         }
-    }
 
-    fn move_to_frequent<T, Q>(&mut self, k: &Q, v: T) -> Option<T>
-    where
-        KeyRef<K>: Borrow<Q>,
-        Q: Hash + Eq + ?Sized,
-    {
-        match self.recent.remove_and_return_ent(k) {
-            None => None,
-            Some(ent) => {
-                self.frequent.put_box(ent);
-                Some(v)
+        // Check if this value was recently evicted as part of the
+        // frequently used list
+        if self.frequent_evict.remove(&hash).is_some() {
+            self.stats.record_ghost_hit();
+            // frequent set is too small, decrease P appropriately
+            let mut delta = 1usize;
+            if recent_evict_len > freq_evict_len {
+                delta = recent_evict_len / freq_evict_len;
+            }
+
+            if delta >= self.p {
+                self.p = 0;
+            } else {
+                self.p -= delta;
             }
+
+            // Potentially need to make room in the cache
+            let evicted = if recent_len + freq_len >= self.size {
+                self.replace(true)
+            } else {
+                None
+            };
+
+            // add the key to the frequently used list
+            self.frequent.put(k, v);
+            return match evicted {
+                Some((key, value)) => PutResult::Evicted { key, value },
+                None => PutResult::Put,
+            };
+        }
+
+        // Potentially need to make room in the cache
+        let evicted = if recent_len + freq_len >= self.size {
+            self.replace(false)
+        } else {
+            None
+        };
+
+        // Keep the size of the ghost buffers trim
+        if recent_evict_len > self.size - self.p {
+            self.recent_evict.remove_lru();
+        }
+
+        if freq_evict_len > self.p {
+            self.frequent_evict.remove_lru();
+        }
+
+        // Add to the recently seen list, the recent LRU never evicts here,
+        // because we have made room in the cache.
+        self.recent.put(k, v);
+        match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
         }
     }
 }
 
+impl<
+        K: Hash + Eq,
+        V,
+        RH: BuildHasher,
+        REH: BuildHasher,
+        FH: BuildHasher,
+        FEH: BuildHasher,
+        E: OnEvictCallback,
+    > EntryCache<K, V> for AdaptiveCache<K, V, RH, REH, FH, FEH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a recently evicted key is put into the frequent LRU, other keys into the recent LRU
+        let hash = self.hash_key(&k);
+        let to_frequent = self.recent_evict.contains(&hash) || self.frequent_evict.contains(&hash);
+        let rst = self.put(k, v);
+        let v = if to_frequent {
+            self.frequent.peek_mru_mut()
+        } else {
+            self.recent.peek_mru_mut()
+        };
+        let v = v.map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use crate::{AdaptiveCache, Cache};
+    use crate::{AdaptiveCache, AdaptiveCacheBuilder, Cache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
     use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
     use rand::seq::SliceRandom;
     use rand::{thread_rng, Rng};
 
@@ -1976,4 +1753,79 @@ mod test {
         cache.put(3, 3);
         assert!(!cache.contains(&1));
     }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_arc_drops_values_at_eviction() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut cache = AdaptiveCache::new(8).unwrap();
+
+        let mut created = 0;
+        for k in (0..32u64).chain(0..32).chain(16..48) {
+            created += 1;
+            cache.put(k, DropCounter(drops.clone()));
+            // the ghost LRUs hold no values, so every value not in the cache has been dropped
+            assert_eq!(drops.load(Ordering::SeqCst), created - cache.len());
+        }
+        assert!(cache.recent_evict_len() + cache.frequent_evict_len() > 0);
+
+        match cache.put(100, DropCounter(drops.clone())) {
+            PutResult::Evicted { value, .. } => {
+                // the value of the evicted entry is handed back to the caller
+                assert_eq!(drops.load(Ordering::SeqCst), created - cache.len());
+                drop(value);
+            }
+            _ => panic!("expected an eviction"),
+        }
+        assert_eq!(drops.load(Ordering::SeqCst), created + 1 - cache.len());
+    }
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_arc_cache_on_evict() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = AdaptiveCacheBuilder::new(64)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64, u64>()
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 256;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Put | PutResult::Evicted { .. } => inserted += 1,
+                    _ => {}
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
 }
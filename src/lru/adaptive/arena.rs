@@ -0,0 +1,808 @@
+use super::AdaptiveCacheBuilder;
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::{Arena, ArenaIter};
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
+
+const RECENT: usize = 0;
+const FREQUENT: usize = 1;
+// the lists of the ghost arena
+const RECENT_EVICT: usize = 0;
+const FREQUENT_EVICT: usize = 1;
+
+impl<RH: BuildHasher, REH: BuildHasher, FH: BuildHasher, FEH: BuildHasher, E: OnEvictCallback>
+    AdaptiveCacheBuilder<RH, REH, FH, FEH, E>
+{
+    /// Finalize the builder to [`ArenaAdaptiveCache`].
+    ///
+    /// The recent and frequent LRUs share one map, which uses the recent hasher. The two evict
+    /// LRUs remember the key hashes in their own arena, which uses the recent evict hasher.
+    /// The other hashers are not used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{AdaptiveCacheBuilder, Cache};
+    ///
+    /// let mut cache = AdaptiveCacheBuilder::new(4).finalize_arena().unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// ```
+    ///
+    /// [`ArenaAdaptiveCache`]: struct.ArenaAdaptiveCache.html
+    pub fn finalize_arena<K: Hash + Eq, V>(
+        self,
+    ) -> Result<ArenaAdaptiveCache<K, V, RH, REH, E>, CacheError> {
+        let size = self.size;
+        if size == 0 || size >= (u32::MAX / 3) as usize {
+            return Err(CacheError::InvalidSize(size));
+        }
+
+        Ok(ArenaAdaptiveCache {
+            arena: Arena::with_hasher(size, 2, self.recent_hasher.unwrap()),
+            ghost: Arena::with_hasher(size, 2, self.recent_evict_hasher.unwrap()),
+            size,
+            p: 0,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+/// `ArenaAdaptiveCache` is an [`AdaptiveCache`] which stores its recent and frequent LRUs in
+/// one arena, like [`ArenaLRU`], and the key hashes of its two evict LRUs in another one.
+///
+/// Both arenas are preallocated to the size of the cache, which is enough for the entries
+/// and the ghost keys in most of situations, moving an entry between the recent and frequent
+/// LRUs only relinks it. It behaves the same as an [`AdaptiveCache`] with the same size.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{ArenaAdaptiveCache, Cache, PutResult};
+///
+/// let mut cache = ArenaAdaptiveCache::new(4).unwrap();
+///
+/// // Fill recent
+/// (0..4).for_each(|i| {
+///     cache.put(i, i);
+/// });
+///
+/// // Move 0 to frequent
+/// cache.get(&0);
+/// assert_eq!(cache.recent_len(), 3);
+/// assert_eq!(cache.frequent_len(), 1);
+///
+/// // Add 4, should evict 1 to the recent evict LRU
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 1, value: 1 });
+/// assert_eq!(cache.recent_evict_len(), 1);
+///
+/// // Add 1 back, should increase p, and 2 is evicted
+/// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.partition(), 1);
+/// assert_eq!(cache.frequent_len(), 2);
+/// ```
+///
+/// [`AdaptiveCache`]: struct.AdaptiveCache.html
+/// [`ArenaLRU`]: struct.ArenaLRU.html
+pub struct ArenaAdaptiveCache<
+    K,
+    V,
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    arena: Arena<K, V, S>,
+    // the evict LRUs only keep the hashes of the evicted keys
+    ghost: Arena<u64, (), GH>,
+    size: usize,
+    p: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> ArenaAdaptiveCache<K, V> {
+    /// Create an `ArenaAdaptiveCache` with size and default configurations.
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        AdaptiveCacheBuilder::new(size).finalize_arena()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for ArenaAdaptiveCache<K, V, S, GH, E>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache, PutResult};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(1, "b"), PutResult::Update("a"));
+    /// ```
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the frequent LRU if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, FREQUENT);
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the frequent LRU if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, FREQUENT);
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LRU list so the key's
+    /// position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the LRU
+    /// list so the key's position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not update the
+    /// LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = match self.arena.index(k) {
+            Some(idx) => idx,
+            None => {
+                let hash = self.hash_key(k);
+                if let Some(idx) = self.ghost.index(&hash) {
+                    self.ghost.remove(idx);
+                }
+                return None;
+            }
+        };
+        let (key, val) = self.arena.remove(idx);
+        let val = val.unwrap();
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache: ArenaAdaptiveCache<isize, &str> = ArenaAdaptiveCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        for &list in &[RECENT, FREQUENT] {
+            while let Some(idx) = self.arena.back(list) {
+                let (k, v) = self.arena.remove(idx);
+                self.cb(&k, &v.unwrap(), EvictionReason::Purged);
+            }
+        }
+        for &list in &[RECENT_EVICT, FREQUENT_EVICT] {
+            while let Some(idx) = self.ghost.back(list) {
+                self.ghost.remove(idx);
+            }
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache = ArenaAdaptiveCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.recent_len() + self.frequent_len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    /// let mut cache: ArenaAdaptiveCache<isize, &str> = ArenaAdaptiveCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    ArenaAdaptiveCache<K, V, S, GH, E>
+{
+    /// Returns the current partition value of the cache.
+    pub fn partition(&self) -> usize {
+        self.p
+    }
+
+    /// Returns the number of key-value pairs that are currently in the recent LRU.
+    pub fn recent_len(&self) -> usize {
+        self.arena.list_len(RECENT)
+    }
+
+    /// Returns the number of key-value pairs that are currently in the frequent LRU.
+    pub fn frequent_len(&self) -> usize {
+        self.arena.list_len(FREQUENT)
+    }
+
+    /// Returns the number of key hashes that are currently in the recent evict LRU.
+    pub fn recent_evict_len(&self) -> usize {
+        self.ghost.list_len(RECENT_EVICT)
+    }
+
+    /// Returns the number of key hashes that are currently in the frequent evict LRU.
+    pub fn frequent_evict_len(&self) -> usize {
+        self.ghost.list_len(FREQUENT_EVICT)
+    }
+
+    /// An iterator visiting all entries of recent LRU in most-recently used order.
+    /// The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    ///
+    /// let mut cache = ArenaAdaptiveCache::new(4).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.get(&"a");
+    ///
+    /// assert_eq!(cache.recent_iter().collect::<Vec<_>>(), vec![(&"b", &2)]);
+    /// ```
+    pub fn recent_iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(RECENT)
+    }
+
+    /// An iterator visiting all entries of frequent LRU in most-recently used order.
+    /// The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaAdaptiveCache, Cache};
+    ///
+    /// let mut cache = ArenaAdaptiveCache::new(4).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.get(&"a");
+    ///
+    /// assert_eq!(cache.frequent_iter().collect::<Vec<_>>(), vec![(&"a", &1)]);
+    /// ```
+    pub fn frequent_iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(FREQUENT)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaAdaptiveCache, Cache};
+        /// let mut cache = ArenaAdaptiveCache::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// // 1 is in the ghost list of the recent LRU
+        /// cache.put(1, "a");
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 3);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaAdaptiveCache, Cache, CacheStats};
+        /// let mut cache = ArenaAdaptiveCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key<Q: Hash + ?Sized>(&self, k: &Q) -> u64 {
+        let mut hasher = self.arena.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    fn remove_lru(&mut self, list: usize) {
+        if let Some(idx) = self.ghost.back(list) {
+            self.ghost.remove(idx);
+        }
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(idx) = self.arena.index(&KeyRef { k: &k }) {
+            // the value is recently or frequently used, update it
+            // and promote it to frequent
+            let old = self.arena.replace_val(idx, Some(v)).unwrap();
+            self.arena.move_to_front(idx, FREQUENT);
+            self.cb(&k, &old, EvictionReason::Replaced);
+            return PutResult::Update(old);
+        }
+
+        let recent_len = self.recent_len();
+        let freq_len = self.frequent_len();
+        let recent_evict_len = self.recent_evict_len();
+        let freq_evict_len = self.frequent_evict_len();
+
+        let (list, evicted) = match self.ghost.index(&self.hash_key(&k)) {
+            Some(idx) => {
+                self.stats.record_ghost_hit();
+                let freq_contains_key = self.ghost.list(idx) == FREQUENT_EVICT;
+                self.ghost.remove(idx);
+
+                if freq_contains_key {
+                    // frequent set is too small, decrease P appropriately
+                    let mut delta = 1usize;
+                    if recent_evict_len > freq_evict_len {
+                        delta = recent_evict_len / freq_evict_len;
+                    }
+                    self.p = self.p.saturating_sub(delta);
+                } else {
+                    // freq set is too small, increase P appropriately
+                    let mut delta = 1usize;
+                    if freq_evict_len > recent_evict_len {
+                        delta = freq_evict_len / recent_evict_len;
+                    }
+                    self.p = (self.p + delta).min(self.size);
+                }
+
+                // potentially need to make room in the cache, the key is added to the
+                // frequently used list
+                let evicted = if recent_len + freq_len >= self.size {
+                    self.replace(freq_contains_key)
+                } else {
+                    None
+                };
+                (FREQUENT, evicted)
+            }
+            None => {
+                // Potentially need to make room in the cache
+                let evicted = if recent_len + freq_len >= self.size {
+                    self.replace(false)
+                } else {
+                    None
+                };
+
+                // Keep the size of the ghost buffers trim
+                if recent_evict_len > self.size - self.p {
+                    self.remove_lru(RECENT_EVICT);
+                }
+
+                if freq_evict_len > self.p {
+                    self.remove_lru(FREQUENT_EVICT);
+                }
+
+                // Add to the recently seen list
+                (RECENT, evicted)
+            }
+        };
+
+        self.arena.push_front(list, k, Some(v));
+        match evicted {
+            Some((key, value)) => {
+                // the cache is consistent before the callback runs, even if it panics
+                self.cb(&key, &value, EvictionReason::Capacity);
+                PutResult::Evicted { key, value }
+            }
+            None => PutResult::Put,
+        }
+    }
+
+    /// replace is used to adaptively evict from either recent or frequent
+    /// based on the current learned value of P, the evicted entry is returned.
+    /// If the chosen list is empty, the other one is evicted from.
+    fn replace(&mut self, freq_contains_key: bool) -> Option<(K, V)> {
+        let recent_len = self.recent_len();
+        let (from, to) = if (recent_len > 0
+            && (recent_len > self.p || (recent_len == self.p && freq_contains_key)))
+            || self.frequent_len() == 0
+        {
+            (RECENT, RECENT_EVICT)
+        } else {
+            (FREQUENT, FREQUENT_EVICT)
+        };
+
+        let idx = self.arena.back(from)?;
+
+        // only the key hash is remembered, the entry is returned to the caller
+        let hash = self.hash_key(self.arena.key(idx));
+        let (key, val) = self.arena.remove(idx);
+        match self.ghost.index(&hash) {
+            Some(idx) => self.ghost.move_to_front(idx, to),
+            None => {
+                if self.ghost.list_len(to) >= self.size {
+                    self.remove_lru(to);
+                }
+                self.ghost.push_front(to, hash, None);
+            }
+        }
+        Some((key, val.unwrap()))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for ArenaAdaptiveCache<K, V, S, GH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a recently evicted key is put into the frequent LRU, other keys into the recent LRU
+        let list = match self.ghost.index(&self.hash_key(&k)) {
+            Some(_) => FREQUENT,
+            None => RECENT,
+        };
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(list)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> fmt::Debug
+    for ArenaAdaptiveCache<K, V, S, GH, E>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArenaAdaptiveCache")
+            .field("len", &(self.recent_len() + self.frequent_len()))
+            .field("cap", &self.size)
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, S: Send, GH: Send, E: Send> Send
+    for ArenaAdaptiveCache<K, V, S, GH, E>
+{
+}
+unsafe impl<K: Sync, V: Sync, S: Sync, GH: Sync, E: Sync> Sync
+    for ArenaAdaptiveCache<K, V, S, GH, E>
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::{FREQUENT_EVICT, RECENT_EVICT};
+    use crate::lru::RawLRU;
+    use crate::{
+        AdaptiveCache, AdaptiveCacheBuilder, ArenaAdaptiveCache, Cache, OnEvictCallback, PutResult,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    // The caches hash the keys with different seeds, so the ghost hashes are compared by
+    // the keys below `n` they belong to.
+    fn arena_ghost_keys(cache: &ArenaAdaptiveCache<u64, u64>, list: usize, n: u64) -> Vec<u64> {
+        cache
+            .ghost
+            .keys(list)
+            .map(|h| (0..n).find(|k| cache.hash_key(k) == *h).unwrap())
+            .collect()
+    }
+
+    fn ghost_keys(cache: &AdaptiveCache<u64, u64>, ghost: &RawLRU<u64, ()>, n: u64) -> Vec<u64> {
+        ghost
+            .keys()
+            .map(|h| (0..n).find(|k| cache.hash_key(k) == *h).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_arena_arc_same_as_arc() {
+        let mut arena = ArenaAdaptiveCache::new(128).unwrap();
+        let mut cache = AdaptiveCache::new(128).unwrap();
+        let mut rng = thread_rng();
+
+        (0..200_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 4 {
+                0 | 1 => assert_eq!(arena.put(k, k), cache.put(k, k)),
+                2 => assert_eq!(arena.get(&k), cache.get(&k)),
+                _ => assert_eq!(arena.remove(&k), cache.remove(&k)),
+            }
+            assert_eq!(arena.partition(), cache.partition());
+            assert_eq!(arena.recent_len(), cache.recent_len());
+            assert_eq!(arena.frequent_len(), cache.frequent_len());
+            assert_eq!(arena.recent_evict_len(), cache.recent_evict_len());
+            assert_eq!(arena.frequent_evict_len(), cache.frequent_evict_len());
+        });
+
+        assert!(arena.recent_iter().eq(cache.recent_iter()));
+        assert!(arena.frequent_iter().eq(cache.frequent_iter()));
+        assert_eq!(
+            arena_ghost_keys(&arena, RECENT_EVICT, 512),
+            ghost_keys(&cache, &cache.recent_evict, 512)
+        );
+        assert_eq!(
+            arena_ghost_keys(&arena, FREQUENT_EVICT, 512),
+            ghost_keys(&cache, &cache.frequent_evict, 512)
+        );
+    }
+
+    #[test]
+    fn test_arena_arc_same_as_arc_small() {
+        let mut arena = ArenaAdaptiveCache::new(8).unwrap();
+        let mut cache = AdaptiveCache::new(8).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        (0..20_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 32;
+            match rng.gen::<u64>() % 4 {
+                0 | 1 => assert_eq!(arena.put(k, k), cache.put(k, k)),
+                2 => assert_eq!(arena.get(&k), cache.get(&k)),
+                _ => assert_eq!(arena.remove(&k), cache.remove(&k)),
+            }
+            assert!(arena.len() <= arena.cap());
+            assert_eq!(arena.partition(), cache.partition());
+            assert!(arena.recent_iter().eq(cache.recent_iter()));
+            assert!(arena.frequent_iter().eq(cache.frequent_iter()));
+            assert_eq!(
+                arena_ghost_keys(&arena, RECENT_EVICT, 32),
+                ghost_keys(&cache, &cache.recent_evict, 32)
+            );
+            assert_eq!(
+                arena_ghost_keys(&arena, FREQUENT_EVICT, 32),
+                ghost_keys(&cache, &cache.frequent_evict, 32)
+            );
+        });
+    }
+
+    #[test]
+    fn test_arena_arc_evicts_from_the_other_list() {
+        let mut arena = ArenaAdaptiveCache::new(2).unwrap();
+        let mut cache = AdaptiveCache::new(2).unwrap();
+
+        // 1 is evicted to the frequent evict LRU, and the recent LRU is empty when it comes back
+        for (k, get) in [
+            (1, false),
+            (2, false),
+            (1, true),
+            (2, true),
+            (3, false),
+            (3, true),
+        ] {
+            if get {
+                assert_eq!(arena.get(&k), cache.get(&k));
+            } else {
+                assert_eq!(arena.put(k, k), cache.put(k, k));
+            }
+        }
+        assert_eq!(arena.recent_len(), 0);
+        assert_eq!(arena.frequent_evict_len(), 1);
+
+        assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(arena.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(arena.len(), 2);
+    }
+
+    #[test]
+    fn test_arena_arc_panicking_callback() {
+        struct PanicOnEvict;
+
+        impl OnEvictCallback for PanicOnEvict {
+            fn on_evict<K, V>(&self, _: &K, _: &V) {
+                panic!("on_evict");
+            }
+        }
+
+        let mut cache = AdaptiveCacheBuilder::new(2)
+            .set_on_evict(PanicOnEvict)
+            .finalize_arena()
+            .unwrap();
+        cache.put(1, 1);
+        cache.put(2, 2);
+
+        // 1 is evicted to the recent evict LRU and 3 is added before the callback panics
+        assert!(catch_unwind(AssertUnwindSafe(|| cache.put(3, 3))).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.recent_evict_len(), 1);
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.peek(&3), Some(&3));
+
+        // 1 comes back from the recent evict LRU, 2 is evicted
+        assert!(catch_unwind(AssertUnwindSafe(|| cache.put(1, 1))).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.recent_evict_len(), 1);
+        assert_eq!(cache.frequent_iter().collect::<Vec<_>>(), vec![(&1, &1)]);
+        assert_eq!(cache.recent_iter().collect::<Vec<_>>(), vec![(&3, &3)]);
+    }
+}
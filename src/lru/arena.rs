@@ -0,0 +1,1084 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder,
+    EvictionReason, KeyRef, OnEvictCallback, PutResult, ResizableCache,
+};
+use alloc::vec;
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::iter::FusedIterator;
+use core::ptr::NonNull;
+
+import_hashbrown!(HashMap);
+import_std!(HashMap);
+
+// the null link of the arena.
+const NIL: u32 = u32::MAX;
+
+// An entry of the arena. The key is `None` when the node is free, the value is also `None`
+// when the node only remembers a key, e.g. the ghost entries of the CAR and CLOCK-Pro caches.
+// `freq` is the reference bit of the CLOCK based caches, the page state of the CLOCK-Pro
+// cache or the frequency counter of the S3-FIFO cache, it is cleared on insertion.
+struct ArenaNode<K, V> {
+    key: Option<K>,
+    val: Option<V>,
+    prev: u32,
+    next: u32,
+    list: u8,
+    freq: u8,
+}
+
+#[derive(Copy, Clone)]
+struct List {
+    head: u32,
+    tail: u32,
+    len: usize,
+}
+
+impl List {
+    const EMPTY: List = List {
+        head: NIL,
+        tail: NIL,
+        len: 0,
+    };
+}
+
+/// `Arena` stores the entries of several LRU lists in one `Vec`, which is preallocated to the
+/// capacity of the cache. The lists are linked by `u32` indices and the free nodes are reused,
+/// so inserting and evicting entries do not allocate, and moving an entry to another list
+/// only relinks it.
+pub(crate) struct Arena<K, V, S> {
+    map: HashMap<KeyRef<K>, u32, S>,
+    nodes: Vec<ArenaNode<K, V>>,
+    free: u32,
+    lists: Vec<List>,
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> Arena<K, V, S> {
+    /// Creates an arena with `lists` empty lists, which has room for `cap` entries before it grows.
+    pub(crate) fn with_hasher(cap: usize, lists: usize, hasher: S) -> Self {
+        Self {
+            map: HashMap::with_capacity_and_hasher(cap, hasher),
+            nodes: Vec::with_capacity(cap),
+            free: NIL,
+            lists: vec![List::EMPTY; lists],
+        }
+    }
+
+    /// Returns the index of the key.
+    pub(crate) fn index<Q>(&self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.get(k).copied()
+    }
+
+    /// Returns the hasher of the map.
+    pub(crate) fn hasher(&self) -> &S {
+        self.map.hasher()
+    }
+
+    /// Returns the number of entries in all the lists.
+    pub(crate) fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    pub(crate) fn list_len(&self, list: usize) -> usize {
+        self.lists[list].len
+    }
+
+    /// Returns the index of the most recently used entry of the list.
+    pub(crate) fn front(&self, list: usize) -> Option<u32> {
+        Some(self.lists[list].head).filter(|idx| *idx != NIL)
+    }
+
+    /// Returns the index of the least recently used entry of the list.
+    pub(crate) fn back(&self, list: usize) -> Option<u32> {
+        Some(self.lists[list].tail).filter(|idx| *idx != NIL)
+    }
+
+    /// Returns the index of the entry in front of the entry in its list, which is more recently
+    /// inserted or used.
+    pub(crate) fn prev(&self, idx: u32) -> Option<u32> {
+        Some(self.nodes[idx as usize].prev).filter(|idx| *idx != NIL)
+    }
+
+    /// Returns the index of the entry behind the entry in its list, which is less recently
+    /// inserted or used.
+    pub(crate) fn next(&self, idx: u32) -> Option<u32> {
+        Some(self.nodes[idx as usize].next).filter(|idx| *idx != NIL)
+    }
+
+    /// Returns the list which the entry belongs to.
+    pub(crate) fn list(&self, idx: u32) -> usize {
+        self.nodes[idx as usize].list as usize
+    }
+
+    pub(crate) fn key(&self, idx: u32) -> &K {
+        self.nodes[idx as usize].key.as_ref().unwrap()
+    }
+
+    pub(crate) fn val(&self, idx: u32) -> Option<&V> {
+        self.nodes[idx as usize].val.as_ref()
+    }
+
+    pub(crate) fn val_mut(&mut self, idx: u32) -> Option<&mut V> {
+        self.nodes[idx as usize].val.as_mut()
+    }
+
+    pub(crate) fn entry(&self, idx: u32) -> Option<(&K, &V)> {
+        let node = &self.nodes[idx as usize];
+        Some((node.key.as_ref()?, node.val.as_ref()?))
+    }
+
+    pub(crate) fn referenced(&self, idx: u32) -> bool {
+        self.nodes[idx as usize].freq != 0
+    }
+
+    pub(crate) fn set_referenced(&mut self, idx: u32, referenced: bool) {
+        self.nodes[idx as usize].freq = referenced as u8;
+    }
+
+    pub(crate) fn freq(&self, idx: u32) -> u8 {
+        self.nodes[idx as usize].freq
+    }
+
+    pub(crate) fn set_freq(&mut self, idx: u32, freq: u8) {
+        self.nodes[idx as usize].freq = freq;
+    }
+
+    /// Replaces the value of the entry, returns the old value.
+    pub(crate) fn replace_val(&mut self, idx: u32, val: Option<V>) -> Option<V> {
+        core::mem::replace(&mut self.nodes[idx as usize].val, val)
+    }
+
+    /// Inserts a new entry to the front of the list, the key must not be in the arena.
+    pub(crate) fn push_front(&mut self, list: usize, k: K, v: Option<V>) -> u32 {
+        let idx = self.alloc(k, v);
+        self.attach(idx, list);
+        idx
+    }
+
+    /// Inserts a new entry right in front of the entry `at` in its list, the key must not be in
+    /// the arena.
+    pub(crate) fn insert_before(&mut self, at: u32, k: K, v: Option<V>) -> u32 {
+        let idx = self.alloc(k, v);
+        self.attach_before(idx, at);
+        idx
+    }
+
+    /// Removes the entry from its list and the arena, the node is reused by the next insertion.
+    pub(crate) fn remove(&mut self, idx: u32) -> (K, Option<V>) {
+        let k: *const K = self.key(idx);
+        self.map.remove(&KeyRef { k });
+        self.detach(idx);
+
+        let node = &mut self.nodes[idx as usize];
+        let key = node.key.take().unwrap();
+        let val = node.val.take();
+        node.next = self.free;
+        self.free = idx;
+        (key, val)
+    }
+
+    /// Moves the entry to the front of the list, the list can be the one the entry belongs to.
+    pub(crate) fn move_to_front(&mut self, idx: u32, list: usize) {
+        self.detach(idx);
+        self.attach(idx, list);
+    }
+
+    /// Makes room for `cap` entries without growing.
+    pub(crate) fn reserve(&mut self, cap: usize) {
+        if cap > self.nodes.capacity() {
+            let ptr = self.nodes.as_ptr();
+            self.nodes.reserve_exact(cap - self.nodes.len());
+            if ptr != self.nodes.as_ptr() {
+                self.rebuild_map();
+            }
+        }
+    }
+
+    /// Returns an iterator over the entries of the list, from the most recently used one.
+    pub(crate) fn iter(&self, list: usize) -> ArenaIter<'_, K, V> {
+        ArenaIter {
+            nodes: &self.nodes,
+            idx: self.lists[list].head,
+            len: self.lists[list].len,
+            rev: false,
+        }
+    }
+
+    /// Returns an iterator over the entries of the list, from the least recently used one.
+    pub(crate) fn iter_lru(&self, list: usize) -> ArenaIter<'_, K, V> {
+        ArenaIter {
+            nodes: &self.nodes,
+            idx: self.lists[list].tail,
+            len: self.lists[list].len,
+            rev: true,
+        }
+    }
+
+    /// Returns an iterator over the keys of the list, from the most recently used one, the ghost
+    /// entries are also visited.
+    pub(crate) fn keys(&self, list: usize) -> ArenaKeys<'_, K, V> {
+        ArenaKeys {
+            nodes: &self.nodes,
+            idx: self.lists[list].head,
+            len: self.lists[list].len,
+        }
+    }
+
+    // Stores the entry in a free node, the node is not linked to any list yet.
+    fn alloc(&mut self, k: K, v: Option<V>) -> u32 {
+        let node = ArenaNode {
+            key: Some(k),
+            val: v,
+            prev: NIL,
+            next: NIL,
+            list: 0,
+            freq: 0,
+        };
+
+        let idx = if self.free != NIL {
+            let idx = self.free;
+            self.free = self.nodes[idx as usize].next;
+            self.nodes[idx as usize] = node;
+            idx
+        } else {
+            assert!(
+                self.nodes.len() < NIL as usize,
+                "too many entries in the arena"
+            );
+            let ptr = self.nodes.as_ptr();
+            self.nodes.push(node);
+            // the keys referenced by the map are moved if the nodes are reallocated.
+            if ptr != self.nodes.as_ptr() {
+                self.rebuild_map();
+            }
+            (self.nodes.len() - 1) as u32
+        };
+
+        let k: *const K = self.key(idx);
+        self.map.insert(KeyRef { k }, idx);
+        idx
+    }
+
+    fn rebuild_map(&mut self) {
+        self.map.clear();
+        for (idx, node) in self.nodes.iter().enumerate() {
+            if let Some(k) = node.key.as_ref() {
+                self.map.insert(KeyRef { k }, idx as u32);
+            }
+        }
+    }
+
+    fn detach(&mut self, idx: u32) {
+        let (prev, next, list) = {
+            let node = &self.nodes[idx as usize];
+            (node.prev, node.next, node.list as usize)
+        };
+
+        if prev != NIL {
+            self.nodes[prev as usize].next = next;
+        } else {
+            self.lists[list].head = next;
+        }
+
+        if next != NIL {
+            self.nodes[next as usize].prev = prev;
+        } else {
+            self.lists[list].tail = prev;
+        }
+        self.lists[list].len -= 1;
+    }
+
+    fn attach(&mut self, idx: u32, list: usize) {
+        let head = self.lists[list].head;
+        {
+            let node = &mut self.nodes[idx as usize];
+            node.prev = NIL;
+            node.next = head;
+            node.list = list as u8;
+        }
+
+        if head != NIL {
+            self.nodes[head as usize].prev = idx;
+        } else {
+            self.lists[list].tail = idx;
+        }
+        self.lists[list].head = idx;
+        self.lists[list].len += 1;
+    }
+
+    fn attach_before(&mut self, idx: u32, at: u32) {
+        let (prev, list) = {
+            let node = &self.nodes[at as usize];
+            (node.prev, node.list)
+        };
+        {
+            let node = &mut self.nodes[idx as usize];
+            node.prev = prev;
+            node.next = at;
+            node.list = list;
+        }
+
+        self.nodes[at as usize].prev = idx;
+        if prev != NIL {
+            self.nodes[prev as usize].next = idx;
+        } else {
+            self.lists[list as usize].head = idx;
+        }
+        self.lists[list as usize].len += 1;
+    }
+}
+
+/// An iterator over the entries of an arena based cache.
+///
+/// The iterator element type is `(&'a K, &'a V)`.
+pub struct ArenaIter<'a, K, V> {
+    nodes: &'a [ArenaNode<K, V>],
+    idx: u32,
+    len: usize,
+    rev: bool,
+}
+
+impl<'a, K, V> Iterator for ArenaIter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = &self.nodes[self.idx as usize];
+        self.len -= 1;
+        self.idx = if self.rev { node.prev } else { node.next };
+        Some((node.key.as_ref()?, node.val.as_ref()?))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ArenaIter<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for ArenaIter<'a, K, V> {}
+
+/// An iterator over the keys of an arena based cache, e.g. the keys of the ghost LRUs.
+///
+/// The iterator element type is `&'a K`.
+pub struct ArenaKeys<'a, K, V> {
+    nodes: &'a [ArenaNode<K, V>],
+    idx: u32,
+    len: usize,
+}
+
+impl<'a, K, V> Iterator for ArenaKeys<'a, K, V> {
+    type Item = &'a K;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.len == 0 {
+            return None;
+        }
+
+        let node = &self.nodes[self.idx as usize];
+        self.len -= 1;
+        self.idx = node.next;
+        node.key.as_ref()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, K, V> ExactSizeIterator for ArenaKeys<'a, K, V> {}
+
+impl<'a, K, V> FusedIterator for ArenaKeys<'a, K, V> {}
+
+const LRU: usize = 0;
+
+/// `ArenaLRU` is a fixed size LRU cache, which stores its entries in an arena instead of
+/// boxing every entry like [`RawLRU`].
+///
+/// The arena is preallocated to the capacity of the cache and the entries are linked by
+/// `u32` indices, so `put` does not allocate in the steady state: the node of the evicted entry
+/// is reused by the new one. The cost is the memory of the whole capacity being allocated
+/// up front, and the capacity is limited to `u32::MAX - 1` entries.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{ArenaLRU, Cache, PutResult};
+///
+/// let mut cache = ArenaLRU::new(2).unwrap();
+/// // fill the cache
+/// assert_eq!(cache.put(1, 1), PutResult::Put);
+/// assert_eq!(cache.put(2, 2), PutResult::Put);
+///
+/// // put 3, should evict the entry (1, 1)
+/// assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 1, value: 1 });
+///
+/// // get 2, should update the recent-ness
+/// assert_eq!(cache.get(&2), Some(&2));
+///
+/// // put 4, should evict the entry (3, 3)
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 3, value: 3 });
+/// ```
+///
+/// [`RawLRU`]: struct.RawLRU.html
+pub struct ArenaLRU<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    arena: Arena<K, V, S>,
+    cap: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+fn check_size(size: usize) -> Result<(), CacheError> {
+    if size == 0 || size >= NIL as usize {
+        Err(CacheError::InvalidSize(size))
+    } else {
+        Ok(())
+    }
+}
+
+impl<K: Hash + Eq, V> ArenaLRU<K, V> {
+    /// Creates a new `ArenaLRU` that holds at most `cap` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::ArenaLRU;
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::new(10).unwrap();
+    /// ```
+    pub fn new(cap: usize) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ArenaLRU<K, V, DefaultEvictCallback, S> {
+    /// Creates a new `ArenaLRU` that holds at most `cap` items and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, DefaultHashBuilder};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::with_hasher(10, s).unwrap();
+    /// ```
+    pub fn with_hasher(cap: usize, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback> ArenaLRU<K, V, E> {
+    /// Creates a new `ArenaLRU` that holds at most `cap` items and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = ArenaLRU::with_on_evict_cb(1, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(cap: usize, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ArenaLRU<K, V, E, S> {
+    /// Creates a new `ArenaLRU` that holds at most `cap` items,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, DefaultEvictCallback, DefaultHashBuilder};
+    ///
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(cap: usize, cb: E, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        cap: usize,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        check_size(cap)?;
+        Ok(Self {
+            arena: Arena::with_hasher(cap, 1, hasher),
+            cap,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// Returns the least recent used entry(&K, &V) in the cache or `None` if the cache is empty.
+    /// Does not update the LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_lru(), Some((&1, &"a")));
+    /// ```
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.arena.entry(self.arena.back(LRU)?)
+    }
+
+    /// Returns the most recent used entry(&K, &V) in the cache or `None` if the cache is empty.
+    /// Does not update the LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mru(), Some((&2, &"b")));
+    /// ```
+    pub fn peek_mru(&self) -> Option<(&K, &V)> {
+        self.arena.entry(self.arena.front(LRU)?)
+    }
+
+    /// Removes and returns the least recent used entry in the cache or `None` if the cache is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.remove_lru(), Some((1, "a")));
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn remove_lru(&mut self) -> Option<(K, V)> {
+        self.remove_lru_with(EvictionReason::Removed)
+    }
+
+    /// An iterator visiting all entries in most-recently used order. The iterator element type is
+    /// `(&'a K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    ///
+    /// let mut cache = ArenaLRU::new(3).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.put("c", 3);
+    ///
+    /// assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec!["c", "b", "a"]);
+    /// ```
+    pub fn iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(LRU)
+    }
+
+    /// An iterator visiting all entries in less-recently used order. The iterator element type is
+    /// `(&'a K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    ///
+    /// let mut cache = ArenaLRU::new(3).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.put("c", 3);
+    ///
+    /// assert_eq!(cache.iter_lru().map(|(k, _)| *k).collect::<Vec<_>>(), vec!["a", "b", "c"]);
+    /// ```
+    pub fn iter_lru(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter_lru(LRU)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaLRU, Cache};
+        /// let mut cache = ArenaLRU::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.get(&2);
+        ///
+        /// assert_eq!(cache.stats().inserts(), 2);
+        /// assert_eq!(cache.stats().evictions(), 1);
+        /// assert_eq!(cache.stats().hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaLRU, Cache, CacheStats};
+        /// let mut cache = ArenaLRU::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(idx) = self.arena.index(&KeyRef { k: &k }) {
+            let old = self.arena.replace_val(idx, Some(v)).unwrap();
+            self.arena.move_to_front(idx, LRU);
+            self.cb(&k, &old, EvictionReason::Replaced);
+            return PutResult::Update(old);
+        }
+
+        // the node of the evicted entry is reused by the new entry.
+        let evicted = if self.arena.len() >= self.cap {
+            self.remove_lru_with(EvictionReason::Capacity)
+        } else {
+            None
+        };
+        self.arena.push_front(LRU, k, Some(v));
+
+        match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
+        }
+    }
+
+    fn remove_lru_with(&mut self, reason: EvictionReason) -> Option<(K, V)> {
+        let (k, v) = self.arena.remove(self.arena.back(LRU)?);
+        let v = v.unwrap();
+        self.cb(&k, &v, reason);
+        Some((k, v))
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for ArenaLRU<K, V, E, S> {
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache, PutResult};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
+    /// assert_eq!(PutResult::Evicted { key: 1, value: "a" }, cache.put(3, "c"));
+    ///
+    /// assert_eq!(cache.get(&2), Some(&"beta"));
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"b"));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, LRU);
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// *cache.get_mut(&"apple").unwrap() = 9;
+    ///
+    /// assert_eq!(cache.get(&"apple"), Some(&9));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, LRU);
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LRU list so the key's
+    /// position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the LRU
+    /// list so the key's position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// *cache.peek_mut(&1).unwrap() = "b";
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not update the
+    /// LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        let (key, val) = self.arena.remove(idx);
+        let val = val.unwrap();
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.purge();
+    ///
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        while self.remove_lru_with(EvictionReason::Purged).is_some() {}
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns a bool indicating whether the cache is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache};
+    /// let mut cache = ArenaLRU::new(2).unwrap();
+    /// assert!(cache.is_empty());
+    ///
+    /// cache.put(1, "a");
+    /// assert!(!cache.is_empty());
+    /// ```
+    fn is_empty(&self) -> bool {
+        self.arena.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for ArenaLRU<K, V, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the new entry is put to the front of the LRU list
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(LRU)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ResizableCache for ArenaLRU<K, V, E, S> {
+    /// Resizes the cache. If the new capacity is smaller than the size of the current
+    /// cache any entries past the new capacity are discarded. Growing the cache
+    /// reallocates the arena to the new capacity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaLRU, Cache, ResizableCache};
+    /// let mut cache: ArenaLRU<isize, &str> = ArenaLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.resize(4);
+    /// cache.put(3, "c");
+    /// cache.put(4, "d");
+    ///
+    /// assert_eq!(cache.len(), 4);
+    /// assert_eq!(cache.resize(1), 3);
+    /// assert_eq!(cache.get(&4), Some(&"d"));
+    /// ```
+    fn resize(&mut self, cap: usize) -> u64 {
+        let mut evicted = 0u64;
+        while self.arena.len() > cap {
+            self.remove_lru_with(EvictionReason::Resized);
+            evicted += 1;
+        }
+        self.arena.reserve(cap);
+        self.cap = cap;
+        evicted
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> fmt::Debug for ArenaLRU<K, V, E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArenaLRU")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, E: Send, S: Send> Send for ArenaLRU<K, V, E, S> {}
+unsafe impl<K: Sync, V: Sync, E: Sync, S: Sync> Sync for ArenaLRU<K, V, E, S> {}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::{ArenaLRU, RawLRU};
+    use crate::{Cache, PutResult, ResizableCache};
+    use alloc::vec::Vec;
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_arena_lru_same_as_raw_lru() {
+        let mut arena = ArenaLRU::new(128).unwrap();
+        let mut raw = RawLRU::new(128).unwrap();
+        let mut rng = thread_rng();
+
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => assert_eq!(arena.put(k, k), raw.put(k, k)),
+                1 => assert_eq!(arena.get(&k), raw.get(&k)),
+                _ => assert_eq!(arena.remove(&k), raw.remove(&k)),
+            }
+        });
+
+        assert!(arena.iter().eq(raw.iter()));
+        assert!(arena.iter_lru().eq(raw.iter_lru()));
+    }
+
+    #[test]
+    fn test_arena_lru_reuse_nodes() {
+        let mut cache = ArenaLRU::new(4).unwrap();
+        (0..4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+
+        // the nodes of the evicted entries are reused, the arena is not reallocated.
+        (4..1000u64).for_each(|i| {
+            assert_eq!(
+                cache.put(i, i),
+                PutResult::Evicted {
+                    key: i - 4,
+                    value: i - 4
+                }
+            );
+        });
+        assert_eq!(cache.arena.nodes.len(), 4);
+        assert_eq!(cache.arena.nodes.capacity(), 4);
+
+        // growing the arena keeps the entries reachable.
+        cache.resize(64);
+        (1000..1060u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(cache.len(), 64);
+        assert!((996..1060u64).all(|i| cache.peek(&i) == Some(&i)));
+        assert_eq!(
+            cache.iter_lru().map(|(k, _)| *k).collect::<Vec<_>>(),
+            (996..1060u64).collect::<Vec<_>>()
+        );
+    }
+}
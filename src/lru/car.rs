@@ -0,0 +1,755 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::{Arena, ArenaKeys};
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+// the clocks are arena lists, the hand is the back of the list and
+// the tail of the clock, where the new pages are put, is the front.
+const RECENT: usize = 0;
+const FREQUENT: usize = 1;
+const RECENT_EVICT: usize = 2;
+const FREQUENT_EVICT: usize = 3;
+
+/// `CarCache` is a fixed size CAR (Clock with Adaptive Replacement) cache.
+///
+/// CAR keeps the adaptivity of [`AdaptiveCache`]: the recent (T1) and frequent (T2) entries,
+/// the ghost keys of the entries evicted from them (B1 and B2), and the target size `p` of T1,
+/// which grows on a hit in B1 and shrinks on a hit in B2. But T1 and T2 are clocks instead of
+/// LRU lists, so a hit only sets the reference bit of the entry. When the cache is full,
+/// the hand of T1 moves the referenced entries to T2, and the hand of T2 gives them a second
+/// chance, until an unreferenced entry is found and evicted to B1 or B2.
+///
+/// All the entries and the ghost keys are stored in one arena, like [`ArenaAdaptiveCache`].
+/// The ghost lists only remember the keys, so the keys are cloned when the entries are evicted.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, CarCache, PutResult};
+///
+/// let mut cache = CarCache::new(4).unwrap();
+///
+/// // Fill recent
+/// (0..4).for_each(|i| {
+///     cache.put(i, i);
+/// });
+///
+/// // 0 is referenced, the hand moves it to frequent and evicts 1 to the recent evict list
+/// cache.get(&0);
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 1, value: 1 });
+/// assert_eq!(cache.recent_len(), 3);
+/// assert_eq!(cache.frequent_len(), 1);
+/// assert_eq!(cache.recent_evict_len(), 1);
+///
+/// // Add 1 back, should increase p
+/// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.partition(), 1);
+/// assert_eq!(cache.frequent_len(), 2);
+/// ```
+///
+/// [`AdaptiveCache`]: struct.AdaptiveCache.html
+/// [`ArenaAdaptiveCache`]: struct.ArenaAdaptiveCache.html
+pub struct CarCache<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    arena: Arena<K, V, S>,
+    size: usize,
+    p: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> CarCache<K, V> {
+    /// Creates a new `CarCache` that holds at most `size` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::CarCache;
+    /// let mut cache: CarCache<isize, &str> = CarCache::new(10).unwrap();
+    /// ```
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(size, None, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> CarCache<K, V, DefaultEvictCallback, S> {
+    /// Creates a new `CarCache` that holds at most `size` items and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, DefaultHashBuilder};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: CarCache<isize, &str> = CarCache::with_hasher(10, s).unwrap();
+    /// ```
+    pub fn with_hasher(size: usize, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(size, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback> CarCache<K, V, E> {
+    /// Creates a new `CarCache` that holds at most `size` items and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, CarCache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = CarCache::with_on_evict_cb(1, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(size: usize, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(size, Some(cb), DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> CarCache<K, V, E, S> {
+    /// Creates a new `CarCache` that holds at most `size` items,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, DefaultEvictCallback, DefaultHashBuilder};
+    ///
+    /// let mut cache: CarCache<isize, &str> = CarCache::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(size: usize, cb: E, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(size, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        size: usize,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        if size == 0 || size >= (u32::MAX / 3) as usize {
+            return Err(CacheError::InvalidSize(size));
+        }
+
+        Ok(Self {
+            arena: Arena::with_hasher(2 * size, 4, hasher),
+            size,
+            p: 0,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// Returns the current partition value of the cache, the target size of the recent clock.
+    pub fn partition(&self) -> usize {
+        self.p
+    }
+
+    /// Returns the number of key-value pairs that are currently in the recent clock.
+    pub fn recent_len(&self) -> usize {
+        self.arena.list_len(RECENT)
+    }
+
+    /// Returns the number of key-value pairs that are currently in the frequent clock.
+    pub fn frequent_len(&self) -> usize {
+        self.arena.list_len(FREQUENT)
+    }
+
+    /// Returns the number of keys that are currently in the recent evict LRU.
+    pub fn recent_evict_len(&self) -> usize {
+        self.arena.list_len(RECENT_EVICT)
+    }
+
+    /// Returns the number of keys that are currently in the frequent evict LRU.
+    pub fn frequent_evict_len(&self) -> usize {
+        self.arena.list_len(FREQUENT_EVICT)
+    }
+
+    /// An iterator visiting all keys of the recent evict LRU in most-recently evicted order.
+    /// The iterator element type is `&'a K`.
+    pub fn recent_evict_keys(&self) -> ArenaKeys<'_, K, V> {
+        self.arena.keys(RECENT_EVICT)
+    }
+
+    /// An iterator visiting all keys of the frequent evict LRU in most-recently evicted order.
+    /// The iterator element type is `&'a K`.
+    pub fn frequent_evict_keys(&self) -> ArenaKeys<'_, K, V> {
+        self.arena.keys(FREQUENT_EVICT)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CarCache};
+        /// let mut cache = CarCache::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.get(&1);
+        /// cache.put(2, "b");
+        /// cache.put(3, "c");
+        /// // 2 is in the ghost list of the recent clock
+        /// cache.put(2, "b");
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 4);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CarCache, CacheStats};
+        /// let mut cache = CarCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    // Returns the index of the key if it is in the cache, the ghost keys are skipped.
+    fn index<Q>(&self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena
+            .index(k)
+            .filter(|idx| self.arena.val(*idx).is_some())
+    }
+
+    // Returns the index of the key and sets its reference bit if it is in the cache.
+    fn hit<Q>(&mut self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.set_referenced(idx, true);
+        Some(idx)
+    }
+
+    fn remove_lru(&mut self, list: usize) {
+        if let Some(idx) = self.arena.back(list) {
+            self.arena.remove(idx);
+        }
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> CarCache<K, V, E, S> {
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let ghost = match self.arena.index(&KeyRef { k: &k }) {
+            Some(idx) if self.arena.val(idx).is_some() => {
+                let old = self.arena.replace_val(idx, Some(v)).unwrap();
+                self.arena.set_referenced(idx, true);
+                self.cb(&k, &old, EvictionReason::Replaced);
+                return PutResult::Update(old);
+            }
+            ghost => ghost,
+        };
+
+        let evicted = if self.len() >= self.size {
+            self.replace()
+        } else {
+            None
+        };
+
+        match ghost {
+            None => {
+                // keep the size of the ghost lists trim
+                if self.recent_len() + self.recent_evict_len() >= self.size {
+                    self.remove_lru(RECENT_EVICT);
+                } else if self.len() + self.recent_evict_len() + self.frequent_evict_len()
+                    >= 2 * self.size
+                {
+                    self.remove_lru(FREQUENT_EVICT);
+                }
+
+                // add to the recent clock
+                self.arena.push_front(RECENT, k, Some(v));
+            }
+            Some(idx) => {
+                self.stats.record_ghost_hit();
+                let recent_evict_len = self.recent_evict_len();
+                let freq_evict_len = self.frequent_evict_len();
+                if self.arena.list(idx) == RECENT_EVICT {
+                    // the recent clock is too small, increase p appropriately
+                    let delta = (freq_evict_len / recent_evict_len).max(1);
+                    self.p = (self.p + delta).min(self.size);
+                } else {
+                    // the frequent clock is too small, decrease p appropriately
+                    let delta = (recent_evict_len / freq_evict_len).max(1);
+                    self.p = self.p.saturating_sub(delta);
+                }
+
+                // add the key to the frequent clock
+                self.arena.replace_val(idx, Some(v));
+                self.arena.move_to_front(idx, FREQUENT);
+            }
+        }
+
+        match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
+        }
+    }
+
+    // Sweeps the hands of the clocks until an unreferenced entry is found, which is
+    // evicted to the ghost list of its clock. The hand of the recent clock is moved
+    // if the recent clock is larger than `p`, otherwise the hand of the frequent clock.
+    fn replace(&mut self) -> Option<(K, V)> {
+        loop {
+            let (from, to) = if self.recent_len() >= self.p.max(1) {
+                (RECENT, RECENT_EVICT)
+            } else {
+                (FREQUENT, FREQUENT_EVICT)
+            };
+
+            let idx = self.arena.back(from)?;
+
+            // the referenced entries get a second chance in the frequent clock
+            if self.arena.referenced(idx) {
+                self.arena.set_referenced(idx, false);
+                self.arena.move_to_front(idx, FREQUENT);
+                continue;
+            }
+
+            // only the key is remembered, the entry is returned to the caller
+            let v = self.arena.replace_val(idx, None).unwrap();
+            self.arena.move_to_front(idx, to);
+            let k = self.arena.key(idx).clone();
+            self.cb(&k, &v, EvictionReason::Capacity);
+            return Some((k, v));
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V>
+    for CarCache<K, V, E, S>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// A new key is put to the recent clock, a key in one of the ghost lists is put to
+    /// the frequent clock.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache, PutResult};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(1, "b"), PutResult::Update("a"));
+    /// assert_eq!(cache.put(2, "c"), PutResult::Put);
+    /// assert_eq!(cache.put(3, "d"), PutResult::Evicted { key: 2, value: "c" });
+    /// ```
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the reference bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val(idx)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the reference bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not set the reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.val(self.arena.index(k)?)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not set the
+    /// reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not set the
+    /// reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // the ghost entry of the key is removed as well
+        let (key, val) = self.arena.remove(self.arena.index(k)?);
+        let val = val?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache: CarCache<isize, &str> = CarCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        for &list in &[RECENT, FREQUENT] {
+            while let Some(idx) = self.arena.back(list) {
+                let (k, v) = self.arena.remove(idx);
+                self.cb(&k, &v.unwrap(), EvictionReason::Purged);
+            }
+        }
+        for &list in &[RECENT_EVICT, FREQUENT_EVICT] {
+            while let Some(idx) = self.arena.back(list) {
+                self.arena.remove(idx);
+            }
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache = CarCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.recent_len() + self.frequent_len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{CarCache, Cache};
+    /// let mut cache: CarCache<isize, &str> = CarCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for CarCache<K, V, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a ghost key is put to the frequent clock, other keys to the recent clock
+        let list = match self.arena.index(&KeyRef { k: &k }) {
+            Some(_) => FREQUENT,
+            None => RECENT,
+        };
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(list)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> fmt::Debug for CarCache<K, V, E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("CarCache")
+            .field("len", &(self.recent_len() + self.frequent_len()))
+            .field("cap", &self.size)
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, E: Send, S: Send> Send for CarCache<K, V, E, S> {}
+unsafe impl<K: Sync, V: Sync, E: Sync, S: Sync> Sync for CarCache<K, V, E, S> {}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::CarCache;
+    use crate::{Cache, PutResult};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::hash::{BuildHasherDefault, Hasher};
+
+    // every key has the same hash, so the ghost keys can only be told apart by comparing them.
+    #[derive(Default)]
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn test_car_cache_adaptation() {
+        let mut cache = CarCache::new(4).unwrap();
+        (0..4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+
+        // a hit does not move the entries, the hand of the recent clock moves
+        // the referenced entries to the frequent clock when the cache is full.
+        cache.get(&0);
+        cache.get(&1);
+        assert_eq!(cache.recent_len(), 4);
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(cache.recent_len(), 2);
+        assert_eq!(cache.frequent_len(), 2);
+        assert_eq!(cache.recent_evict_len(), 1);
+
+        // a hit in B1 increases p, and the key is put to the frequent clock
+        assert_eq!(cache.put(2, 2), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.partition(), 1);
+        assert_eq!(cache.frequent_len(), 3);
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(cache.partition(), 2);
+        assert_eq!(cache.recent_len(), 0);
+
+        // the recent clock is smaller than p, the hand of the frequent clock evicts to B2
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 0, value: 0 });
+        assert_eq!(cache.frequent_evict_len(), 1);
+
+        // a hit in B2 decreases p
+        assert_eq!(cache.put(0, 0), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.partition(), 1);
+        assert_eq!(cache.frequent_len(), 3);
+        assert_eq!(cache.recent_evict_len(), 1);
+        assert_eq!(cache.frequent_evict_len(), 1);
+    }
+
+    #[test]
+    fn test_car_cache_ghosts() {
+        let mut cache = CarCache::new(4).unwrap();
+
+        // a scan only goes through the recent clock, T1 and B1 hold at most `size` keys
+        for k in 0..10u64 {
+            cache.put(k, k);
+            assert!(cache.recent_len() + cache.recent_evict_len() <= 4);
+        }
+        assert_eq!(cache.recent_evict_len(), 0);
+
+        cache.get(&6);
+        cache.get(&7);
+        assert_eq!(cache.put(10, 10), PutResult::Evicted { key: 8, value: 8 });
+        assert_eq!(cache.recent_evict_len(), 1);
+
+        // removing a key forgets its ghost entry as well, so it is not a hit in B1 anymore
+        assert_eq!(cache.remove(&8), None);
+        assert_eq!(cache.recent_evict_len(), 0);
+        assert_eq!(cache.put(8, 8), PutResult::Evicted { key: 9, value: 9 });
+        assert_eq!(cache.partition(), 0);
+        assert_eq!(cache.recent_len(), 2);
+    }
+
+    #[test]
+    fn test_car_cache_ghosts_compare_keys() {
+        let hasher = BuildHasherDefault::<CollidingHasher>::default();
+        let mut cache = CarCache::with_hasher(4, hasher).unwrap();
+        (0..4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        cache.get(&0);
+        cache.get(&1);
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(cache.recent_evict_keys().collect::<Vec<_>>(), vec![&2]);
+
+        // 5 has the same hash as the ghost key 2, but it is a new key
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.partition(), 0);
+        assert_eq!(cache.frequent_len(), 2);
+
+        // 2 is a hit in B1
+        assert_eq!(cache.put(2, 2), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(cache.partition(), 1);
+        assert_eq!(cache.frequent_len(), 3);
+    }
+}
@@ -0,0 +1,736 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder,
+    EvictionReason, KeyRef, OnEvictCallback, PutResult, ResizableCache,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+use core::sync::atomic::{AtomicBool, Ordering};
+
+import_hashbrown!(HashMap);
+import_std!(HashMap);
+
+// A slot of the clock. The reference bit is atomic, so a hit can set it through `&self`.
+struct ClockEntry<K, V> {
+    key: K,
+    val: V,
+    referenced: AtomicBool,
+}
+
+/// `ClockCache` is a fixed size cache which approximates LRU with the CLOCK algorithm.
+///
+/// The entries live in a circular buffer, each of them with a reference bit. A hit only
+/// sets the bit of the entry, and when the cache is full the hand sweeps the buffer:
+/// an entry whose bit is set gets a second chance (the bit is cleared and the hand moves on),
+/// the first entry whose bit is clear is evicted and its slot is reused by the new entry.
+///
+/// Compared to [`LRUCache`], a hit does not relink any node, so it is cheaper and
+/// [`get_shared`] can even be called through a shared reference, e.g. under a read lock.
+/// The price is precision: the cache only knows whether an entry was used since the hand
+/// last passed it, not how recently. This is a good fit for large, read-mostly caches.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, ClockCache, PutResult};
+///
+/// let mut cache = ClockCache::new(3).unwrap();
+/// cache.put(1, 1);
+/// cache.put(2, 2);
+/// cache.put(3, 3);
+///
+/// // 1 is referenced, so the hand gives it a second chance and evicts 2
+/// assert_eq!(cache.get(&1), Some(&1));
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+///
+/// // the reference bit of 1 was cleared by the sweep
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+/// assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 1, value: 1 });
+/// ```
+///
+/// [`LRUCache`]: type.LRUCache.html
+/// [`get_shared`]: struct.ClockCache.html#method.get_shared
+pub struct ClockCache<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    map: HashMap<KeyRef<K>, usize, S>,
+    slots: Vec<Option<ClockEntry<K, V>>>,
+    free: Vec<usize>,
+    hand: usize,
+    cap: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> ClockCache<K, V> {
+    /// Creates a new `ClockCache` that holds at most `cap` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::ClockCache;
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::new(10).unwrap();
+    /// ```
+    pub fn new(cap: usize) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ClockCache<K, V, DefaultEvictCallback, S> {
+    /// Creates a new `ClockCache` that holds at most `cap` items and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ClockCache, DefaultHashBuilder};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::with_hasher(10, s).unwrap();
+    /// ```
+    pub fn with_hasher(cap: usize, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback> ClockCache<K, V, E> {
+    /// Creates a new `ClockCache` that holds at most `cap` items and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = ClockCache::with_on_evict_cb(1, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(cap: usize, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ClockCache<K, V, E, S> {
+    /// Creates a new `ClockCache` that holds at most `cap` items,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ClockCache, DefaultEvictCallback, DefaultHashBuilder};
+    ///
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(cap: usize, cb: E, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        cap: usize,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        if cap == 0 {
+            return Err(CacheError::InvalidSize(cap));
+        }
+
+        Ok(Self {
+            map: HashMap::with_hasher(hasher),
+            slots: Vec::new(),
+            free: Vec::new(),
+            hand: 0,
+            cap,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Like `get`, sets the reference bit of the entry, but takes
+    /// `&self`, so it can be called while the cache is shared, e.g. behind a `RwLock` read guard.
+    ///
+    /// The lookup is not counted in the statistics of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache, PutResult};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// let shared = &cache;
+    /// assert_eq!(shared.get_shared(&1), Some(&"a"));
+    /// assert_eq!(shared.get_shared(&3), None);
+    ///
+    /// // 1 was referenced, so 2 is evicted
+    /// assert_eq!(cache.put(3, "c"), PutResult::Evicted { key: 2, value: "b" });
+    /// ```
+    pub fn get_shared<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let ent = self.slots[*self.map.get(k)?].as_ref()?;
+        ent.referenced.store(true, Ordering::Relaxed);
+        Some(&ent.val)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, ClockCache};
+        /// let mut cache = ClockCache::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.get(&2);
+        ///
+        /// assert_eq!(cache.stats().inserts(), 2);
+        /// assert_eq!(cache.stats().evictions(), 1);
+        /// assert_eq!(cache.stats().hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, ClockCache, CacheStats};
+        /// let mut cache = ClockCache::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(&idx) = self.map.get(&KeyRef { k: &k }) {
+            let ent = self.slots[idx].as_mut().unwrap();
+            let old = core::mem::replace(&mut ent.val, v);
+            *ent.referenced.get_mut() = true;
+            self.cb(&k, &old, EvictionReason::Replaced);
+            return PutResult::Update(old);
+        }
+        self.put_new(k, v).0
+    }
+
+    // Puts a key which is not in the cache, returns the result and the slot of the new entry.
+    fn put_new(&mut self, k: K, v: V) -> (PutResult<K, V>, usize) {
+        // the slot of the evicted entry is reused by the new entry, right behind the hand.
+        let evicted = if self.map.len() >= self.cap {
+            self.evict_with(EvictionReason::Capacity)
+        } else {
+            None
+        };
+        let idx = self.insert(k, v);
+
+        let rst = match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
+        };
+        (rst, idx)
+    }
+
+    fn insert(&mut self, k: K, v: V) -> usize {
+        let ent = Some(ClockEntry {
+            key: k,
+            val: v,
+            referenced: AtomicBool::new(false),
+        });
+
+        let idx = match self.free.pop() {
+            Some(idx) => {
+                self.slots[idx] = ent;
+                idx
+            }
+            None => {
+                let ptr = self.slots.as_ptr();
+                self.slots.push(ent);
+                // the keys referenced by the map are moved if the slots are reallocated.
+                if ptr != self.slots.as_ptr() {
+                    self.rebuild_map();
+                }
+                self.slots.len() - 1
+            }
+        };
+
+        let k: *const K = &self.slots[idx].as_ref().unwrap().key;
+        self.map.insert(KeyRef { k }, idx);
+        idx
+    }
+
+    /// Sweeps the hand until it finds an entry whose reference bit is clear, and evicts it.
+    /// The reference bits of the entries passed over are cleared.
+    fn evict_with(&mut self, reason: EvictionReason) -> Option<(K, V)> {
+        if self.map.is_empty() {
+            return None;
+        }
+
+        loop {
+            let idx = self.hand;
+            self.hand = (self.hand + 1) % self.slots.len();
+            if let Some(ent) = self.slots[idx].as_mut() {
+                let referenced = ent.referenced.get_mut();
+                if *referenced {
+                    *referenced = false;
+                } else {
+                    return Some(self.remove_at(idx, reason));
+                }
+            }
+        }
+    }
+
+    fn remove_at(&mut self, idx: usize, reason: EvictionReason) -> (K, V) {
+        let k: *const K = &self.slots[idx].as_ref().unwrap().key;
+        self.map.remove(&KeyRef { k });
+        let ent = self.slots[idx].take().unwrap();
+        self.free.push(idx);
+        self.cb(&ent.key, &ent.val, reason);
+        (ent.key, ent.val)
+    }
+
+    /// Moves the entries to the front of the buffer in clock order, starting from the hand,
+    /// so the buffer has no free slots.
+    fn compact(&mut self) {
+        let len = self.slots.len();
+        let mut slots = Vec::with_capacity(self.map.len());
+        for i in 0..len {
+            if let Some(ent) = self.slots[(self.hand + i) % len].take() {
+                slots.push(Some(ent));
+            }
+        }
+        self.slots = slots;
+        self.free.clear();
+        self.hand = 0;
+        self.rebuild_map();
+    }
+
+    fn rebuild_map(&mut self) {
+        self.map.clear();
+        for (idx, ent) in self.slots.iter().enumerate() {
+            if let Some(ent) = ent.as_ref() {
+                self.map.insert(KeyRef { k: &ent.key }, idx);
+            }
+        }
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for ClockCache<K, V, E, S> {
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// Updating the value of a key sets its reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache, PutResult};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
+    /// assert_eq!(PutResult::Evicted { key: 1, value: "a" }, cache.put(3, "c"));
+    ///
+    /// assert_eq!(cache.get(&2), Some(&"beta"));
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Sets the reference bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"b"));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.map.get(k).copied();
+        self.stats.record_get(idx.is_some());
+        let ent = self.slots[idx?].as_mut()?;
+        *ent.referenced.get_mut() = true;
+        Some(&ent.val)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the reference bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// *cache.get_mut(&"apple").unwrap() = 9;
+    ///
+    /// assert_eq!(cache.get(&"apple"), Some(&9));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.map.get(k).copied();
+        self.stats.record_get(idx.is_some());
+        let ent = self.slots[idx?].as_mut()?;
+        *ent.referenced.get_mut() = true;
+        Some(&mut ent.val)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not set the reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache, PutResult};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.put(3, "c"), PutResult::Evicted { key: 1, value: "a" });
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.slots[*self.map.get(k)?].as_ref().map(|ent| &ent.val)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not set the
+    /// reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// *cache.peek_mut(&1).unwrap() = "b";
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = *self.map.get(k)?;
+        self.slots[idx].as_mut().map(|ent| &mut ent.val)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not set the
+    /// reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(k)
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = *self.map.get(k)?;
+        Some(self.remove_at(idx, EvictionReason::Removed).1)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.purge();
+    ///
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        self.map.clear();
+        self.free.clear();
+        self.hand = 0;
+        let slots = core::mem::take(&mut self.slots);
+        for ent in slots.into_iter().flatten() {
+            self.cb(&ent.key, &ent.val, EvictionReason::Purged);
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns a bool indicating whether the cache is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache};
+    /// let mut cache = ClockCache::new(2).unwrap();
+    /// assert!(cache.is_empty());
+    ///
+    /// cache.put(1, "a");
+    /// assert!(!cache.is_empty());
+    /// ```
+    fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for ClockCache<K, V, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the lookup of the entry has found that the key is not in the cache
+        let (rst, idx) = self.put_new(k, v);
+        self.stats.record_put(&rst);
+        let v = self.slots[idx]
+            .as_mut()
+            .map(|ent| NonNull::from(&mut ent.val));
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ResizableCache
+    for ClockCache<K, V, E, S>
+{
+    /// Resizes the cache. If the new capacity is smaller than the size of the current
+    /// cache, the hand sweeps the buffer and evicts entries until they fit. The remaining
+    /// entries are compacted in clock order.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockCache, ResizableCache};
+    /// let mut cache: ClockCache<isize, &str> = ClockCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.resize(4);
+    /// cache.put(3, "c");
+    /// cache.put(4, "d");
+    /// cache.get(&4);
+    ///
+    /// assert_eq!(cache.len(), 4);
+    /// assert_eq!(cache.resize(1), 3);
+    /// assert_eq!(cache.get(&4), Some(&"d"));
+    /// ```
+    fn resize(&mut self, cap: usize) -> u64 {
+        let mut evicted = 0u64;
+        while self.map.len() > cap {
+            self.evict_with(EvictionReason::Resized);
+            evicted += 1;
+        }
+        self.compact();
+        self.cap = cap;
+        evicted
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> fmt::Debug for ClockCache<K, V, E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClockCache")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, E: Send, S: Send> Send for ClockCache<K, V, E, S> {}
+unsafe impl<K: Sync, V: Sync, E: Sync, S: Sync> Sync for ClockCache<K, V, E, S> {}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::ClockCache;
+    use crate::{Cache, PutResult, ResizableCache};
+
+    #[test]
+    fn test_clock_cache_second_chance() {
+        let mut cache = ClockCache::new(4).unwrap();
+        (0..4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        cache.get(&0);
+        cache.get(&2);
+
+        // the hand skips the referenced entries and clears their bits.
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 0, value: 0 });
+
+        // a new entry is not referenced, so it is evicted before the entries passed over.
+        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 4, value: 4 });
+
+        // the slots of the evicted entries are reused, the buffer does not grow.
+        assert_eq!(cache.slots.len(), 4);
+        assert!([2u64, 5, 6, 7].iter().all(|i| cache.peek(i) == Some(i)));
+    }
+
+    #[test]
+    fn test_clock_cache_remove() {
+        let mut cache = ClockCache::new(3).unwrap();
+        (0..3u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(cache.remove(&1), Some(1));
+
+        // the slot of the removed entry is reused, so the new entry is swept in its place.
+        assert_eq!(cache.put(3, 3), PutResult::Put);
+        assert_eq!(cache.slots.len(), 3);
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 0, value: 0 });
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 2, value: 2 });
+    }
+
+    #[test]
+    fn test_clock_cache_resize() {
+        let mut cache = ClockCache::new(4).unwrap();
+        (0..4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        cache.get(&1);
+
+        // the hand sweeps 0, 1 and 2, only 1 gets a second chance.
+        assert_eq!(cache.resize(2), 2);
+        assert_eq!(cache.slots.len(), 2);
+        assert!(!cache.contains(&0) && !cache.contains(&2));
+
+        // the entries are compacted in clock order from the hand, and the bit of 1 is cleared.
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+
+        cache.resize(3);
+        assert_eq!(cache.put(6, 6), PutResult::Put);
+        assert_eq!(cache.len(), 3);
+    }
+}
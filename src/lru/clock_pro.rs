@@ -0,0 +1,1000 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::Arena;
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+// the clock is an arena list, the hands move from the front to the back and wrap around.
+// The test pages are the ghost entries of the arena, and the `freq` of a resident page
+// keeps its reference bit and whether it is hot.
+const CLOCK: usize = 0;
+const REFERENCED: u8 = 1;
+const HOT: u8 = 2;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Page {
+    Hot,
+    Cold,
+    // a non-resident cold page in its test period, only the key is kept.
+    Test,
+}
+
+/// `ClockProCache` is a fixed size CLOCK-Pro cache.
+///
+/// CLOCK-Pro brings the scan resistance of [`LIRSCache`] to the cost of [`ClockCache`].
+/// All the pages are kept on a single clock, and a hit only sets the reference bit of
+/// the page. The pages are either hot, cold or test pages:
+///
+/// - hot pages are the frequently used entries, the hot hand demotes the ones which were
+///   not referenced since it last passed them to cold pages.
+/// - cold pages are the other entries. The cold hand promotes a referenced cold page to
+///   a hot page, and evicts the value of an unreferenced one, which becomes a test page.
+/// - test pages only keep the key of a recently evicted cold page. If the key is put again
+///   during its test period, it comes back as a hot page. The test hand ends the test periods.
+///
+/// The share of the cache for cold pages adapts to the workload: a hit on a test page grows it,
+/// and a test page whose period ends without a hit shrinks it. It starts with the whole cache,
+/// and at least one page is always left to cold pages. At most `cap` test pages are kept.
+///
+/// All the pages are stored in one arena, which is preallocated to `2 * cap` pages. The key of
+/// an evicted page is cloned, as it is both returned and kept by the test page.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, ClockProCache, PutResult};
+///
+/// let mut cache = ClockProCache::new(2).unwrap();
+/// cache.put(1, 1);
+/// cache.put(2, 2);
+/// assert_eq!(cache.cold_len(), 2);
+///
+/// // 1 is evicted, and kept as a test page
+/// assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 1, value: 1 });
+/// assert_eq!(cache.get(&1), None);
+/// assert_eq!(cache.test_len(), 1);
+///
+/// // 1 is put again during its test period, which evicts 2
+/// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.get(&1), Some(&1));
+/// assert_eq!(cache.get(&2), None);
+/// assert_eq!(cache.test_len(), 1);
+/// assert_eq!(cache.len(), 2);
+/// ```
+///
+/// [`LIRSCache`]: struct.LIRSCache.html
+/// [`ClockCache`]: struct.ClockCache.html
+pub struct ClockProCache<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    arena: Arena<K, V, S>,
+    hand_hot: Option<u32>,
+    hand_cold: Option<u32>,
+    hand_test: Option<u32>,
+    hot_len: usize,
+    cold_len: usize,
+    test_len: usize,
+    cold_cap: usize,
+    cap: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> ClockProCache<K, V> {
+    /// Creates a new `ClockProCache` that holds at most `cap` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::ClockProCache;
+    /// let mut cache: ClockProCache<isize, &str> = ClockProCache::new(10).unwrap();
+    /// ```
+    pub fn new(cap: usize) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> ClockProCache<K, V, DefaultEvictCallback, S> {
+    /// Creates a new `ClockProCache` that holds at most `cap` items and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ClockProCache, DefaultHashBuilder};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: ClockProCache<isize, &str> = ClockProCache::with_hasher(10, s).unwrap();
+    /// ```
+    pub fn with_hasher(cap: usize, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback> ClockProCache<K, V, E> {
+    /// Creates a new `ClockProCache` that holds at most `cap` items and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = ClockProCache::with_on_evict_cb(1, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(cap: usize, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ClockProCache<K, V, E, S> {
+    /// Creates a new `ClockProCache` that holds at most `cap` items,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ClockProCache, DefaultEvictCallback, DefaultHashBuilder};
+    ///
+    /// let mut cache: ClockProCache<isize, &str> = ClockProCache::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(cap: usize, cb: E, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        cap: usize,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        if cap == 0 {
+            return Err(CacheError::InvalidSize(cap));
+        }
+
+        Ok(Self {
+            arena: Arena::with_hasher(2 * cap, 1, hasher),
+            hand_hot: None,
+            hand_cold: None,
+            hand_test: None,
+            hot_len: 0,
+            cold_len: 0,
+            test_len: 0,
+            cold_cap: cap,
+            cap,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// Returns the number of hot pages in the cache.
+    pub fn hot_len(&self) -> usize {
+        self.hot_len
+    }
+
+    /// Returns the number of resident cold pages in the cache.
+    pub fn cold_len(&self) -> usize {
+        self.cold_len
+    }
+
+    /// Returns the number of test pages, the evicted cold pages in their test period.
+    pub fn test_len(&self) -> usize {
+        self.test_len
+    }
+
+    /// Returns the current share of the cache for cold pages, which is adapted to the workload.
+    pub fn cold_cap(&self) -> usize {
+        self.cold_cap
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// A hit on a test page is counted as a ghost hit.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, ClockProCache};
+        /// let mut cache = ClockProCache::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.put(1, "a");
+        ///
+        /// assert_eq!(cache.stats().inserts(), 3);
+        /// assert_eq!(cache.stats().evictions(), 2);
+        /// assert_eq!(cache.stats().ghost_hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, ClockProCache, CacheStats};
+        /// let mut cache = ClockProCache::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    // Returns the index of the resident page of the key, the test pages are skipped.
+    fn index<Q>(&self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena
+            .index(k)
+            .filter(|idx| self.page(*idx) != Page::Test)
+    }
+
+    fn get_index<Q>(&mut self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.set_referenced(idx);
+        Some(idx)
+    }
+
+    fn page(&self, idx: u32) -> Page {
+        if self.arena.val(idx).is_none() {
+            Page::Test
+        } else if self.arena.freq(idx) & HOT != 0 {
+            Page::Hot
+        } else {
+            Page::Cold
+        }
+    }
+
+    fn set_referenced(&mut self, idx: u32) {
+        let freq = self.arena.freq(idx);
+        self.arena.set_freq(idx, freq | REFERENCED);
+    }
+
+    // Returns the page after the page on the clock.
+    fn next(&self, idx: u32) -> u32 {
+        self.arena
+            .next(idx)
+            .or_else(|| self.arena.front(CLOCK))
+            .unwrap()
+    }
+
+    // Runs the hot hand until the hot pages fit in their share of the cache,
+    // and the test hand until at most `cap` test pages are left.
+    //
+    // As the cold share is at least 1, there is always a cold page for the cold hand
+    // when the cache is full.
+    fn balance(&mut self) {
+        while self.hot_len > self.cap - self.cold_cap {
+            self.run_hand_hot();
+        }
+        while self.test_len > self.cap {
+            self.run_hand_test();
+        }
+    }
+
+    // Moves the hot hand to the next hot page which is not referenced, and demotes it to
+    // a cold page. The reference bits of the hot pages passed over are cleared.
+    fn run_hand_hot(&mut self) {
+        loop {
+            let idx = self.hand_hot.unwrap();
+            self.hand_hot = Some(self.next(idx));
+            if self.page(idx) != Page::Hot {
+                continue;
+            }
+
+            if self.arena.freq(idx) & REFERENCED != 0 {
+                self.arena.set_freq(idx, HOT);
+            } else {
+                self.arena.set_freq(idx, 0);
+                self.hot_len -= 1;
+                self.cold_len += 1;
+                return;
+            }
+        }
+    }
+
+    // Moves the test hand to the next test page and ends its test period.
+    fn run_hand_test(&mut self) {
+        loop {
+            let idx = self.hand_test.unwrap();
+            self.hand_test = Some(self.next(idx));
+            if self.page(idx) != Page::Test {
+                continue;
+            }
+
+            // the test period ends without a hit, the cold pages deserve less room.
+            self.remove_page(idx);
+            if self.cold_cap > 1 {
+                self.cold_cap -= 1;
+            }
+            return;
+        }
+    }
+
+    // Inserts the page right behind the hot hand, which is the head of the clock.
+    fn insert(&mut self, k: K, v: V, freq: u8) -> u32 {
+        let idx = match self.hand_hot {
+            Some(hand) => self.arena.insert_before(hand, k, Some(v)),
+            None => {
+                let idx = self.arena.push_front(CLOCK, k, Some(v));
+                self.hand_hot = Some(idx);
+                self.hand_cold = Some(idx);
+                self.hand_test = Some(idx);
+                idx
+            }
+        };
+        self.arena.set_freq(idx, freq);
+        idx
+    }
+
+    // Removes the page from the clock and from the count of its kind of pages.
+    fn remove_page(&mut self, idx: u32) -> (K, Option<V>) {
+        match self.page(idx) {
+            Page::Hot => self.hot_len -= 1,
+            Page::Cold => self.cold_len -= 1,
+            Page::Test => self.test_len -= 1,
+        }
+        self.unlink(idx);
+        self.arena.remove(idx)
+    }
+
+    // Moves the hands on the page back to the previous page, before it is removed from the clock.
+    fn unlink(&mut self, idx: u32) {
+        let prev = self
+            .arena
+            .prev(idx)
+            .or_else(|| self.arena.back(CLOCK))
+            .filter(|prev| *prev != idx);
+        for hand in [&mut self.hand_hot, &mut self.hand_cold, &mut self.hand_test] {
+            if *hand == Some(idx) {
+                *hand = prev;
+            }
+        }
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> ClockProCache<K, V, E, S> {
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let test = match self.arena.index(&KeyRef { k: &k }) {
+            Some(idx) if self.page(idx) != Page::Test => {
+                let old = self.arena.replace_val(idx, Some(v)).unwrap();
+                self.set_referenced(idx);
+                self.cb(&k, &old, EvictionReason::Replaced);
+                return PutResult::Update(old);
+            }
+            test => test,
+        };
+        self.put_new(k, v, test).0
+    }
+
+    // Puts a key which is not resident, `test` is the page of the key if it is in its test
+    // period. Returns the result and the page of the new entry.
+    fn put_new(&mut self, k: K, v: V, test: Option<u32>) -> (PutResult<K, V>, u32) {
+        let (evicted, idx) = match test {
+            None => {
+                let evicted = self.evict();
+                let idx = self.insert(k, v, 0);
+                self.cold_len += 1;
+                (evicted, idx)
+            }
+            Some(idx) => {
+                // the key is put again during its test period, the cold pages deserve more room
+                // and the page comes back as a hot page.
+                self.stats.record_ghost_hit();
+                if self.cold_cap < self.cap {
+                    self.cold_cap += 1;
+                }
+                self.remove_page(idx);
+                let evicted = self.evict();
+                let idx = self.insert(k, v, HOT);
+                self.hot_len += 1;
+                self.balance();
+                (evicted, idx)
+            }
+        };
+
+        let rst = match evicted {
+            Some((key, value)) => {
+                // the cache is consistent before the callback runs, even if it panics
+                self.cb(&key, &value, EvictionReason::Capacity);
+                PutResult::Evicted { key, value }
+            }
+            None => PutResult::Put,
+        };
+        (rst, idx)
+    }
+
+    // Runs the cold hand until there is room for a new resident page, returns the evicted entry.
+    fn evict(&mut self) -> Option<(K, V)> {
+        let mut evicted = None;
+        // the cache is full with `cap` resident pages at most, so one entry is evicted at most.
+        while self.hot_len + self.cold_len >= self.cap {
+            evicted = self.run_hand_cold().or(evicted);
+            self.balance();
+        }
+        evicted
+    }
+
+    // Moves the cold hand to the next cold page, which is promoted to a hot page if it is
+    // referenced, otherwise it is evicted and becomes a test page.
+    fn run_hand_cold(&mut self) -> Option<(K, V)> {
+        loop {
+            let idx = self.hand_cold.unwrap();
+            self.hand_cold = Some(self.next(idx));
+            if self.page(idx) != Page::Cold {
+                continue;
+            }
+
+            if self.arena.freq(idx) & REFERENCED != 0 {
+                self.arena.set_freq(idx, HOT);
+                self.cold_len -= 1;
+                self.hot_len += 1;
+                return None;
+            }
+
+            // the page stays on the clock as a test page, which only keeps the key.
+            let val = self.arena.replace_val(idx, None).unwrap();
+            let key = self.arena.key(idx).clone();
+            self.cold_len -= 1;
+            self.test_len += 1;
+            return Some((key, val));
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V>
+    for ClockProCache<K, V, E, S>
+{
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// A new key is put as a cold page, a key in its test period comes back as a hot page.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache, PutResult};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
+    /// assert_eq!(PutResult::Evicted { key: 1, value: "a" }, cache.put(3, "c"));
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"beta"));
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Sets the reference bit of the page if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"b"));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.get_index(k)?;
+        self.arena.val(idx)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the reference bit of the page if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// *cache.get_mut(&"apple").unwrap() = 9;
+    ///
+    /// assert_eq!(cache.get(&"apple"), Some(&9));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.get_index(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not set the reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.val(self.arena.index(k)?)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not set the
+    /// reference bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// *cache.peek_mut(&1).unwrap() = "b";
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.index(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not set the
+    /// reference bit, and the keys of the test pages are not in the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist. The test page of the key is also removed.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        let (key, val) = self.remove_page(idx);
+        let val = val?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache, including the test pages.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache: ClockProCache<isize, &str> = ClockProCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// cache.purge();
+    ///
+    /// assert_eq!(cache.len(), 0);
+    /// assert_eq!(cache.test_len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        self.cold_cap = self.cap;
+        // the hands and the counts follow each removed page, so the cache stays consistent
+        // if a callback panics.
+        while let Some(idx) = self.arena.back(CLOCK) {
+            if let (k, Some(v)) = self.remove_page(idx) {
+                self.cb(&k, &v, EvictionReason::Purged);
+            }
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    fn len(&self) -> usize {
+        self.hot_len + self.cold_len
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache: ClockProCache<isize, &str> = ClockProCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.cap
+    }
+
+    /// Returns a bool indicating whether the cache is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ClockProCache};
+    /// let mut cache = ClockProCache::new(2).unwrap();
+    /// assert!(cache.is_empty());
+    ///
+    /// cache.put(1, "a");
+    /// assert!(!cache.is_empty());
+    /// ```
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for ClockProCache<K, V, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the lookup of the entry has found that the key is not resident
+        let test = self.arena.index(&KeyRef { k: &k });
+        let (rst, idx) = self.put_new(k, v, test);
+        self.stats.record_put(&rst);
+        (rst, self.arena.val_mut(idx).map(NonNull::from))
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> fmt::Debug for ClockProCache<K, V, E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ClockProCache")
+            .field("len", &(self.hot_len + self.cold_len))
+            .field("cap", &self.cap)
+            .field("hot_len", &self.hot_len)
+            .field("cold_len", &self.cold_len)
+            .field("test_len", &self.test_len)
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, E: Send, S: Send> Send for ClockProCache<K, V, E, S> {}
+unsafe impl<K: Sync, V: Sync, E: Sync, S: Sync> Sync for ClockProCache<K, V, E, S> {}
+
+#[cfg(test)]
+mod test {
+    use super::CLOCK;
+    use crate::lru::ClockProCache;
+    use crate::{Cache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use core::hash::{BuildHasherDefault, Hasher};
+
+    // every key has the same hash, so the ghost keys can only be told apart by comparing them.
+    #[derive(Default)]
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+    use std::collections::HashMap;
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_clock_pro_cache_scan_resistance() {
+        let mut cache = ClockProCache::new(100).unwrap();
+
+        // the working set is reused after a scan, its pages become hot
+        for round in 0..5u64 {
+            for k in 0..50u64 {
+                if cache.get(&k).is_none() {
+                    cache.put(k, k);
+                }
+            }
+            for k in 1000 + round * 100..1100 + round * 100 {
+                cache.put(k, k);
+            }
+        }
+        assert!(cache.hot_len() > 0);
+
+        // a long scan of keys which are used only once does not flush the working set,
+        // even if the working set is only used once every 100 keys of the scan.
+        for k in 10_000..20_000u64 {
+            cache.put(k, k);
+            if k % 100 == 0 {
+                for i in 0..50u64 {
+                    cache.get(&i);
+                }
+            }
+        }
+        assert!((0..50u64).filter(|k| cache.contains(k)).count() >= 40);
+        assert!(cache.test_len() <= cache.cap());
+    }
+
+    #[test]
+    fn test_clock_pro_cache_adaptation() {
+        let mut cache = ClockProCache::new(2).unwrap();
+        cache.put(0, 0);
+        cache.put(1, 1);
+
+        // a scan evicts every cold page, the test period of 0 ends without a hit,
+        // so the share of the cold pages shrinks.
+        assert_eq!(cache.put(2, 2), PutResult::Evicted { key: 0, value: 0 });
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(cache.test_len(), 2);
+        assert_eq!(cache.cold_cap(), 1);
+
+        // the cold hand promotes the referenced 3 to a hot page, and evicts 4 instead.
+        cache.get(&3);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(cache.hot_len(), 1);
+        assert_eq!(cache.cold_len(), 1);
+
+        // 2 is put again during its test period, the share of the cold pages grows back
+        // and the hot hand demotes 3.
+        assert_eq!(cache.put(2, 2), PutResult::Evicted { key: 5, value: 5 });
+        assert_eq!(cache.cold_cap(), 2);
+        assert_eq!(cache.hot_len(), 0);
+        assert!(cache.contains(&2) && cache.contains(&3));
+        assert_eq!(cache.test_len(), 2);
+        assert_eq!(cache.arena.len(), 4);
+    }
+
+    #[test]
+    fn test_clock_pro_cache_remove() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache =
+            ClockProCache::with_on_evict_cb(2, EvictedCounter { ctr: ctr.clone() }).unwrap();
+        (0..3u64).for_each(|k| {
+            cache.put(k, k);
+        });
+        assert_eq!(cache.test_len(), 1);
+
+        // removing a key also ends its test period, so it is not a test hit when it is put again.
+        assert_eq!(cache.remove(&0), None);
+        assert_eq!(cache.test_len(), 0);
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.put(0, 0), PutResult::Put);
+        assert_eq!(cache.hot_len(), 0);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(ctr.load(Ordering::SeqCst), 2);
+
+        cache.purge();
+        assert_eq!(ctr.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.len(), 0);
+        assert!(cache.arena.front(CLOCK).is_none());
+    }
+
+    #[test]
+    fn test_clock_pro_cache_random_ops() {
+        for cap in [1, 2, 3, 8, 64] {
+            let ctr = Arc::new(AtomicUsize::new(0));
+            let mut cache =
+                ClockProCache::with_on_evict_cb(cap, EvictedCounter { ctr: ctr.clone() }).unwrap();
+            let mut model = HashMap::new();
+            let mut inserted = 0;
+            let mut rng = thread_rng();
+
+            (0..50_000u64).for_each(|i| {
+                let k = rng.gen::<u64>() % (cap as u64 * 4);
+                match rng.gen::<u64>() % 4 {
+                    0 | 1 => match cache.put(k, i) {
+                        PutResult::Put => {
+                            assert!(model.insert(k, i).is_none());
+                            inserted += 1;
+                        }
+                        PutResult::Update(old) => assert_eq!(model.insert(k, i), Some(old)),
+                        PutResult::Evicted { key, value } => {
+                            assert_eq!(model.remove(&key), Some(value));
+                            assert!(model.insert(k, i).is_none());
+                            inserted += 1;
+                        }
+                        _ => unreachable!(),
+                    },
+                    2 => assert_eq!(cache.get(&k), model.get(&k)),
+                    _ => assert_eq!(cache.remove(&k), model.remove(&k)),
+                }
+
+                assert_eq!(cache.len(), model.len());
+                assert_eq!(cache.len(), cache.hot_len() + cache.cold_len());
+                assert!(cache.len() <= cache.cap());
+                assert!(cache.test_len() <= cache.cap());
+                assert!(cache.cold_cap() >= 1 && cache.cold_cap() <= cache.cap());
+                assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+            });
+
+            assert!(model.iter().all(|(k, v)| cache.peek(k) == Some(v)));
+            cache.purge();
+            assert_eq!(cache.test_len(), 0);
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+        }
+    }
+
+    #[test]
+    fn test_clock_pro_cache_panicking_callback() {
+        struct PanicOnEvict;
+
+        impl OnEvictCallback for PanicOnEvict {
+            fn on_evict<K, V>(&self, _: &K, _: &V) {
+                panic!("on_evict");
+            }
+        }
+
+        let mut cache = ClockProCache::with_on_evict_cb(2, PanicOnEvict).unwrap();
+        cache.put(0, 0);
+        cache.put(1, 1);
+
+        // the cold hand evicts 0 to a test page and 2 is added before the callback panics
+        assert!(catch_unwind(AssertUnwindSafe(|| cache.put(2, 2))).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.test_len(), 1);
+        assert!(!cache.contains(&0));
+        assert_eq!(cache.peek(&2), Some(&2));
+
+        (3..5).for_each(|k| {
+            assert!(catch_unwind(AssertUnwindSafe(|| cache.put(k, k))).is_err());
+            assert_eq!(cache.len(), 2);
+            assert_eq!(cache.len(), (0..5).filter(|k| cache.contains(k)).count());
+        });
+        assert_eq!(cache.test_len(), 2);
+        assert_eq!(cache.cold_cap(), 1);
+
+        // every page removed before the callback panics is gone from the counts and the hands
+        while catch_unwind(AssertUnwindSafe(|| cache.purge())).is_err() {
+            assert_eq!(cache.len(), (0..5).filter(|k| cache.contains(k)).count());
+            assert!(cache.test_len() <= 2);
+        }
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.test_len(), 0);
+        assert!(cache.arena.front(CLOCK).is_none());
+
+        assert_eq!(cache.put(0, 0), PutResult::Put);
+        assert_eq!(cache.put(1, 1), PutResult::Put);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_clock_pro_cache_test_pages_compare_keys() {
+        let hasher = BuildHasherDefault::<CollidingHasher>::default();
+        let mut cache = ClockProCache::with_hasher(2, hasher).unwrap();
+        cache.put(1, 1);
+        cache.put(2, 2);
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.test_len(), 1);
+
+        // 4 has the same hash as the test page of 1, but it is a new key
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(cache.hot_len(), 0);
+        assert_eq!(cache.test_len(), 2);
+
+        // 1 is put again during its test period, which ends, and 3 becomes a test page
+        assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.test_len(), 2);
+        assert!(cache.contains(&1) && cache.contains(&4));
+        assert!(!cache.contains(&2) && !cache.contains(&3));
+    }
+}
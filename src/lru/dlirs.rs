@@ -0,0 +1,729 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::{CacheError, LIRSCache, LIRSCacheBuilder};
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, KeyRef, OnEvictCallback, PutResult,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+/// `DLIRSCacheBuilder` is used to help build a [`DLIRSCache`] with custom configuration.
+///
+/// [`DLIRSCache`]: struct.DLIRSCache.html
+pub struct DLIRSCacheBuilder<
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    inner: LIRSCacheBuilder<S, GH, E>,
+}
+
+impl Default for DLIRSCacheBuilder {
+    /// Create a default `DLIRSCacheBuilder`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{DLIRSCacheBuilder, DLIRSCache, Cache};
+    /// let mut cache: DLIRSCache<u64, u64> = DLIRSCacheBuilder::default()
+    ///     .set_size(5)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    fn default() -> Self {
+        Self {
+            inner: LIRSCacheBuilder::default(),
+        }
+    }
+}
+
+impl DLIRSCacheBuilder {
+    /// Returns a default [`DLIRSCacheBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{DLIRSCacheBuilder, DLIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = DLIRSCacheBuilder::new(100)
+    ///     .set_hir_ratio(0.1)
+    ///     .set_ghost_ratio(2.0)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .set_ghost_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`DLIRSCacheBuilder`]: struct.DLIRSCacheBuilder.html
+    pub fn new(size: usize) -> Self {
+        Self::default().set_size(size)
+    }
+}
+
+impl<S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> DLIRSCacheBuilder<S, GH, E> {
+    /// Set the cache size
+    pub fn set_size(self, size: usize) -> Self {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_size(size),
+        }
+    }
+
+    /// Set the initial ratio of the cache dedicated to resident HIR entries
+    pub fn set_hir_ratio(self, ratio: f64) -> Self {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_hir_ratio(ratio),
+        }
+    }
+
+    /// Set the ratio of non-resident HIR entries kept in the stack, relative to the cache size
+    pub fn set_ghost_ratio(self, ratio: f64) -> Self {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_ghost_ratio(ratio),
+        }
+    }
+
+    /// Set the hash builder of the resident entries
+    pub fn set_hasher<NS: BuildHasher>(self, hasher: NS) -> DLIRSCacheBuilder<NS, GH, E> {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_hasher(hasher),
+        }
+    }
+
+    /// Set the hash builder of the non-resident HIR entries
+    pub fn set_ghost_hasher<NGH: BuildHasher>(self, hasher: NGH) -> DLIRSCacheBuilder<S, NGH, E> {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_ghost_hasher(hasher),
+        }
+    }
+
+    /// Set the callback invoked when an entry leaves the cache,
+    /// see [`LIRSCacheBuilder::set_on_evict`].
+    ///
+    /// [`LIRSCacheBuilder::set_on_evict`]: struct.LIRSCacheBuilder.html#method.set_on_evict
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> DLIRSCacheBuilder<S, GH, NE> {
+        DLIRSCacheBuilder {
+            inner: self.inner.set_on_evict(cb),
+        }
+    }
+
+    /// Finalize the builder to [`DLIRSCache`]
+    ///
+    /// [`DLIRSCache`]: struct.DLIRSCache.html
+    pub fn finalize<K: Hash + Eq, V>(self) -> Result<DLIRSCache<K, V, S, GH, E>, CacheError> {
+        self.inner
+            .finalize_in(true)
+            .map(|inner| DLIRSCache { inner })
+    }
+}
+
+/// `DLIRSCache` is a fixed size Dynamic LIRS cache.
+///
+/// DLIRS works the same way as [`LIRSCache`], but the number of resident entries reserved
+/// for HIR entries is not fixed. Like the partition `p` of [`AdaptiveCache`]:
+/// - a hit on a non-resident HIR entry means the HIR part is too small,
+///   so the partition grows.
+/// - a hit on a resident HIR entry which was demoted from a LIR entry means the LIR part
+///   is too small, so the partition shrinks.
+///
+/// The partition always leaves at least one entry for the HIR part and one entry for the
+/// LIR part, and it can be observed by [`partition`].
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, DLIRSCache, PutResult};
+///
+/// // 3 slots for LIR entries and 1 slot for resident HIR entries
+/// let mut cache = DLIRSCache::<u64, u64>::builder(4)
+///     .set_hir_ratio(0.25)
+///     .finalize()
+///     .unwrap();
+///
+/// (1..=4).for_each(|i| {
+///     assert_eq!(cache.put(i, i), PutResult::Put);
+/// });
+/// assert_eq!(cache.partition(), 1);
+///
+/// // 4 is evicted and becomes a non-resident HIR entry.
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 4, value: 4 });
+///
+/// // 4 comes back, the HIR part grows, 1 and 2 are demoted.
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 5, value: 5 });
+/// assert_eq!(cache.partition(), 2);
+/// assert_eq!(cache.lir_len(), 2);
+/// assert_eq!(cache.demoted_len(), 2);
+///
+/// // the demoted entry 1 is accessed again, the LIR part grows.
+/// assert_eq!(cache.get(&1), Some(&1));
+/// assert_eq!(cache.partition(), 1);
+/// ```
+///
+/// [`LIRSCache`]: struct.LIRSCache.html
+/// [`AdaptiveCache`]: struct.AdaptiveCache.html
+/// [`partition`]: struct.DLIRSCache.html#method.partition
+pub struct DLIRSCache<
+    K,
+    V,
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    inner: LIRSCache<K, V, S, GH, E>,
+}
+
+impl<K: Hash + Eq, V> DLIRSCache<K, V> {
+    /// Create a `DLIRSCache` with size and default configurations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{Cache, DLIRSCache};
+    ///
+    /// let mut cache: DLIRSCache<u64, u64> = DLIRSCache::new(5).unwrap();
+    /// ```
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        DLIRSCacheBuilder::new(size).finalize()
+    }
+
+    /// Returns a [`DLIRSCacheBuilder`] to help build a [`DLIRSCache`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{DLIRSCacheBuilder, DLIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = DLIRSCache::<u64, u64>::builder(100)
+    ///     .set_hir_ratio(0.1)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`DLIRSCacheBuilder`]: struct.DLIRSCacheBuilder.html
+    /// [`DLIRSCache`]: struct.DLIRSCache.html
+    pub fn builder(size: usize) -> DLIRSCacheBuilder {
+        DLIRSCacheBuilder::new(size)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for DLIRSCache<K, V, S, GH, E>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// # Note
+    /// - If the cache is full, the least recent resident HIR entry is evicted.
+    /// - A new entry becomes a LIR entry if the LIR part is not full, or if it is
+    ///   a non-resident HIR entry, otherwise it becomes a resident HIR entry.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, DLIRSCache, PutResult};
+    ///
+    /// let mut cache = DLIRSCache::<u64, u64>::builder(3)
+    ///     .set_hir_ratio(0.34)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(cache.put(1, 1), PutResult::Put);
+    /// assert_eq!(cache.put(2, 2), PutResult::Put);
+    /// assert_eq!(cache.put(3, 3), PutResult::Put);
+    /// assert_eq!(cache.put(3, 33), PutResult::Update(3));
+    /// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 1, value: 1 });
+    /// ```
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        self.inner.put(k, v)
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Updates the LIR/HIR status of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"apple"), None);
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// assert_eq!(cache.get(&"pear"), Some(&2));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get(k)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Updates the LIR/HIR status of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.get_mut(k)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.peek(k)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the
+    /// LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.peek_mut(k)
+    }
+
+    /// Returns a bool indicating whether the given key is resident in the cache.
+    /// Does not update the LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.contains(k)
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.remove(k)
+    }
+
+    /// Clears the contents of the cache, including the non-resident HIR entries.
+    /// The partition goes back to its initial value.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache: DLIRSCache<isize, &str> = DLIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        self.inner.purge()
+    }
+
+    /// Returns the number of resident entries that are currently in the the cache
+    /// (excluding the non-resident HIR entries).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache = DLIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns the maximum number of resident entries the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, DLIRSCache};
+    /// let mut cache: DLIRSCache<isize, &str> = DLIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.inner.cap()
+    }
+
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    DLIRSCache<K, V, S, GH, E>
+{
+    /// Create a [`DLIRSCache`] from [`DLIRSCacheBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{DLIRSCacheBuilder, DLIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let builder = DLIRSCacheBuilder::new(5)
+    ///     .set_hir_ratio(0.2)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default());
+    ///
+    /// let mut cache = DLIRSCache::from_builder(builder).unwrap();
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`DLIRSCacheBuilder`]: struct.DLIRSCacheBuilder.html
+    /// [`DLIRSCache`]: struct.DLIRSCache.html
+    pub fn from_builder(builder: DLIRSCacheBuilder<S, GH, E>) -> Result<Self, CacheError> {
+        builder.finalize()
+    }
+
+    /// Returns the current partition value of the cache, which is the number of
+    /// resident entries reserved for the HIR part.
+    pub fn partition(&self) -> usize {
+        self.inner.partition()
+    }
+
+    /// Returns the number of LIR entries that are currently in the cache.
+    pub fn lir_len(&self) -> usize {
+        self.inner.lir_len()
+    }
+
+    /// Returns the number of resident HIR entries that are currently in the cache.
+    pub fn hir_len(&self) -> usize {
+        self.inner.hir_len()
+    }
+
+    /// Returns the number of resident HIR entries which were demoted from LIR entries
+    /// and have not been accessed since.
+    pub fn demoted_len(&self) -> usize {
+        self.inner.demoted_len()
+    }
+
+    /// Returns the number of non-resident HIR entries that are currently in the stack.
+    pub fn non_resident_len(&self) -> usize {
+        self.inner.non_resident_len()
+    }
+
+    /// Returns the number of entries, resident or not, that are currently in the stack.
+    pub fn stack_len(&self) -> usize {
+        self.inner.stack_len()
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// A put of a non-resident HIR entry is counted as a ghost hit.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, DLIRSCache};
+        /// let mut cache = DLIRSCache::<u64, &str>::builder(2)
+        ///     .set_hir_ratio(0.5)
+        ///     .finalize()
+        ///     .unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.put(3, "c");
+        /// cache.put(2, "b");
+        /// cache.get(&1);
+        /// cache.get(&3);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 4);
+        /// assert_eq!(stats.evictions(), 2);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.inner.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, DLIRSCache};
+        /// let mut cache = DLIRSCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.inner.reset_stats();
+        }
+    );
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for DLIRSCache<K, V, S, GH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        self.inner.put_vacant(k, v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> fmt::Debug
+    for DLIRSCache<K, V, S, GH, E>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("DLIRSCache")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .field("partition", &self.partition())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::dlirs::{DLIRSCache, DLIRSCacheBuilder};
+    use crate::{Cache, EvictionReason, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+    use std::sync::Mutex;
+
+    #[test]
+    fn test_dlirs_cache_partition_bounds() {
+        let size = 16;
+        let mut cache: DLIRSCache<u64, u64> = DLIRSCacheBuilder::new(size)
+            .set_hir_ratio(0.25)
+            .finalize()
+            .unwrap();
+        (0..size as u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(cache.partition(), 4);
+
+        // every evicted entry is accessed again right away while it is still
+        // in the stack, so the HIR part keeps growing until only one LIR entry is left.
+        (size as u64..size as u64 * 4).for_each(|i| {
+            if let PutResult::Evicted { key, value } = cache.put(i, i) {
+                cache.put(key, value);
+            }
+        });
+        assert_eq!(cache.partition(), size - 1);
+        assert_eq!(cache.lir_len(), 1);
+
+        // hits on the demoted entries give the room back to the LIR part.
+        let keys: Vec<u64> = (0..size as u64 * 4).filter(|k| cache.contains(k)).collect();
+        (0..4).for_each(|_| {
+            keys.iter().for_each(|k| {
+                cache.get(k);
+            })
+        });
+        assert!(cache.partition() < size - 1);
+
+        cache.purge();
+        assert!(cache.is_empty());
+        assert_eq!(cache.demoted_len(), 0);
+        assert_eq!(cache.partition(), 4);
+    }
+
+    #[test]
+    fn test_dlirs_cache_demote_bottom_lir() {
+        let mut cache = DLIRSCache::new(4).unwrap();
+        for &k in &[1, 7, 6, 3, 2, 3, 2] {
+            cache.put(k, k);
+        }
+        assert_eq!(cache.remove(&2), Some(2));
+        cache.put(1, 1);
+        cache.put(11, 11);
+        assert_eq!(cache.remove(&1), Some(1));
+        for &k in &[11, 1, 2, 4, 6, 6, 3] {
+            cache.put(k, k);
+        }
+        assert_eq!(cache.get(&4), Some(&4));
+
+        // the ghost hit grows the HIR part, the LIR entries over the new LIR capacity
+        // are demoted from the bottom of the stack.
+        cache.put(9, 9);
+        assert!(cache.lir_len() <= 4 - cache.partition());
+        assert_eq!(cache.lir_len() + cache.hir_len(), cache.len());
+        assert!(cache.demoted_len() <= cache.hir_len());
+    }
+
+    #[test]
+    fn test_dlirs_cache_random_ops() {
+        // the small caches empty their LIR part with a few removes.
+        for &size in &[3, 4, 8, 128] {
+            let mut rng = StdRng::seed_from_u64(size as u64);
+            let mut cache = DLIRSCache::new(size).unwrap();
+
+            (0..200_000).for_each(|_| {
+                let k = rng.gen::<u64>() % (size as u64 * 4);
+                match rng.gen::<u64>() % 3 {
+                    0 => {
+                        let _ = cache.put(k, k);
+                    }
+                    1 => {
+                        if let Some(v) = cache.get(&k) {
+                            assert_eq!(*v, k);
+                        }
+                    }
+                    _ => {
+                        let _ = cache.remove(&k);
+                    }
+                }
+
+                assert!(cache.len() <= size);
+                assert!(cache.partition() >= 1 && cache.partition() < size);
+                assert!(cache.lir_len() <= size - cache.partition());
+                assert!(cache.demoted_len() <= cache.hir_len());
+            })
+        }
+    }
+
+    struct Reasons(Arc<Mutex<Vec<EvictionReason>>>);
+
+    impl OnEvictCallback for Reasons {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {}
+
+        fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+            self.0.lock().unwrap().push(reason);
+        }
+    }
+
+    #[test]
+    fn test_dlirs_cache_on_evict() {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = DLIRSCacheBuilder::new(4)
+            .set_hir_ratio(0.25)
+            .set_on_evict(Reasons(reasons.clone()))
+            .finalize::<u64, u64>()
+            .unwrap();
+
+        (1..=4).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(cache.put(1, 11), PutResult::Update(1));
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(cache.remove(&2), Some(2));
+        cache.purge();
+
+        assert_eq!(
+            *reasons.lock().unwrap(),
+            vec![
+                EvictionReason::Replaced,
+                EvictionReason::Capacity,
+                EvictionReason::Removed,
+                EvictionReason::Purged,
+                EvictionReason::Purged,
+                EvictionReason::Purged,
+            ]
+        );
+    }
+}
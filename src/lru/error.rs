@@ -9,10 +9,19 @@ pub enum CacheError {
     ///
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
     InvalidRecentRatio(f64),
-    /// Invalid ghost ratio for [`TwoQueueCache`]
+    /// Invalid ghost ratio for [`TwoQueueCache`] and [`LIRSCache`]
     ///
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
+    /// [`LIRSCache`]: struct.LIRSCache.html
     InvalidGhostRatio(f64),
+    /// Invalid HIR ratio for [`LIRSCache`]
+    ///
+    /// [`LIRSCache`]: struct.LIRSCache.html
+    InvalidHIRRatio(f64),
+    /// Invalid small ratio for [`S3FifoCache`]
+    ///
+    /// [`S3FifoCache`]: struct.S3FifoCache.html
+    InvalidSmallRatio(f64),
 }
 
 impl Display for CacheError {
@@ -21,6 +30,8 @@ impl Display for CacheError {
             CacheError::InvalidSize(size) => write!(f, "invalid cache size {}", *size),
             CacheError::InvalidRecentRatio(r) => write!(f, "invalid recent ratio {}", *r),
             CacheError::InvalidGhostRatio(r) => write!(f, "invalid ghost ratio {}", *r),
+            CacheError::InvalidHIRRatio(r) => write!(f, "invalid HIR ratio {}", *r),
+            CacheError::InvalidSmallRatio(r) => write!(f, "invalid small ratio {}", *r),
         }
     }
 }
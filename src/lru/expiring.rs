@@ -0,0 +1,945 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::raw::EntryNode;
+use crate::lru::{CacheError, RawLRU};
+use crate::{
+    Cache, Clock, DefaultClock, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult, ResizableCache,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+use core::time::Duration;
+
+/// The value stored in the inner [`RawLRU`], the value and the time it expires at.
+///
+/// [`RawLRU`]: struct.RawLRU.html
+struct TimedValue<V> {
+    val: V,
+    deadline: Option<Duration>,
+}
+
+impl<V> TimedValue<V> {
+    #[inline]
+    fn is_expired(&self, now: Duration) -> bool {
+        match self.deadline {
+            None => false,
+            Some(deadline) => deadline <= now,
+        }
+    }
+}
+
+/// `ExpiringLRUBuilder` is used to help build a [`ExpiringLRU`] with custom configuration.
+///
+/// [`ExpiringLRU`]: struct.ExpiringLRU.html
+pub struct ExpiringLRUBuilder<E = DefaultEvictCallback, S = DefaultHashBuilder, C = DefaultClock> {
+    size: usize,
+    ttl: Option<Duration>,
+    on_evict: Option<E>,
+    hasher: Option<S>,
+    clock: Option<C>,
+}
+
+impl Default for ExpiringLRUBuilder {
+    /// Create a default `ExpiringLRUBuilder`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{ExpiringLRUBuilder, ExpiringLRU, Cache};
+    /// let mut cache: ExpiringLRU<u64, u64> = ExpiringLRUBuilder::default()
+    ///     .set_size(5)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    fn default() -> Self {
+        Self {
+            size: 0,
+            ttl: None,
+            on_evict: None,
+            hasher: Some(DefaultHashBuilder::default()),
+            clock: Some(DefaultClock::default()),
+        }
+    }
+}
+
+impl ExpiringLRUBuilder {
+    /// Returns a default [`ExpiringLRUBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{ExpiringLRUBuilder, ExpiringLRU, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    /// use std::time::Duration;
+    ///
+    /// let mut cache = ExpiringLRUBuilder::new(3)
+    ///     .set_ttl(Duration::from_secs(60))
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`ExpiringLRUBuilder`]: struct.ExpiringLRUBuilder.html
+    pub fn new(size: usize) -> Self {
+        Self::default().set_size(size)
+    }
+}
+
+impl<E: OnEvictCallback, S: BuildHasher, C: Clock> ExpiringLRUBuilder<E, S, C> {
+    /// Set the cache size
+    pub fn set_size(self, size: usize) -> Self {
+        ExpiringLRUBuilder {
+            size,
+            ttl: self.ttl,
+            on_evict: self.on_evict,
+            hasher: self.hasher,
+            clock: self.clock,
+        }
+    }
+
+    /// Set the default time-to-live, which is used by `put`.
+    /// Entries never expire if no default time-to-live is set.
+    pub fn set_ttl(self, ttl: Duration) -> Self {
+        ExpiringLRUBuilder {
+            size: self.size,
+            ttl: Some(ttl),
+            on_evict: self.on_evict,
+            hasher: self.hasher,
+            clock: self.clock,
+        }
+    }
+
+    /// Set the evict callback
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> ExpiringLRUBuilder<NE, S, C> {
+        ExpiringLRUBuilder {
+            size: self.size,
+            ttl: self.ttl,
+            on_evict: Some(cb),
+            hasher: self.hasher,
+            clock: self.clock,
+        }
+    }
+
+    /// Set the hash builder
+    pub fn set_hasher<NS: BuildHasher>(self, hasher: NS) -> ExpiringLRUBuilder<E, NS, C> {
+        ExpiringLRUBuilder {
+            size: self.size,
+            ttl: self.ttl,
+            on_evict: self.on_evict,
+            hasher: Some(hasher),
+            clock: self.clock,
+        }
+    }
+
+    /// Set the clock which is used to decide whether an entry has expired
+    pub fn set_clock<NC: Clock>(self, clock: NC) -> ExpiringLRUBuilder<E, S, NC> {
+        ExpiringLRUBuilder {
+            size: self.size,
+            ttl: self.ttl,
+            on_evict: self.on_evict,
+            hasher: self.hasher,
+            clock: Some(clock),
+        }
+    }
+
+    /// Finalize the builder to [`ExpiringLRU`]
+    ///
+    /// [`ExpiringLRU`]: struct.ExpiringLRU.html
+    pub fn finalize<K: Hash + Eq, V>(self) -> Result<ExpiringLRU<K, V, E, S, C>, CacheError> {
+        let inner = RawLRU::with_hasher(self.size, self.hasher.unwrap())?;
+        Ok(ExpiringLRU {
+            inner,
+            ttl: self.ttl,
+            on_evict: self.on_evict,
+            clock: self.clock.unwrap(),
+        })
+    }
+}
+
+/// `ExpiringLRU` is a fixed size LRU cache whose entries can expire after a time-to-live.
+///
+/// Expired entries are treated as absent by `get`, `peek` and `contains`.
+/// They are removed lazily when they are accessed through a `&mut self` method,
+/// or all at once by [`remove_expired`]. Until then they still count in `len`
+/// and can be evicted by capacity like any other entry.
+///
+/// The evict callback is invoked whenever an entry leaves the cache,
+/// including when it is removed because it expired.
+///
+/// Time is read from the [`Clock`] set by the builder, [`StdClock`] by default.
+/// Without `std`, the default clock is a [`ManualClock`] which has to be advanced by hand.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, ExpiringLRU, ManualClock, PutResult};
+/// use std::time::Duration;
+///
+/// let clock = ManualClock::new();
+/// let mut cache = ExpiringLRU::<u64, u64>::builder(2)
+///     .set_clock(clock.clone())
+///     .finalize()
+///     .unwrap();
+///
+/// assert_eq!(cache.put_with_ttl(1, 1, Duration::from_secs(10)), PutResult::Put);
+/// assert_eq!(cache.put(2, 2), PutResult::Put);
+///
+/// clock.advance(Duration::from_secs(10));
+///
+/// // entry (1, 1) has expired.
+/// assert_eq!(cache.get(&1), None);
+/// assert_eq!(cache.get(&2), Some(&2));
+/// assert_eq!(cache.len(), 1);
+/// ```
+///
+/// [`remove_expired`]: struct.ExpiringLRU.html#method.remove_expired
+/// [`Clock`]: trait.Clock.html
+/// [`StdClock`]: struct.StdClock.html
+/// [`ManualClock`]: struct.ManualClock.html
+pub struct ExpiringLRU<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder, C = DefaultClock> {
+    inner: RawLRU<K, TimedValue<V>, DefaultEvictCallback, S>,
+    ttl: Option<Duration>,
+    on_evict: Option<E>,
+    clock: C,
+}
+
+impl<K: Hash + Eq, V> ExpiringLRU<K, V> {
+    /// Creates a new `ExpiringLRU` that holds at most `cap` items, and entries never
+    /// expire unless they are put by `put_with_ttl`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache: ExpiringLRU<isize, &str> = ExpiringLRU::new(10).unwrap();
+    /// ```
+    pub fn new(cap: usize) -> Result<Self, CacheError> {
+        ExpiringLRUBuilder::new(cap).finalize()
+    }
+
+    /// Creates a new `ExpiringLRU` that holds at most `cap` items, and entries put by
+    /// `put` expire after `ttl`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// use std::time::Duration;
+    ///
+    /// let mut cache: ExpiringLRU<isize, &str> = ExpiringLRU::with_ttl(10, Duration::from_secs(60)).unwrap();
+    /// assert_eq!(cache.ttl(), Some(Duration::from_secs(60)));
+    /// ```
+    pub fn with_ttl(cap: usize, ttl: Duration) -> Result<Self, CacheError> {
+        ExpiringLRUBuilder::new(cap).set_ttl(ttl).finalize()
+    }
+
+    /// Returns a [`ExpiringLRUBuilder`] to help build a [`ExpiringLRU`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, ExpiringLRU};
+    /// use std::time::Duration;
+    ///
+    /// let mut cache = ExpiringLRU::<u64, u64>::builder(3)
+    ///     .set_ttl(Duration::from_secs(60))
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`ExpiringLRUBuilder`]: struct.ExpiringLRUBuilder.html
+    /// [`ExpiringLRU`]: struct.ExpiringLRU.html
+    pub fn builder(cap: usize) -> ExpiringLRUBuilder {
+        ExpiringLRUBuilder::new(cap)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher, C: Clock> Cache<K, V>
+    for ExpiringLRU<K, V, E, S, C>
+{
+    /// Puts a key-value pair into cache with the default time-to-live, returns a [`PutResult`].
+    /// An expired entry with the same key is treated as absent.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, PutResult};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "beta"));
+    /// assert_eq!(PutResult::Evicted{ key: 1, value: "a"}, cache.put(3, "c"));
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"beta"));
+    /// ```
+    ///
+    /// [`PutResult`]: struct.PutResult.html
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let deadline = self.ttl.and_then(|ttl| self.deadline(ttl));
+        self.put_in(k, v, deadline)
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache or has expired. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = ExpiringLRU::<u64, &str>::builder(2)
+    ///     .set_clock(clock.clone())
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put_with_ttl(1, "a", Duration::from_millis(10));
+    /// cache.put(2, "b");
+    ///
+    /// clock.advance(Duration::from_millis(10));
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"b"));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.remove_if_expired(k) {
+            return None;
+        }
+        self.inner.get(k).map(|ent| &ent.val)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache or has expired. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.remove_if_expired(k) {
+            return None;
+        }
+        self.inner.get_mut(k).map(|ent| &mut ent.val)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache or has expired. Unlike `get`, `peek` does not update the LRU list
+    /// and does not remove the expired entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = ExpiringLRU::<u64, &str>::builder(2)
+    ///     .set_clock(clock.clone())
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put_with_ttl(1, "a", Duration::from_millis(10));
+    /// cache.put(2, "b");
+    ///
+    /// clock.advance(Duration::from_millis(10));
+    ///
+    /// assert_eq!(cache.peek(&1), None);
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+        self.inner
+            .peek(k)
+            .filter(|ent| !ent.is_expired(now))
+            .map(|ent| &ent.val)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache or has expired. Unlike `get_mut`, `peek_mut` does not
+    /// update the LRU list so the key's position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        if self.remove_if_expired(k) {
+            return None;
+        }
+        self.inner.peek_mut(k).map(|ent| &mut ent.val)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache and has not expired.
+    /// Does not update the LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = ExpiringLRU::<u64, &str>::builder(2)
+    ///     .set_clock(clock.clone())
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put_with_ttl(1, "a", Duration::from_millis(10));
+    /// cache.put(2, "b");
+    ///
+    /// clock.advance(Duration::from_millis(10));
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+        matches!(self.inner.peek(k), Some(ent) if !ent.is_expired(now))
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist or has expired.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (key, ent) = self.remove_entry(k)?;
+        if ent.is_expired(self.clock.now()) {
+            self.cb(&key, &ent.val, EvictionReason::Expired);
+            None
+        } else {
+            self.cb(&key, &ent.val, EvictionReason::Removed);
+            Some(ent.val)
+        }
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache: ExpiringLRU<isize, &str> = ExpiringLRU::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        while let Some((k, ent)) = self.inner.remove_lru() {
+            self.cb(&k, &ent.val, EvictionReason::Purged);
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache,
+    /// including the expired entries which have not been removed yet.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.inner.len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache: ExpiringLRU<isize, &str> = ExpiringLRU::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.inner.cap()
+    }
+
+    /// Returns a bool indicating whether the cache is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU};
+    /// let mut cache = ExpiringLRU::new(2).unwrap();
+    /// assert!(cache.is_empty());
+    ///
+    /// cache.put(1, "a");
+    /// assert!(!cache.is_empty());
+    /// ```
+    fn is_empty(&self) -> bool {
+        self.inner.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher, C: Clock> EntryCache<K, V>
+    for ExpiringLRU<K, V, E, S, C>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the new entry is put to the head of the inner LRU list
+        let rst = self.put(k, v);
+        let v = self
+            .inner
+            .peek_mru_mut()
+            .map(|(_, v)| NonNull::from(&mut v.val));
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher, C: Clock> ResizableCache
+    for ExpiringLRU<K, V, E, S, C>
+{
+    /// Resizes the cache. If the new capacity is smaller than the size of the current
+    /// cache any entries past the new capacity are discarded.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ResizableCache};
+    /// let mut cache: ExpiringLRU<isize, &str> = ExpiringLRU::new(4).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert_eq!(cache.resize(2), 1);
+    /// assert_eq!(cache.get(&1), None);
+    /// ```
+    fn resize(&mut self, cap: usize) -> u64 {
+        let mut evicted = 0u64;
+        while self.inner.len() > cap {
+            if let Some((k, ent)) = self.inner.remove_lru() {
+                self.cb(&k, &ent.val, EvictionReason::Resized);
+                evicted += 1;
+            }
+        }
+        self.inner.resize(cap);
+        evicted
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher, C: Clock> ExpiringLRU<K, V, E, S, C> {
+    /// Creates a `ExpiringLRU` according to the [`ExpiringLRUBuilder`]
+    ///
+    /// [`ExpiringLRUBuilder`]: struct.ExpiringLRUBuilder.html
+    pub fn from_builder(builder: ExpiringLRUBuilder<E, S, C>) -> Result<Self, CacheError> {
+        builder.finalize()
+    }
+
+    /// Returns the default time-to-live used by `put`.
+    pub fn ttl(&self) -> Option<Duration> {
+        self.ttl
+    }
+
+    /// Returns a reference to the clock of the cache.
+    pub fn clock(&self) -> &C {
+        &self.clock
+    }
+
+    /// Puts a key-value pair into cache which expires after `ttl`, returns a [`PutResult`].
+    /// An expired entry with the same key is treated as absent. An entry whose expiration
+    /// time overflows `Duration`, e.g. with `Duration::MAX`, never expires.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ManualClock, PutResult};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = ExpiringLRU::<u64, &str>::builder(2)
+    ///     .set_clock(clock.clone())
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(cache.put_with_ttl(1, "a", Duration::from_millis(10)), PutResult::Put);
+    /// assert_eq!(cache.put_with_ttl(1, "b", Duration::from_millis(10)), PutResult::Update("a"));
+    ///
+    /// clock.advance(Duration::from_millis(10));
+    ///
+    /// // the previous entry has expired, so this is a new entry.
+    /// assert_eq!(cache.put_with_ttl(1, "c", Duration::from_secs(60)), PutResult::Put);
+    /// assert_eq!(cache.get(&1), Some(&"c"));
+    /// ```
+    ///
+    /// [`PutResult`]: struct.PutResult.html
+    pub fn put_with_ttl(&mut self, k: K, v: V, ttl: Duration) -> PutResult<K, V> {
+        let deadline = self.deadline(ttl);
+        self.put_in(k, v, deadline)
+    }
+
+    /// Removes all of the expired entries from the cache, returns the number of removed entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ExpiringLRU, ManualClock};
+    /// use std::time::Duration;
+    ///
+    /// let clock = ManualClock::new();
+    /// let mut cache = ExpiringLRU::<u64, &str>::builder(3)
+    ///     .set_clock(clock.clone())
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put_with_ttl(1, "a", Duration::from_millis(10));
+    /// cache.put_with_ttl(2, "b", Duration::from_millis(10));
+    /// cache.put(3, "c");
+    ///
+    /// clock.advance(Duration::from_millis(10));
+    ///
+    /// assert_eq!(cache.len(), 3);
+    /// assert_eq!(cache.remove_expired(), 2);
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    pub fn remove_expired(&mut self) -> usize {
+        let now = self.clock.now();
+        let removed = self.inner.remove_where_in(|_, ent| ent.is_expired(now));
+        removed
+            .iter()
+            .for_each(|(k, ent)| self.cb(k, &ent.val, EvictionReason::Expired));
+        removed.len()
+    }
+
+    // Returns the time an entry put now with `ttl` expires at, or `None` if it
+    // overflows `Duration`, in which case the entry never expires.
+    #[inline]
+    fn deadline(&self, ttl: Duration) -> Option<Duration> {
+        self.clock.now().checked_add(ttl)
+    }
+
+    fn put_in(&mut self, k: K, v: V, deadline: Option<Duration>) -> PutResult<K, V> {
+        self.remove_if_expired(&KeyRef { k: &k });
+        if self.on_evict.is_some() {
+            if let Some(old) = self.inner.peek(&KeyRef { k: &k }) {
+                self.cb(&k, &old.val, EvictionReason::Replaced);
+            }
+        }
+
+        match self.inner.put(k, TimedValue { val: v, deadline }) {
+            PutResult::Put => PutResult::Put,
+            PutResult::Update(old) => PutResult::Update(old.val),
+            PutResult::Evicted { key, value } => {
+                self.cb(&key, &value.val, self.capacity_reason(&value));
+                PutResult::Evicted {
+                    key,
+                    value: value.val,
+                }
+            }
+            // RawLRU never returns PutResult::EvictedAndUpdate,
+            // we keep this for good measure.
+            PutResult::EvictedAndUpdate { evicted, update } => {
+                self.cb(&evicted.0, &evicted.1.val, self.capacity_reason(&evicted.1));
+                PutResult::EvictedAndUpdate {
+                    evicted: (evicted.0, evicted.1.val),
+                    update: update.val,
+                }
+            }
+            // RawLRU never returns the results of the weighted caches,
+            // we keep this for good measure.
+            PutResult::EvictedMany { evicted, update } => {
+                let evicted = evicted
+                    .into_iter()
+                    .map(|(key, value)| {
+                        self.cb(&key, &value.val, self.capacity_reason(&value));
+                        (key, value.val)
+                    })
+                    .collect();
+                PutResult::EvictedMany {
+                    evicted,
+                    update: update.map(|old| old.val),
+                }
+            }
+            PutResult::Rejected { key, value } => PutResult::Rejected {
+                key,
+                value: value.val,
+            },
+        }
+    }
+
+    /// Removes the entry if it exists and has expired, returns whether it was removed.
+    fn remove_if_expired<Q>(&mut self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let now = self.clock.now();
+        if !matches!(self.inner.peek(k), Some(ent) if ent.is_expired(now)) {
+            return false;
+        }
+
+        if let Some((key, ent)) = self.remove_entry(k) {
+            self.cb(&key, &ent.val, EvictionReason::Expired);
+        }
+        true
+    }
+
+    fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, TimedValue<V>)>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.inner.remove_and_return_ent(k).map(|ent| {
+            let EntryNode { key, val, .. } = *ent;
+            unsafe { (key.assume_init(), val.assume_init()) }
+        })
+    }
+
+    #[inline]
+    fn cb(&self, k: &K, v: &V, reason: EvictionReason) {
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    /// An expired LRU victim is reported as expired rather than as a capacity eviction.
+    #[inline]
+    fn capacity_reason(&self, ent: &TimedValue<V>) -> EvictionReason {
+        if ent.is_expired(self.clock.now()) {
+            EvictionReason::Expired
+        } else {
+            EvictionReason::Capacity
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher, C: Clock> fmt::Debug
+    for ExpiringLRU<K, V, E, S, C>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ExpiringLRU")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .field("ttl", &self.ttl)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{ExpiringLRU, ExpiringLRUBuilder};
+    use crate::{Cache, ManualClock, OnEvictCallback, PutResult, ResizableCache};
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use core::time::Duration;
+
+    const SHORT: Duration = Duration::from_secs(1);
+    const LONG: Duration = Duration::from_secs(3600);
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    fn new_cache<K: core::hash::Hash + Eq, V>(
+        cap: usize,
+    ) -> (
+        ExpiringLRU<K, V, crate::DefaultEvictCallback, crate::DefaultHashBuilder, ManualClock>,
+        ManualClock,
+    ) {
+        let clock = ManualClock::new();
+        let cache = ExpiringLRUBuilder::new(cap)
+            .set_clock(clock.clone())
+            .finalize()
+            .unwrap();
+        (cache, clock)
+    }
+
+    #[test]
+    fn test_expiring_get_and_peek() {
+        let (mut cache, clock) = new_cache(4);
+        cache.put_with_ttl(1, 1, SHORT);
+        cache.put_with_ttl(2, 2, LONG);
+        cache.put(3, 3);
+
+        clock.advance(SHORT - Duration::from_nanos(1));
+        assert_eq!(cache.peek(&1), Some(&1));
+        clock.advance(Duration::from_nanos(1));
+
+        assert_eq!(cache.peek(&1), None);
+        assert!(!cache.contains(&1));
+        // peek can not remove the expired entry
+        assert_eq!(cache.len(), 3);
+
+        assert_eq!(cache.get(&1), None);
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.get(&2), Some(&2));
+        assert_eq!(cache.get_mut(&3), Some(&mut 3));
+    }
+
+    #[test]
+    fn test_expiring_default_ttl() {
+        let clock = ManualClock::new();
+        let mut cache = ExpiringLRUBuilder::new(4)
+            .set_ttl(SHORT)
+            .set_clock(clock.clone())
+            .finalize()
+            .unwrap();
+        cache.put(1, 1);
+        cache.put_with_ttl(2, 2, LONG);
+        clock.advance(SHORT);
+
+        assert_eq!(cache.peek_mut(&1), None);
+        assert_eq!(cache.peek_mut(&2), Some(&mut 2));
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_expiring_max_ttl() {
+        let clock = ManualClock::new();
+        let mut cache = ExpiringLRUBuilder::new(4)
+            .set_ttl(Duration::MAX)
+            .set_clock(clock.clone())
+            .finalize()
+            .unwrap();
+        clock.advance(SHORT);
+
+        // the deadlines overflow, the entries never expire.
+        assert_eq!(cache.put(1, 1), PutResult::Put);
+        assert_eq!(cache.put_with_ttl(2, 2, Duration::MAX), PutResult::Put);
+        clock.advance(LONG);
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.get(&2), Some(&2));
+        assert_eq!(cache.remove_expired(), 0);
+    }
+
+    #[test]
+    fn test_expiring_put_over_expired() {
+        let (mut cache, clock) = new_cache(2);
+        assert_eq!(cache.put_with_ttl(1, 1, SHORT), PutResult::Put);
+        clock.advance(SHORT);
+
+        assert_eq!(cache.put(1, 11), PutResult::Put);
+        assert_eq!(cache.put(1, 111), PutResult::Update(11));
+        assert_eq!(cache.put(2, 2), PutResult::Put);
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 1, value: 111 });
+    }
+
+    #[test]
+    fn test_expiring_remove_expired() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let clock = ManualClock::new();
+        let mut cache = ExpiringLRUBuilder::new(8)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .set_clock(clock.clone())
+            .finalize()
+            .unwrap();
+
+        (0..8).for_each(|i| {
+            if i % 2 == 0 {
+                cache.put_with_ttl(i, i, SHORT);
+            } else {
+                cache.put(i, i);
+            }
+        });
+        clock.advance(SHORT);
+
+        assert_eq!(cache.remove_expired(), 4);
+        assert_eq!(ctr.load(Ordering::SeqCst), 4);
+        assert_eq!(cache.len(), 4);
+        (0..8).for_each(|i| assert_eq!(cache.contains(&i), i % 2 == 1));
+
+        assert_eq!(cache.remove_expired(), 0);
+        assert_eq!(cache.resize(2), 2);
+        assert_eq!(ctr.load(Ordering::SeqCst), 6);
+
+        cache.purge();
+        assert_eq!(ctr.load(Ordering::SeqCst), 8);
+        assert!(cache.is_empty());
+    }
+
+    #[test]
+    fn test_expiring_remove() {
+        let (mut cache, clock) = new_cache(2);
+        cache.put_with_ttl(1, 1, SHORT);
+        cache.put_with_ttl(2, 2, LONG);
+        clock.advance(SHORT);
+
+        assert_eq!(cache.remove(&1), None);
+        assert_eq!(cache.remove(&2), Some(2));
+        assert!(cache.is_empty());
+    }
+}
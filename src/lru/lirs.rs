@@ -0,0 +1,1481 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder,
+    EvictionReason, KeyRef, OnEvictCallback, PutResult,
+};
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::mem;
+use core::ptr;
+
+import_hashbrown!(HashMap);
+import_std!(HashMap);
+
+/// `DEFAULT_LIRS_HIR_RATIO` is the default ratio of the [`LIRSCache`] dedicated
+/// to resident HIR entries.
+///
+/// [`LIRSCache`]: struct.LIRSCache.html
+pub const DEFAULT_LIRS_HIR_RATIO: f64 = 0.01;
+
+/// `DEFAULT_LIRS_GHOST_RATIO` is the default ratio of non-resident HIR entries
+/// kept in the stack of [`LIRSCache`], relative to the size of the cache.
+///
+/// [`LIRSCache`]: struct.LIRSCache.html
+pub const DEFAULT_LIRS_GHOST_RATIO: f64 = 1.0;
+
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+enum Status {
+    Lir,
+    Hir,
+}
+
+// index of the links of the stack S.
+const STACK: usize = 0;
+// index of the links of the queue Q for resident HIR entries,
+// non-resident HIR entries use the same links for the non-resident list.
+const QUEUE: usize = 1;
+
+struct Link<K, V> {
+    prev: *mut LIRSNode<K, V>,
+    next: *mut LIRSNode<K, V>,
+}
+
+impl<K, V> Clone for Link<K, V> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<K, V> Copy for Link<K, V> {}
+
+impl<K, V> Link<K, V> {
+    const fn new() -> Self {
+        Self {
+            prev: ptr::null_mut(),
+            next: ptr::null_mut(),
+        }
+    }
+}
+
+// A node of the LIRS stack and queue. The key and the value are `None`
+// when the node is a non-resident HIR entry, which only keeps the hash of the key.
+struct LIRSNode<K, V> {
+    key: Option<K>,
+    val: Option<V>,
+    hash: u64,
+    status: Status,
+    in_stack: bool,
+    // whether the entry is a resident HIR entry demoted from a LIR entry.
+    demoted: bool,
+    links: [Link<K, V>; 2],
+}
+
+impl<K, V> LIRSNode<K, V> {
+    fn new(key: K, val: V, status: Status) -> Self {
+        Self {
+            key: Some(key),
+            val: Some(val),
+            hash: 0,
+            status,
+            in_stack: false,
+            demoted: false,
+            links: [Link::new(); 2],
+        }
+    }
+
+    #[inline]
+    fn key_ref(&self) -> KeyRef<K> {
+        KeyRef {
+            k: self.key.as_ref().unwrap(),
+        }
+    }
+}
+
+// An intrusive doubly linked list over one of the links of `LIRSNode`.
+// The head is the most recently used node and the tail is the bottom.
+struct NodeList<K, V> {
+    head: *mut LIRSNode<K, V>,
+    tail: *mut LIRSNode<K, V>,
+    len: usize,
+    idx: usize,
+}
+
+impl<K, V> NodeList<K, V> {
+    const fn new(idx: usize) -> Self {
+        Self {
+            head: ptr::null_mut(),
+            tail: ptr::null_mut(),
+            len: 0,
+            idx,
+        }
+    }
+
+    #[inline]
+    fn len(&self) -> usize {
+        self.len
+    }
+
+    #[inline]
+    fn tail(&self) -> *mut LIRSNode<K, V> {
+        self.tail
+    }
+
+    #[cfg(test)]
+    fn head(&self) -> *mut LIRSNode<K, V> {
+        self.head
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.head = ptr::null_mut();
+        self.tail = ptr::null_mut();
+        self.len = 0;
+    }
+
+    /// # Safety
+    /// `node` must be valid and must not be in the list.
+    unsafe fn push_front(&mut self, node: *mut LIRSNode<K, V>) {
+        let idx = self.idx;
+        (*node).links[idx].prev = ptr::null_mut();
+        (*node).links[idx].next = self.head;
+        if self.head.is_null() {
+            self.tail = node;
+        } else {
+            (*self.head).links[idx].prev = node;
+        }
+        self.head = node;
+        self.len += 1;
+    }
+
+    /// # Safety
+    /// `node` must be valid and must be in the list.
+    unsafe fn remove(&mut self, node: *mut LIRSNode<K, V>) {
+        let idx = self.idx;
+        let Link { prev, next } = (*node).links[idx];
+        if prev.is_null() {
+            self.head = next;
+        } else {
+            (*prev).links[idx].next = next;
+        }
+        if next.is_null() {
+            self.tail = prev;
+        } else {
+            (*next).links[idx].prev = prev;
+        }
+        (*node).links[idx] = Link::new();
+        self.len -= 1;
+    }
+
+    /// # Safety
+    /// `node` must be valid and must be in the list.
+    #[inline]
+    unsafe fn move_to_front(&mut self, node: *mut LIRSNode<K, V>) {
+        self.remove(node);
+        self.push_front(node);
+    }
+}
+
+/// `LIRSCacheBuilder` is used to help build a [`LIRSCache`] with custom configuration.
+///
+/// [`LIRSCache`]: struct.LIRSCache.html
+pub struct LIRSCacheBuilder<
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    size: usize,
+    hir_ratio: Option<f64>,
+    ghost_ratio: Option<f64>,
+    hasher: Option<S>,
+    ghost_hasher: Option<GH>,
+    on_evict: Option<E>,
+}
+
+impl Default for LIRSCacheBuilder {
+    /// Create a default `LIRSCacheBuilder`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{LIRSCacheBuilder, LIRSCache, Cache};
+    /// let mut cache: LIRSCache<u64, u64> = LIRSCacheBuilder::default()
+    ///     .set_size(5)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    fn default() -> Self {
+        Self {
+            size: 0,
+            hir_ratio: Some(DEFAULT_LIRS_HIR_RATIO),
+            ghost_ratio: Some(DEFAULT_LIRS_GHOST_RATIO),
+            hasher: Some(DefaultHashBuilder::default()),
+            ghost_hasher: Some(DefaultHashBuilder::default()),
+            on_evict: None,
+        }
+    }
+}
+
+impl LIRSCacheBuilder {
+    /// Returns a default [`LIRSCacheBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{LIRSCacheBuilder, LIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = LIRSCacheBuilder::new(100)
+    ///     .set_hir_ratio(0.1)
+    ///     .set_ghost_ratio(2.0)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .set_ghost_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`LIRSCacheBuilder`]: struct.LIRSCacheBuilder.html
+    pub fn new(size: usize) -> Self {
+        Self::default().set_size(size)
+    }
+}
+
+impl<S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> LIRSCacheBuilder<S, GH, E> {
+    /// Set the cache size
+    pub fn set_size(self, size: usize) -> Self {
+        LIRSCacheBuilder {
+            size,
+            hir_ratio: self.hir_ratio,
+            ghost_ratio: self.ghost_ratio,
+            hasher: self.hasher,
+            ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the ratio of the cache dedicated to resident HIR entries
+    pub fn set_hir_ratio(self, ratio: f64) -> Self {
+        LIRSCacheBuilder {
+            size: self.size,
+            hir_ratio: Some(ratio),
+            ghost_ratio: self.ghost_ratio,
+            hasher: self.hasher,
+            ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the ratio of non-resident HIR entries kept in the stack, relative to the cache size
+    pub fn set_ghost_ratio(self, ratio: f64) -> Self {
+        LIRSCacheBuilder {
+            size: self.size,
+            hir_ratio: self.hir_ratio,
+            ghost_ratio: Some(ratio),
+            hasher: self.hasher,
+            ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the hash builder of the resident entries
+    pub fn set_hasher<NS: BuildHasher>(self, hasher: NS) -> LIRSCacheBuilder<NS, GH, E> {
+        LIRSCacheBuilder {
+            size: self.size,
+            hir_ratio: self.hir_ratio,
+            ghost_ratio: self.ghost_ratio,
+            hasher: Some(hasher),
+            ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the hash builder of the non-resident HIR entries
+    pub fn set_ghost_hasher<NGH: BuildHasher>(self, hasher: NGH) -> LIRSCacheBuilder<S, NGH, E> {
+        LIRSCacheBuilder {
+            size: self.size,
+            hir_ratio: self.hir_ratio,
+            ghost_ratio: self.ghost_ratio,
+            hasher: self.hasher,
+            ghost_hasher: Some(hasher),
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the callback invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry changes between LIR and HIR. It is invoked
+    /// when a resident entry is evicted (a non-resident HIR entry only remembers the hash of
+    /// the key, so the value is dropped right after the callback), removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, LIRSCache, LIRSCacheBuilder, OnEvictCallback};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: LIRSCache<u64, u64, _, _, _> = LIRSCacheBuilder::new(4)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// (1..=4).for_each(|i| {
+    ///     cache.put(i, i);
+    /// });
+    ///
+    /// // the resident HIR entry 4 is evicted, only the hash of its key is kept
+    /// cache.put(5, 5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    ///
+    /// cache.remove(&5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(counter.load(Ordering::SeqCst), 5);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> LIRSCacheBuilder<S, GH, NE> {
+        LIRSCacheBuilder {
+            size: self.size,
+            hir_ratio: self.hir_ratio,
+            ghost_ratio: self.ghost_ratio,
+            hasher: self.hasher,
+            ghost_hasher: self.ghost_hasher,
+            on_evict: Some(cb),
+        }
+    }
+
+    /// Finalize the builder to [`LIRSCache`]
+    ///
+    /// [`LIRSCache`]: struct.LIRSCache.html
+    pub fn finalize<K: Hash + Eq, V>(self) -> Result<LIRSCache<K, V, S, GH, E>, CacheError> {
+        self.finalize_in(false)
+    }
+
+    pub(crate) fn finalize_in<K: Hash + Eq, V>(
+        self,
+        adaptive: bool,
+    ) -> Result<LIRSCache<K, V, S, GH, E>, CacheError> {
+        let size = self.size;
+        if size == 0 {
+            return Err(CacheError::InvalidSize(0));
+        }
+
+        let hr = self.hir_ratio.unwrap();
+        if !(0.0..=1.0).contains(&hr) {
+            return Err(CacheError::InvalidHIRRatio(hr));
+        }
+
+        let gr = self.ghost_ratio.unwrap();
+        if gr < 0.0 {
+            return Err(CacheError::InvalidGhostRatio(gr));
+        }
+
+        let hir_cap = hir_size(size, hr);
+        Ok(LIRSCache {
+            size,
+            hir_ratio: hr,
+            hir_cap,
+            lir_cap: size - hir_cap,
+            ghost_cap: ((size as f64) * gr).floor() as usize,
+            lir_len: 0,
+            demoted_len: 0,
+            adaptive,
+            map: HashMap::with_capacity_and_hasher(size, self.hasher.unwrap()),
+            ghosts: HashMap::with_hasher(self.ghost_hasher.unwrap()),
+            stack: NodeList::new(STACK),
+            queue: NodeList::new(QUEUE),
+            non_resident: NodeList::new(QUEUE),
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+// The HIR part always has at least one slot, and leaves at least one slot for the LIR part.
+fn hir_size(size: usize, ratio: f64) -> usize {
+    if size < 2 {
+        return 0;
+    }
+    (((size as f64) * ratio).floor() as usize).clamp(1, size - 1)
+}
+
+/// `LIRSCache` is a fixed size cache based on the Low Inter-reference Recency Set
+/// replacement policy.
+///
+/// LIRS uses the reuse distance of the entries, rather than their recency, to decide
+/// which entries to keep. Entries with a short reuse distance are LIR entries and are
+/// always resident. The other entries are HIR entries, only a small part of the cache
+/// (see [`DEFAULT_LIRS_HIR_RATIO`]) is dedicated to the resident HIR entries.
+///
+/// The recency of the entries is tracked by the stack S, which holds the LIR entries,
+/// and the HIR entries, resident or not, that are more recent than the least recent LIR entry.
+/// The resident HIR entries are also kept in the queue Q, the front of which is evicted first.
+/// When an evicted HIR entry is still in the stack, it is kept as a non-resident HIR entry
+/// (which only stores the hash of the key), so that it can become a LIR entry if it comes back soon.
+///
+/// Compared with [`LRUCache`] and [`TwoQueueCache`], LIRS is not polluted by
+/// scans of entries which are accessed only once.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, LIRSCache, PutResult};
+///
+/// // 2 slots for LIR entries and 1 slot for resident HIR entries
+/// let mut cache = LIRSCache::<u64, u64>::builder(3)
+///     .set_hir_ratio(0.34)
+///     .finalize()
+///     .unwrap();
+///
+/// // the first entries become LIR entries until the LIR part is full
+/// assert_eq!(cache.put(1, 1), PutResult::Put);
+/// assert_eq!(cache.put(2, 2), PutResult::Put);
+/// assert_eq!(cache.lir_len(), 2);
+///
+/// // then new entries are resident HIR entries
+/// assert_eq!(cache.put(3, 3), PutResult::Put);
+/// assert_eq!(cache.hir_len(), 1);
+///
+/// // evict the resident HIR entry 3, which becomes non-resident
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 3, value: 3 });
+/// assert_eq!(cache.non_resident_len(), 1);
+///
+/// // 3 comes back before the least recent LIR entry is used again,
+/// // so 3 becomes a LIR entry and 1 is demoted to a resident HIR entry.
+/// assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 4, value: 4 });
+/// assert_eq!(cache.lir_len(), 2);
+/// assert_eq!(cache.hir_len(), 1);
+/// assert!(cache.contains(&1));
+/// ```
+///
+/// [`DEFAULT_LIRS_HIR_RATIO`]: constant.DEFAULT_LIRS_HIR_RATIO.html
+/// [`LRUCache`]: type.LRUCache.html
+/// [`TwoQueueCache`]: struct.TwoQueueCache.html
+pub struct LIRSCache<
+    K,
+    V,
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    size: usize,
+    // the initial hir_cap is computed from hir_ratio, purge goes back to it.
+    hir_ratio: f64,
+    hir_cap: usize,
+    lir_cap: usize,
+    ghost_cap: usize,
+    lir_len: usize,
+    demoted_len: usize,
+    // adapts hir_cap as DLIRS if true.
+    adaptive: bool,
+    map: HashMap<KeyRef<K>, Box<LIRSNode<K, V>>, S>,
+    ghosts: HashMap<u64, Box<LIRSNode<K, V>>, GH>,
+    stack: NodeList<K, V>,
+    queue: NodeList<K, V>,
+    non_resident: NodeList<K, V>,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> LIRSCache<K, V> {
+    /// Create a `LIRSCache` with size and default configurations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{Cache, LIRSCache};
+    ///
+    /// let mut cache: LIRSCache<u64, u64> = LIRSCache::new(5).unwrap();
+    /// ```
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        LIRSCacheBuilder::new(size).finalize()
+    }
+
+    /// Returns a [`LIRSCacheBuilder`] to help build a [`LIRSCache`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{LIRSCacheBuilder, LIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = LIRSCache::<u64, u64>::builder(100)
+    ///     .set_hir_ratio(0.1)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`LIRSCacheBuilder`]: struct.LIRSCacheBuilder.html
+    /// [`LIRSCache`]: struct.LIRSCache.html
+    pub fn builder(size: usize) -> LIRSCacheBuilder {
+        LIRSCacheBuilder::new(size)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for LIRSCache<K, V, S, GH, E>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// # Note
+    /// - If the cache is full, the least recent resident HIR entry is evicted.
+    /// - A new entry becomes a LIR entry if the LIR part is not full, or if it is
+    ///   a non-resident HIR entry, otherwise it becomes a resident HIR entry.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, LIRSCache, PutResult};
+    ///
+    /// let mut cache = LIRSCache::<u64, u64>::builder(3)
+    ///     .set_hir_ratio(0.34)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// assert_eq!(cache.put(1, 1), PutResult::Put);
+    /// assert_eq!(cache.put(2, 2), PutResult::Put);
+    /// assert_eq!(cache.put(3, 3), PutResult::Put);
+    /// // 3 is accessed again while it is in the stack, so it becomes a LIR entry
+    /// // and the least recent LIR entry 1 is demoted to a resident HIR entry.
+    /// assert_eq!(cache.put(3, 33), PutResult::Update(3));
+    /// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 1, value: 1 });
+    /// ```
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Updates the LIR/HIR status of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// // banana becomes a LIR entry, and apple is demoted and evicted.
+    /// assert_eq!(cache.get(&"apple"), None);
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// assert_eq!(cache.get(&"pear"), Some(&2));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_node(k)
+            .map(|node| unsafe { &*((*node).val.as_ref().unwrap() as *const V) })
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Updates the LIR/HIR status of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.get_node(k)
+            .map(|node| unsafe { &mut *((*node).val.as_mut().unwrap() as *mut V) })
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map
+            .get(k)
+            .map(|node| unsafe { &*(node.val.as_ref().unwrap() as *const V) })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the
+    /// LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map
+            .get_mut(k)
+            .map(|node| unsafe { &mut *(node.val.as_mut().unwrap() as *mut V) })
+    }
+
+    /// Returns a bool indicating whether the given key is resident in the cache.
+    /// Does not update the LIR/HIR status of the entry.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(k)
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let mut node = self.map.remove(k)?;
+        let ptr: *mut LIRSNode<K, V> = &mut *node;
+        unsafe {
+            match node.status {
+                Status::Lir => {
+                    let bottom = self.stack.tail() == ptr;
+                    self.stack.remove(ptr);
+                    self.lir_len -= 1;
+                    if bottom {
+                        self.prune();
+                    }
+                }
+                Status::Hir => {
+                    self.queue.remove(ptr);
+                    self.clear_demoted(ptr);
+                    if node.in_stack {
+                        self.stack.remove(ptr);
+                    }
+                }
+            }
+        }
+        let key = node.key.take().unwrap();
+        let val = node.val.take().unwrap();
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache, including the non-resident HIR entries.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache: LIRSCache<isize, &str> = LIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        if let Some(ref cb) = self.on_evict {
+            self.map.values().for_each(|node| {
+                cb.on_evict_with_reason(
+                    node.key.as_ref().unwrap(),
+                    node.val.as_ref().unwrap(),
+                    EvictionReason::Purged,
+                )
+            });
+        }
+        self.stack.clear();
+        self.queue.clear();
+        self.non_resident.clear();
+        self.map.clear();
+        self.ghosts.clear();
+        self.lir_len = 0;
+        self.demoted_len = 0;
+        self.hir_cap = hir_size(self.size, self.hir_ratio);
+        self.lir_cap = self.size - self.hir_cap;
+    }
+
+    /// Returns the number of resident entries that are currently in the the cache
+    /// (excluding the non-resident HIR entries).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache = LIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Returns the maximum number of resident entries the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, LIRSCache};
+    /// let mut cache: LIRSCache<isize, &str> = LIRSCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    LIRSCache<K, V, S, GH, E>
+{
+    /// Create a [`LIRSCache`] from [`LIRSCacheBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{LIRSCacheBuilder, LIRSCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let builder = LIRSCacheBuilder::new(5)
+    ///     .set_hir_ratio(0.2)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default());
+    ///
+    /// let mut cache = LIRSCache::from_builder(builder).unwrap();
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`LIRSCacheBuilder`]: struct.LIRSCacheBuilder.html
+    /// [`LIRSCache`]: struct.LIRSCache.html
+    pub fn from_builder(builder: LIRSCacheBuilder<S, GH, E>) -> Result<Self, CacheError> {
+        builder.finalize()
+    }
+
+    /// Returns the number of LIR entries that are currently in the cache.
+    pub fn lir_len(&self) -> usize {
+        self.lir_len
+    }
+
+    /// Returns the number of resident HIR entries that are currently in the cache.
+    pub fn hir_len(&self) -> usize {
+        self.queue.len()
+    }
+
+    /// Returns the number of non-resident HIR entries that are currently in the stack.
+    pub fn non_resident_len(&self) -> usize {
+        self.non_resident.len()
+    }
+
+    /// Returns the number of entries, resident or not, that are currently in the stack.
+    pub fn stack_len(&self) -> usize {
+        self.stack.len()
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// A put of a non-resident HIR entry is counted as a ghost hit.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, LIRSCache};
+        /// let mut cache = LIRSCache::<u64, &str>::builder(2)
+        ///     .set_hir_ratio(0.5)
+        ///     .finalize()
+        ///     .unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.put(3, "c");
+        /// cache.put(2, "b");
+        /// cache.get(&1);
+        /// cache.get(&3);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 4);
+        /// assert_eq!(stats.evictions(), 2);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, LIRSCache};
+        /// let mut cache = LIRSCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn put_in(&mut self, k: K, mut v: V) -> PutResult<K, V> {
+        if let Some(node) = self.map.get_mut(&KeyRef { k: &k }) {
+            let node: *mut LIRSNode<K, V> = &mut **node;
+            unsafe {
+                mem::swap(&mut v, (*node).val.as_mut().unwrap());
+                self.hit(node);
+            }
+            self.cb(&k, &v, EvictionReason::Replaced);
+            return PutResult::Update(v);
+        }
+        self.put_new(k, v).0
+    }
+
+    // Puts a key which is not in the cache, returns the result and the node of the new entry.
+    fn put_new(&mut self, k: K, v: V) -> (PutResult<K, V>, *mut LIRSNode<K, V>) {
+        let evicted = if self.map.len() >= self.size {
+            self.evict()
+        } else {
+            None
+        };
+
+        let hash = self.hash_key(&k);
+        let ptr = unsafe {
+            match self.ghosts.remove(&hash) {
+                Some(mut node) => {
+                    // a non-resident HIR entry comes back, it becomes a LIR entry.
+                    self.stats.record_ghost_hit();
+                    if self.adaptive {
+                        self.grow_hir();
+                    }
+                    let ptr: *mut LIRSNode<K, V> = &mut *node;
+                    self.non_resident.remove(ptr);
+                    self.stack.remove(ptr);
+                    node.key = Some(k);
+                    node.val = Some(v);
+                    node.status = Status::Lir;
+                    self.stack.push_front(ptr);
+                    self.lir_len += 1;
+                    self.map.insert(node.key_ref(), node);
+                    self.fit_lir();
+                    ptr
+                }
+                None => {
+                    let status = if self.lir_len < self.lir_cap {
+                        Status::Lir
+                    } else {
+                        Status::Hir
+                    };
+                    let mut node = Box::new(LIRSNode::new(k, v, status));
+                    let ptr: *mut LIRSNode<K, V> = &mut *node;
+                    node.in_stack = true;
+                    self.stack.push_front(ptr);
+                    match status {
+                        Status::Lir => self.lir_len += 1,
+                        Status::Hir => self.queue.push_front(ptr),
+                    }
+                    self.map.insert(node.key_ref(), node);
+                    ptr
+                }
+            }
+        };
+
+        let rst = match evicted {
+            None => PutResult::Put,
+            Some((key, value)) => {
+                self.cb(&key, &value, EvictionReason::Capacity);
+                PutResult::Evicted { key, value }
+            }
+        };
+        (rst, ptr)
+    }
+
+    fn get_node<Q>(&mut self, k: &Q) -> Option<*mut LIRSNode<K, V>>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let node = self
+            .map
+            .get_mut(k)
+            .map(|node| &mut **node as *mut LIRSNode<K, V>);
+        self.stats.record_get(node.is_some());
+        let node = node?;
+        unsafe {
+            self.hit(node);
+        }
+        Some(node)
+    }
+
+    // Updates the stack and the queue when a resident entry is accessed.
+    unsafe fn hit(&mut self, node: *mut LIRSNode<K, V>) {
+        match (*node).status {
+            Status::Lir => {
+                let bottom = self.stack.tail() == node;
+                self.stack.move_to_front(node);
+                if bottom {
+                    self.prune();
+                }
+            }
+            Status::Hir if (*node).in_stack || self.lir_len < self.lir_cap => {
+                // the reuse distance is shorter than the least recent LIR entry, or the
+                // LIR part is not full, the entry becomes a LIR entry. Pushing a HIR entry
+                // to a stack without LIR entries would leave it at the bottom of the stack.
+                if (*node).in_stack {
+                    self.stack.move_to_front(node);
+                } else {
+                    (*node).in_stack = true;
+                    self.stack.push_front(node);
+                }
+                self.queue.remove(node);
+                self.clear_demoted(node);
+                (*node).status = Status::Lir;
+                self.lir_len += 1;
+                self.fit_lir();
+            }
+            Status::Hir => {
+                if (*node).demoted {
+                    // a demoted entry is accessed again, the LIR part was too small.
+                    if self.adaptive {
+                        self.shrink_hir();
+                    }
+                    self.clear_demoted(node);
+                }
+                (*node).in_stack = true;
+                self.stack.push_front(node);
+                self.queue.move_to_front(node);
+            }
+        }
+    }
+
+    // Demotes the least recent LIR entries until the LIR part fits in its capacity,
+    // stops if there is no LIR entry left to demote.
+    unsafe fn fit_lir(&mut self) {
+        while self.lir_len > self.lir_cap {
+            if !self.demote_bottom_lir() {
+                return;
+            }
+        }
+    }
+
+    // Turns the least recent LIR entry to a resident HIR entry,
+    // returns false if the stack has no LIR entry.
+    unsafe fn demote_bottom_lir(&mut self) -> bool {
+        // only a LIR entry can be demoted, the HIR entries below it leave the stack first.
+        self.prune();
+        let bottom = self.stack.tail();
+        if bottom.is_null() {
+            return false;
+        }
+        self.stack.remove(bottom);
+        (*bottom).in_stack = false;
+        (*bottom).status = Status::Hir;
+        (*bottom).demoted = true;
+        self.lir_len -= 1;
+        self.demoted_len += 1;
+        self.queue.push_front(bottom);
+        self.prune();
+        true
+    }
+
+    #[inline]
+    unsafe fn clear_demoted(&mut self, node: *mut LIRSNode<K, V>) {
+        if (*node).demoted {
+            (*node).demoted = false;
+            self.demoted_len -= 1;
+        }
+    }
+
+    // A non-resident HIR entry is accessed again, gives more room to the HIR part,
+    // the same way as ARC increases `p` on a hit in B1.
+    fn grow_hir(&mut self) {
+        if self.size < 2 {
+            return;
+        }
+        let delta = (self.demoted_len / self.non_resident.len().max(1)).max(1);
+        self.hir_cap = (self.hir_cap + delta).min(self.size - 1);
+        self.lir_cap = self.size - self.hir_cap;
+    }
+
+    // A demoted HIR entry is accessed again, gives more room to the LIR part,
+    // the same way as ARC decreases `p` on a hit in B2.
+    fn shrink_hir(&mut self) {
+        if self.size < 2 {
+            return;
+        }
+        let delta = (self.non_resident.len() / self.demoted_len.max(1)).max(1);
+        self.hir_cap = self.hir_cap.saturating_sub(delta).max(1);
+        self.lir_cap = self.size - self.hir_cap;
+    }
+
+    /// Returns the number of resident entries reserved for the HIR part.
+    pub(crate) fn partition(&self) -> usize {
+        self.hir_cap
+    }
+
+    /// Returns the number of resident HIR entries which were demoted from LIR entries.
+    pub(crate) fn demoted_len(&self) -> usize {
+        self.demoted_len
+    }
+
+    // Removes the HIR entries at the bottom of the stack,
+    // so that the bottom of the stack is always a LIR entry.
+    fn prune(&mut self) {
+        unsafe {
+            loop {
+                let bottom = self.stack.tail();
+                if bottom.is_null() || (*bottom).status == Status::Lir {
+                    return;
+                }
+                self.stack.remove(bottom);
+                (*bottom).in_stack = false;
+                if (*bottom).key.is_none() {
+                    self.non_resident.remove(bottom);
+                    self.ghosts.remove(&(*bottom).hash);
+                }
+            }
+        }
+    }
+
+    // Evicts the least recent resident HIR entry, or the least recent LIR
+    // entry if there is no resident HIR entry.
+    fn evict(&mut self) -> Option<(K, V)> {
+        unsafe {
+            let tail = self.queue.tail();
+            if !tail.is_null() {
+                self.queue.remove(tail);
+                self.clear_demoted(tail);
+                let mut node = self.map.remove(&(*tail).key_ref()).unwrap();
+                let key = node.key.take().unwrap();
+                let val = node.val.take().unwrap();
+                if node.in_stack {
+                    node.hash = self.hash_key(&key);
+                    self.insert_non_resident(node);
+                }
+                return Some((key, val));
+            }
+
+            let bottom = self.stack.tail();
+            if bottom.is_null() {
+                return None;
+            }
+            self.stack.remove(bottom);
+            self.lir_len -= 1;
+            let mut node = self.map.remove(&(*bottom).key_ref()).unwrap();
+            self.prune();
+            Some((node.key.take().unwrap(), node.val.take().unwrap()))
+        }
+    }
+
+    unsafe fn insert_non_resident(&mut self, mut node: Box<LIRSNode<K, V>>) {
+        if let Some(mut old) = self.ghosts.remove(&node.hash) {
+            // hash collision, forget the older one.
+            self.unlink_non_resident(&mut old);
+        }
+        self.non_resident.push_front(&mut *node);
+        self.ghosts.insert(node.hash, node);
+
+        while self.non_resident.len() > self.ghost_cap {
+            let tail = self.non_resident.tail();
+            let mut node = self.ghosts.remove(&(*tail).hash).unwrap();
+            self.unlink_non_resident(&mut node);
+        }
+    }
+
+    unsafe fn unlink_non_resident(&mut self, node: &mut LIRSNode<K, V>) {
+        let ptr: *mut LIRSNode<K, V> = node;
+        self.non_resident.remove(ptr);
+        if node.in_stack {
+            self.stack.remove(ptr);
+        }
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key<Q: Hash + ?Sized>(&self, k: &Q) -> u64 {
+        let mut hasher = self.map.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for LIRSCache<K, V, S, GH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<ptr::NonNull<V>>) {
+        // the lookup of the entry has found that the key is not in the cache
+        let (rst, node) = self.put_new(k, v);
+        self.stats.record_put(&rst);
+        let v = unsafe { (*node).val.as_mut().map(ptr::NonNull::from) };
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> fmt::Debug
+    for LIRSCache<K, V, S, GH, E>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("LIRSCache")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, S: Send, GH: Send, E: Send> Send for LIRSCache<K, V, S, GH, E> {}
+unsafe impl<K: Sync, V: Sync, S: Sync, GH: Sync, E: Sync> Sync for LIRSCache<K, V, S, GH, E> {}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::lirs::{LIRSCache, LIRSCacheBuilder, Status};
+    use crate::lru::CacheError;
+    use crate::{Cache, LRUCache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+
+    fn new_cache(size: usize, hir_ratio: f64) -> LIRSCache<u64, u64> {
+        LIRSCacheBuilder::new(size)
+            .set_hir_ratio(hir_ratio)
+            .finalize()
+            .unwrap()
+    }
+
+    // check the invariants of the stack, the queue and the non-resident list.
+    fn check(cache: &LIRSCache<u64, u64>) {
+        assert!(cache.len() <= cache.cap());
+        assert!(cache.lir_len() <= cache.lir_cap);
+        assert_eq!(cache.lir_len() + cache.hir_len(), cache.len());
+        assert_eq!(cache.non_resident_len(), cache.ghosts.len());
+        assert!(cache.non_resident_len() <= cache.ghost_cap);
+
+        let bottom = cache.stack.tail();
+        if !bottom.is_null() {
+            assert_eq!(unsafe { (*bottom).status }, Status::Lir);
+        }
+    }
+
+    #[test]
+    fn test_lirs_cache_error() {
+        let err = LIRSCache::<u64, u64>::new(0).unwrap_err();
+        assert_eq!(err, CacheError::InvalidSize(0));
+
+        let err = LIRSCacheBuilder::new(3)
+            .set_hir_ratio(1.5)
+            .finalize::<u64, u64>()
+            .unwrap_err();
+        assert_eq!(err, CacheError::InvalidHIRRatio(1.5));
+
+        let err = LIRSCacheBuilder::new(3)
+            .set_ghost_ratio(-1.0)
+            .finalize::<u64, u64>()
+            .unwrap_err();
+        assert_eq!(err, CacheError::InvalidGhostRatio(-1.0));
+    }
+
+    #[test]
+    fn test_lirs_cache_put() {
+        let mut cache = new_cache(3, 0.34);
+        assert_eq!(cache.put(1, 1), PutResult::Put);
+        assert_eq!(cache.put(2, 2), PutResult::Put);
+        assert_eq!(cache.put(3, 3), PutResult::Put);
+        assert_eq!((cache.lir_len(), cache.hir_len()), (2, 1));
+
+        assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.non_resident_len(), 1);
+
+        // 3 comes back as a LIR entry, 1 is demoted.
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(cache.peek(&1), Some(&1));
+        assert_eq!(unsafe { (*cache.queue.head()).key }, Some(1));
+        // 1 is not in the stack anymore, and 4 is non-resident
+        assert_eq!(cache.stack_len(), 3);
+        assert_eq!(cache.non_resident_len(), 1);
+        check(&cache);
+
+        // 1 is accessed again, but it is not in the stack, so it stays HIR.
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.hir_len(), 1);
+        assert_eq!(cache.stack_len(), 4);
+
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.non_resident_len(), 2);
+        check(&cache);
+
+        // 1 is accessed again while in the stack, promote it.
+        assert_eq!(cache.put(1, 11), PutResult::Evicted { key: 5, value: 5 });
+        assert!(cache.contains(&1));
+        assert!(cache.contains(&3));
+        assert_eq!(cache.lir_len(), 2);
+        check(&cache);
+    }
+
+    #[test]
+    fn test_lirs_cache_get_hir_to_lir() {
+        let mut cache = new_cache(4, 0.5);
+        (1..=4).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!((cache.lir_len(), cache.hir_len()), (2, 2));
+
+        // 3 is a resident HIR entry in the stack, it becomes LIR, and 1 is demoted.
+        assert_eq!(cache.get(&3), Some(&3));
+        assert_eq!((cache.lir_len(), cache.hir_len()), (2, 2));
+        assert_eq!(unsafe { (*cache.queue.head()).key }, Some(1));
+        check(&cache);
+    }
+
+    #[test]
+    fn test_lirs_cache_remove() {
+        let mut cache = new_cache(4, 0.5);
+        (1..=4).for_each(|i| {
+            cache.put(i, i);
+        });
+
+        // remove all the LIR entries, 3 and 4 are pruned from the stack.
+        assert_eq!(cache.remove(&2), Some(2));
+        assert_eq!(cache.stack_len(), 3);
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.stack_len(), 0);
+        assert_eq!(cache.remove(&4), Some(4));
+        assert_eq!(cache.remove(&4), None);
+        assert_eq!((cache.lir_len(), cache.hir_len()), (0, 1));
+        check(&cache);
+
+        cache.purge();
+        assert!(cache.is_empty());
+        assert_eq!(cache.stack_len(), 0);
+    }
+
+    #[test]
+    fn test_lirs_cache_hit_without_lir() {
+        let mut cache = LIRSCache::new(3).unwrap();
+        (0..3).for_each(|i| {
+            cache.put(i, i);
+        });
+
+        // all the LIR entries are removed, the stack is empty.
+        assert_eq!(cache.remove(&0), Some(0));
+        assert_eq!(cache.remove(&1), Some(1));
+        assert_eq!(cache.stack_len(), 0);
+
+        // 2 is a resident HIR entry out of the stack, the LIR part is empty, so it becomes
+        // a LIR entry instead of being left at the bottom of the stack.
+        assert_eq!(cache.get(&2), Some(&2));
+        assert_eq!((cache.lir_len(), cache.hir_len()), (1, 0));
+        check(&cache);
+
+        cache.put(0, 0);
+        cache.put(1, 1);
+        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 1, value: 1 });
+        check(&cache);
+        assert_eq!(cache.put(7, 7), PutResult::Update(7));
+        check(&cache);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 2, value: 2 });
+        check(&cache);
+        assert_eq!(cache.len(), 3);
+    }
+
+    #[test]
+    fn test_lirs_cache_scan_resistance() {
+        let size = 100;
+        let mut cache = new_cache(size, 0.1);
+        let mut lru = LRUCache::new(size).unwrap();
+
+        // the working set is accessed twice.
+        (0..2).for_each(|_| {
+            (0..90u64).for_each(|i| {
+                cache.put(i, i);
+                lru.put(i, i);
+            })
+        });
+
+        // a long scan of entries which are never accessed again.
+        (1000..10000u64).for_each(|i| {
+            cache.put(i, i);
+            lru.put(i, i);
+            check(&cache);
+        });
+
+        (0..90u64).for_each(|i| {
+            assert_eq!(cache.get(&i), Some(&i));
+            assert_eq!(lru.get(&i), None);
+        });
+    }
+
+    #[test]
+    fn test_lirs_cache_random_ops() {
+        let size = 128;
+        let mut rng = thread_rng();
+        let mut cache = new_cache(size, 0.1);
+
+        (0..200_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => {
+                    let _ = cache.put(k, k);
+                }
+                1 => {
+                    if let Some(v) = cache.get(&k) {
+                        assert_eq!(*v, k);
+                    }
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            check(&cache);
+        })
+    }
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_lirs_cache_on_evict() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = LIRSCacheBuilder::new(64)
+            .set_hir_ratio(0.1)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64, u64>()
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 256;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Put | PutResult::Evicted { .. } => inserted += 1,
+                    _ => {}
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+}
@@ -35,11 +35,16 @@ use core::mem;
 use core::ptr;
 use core::usize;
 
-use crate::cache_api::ResizableCache;
+use crate::cache_api::{PeekVictim, ResizableCache};
 use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::entry::sealed::EntryCache;
 use crate::{
-    import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder, KeyRef,
-    OnEvictCallback, PutResult,
+    cfg_stats, import_hashbrown, import_std, Cache, DefaultEvictCallback, DefaultHashBuilder,
+    Entry, EvictionReason, KeyRef, OnEvictCallback, PutResult,
 };
 
 import_hashbrown!(HashSet, HashMap);
@@ -158,6 +163,7 @@ pub struct RawLRU<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
     pub(crate) map: HashMap<KeyRef<K>, Box<EntryNode<K, V>>, S>,
     cap: usize,
     on_evict: Option<E>,
+    stats: StatsRecorder,
 
     // head and tail are sigil nodes to faciliate inserting entries
     head: *mut EntryNode<K, V>,
@@ -205,6 +211,12 @@ impl<K: Hash + Eq, V, S: BuildHasher> RawLRU<K, V, DefaultEvictCallback, S> {
             )
         })
     }
+
+    /// Creates a LRU which never evicts entries by itself, it is used by the caches
+    /// whose capacity is not an entry count.
+    pub(crate) fn unbounded_with_hasher(hash_builder: S) -> Self {
+        Self::construct(usize::MAX, HashMap::with_hasher(hash_builder), None)
+    }
 }
 
 impl<K: Hash + Eq, V, E: OnEvictCallback> RawLRU<K, V, E, DefaultHashBuilder> {
@@ -273,13 +285,16 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
             node_ptr
         });
 
-        match node_ptr {
+        let rst = match node_ptr {
             Some(node_ptr) => {
                 self.update(&mut v, node_ptr);
+                self.cb(&k, &v, EvictionReason::Replaced);
                 PutResult::Update(v)
             }
             None => self.put_in(k, v),
-        }
+        };
+        self.stats.record_put(&rst);
+        rst
     }
 
     /// Returns a reference to the value of the key in the cache or `None` if it is not
@@ -300,7 +315,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     /// assert_eq!(cache.get(&2), Some(&"c"));
     /// assert_eq!(cache.get(&3), Some(&"d"));
     /// ```
-    fn get<'a, Q>(&'_ mut self, k: &'a Q) -> Option<&'a V>
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -310,9 +325,11 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
 
             self.detach(node_ptr);
             self.attach(node_ptr);
+            self.stats.record_get(true);
 
             Some(unsafe { &(*(*node_ptr).val.as_ptr()) as &V })
         } else {
+            self.stats.record_get(false);
             None
         }
     }
@@ -335,7 +352,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
     /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
     /// ```
-    fn get_mut<'a, Q>(&'_ mut self, k: &'a Q) -> Option<&'a mut V>
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -345,9 +362,11 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
 
             self.detach(node_ptr);
             self.attach(node_ptr);
+            self.stats.record_get(true);
 
             Some(unsafe { &mut (*(*node_ptr).val.as_mut_ptr()) as &mut V })
         } else {
+            self.stats.record_get(false);
             None
         }
     }
@@ -368,7 +387,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     /// assert_eq!(cache.peek(&1), Some(&"a"));
     /// assert_eq!(cache.peek(&2), Some(&"b"));
     /// ```
-    fn peek<'a, Q>(&'_ self, k: &'a Q) -> Option<&'a V>
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -394,7 +413,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
     /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
     /// ```
-    fn peek_mut<'a, Q>(&'_ mut self, k: &'a Q) -> Option<&'a mut V>
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -459,7 +478,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
                 self.detach(node_ptr);
                 unsafe {
                     let val = old_node.val.assume_init();
-                    self.cb(&*old_node.key.as_ptr(), &val);
+                    self.cb(&*old_node.key.as_ptr(), &val, EvictionReason::Removed);
                     ptr::drop_in_place(old_node.key.as_mut_ptr());
                     Some(val)
                 }
@@ -486,7 +505,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     /// assert_eq!(cache.len(), 0);
     /// ```
     fn purge(&mut self) {
-        while self.remove_lru().is_some() {}
+        while self.remove_lru_with(EvictionReason::Purged).is_some() {}
     }
 
     /// Returns the number of key-value pairs that are currently in the the cache.
@@ -542,6 +561,35 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for RawLRU
     }
 }
 
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> PeekVictim<K, V> for RawLRU<K, V, E, S> {
+    /// Returns the key of the least recently used entry if the cache is full and does not
+    /// contain `k`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PeekVictim, RawLRU};
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.peek_victim(&2), None);
+    ///
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.peek_victim(&2), None);
+    /// assert_eq!(cache.peek_victim(&3), Some(&1));
+    /// ```
+    fn peek_victim<Q>(&self, k: &Q) -> Option<&K>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        if self.len() < self.cap() || self.contains(k) {
+            return None;
+        }
+        self.peek_lru().map(|(k, _)| k)
+    }
+}
+
 impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ResizableCache for RawLRU<K, V, E, S> {
     /// Resizes the cache. If the new capacity is smaller than the size of the current
     /// cache any entries past the new capacity are discarded.
@@ -572,7 +620,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ResizableCache for Raw
         }
 
         while self.map.len() > cap {
-            self.remove_lru();
+            self.remove_lru_with(EvictionReason::Resized);
             evicted += 1;
         }
         self.map.shrink_to_fit();
@@ -634,6 +682,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
             map,
             cap,
             on_evict: cb,
+            stats: StatsRecorder::default(),
             head: Box::into_raw(Box::new(EntryNode::new_sigil())),
             tail: Box::into_raw(Box::new(EntryNode::new_sigil())),
         };
@@ -967,6 +1016,38 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
         }
     }
 
+    /// Gets the given key's corresponding [`Entry`] in the cache for in-place manipulation.
+    /// Looking up the entry updates the recent-ness of the key just like `get`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, Entry, RawLRU, PutResult};
+    /// let mut cache = RawLRU::new(2).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 1);
+    ///
+    /// cache.entry(1).and_modify(|v| *v += 1).or_insert(1);
+    /// assert_eq!(cache.peek(&1), Some(&2));
+    ///
+    /// // 2 is the least recently used entry now
+    /// match cache.entry(3) {
+    ///     Entry::Occupied(_) => unreachable!(),
+    ///     Entry::Vacant(ent) => {
+    ///         let (v, rst) = ent.insert(1);
+    ///         *v += 10;
+    ///         assert_eq!(rst, PutResult::Evicted { key: 2, value: 1 });
+    ///     }
+    /// }
+    /// assert_eq!(cache.peek(&3), Some(&11));
+    /// ```
+    ///
+    /// [`Entry`]: enum.Entry.html
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, V, Self> {
+        Entry::new(self, k)
+    }
+
     /// Removes and returns the key and value corresponding to the least recently
     /// used item or `None` if the cache is empty.
     ///
@@ -987,6 +1068,48 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
     /// assert_eq!(cache.len(), 0);
     /// ```
     pub fn remove_lru(&mut self) -> Option<(K, V)> {
+        self.remove_lru_with(EvictionReason::Removed)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, RawLRU};
+        /// let mut cache = RawLRU::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// assert_eq!(cache.stats().hits(), 1);
+        /// assert_eq!(cache.stats().misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, RawLRU};
+        /// let mut cache = RawLRU::new(2).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn remove_lru_with(&mut self, reason: EvictionReason) -> Option<(K, V)> {
         let node = self.remove_lru_in()?;
         // N.B.: Can't destructure directly because of https://github.com/rust-lang/rust/issues/28536
         let node = *node;
@@ -994,7 +1117,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
         unsafe {
             let key = key.assume_init();
             let val = val.assume_init();
-            self.cb(&key, &val);
+            self.cb(&key, &val, reason);
             Some((key, val))
         }
     }
@@ -1324,7 +1447,7 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
 
             let keyref = unsafe { (*node_ptr).key.as_ptr() };
             self.map.insert(KeyRef { k: keyref }, old_node);
-            self.cb(&k, &v);
+            self.cb(&k, &v, EvictionReason::Capacity);
             PutResult::Evicted { key: k, value: v }
         } else {
             // if the cache is not full allocate a new EntryNode
@@ -1390,6 +1513,18 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
         }
     }
 
+    /// Removes the entry without invoking the evict callback.
+    pub(crate) fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.remove_and_return_ent(k).map(|ent| {
+            let EntryNode { key, val, .. } = *ent;
+            unsafe { (key.assume_init(), val.assume_init()) }
+        })
+    }
+
     pub(crate) fn remove_lru_in(&mut self) -> Option<Box<EntryNode<K, V>>> {
         let prev;
         unsafe { prev = (*self.tail).prev }
@@ -1411,6 +1546,43 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
         }
     }
 
+    /// Removes every entry for which `f` returns true, walking from the least recently
+    /// used entry to the most recently used one. The evict callback is not invoked,
+    /// the removed entries are returned to the caller instead.
+    pub(crate) fn remove_where_in<F>(&mut self, mut f: F) -> Vec<(K, V)>
+    where
+        F: FnMut(&K, &V) -> bool,
+    {
+        let mut removed = Vec::new();
+        unsafe {
+            let mut node = (*self.tail).prev;
+            while node != self.head {
+                let prev = (*node).prev;
+                if f(&*(*node).key.as_ptr(), &*(*node).val.as_ptr()) {
+                    // Safety: the node is in cache, so the cache map must have the node.
+                    let ent = self
+                        .map
+                        .remove(&KeyRef {
+                            k: (*node).key.as_ptr(),
+                        })
+                        .unwrap();
+                    self.detach(node);
+                    let EntryNode { key, val, .. } = *ent;
+                    removed.push((key.assume_init(), val.assume_init()));
+                }
+                node = prev;
+            }
+        }
+        removed
+    }
+
+    /// Sets the capacity without discarding any entry, the caller must make sure
+    /// the length of the cache does not exceed the new capacity.
+    pub(crate) fn set_cap(&mut self, cap: usize) {
+        debug_assert!(self.len() <= cap);
+        self.cap = cap;
+    }
+
     pub(crate) fn detach(&mut self, node: *mut EntryNode<K, V>) {
         unsafe {
             (*(*node).prev).next = (*node).next;
@@ -1428,13 +1600,24 @@ impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> RawLRU<K, V, E, S> {
     }
 
     #[inline]
-    fn cb(&self, k: &K, v: &V) {
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
         if let Some(ref cb) = self.on_evict {
-            cb.on_evict(k, v);
+            cb.on_evict_with_reason(k, v, reason);
         }
     }
 }
 
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V> for RawLRU<K, V, E, S> {
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<ptr::NonNull<V>>) {
+        // the lookup of the entry has found that the key is not in the cache
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        let v = self.peek_mru_mut().map(|(_, v)| ptr::NonNull::from(v));
+        (rst, v)
+    }
+}
+
 impl<K, V, E, S> Drop for RawLRU<K, V, E, S> {
     fn drop(&mut self) {
         self.map.values_mut().for_each(|e| unsafe {
@@ -1977,12 +2160,15 @@ impl_send_and_sync_for_iterator! {
 mod tests {
     use super::RawLRU;
     use crate::lru::CacheError;
-    use crate::{Cache, PutResult, ResizableCache};
+    use crate::{Cache, Entry, EvictionReason, OnEvictCallback, PutResult, ResizableCache};
     use alloc::collections::BTreeMap;
+    use alloc::vec;
+    use alloc::vec::Vec;
     use core::fmt::Debug;
     use scoped_threadpool::Pool;
     use std::collections::HashMap;
     use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
 
     fn assert_opt_eq<V: PartialEq + Debug>(opt: Option<&V>, v: V) {
         assert!(opt.is_some());
@@ -2169,6 +2355,46 @@ mod tests {
         assert_opt_eq(cache.peek(&"pear"), "green");
     }
 
+    #[test]
+    fn test_entry() {
+        let mut cache = RawLRU::new(2).unwrap();
+
+        cache.entry("apple").or_insert("red");
+        cache.entry("banana").or_insert_with(|| "yellow");
+        assert_opt_eq(cache.peek(&"apple"), "red");
+        assert_opt_eq(cache.peek(&"banana"), "yellow");
+
+        // the lookup of an occupied entry updates the recent-ness
+        cache.entry("apple").or_insert("green");
+        assert_opt_eq_tuple(cache.peek_lru(), ("banana", "yellow"));
+
+        match cache.entry("pear") {
+            Entry::Occupied(_) => panic!("pear should not be in the cache"),
+            Entry::Vacant(ent) => {
+                assert_eq!(ent.key(), &"pear");
+                let (v, rst) = ent.insert("green");
+                assert_eq!(*v, "green");
+                assert_eq!(
+                    rst,
+                    PutResult::Evicted {
+                        key: "banana",
+                        value: "yellow"
+                    }
+                );
+            }
+        }
+
+        match cache.entry("apple") {
+            Entry::Occupied(mut ent) => {
+                assert_eq!(ent.insert("green"), "red");
+                assert_eq!(ent.remove(), "green");
+            }
+            Entry::Vacant(_) => panic!("apple should be in the cache"),
+        }
+        assert!(!cache.contains(&"apple"));
+        assert_eq!(cache.len(), 1);
+    }
+
     #[test]
     fn test_peek_mut() {
         let mut cache = RawLRU::new(2).unwrap();
@@ -2328,6 +2554,79 @@ mod tests {
         assert_eq!(cache.get(&4), Some(&"d"));
     }
 
+    struct EvictionReasons(Arc<Mutex<Vec<EvictionReason>>>);
+
+    impl OnEvictCallback for EvictionReasons {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {}
+
+        fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+            self.0.lock().unwrap().push(reason);
+        }
+    }
+
+    #[test]
+    fn test_on_evict_with_reason() {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let mut cache: RawLRU<u64, u64, EvictionReasons> =
+            RawLRU::with_on_evict_cb(2, EvictionReasons(reasons.clone())).unwrap();
+
+        cache.put(1, 1);
+        cache.put(1, 2);
+        cache.put(2, 2);
+        cache.put(3, 3);
+        cache.remove(&2);
+        cache.remove_lru();
+        cache.put(4, 4);
+        cache.put(5, 5);
+        cache.resize(1);
+        cache.purge();
+
+        assert_eq!(
+            *reasons.lock().unwrap(),
+            vec![
+                EvictionReason::Replaced,
+                EvictionReason::Capacity,
+                EvictionReason::Removed,
+                EvictionReason::Removed,
+                EvictionReason::Resized,
+                EvictionReason::Purged,
+            ]
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_stats() {
+        let mut cache = RawLRU::new(16).unwrap();
+        let (mut hits, mut misses, mut inserts, mut updates, mut evictions) = (0, 0, 0, 0, 0);
+        for i in 0..1024u64 {
+            let k = (i * 7) % 37;
+            if i % 2 == 0 {
+                match cache.put(k, k) {
+                    PutResult::Put => inserts += 1,
+                    PutResult::Evicted { .. } => {
+                        inserts += 1;
+                        evictions += 1;
+                    }
+                    _ => updates += 1,
+                }
+            } else if cache.get(&k).is_some() {
+                hits += 1;
+            } else {
+                misses += 1;
+            }
+        }
+        evictions += cache.resize(8);
+
+        let stats = cache.stats();
+        assert_eq!(stats.hits(), hits);
+        assert_eq!(stats.misses(), misses);
+        assert_eq!(stats.inserts(), inserts);
+        assert_eq!(stats.updates(), updates);
+        assert_eq!(stats.evictions(), evictions);
+        assert_eq!(stats.rejections(), 0);
+    }
+
     #[test]
     fn test_send() {
         use std::thread;
@@ -0,0 +1,919 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::{Arena, ArenaKeys};
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+// the FIFOs are arena lists, the new entries are put to the front and
+// the entries are evicted from the back.
+const SMALL: usize = 0;
+const MAIN: usize = 1;
+const GHOST: usize = 2;
+
+// the frequency counters are 2 bits.
+const MAX_FREQ: u8 = 3;
+
+/// `DEFAULT_S3_FIFO_SMALL_RATIO` is the default ratio of the [`S3FifoCache`] dedicated
+/// to the small FIFO, which holds the new entries.
+///
+/// [`S3FifoCache`]: struct.S3FifoCache.html
+pub const DEFAULT_S3_FIFO_SMALL_RATIO: f64 = 0.1;
+
+/// `S3FifoCacheBuilder` is used to help build a [`S3FifoCache`] with custom configuration.
+///
+/// [`S3FifoCache`]: struct.S3FifoCache.html
+pub struct S3FifoCacheBuilder<S = DefaultHashBuilder, E = DefaultEvictCallback> {
+    size: usize,
+    small_ratio: Option<f64>,
+    hasher: Option<S>,
+    on_evict: Option<E>,
+}
+
+impl Default for S3FifoCacheBuilder {
+    /// Create a default `S3FifoCacheBuilder`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{S3FifoCacheBuilder, S3FifoCache, Cache};
+    /// let mut cache: S3FifoCache<u64, u64> = S3FifoCacheBuilder::default()
+    ///     .set_size(5)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    fn default() -> Self {
+        Self {
+            size: 0,
+            small_ratio: Some(DEFAULT_S3_FIFO_SMALL_RATIO),
+            hasher: Some(DefaultHashBuilder::default()),
+            on_evict: None,
+        }
+    }
+}
+
+impl S3FifoCacheBuilder {
+    /// Returns a default [`S3FifoCacheBuilder`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{S3FifoCacheBuilder, S3FifoCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = S3FifoCacheBuilder::new(100)
+    ///     .set_small_ratio(0.2)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`S3FifoCacheBuilder`]: struct.S3FifoCacheBuilder.html
+    pub fn new(size: usize) -> Self {
+        Self::default().set_size(size)
+    }
+}
+
+impl<S: BuildHasher, E: OnEvictCallback> S3FifoCacheBuilder<S, E> {
+    /// Set the cache size
+    pub fn set_size(self, size: usize) -> Self {
+        S3FifoCacheBuilder {
+            size,
+            small_ratio: self.small_ratio,
+            hasher: self.hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the small FIFO size ratio
+    pub fn set_small_ratio(self, ratio: f64) -> Self {
+        S3FifoCacheBuilder {
+            size: self.size,
+            small_ratio: Some(ratio),
+            hasher: self.hasher,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the hash builder, the small, main and ghost FIFOs share one map
+    pub fn set_hasher<NS: BuildHasher>(self, hasher: NS) -> S3FifoCacheBuilder<NS, E> {
+        S3FifoCacheBuilder {
+            size: self.size,
+            small_ratio: self.small_ratio,
+            hasher: Some(hasher),
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the callback which is invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry moves from the small FIFO to the main FIFO.
+    /// It is invoked when an entry is evicted (to the ghost FIFO or from the main FIFO),
+    /// removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, OnEvictCallback, S3FifoCache, S3FifoCacheBuilder};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: S3FifoCache<u64, u64, _, _> = S3FifoCacheBuilder::new(4)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// (1..=4).for_each(|i| {
+    ///     cache.put(i, i);
+    /// });
+    ///
+    /// // 1 is evicted to the ghost FIFO, only its key is kept
+    /// cache.put(5, 5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    ///
+    /// cache.remove(&5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> S3FifoCacheBuilder<S, NE> {
+        S3FifoCacheBuilder {
+            size: self.size,
+            small_ratio: self.small_ratio,
+            hasher: self.hasher,
+            on_evict: Some(cb),
+        }
+    }
+
+    /// Finalize the builder to [`S3FifoCache`]
+    ///
+    /// [`S3FifoCache`]: struct.S3FifoCache.html
+    pub fn finalize<K: Hash + Eq, V>(self) -> Result<S3FifoCache<K, V, S, E>, CacheError> {
+        let size = self.size;
+        if size == 0 || size >= (u32::MAX / 2) as usize {
+            return Err(CacheError::InvalidSize(size));
+        }
+
+        let sr = self.small_ratio.unwrap();
+        if !(0.0..=1.0).contains(&sr) {
+            return Err(CacheError::InvalidSmallRatio(sr));
+        }
+
+        // Determine the sub-sizes, the ghost FIFO remembers as many keys as the main FIFO holds
+        let ss = ((size as f64) * sr).floor() as usize;
+        let gs = size - ss;
+
+        Ok(S3FifoCache {
+            arena: Arena::with_hasher(size + gs, 3, self.hasher.unwrap()),
+            size,
+            small_size: ss,
+            ghost_size: gs,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+/// `S3FifoCache` is a fixed size S3-FIFO cache.
+///
+/// S3-FIFO only uses FIFO queues: a small FIFO for the new entries, a main FIFO and a ghost
+/// FIFO which only remembers the keys evicted from the small FIFO. Each entry
+/// has a 2 bits frequency counter, which a hit increments instead of moving the entry.
+///
+/// - When the small FIFO is over its share of the cache, the entries at its tail which were
+///   accessed are moved to the main FIFO, and the first one which was not accessed is evicted
+///   to the ghost FIFO. So most of the one-hit wonders are evicted quickly.
+/// - Otherwise the main FIFO evicts: the entries at its tail which were accessed are
+///   reinserted to its head with the counter decremented, until one with a zero counter
+///   is found and evicted.
+/// - A new key in the ghost FIFO is put to the main FIFO directly.
+///
+/// Like [`TwoQueueCache`], it is not polluted by scans, but a hit never relinks an entry.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, PutResult, S3FifoCacheBuilder};
+///
+/// let mut cache = S3FifoCacheBuilder::new(4)
+///     .set_small_ratio(0.25)
+///     .finalize()
+///     .unwrap();
+///
+/// (1..=4).for_each(|i| {
+///     cache.put(i, i);
+/// });
+/// assert_eq!(cache.small_len(), 4);
+///
+/// // 1 was not accessed, so it is evicted to the ghost FIFO
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+/// assert!(!cache.contains(&1));
+/// assert_eq!(cache.ghost_len(), 1);
+///
+/// // 2 was accessed, so it is moved to the main FIFO and 3 is evicted
+/// cache.get(&2);
+/// assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 3, value: 3 });
+/// assert_eq!(cache.main_len(), 1);
+///
+/// // 1 is in the ghost FIFO, so it is put to the main FIFO
+/// cache.put(1, 1);
+/// assert_eq!(cache.main_len(), 2);
+/// assert_eq!(cache.small_len(), 2);
+/// assert_eq!(cache.ghost_len(), 2);
+/// ```
+///
+/// [`TwoQueueCache`]: struct.TwoQueueCache.html
+pub struct S3FifoCache<K, V, S = DefaultHashBuilder, E = DefaultEvictCallback> {
+    arena: Arena<K, V, S>,
+    size: usize,
+    small_size: usize,
+    ghost_size: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> S3FifoCache<K, V> {
+    /// Create a `S3FifoCache` with size and default configurations.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{Cache, S3FifoCache};
+    ///
+    /// let mut cache: S3FifoCache<u64, u64> = S3FifoCache::new(5).unwrap();
+    /// ```
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        S3FifoCacheBuilder::new(size).finalize()
+    }
+
+    /// Returns a [`S3FifoCacheBuilder`] to help build a [`S3FifoCache`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{S3FifoCacheBuilder, S3FifoCache, Cache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let mut cache = S3FifoCache::<u64, u64>::builder(100)
+    ///     .set_small_ratio(0.2)
+    ///     .set_hasher(BuildHasherDefault::<FxHasher>::default())
+    ///     .finalize::<u64, u64>()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// ```
+    ///
+    /// [`S3FifoCacheBuilder`]: struct.S3FifoCacheBuilder.html
+    /// [`S3FifoCache`]: struct.S3FifoCache.html
+    pub fn builder(size: usize) -> S3FifoCacheBuilder {
+        S3FifoCacheBuilder::new(size)
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for S3FifoCache<K, V, S, E>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// # Note
+    /// - A new key is put to the small FIFO, a key in the ghost FIFO is put to the main FIFO.
+    /// - The entry evicted from the small FIFO to the ghost FIFO, or from the main FIFO, is
+    ///   returned in [`PutResult::Evicted`], the ghost FIFO only remembers a clone of its key.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, PutResult, S3FifoCache};
+    ///
+    /// let mut cache = S3FifoCache::new(4).unwrap();
+    /// (1..=4).for_each(|i| {
+    ///     assert_eq!(cache.put(i, i), PutResult::Put);
+    /// });
+    /// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+    ///
+    /// // Pull in the recently evicted key, it is put to the main FIFO
+    /// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+    /// assert_eq!(cache.put(1, 11), PutResult::Update(1));
+    /// assert_eq!(cache.main_len(), 1);
+    /// ```
+    ///
+    /// [`PutResult::Evicted`]: enum.PutResult.html#variant.Evicted
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Increments the frequency counter of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val(idx)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Increments the frequency counter of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not increment the frequency counter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.val(self.arena.index(k)?)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not increment the
+    /// frequency counter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.index(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not increment the
+    /// frequency counter.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        // the ghost entry of the key is removed as well
+        let (key, val) = self.arena.remove(self.arena.index(k)?);
+        let val = val?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache: S3FifoCache<isize, &str> = S3FifoCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        for &list in &[SMALL, MAIN] {
+            while let Some(idx) = self.arena.back(list) {
+                let (k, v) = self.arena.remove(idx);
+                self.cb(&k, &v.unwrap(), EvictionReason::Purged);
+            }
+        }
+        while let Some(idx) = self.arena.back(GHOST) {
+            self.arena.remove(idx);
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache
+    /// (excluding the length of inner ghost FIFO).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache = S3FifoCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.small_len() + self.main_len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold
+    /// (excluding the capacity of inner ghost FIFO).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, S3FifoCache};
+    /// let mut cache: S3FifoCache<isize, &str> = S3FifoCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, E: OnEvictCallback> S3FifoCache<K, V, S, E> {
+    /// Returns the number of key-value pairs that are currently in the small FIFO.
+    pub fn small_len(&self) -> usize {
+        self.arena.list_len(SMALL)
+    }
+
+    /// Returns the number of key-value pairs that are currently in the main FIFO.
+    pub fn main_len(&self) -> usize {
+        self.arena.list_len(MAIN)
+    }
+
+    /// Returns the number of keys that are currently in the ghost FIFO.
+    pub fn ghost_len(&self) -> usize {
+        self.arena.list_len(GHOST)
+    }
+
+    /// An iterator visiting all keys of the ghost FIFO, from the most recently evicted one.
+    /// The iterator element type is `&'a K`.
+    pub fn ghost_keys(&self) -> ArenaKeys<'_, K, V> {
+        self.arena.keys(GHOST)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, S3FifoCacheBuilder};
+        /// let mut cache = S3FifoCacheBuilder::new(2)
+        ///     .set_small_ratio(0.5)
+        ///     .finalize()
+        ///     .unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.get(&2);
+        /// // 1 is evicted to the ghost FIFO
+        /// cache.put(3, "c");
+        /// cache.put(1, "a");
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 4);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.ghost_hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, S3FifoCache};
+        /// let mut cache = S3FifoCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    // Returns the index of the key if it is in the cache, the ghost keys are skipped.
+    fn index<Q>(&self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena
+            .index(k)
+            .filter(|idx| self.arena.list(*idx) != GHOST)
+    }
+
+    // Returns the index of the key and increments its counter if it is in the cache.
+    fn hit<Q>(&mut self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.touch(idx);
+        Some(idx)
+    }
+
+    fn touch(&mut self, idx: u32) {
+        let freq = self.arena.freq(idx);
+        self.arena.set_freq(idx, (freq + 1).min(MAX_FREQ));
+    }
+
+    // Reinserts the accessed entries at the tail of the main FIFO with their counters
+    // decremented, and evicts the first one whose counter is zero.
+    fn evict_main(&mut self) -> Option<(K, V)> {
+        while let Some(idx) = self.arena.back(MAIN) {
+            let freq = self.arena.freq(idx);
+            if freq > 0 {
+                self.arena.set_freq(idx, freq - 1);
+                self.arena.move_to_front(idx, MAIN);
+                continue;
+            }
+
+            let (k, v) = self.arena.remove(idx);
+            let v = v.unwrap();
+            self.cb(&k, &v, EvictionReason::Capacity);
+            return Some((k, v));
+        }
+        None
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher, E: OnEvictCallback> S3FifoCache<K, V, S, E> {
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let list = match self.arena.index(&KeyRef { k: &k }) {
+            Some(idx) if self.arena.list(idx) != GHOST => {
+                self.touch(idx);
+                let old = self.arena.replace_val(idx, Some(v)).unwrap();
+                self.cb(&k, &old, EvictionReason::Replaced);
+                return PutResult::Update(old);
+            }
+            // the key was recently evicted from the small FIFO, put it to the main FIFO
+            Some(idx) => {
+                self.stats.record_ghost_hit();
+                self.arena.remove(idx);
+                MAIN
+            }
+            None => SMALL,
+        };
+
+        let evicted = if self.len() >= self.size {
+            self.evict()
+        } else {
+            None
+        };
+        self.arena.push_front(list, k, Some(v));
+        match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
+        }
+    }
+
+    fn evict(&mut self) -> Option<(K, V)> {
+        if self.small_len() >= self.small_size {
+            if let Some(ent) = self.evict_small() {
+                return Some(ent);
+            }
+        }
+        self.evict_main()
+    }
+
+    // Moves the accessed entries at the tail of the small FIFO to the main FIFO, and evicts
+    // the first one which was not accessed to the ghost FIFO. Returns None if all the entries
+    // are moved.
+    fn evict_small(&mut self) -> Option<(K, V)> {
+        while let Some(idx) = self.arena.back(SMALL) {
+            if self.arena.freq(idx) > 0 {
+                self.arena.move_to_front(idx, MAIN);
+                continue;
+            }
+
+            // only the key is remembered, the entry is returned to the caller
+            let v = self.arena.replace_val(idx, None).unwrap();
+            self.arena.move_to_front(idx, GHOST);
+            let k = self.arena.key(idx).clone();
+            self.cb(&k, &v, EvictionReason::Capacity);
+            if self.arena.list_len(GHOST) > self.ghost_size {
+                let oldest = self.arena.back(GHOST).unwrap();
+                self.arena.remove(oldest);
+            }
+            return Some((k, v));
+        }
+        None
+    }
+}
+
+impl<K: Hash + Eq + Clone, V, S: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for S3FifoCache<K, V, S, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a ghost key is put to the main FIFO, other keys to the small FIFO
+        let list = match self.arena.index(&KeyRef { k: &k }) {
+            Some(_) => MAIN,
+            None => SMALL,
+        };
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(list)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, E: OnEvictCallback> fmt::Debug for S3FifoCache<K, V, S, E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("S3FifoCache")
+            .field("len", &(self.small_len() + self.main_len()))
+            .field("cap", &self.size)
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, S: Send, E: Send> Send for S3FifoCache<K, V, S, E> {}
+unsafe impl<K: Sync, V: Sync, S: Sync, E: Sync> Sync for S3FifoCache<K, V, S, E> {}
+
+#[cfg(test)]
+mod test {
+    use super::{MAIN, SMALL};
+    use crate::lru::{CacheError, S3FifoCache, S3FifoCacheBuilder};
+    use crate::{Cache, PutResult};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::hash::{BuildHasherDefault, Hasher};
+
+    // every key has the same hash, so the ghost keys can only be told apart by comparing them.
+    #[derive(Default)]
+    struct CollidingHasher;
+
+    impl Hasher for CollidingHasher {
+        fn finish(&self) -> u64 {
+            0
+        }
+
+        fn write(&mut self, _: &[u8]) {}
+    }
+
+    #[test]
+    fn test_s3fifo_cache_invalid_config() {
+        let cache = S3FifoCacheBuilder::new(0).finalize::<u64, u64>();
+        assert_eq!(cache.unwrap_err(), CacheError::InvalidSize(0));
+
+        let cache = S3FifoCacheBuilder::new(8)
+            .set_small_ratio(1.5)
+            .finalize::<u64, u64>();
+        assert_eq!(cache.unwrap_err(), CacheError::InvalidSmallRatio(1.5));
+    }
+
+    #[test]
+    fn test_s3fifo_cache_scan_resistance() {
+        let mut cache = S3FifoCache::new(100).unwrap();
+        (0..50u64).for_each(|i| {
+            cache.put(i, i);
+            cache.get(&i);
+        });
+
+        // the accessed entries are moved to the main FIFO, the scan only goes
+        // through the small FIFO.
+        (1000..1050u64).for_each(|i| {
+            assert_eq!(cache.put(i, i), PutResult::Put);
+        });
+        (1050..11_000u64).for_each(|i| {
+            let k = i - 50;
+            assert_eq!(cache.put(i, i), PutResult::Evicted { key: k, value: k });
+        });
+        assert_eq!(cache.main_len(), 50);
+        assert_eq!(cache.small_len(), 50);
+        (0..50u64).for_each(|i| assert_eq!(cache.peek(&i), Some(&i)));
+
+        // once the accessed entries fill the cache, the main FIFO evicts. 0 has a higher
+        // counter than 1, so it is reinserted again when 1 is evicted.
+        cache.get(&0);
+        let mut k = 20_000u64;
+        while cache.contains(&1) {
+            cache.put(k, k);
+            cache.get(&k);
+            k += 1;
+        }
+        assert!(cache.contains(&0));
+    }
+
+    fn list_of(cache: &S3FifoCache<u64, u64>, k: u64) -> usize {
+        cache.arena.list(cache.arena.index(&k).unwrap())
+    }
+
+    #[test]
+    fn test_s3fifo_cache_promotion() {
+        let mut cache = S3FifoCacheBuilder::new(4)
+            .set_small_ratio(0.5)
+            .finalize()
+            .unwrap();
+        (1..=4u64).for_each(|i| {
+            assert_eq!(cache.put(i, i), PutResult::Put);
+        });
+
+        // 1 was accessed, so it is moved to the main FIFO and 2 is evicted to the ghost FIFO
+        cache.get(&1);
+        cache.get(&1);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(list_of(&cache, 1), MAIN);
+        assert_eq!(cache.small_len(), 3);
+        assert_eq!(cache.ghost_len(), 1);
+
+        cache.get(&3);
+        cache.get(&4);
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 5, value: 5 });
+        assert_eq!(cache.main_len(), 3);
+        assert_eq!(cache.small_len(), 1);
+        assert_eq!(cache.ghost_len(), 2);
+
+        // the small FIFO is under its share, so the main FIFO evicts. 1 has a higher counter,
+        // it is reinserted twice and 3 is evicted.
+        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(list_of(&cache, 1), MAIN);
+        assert_eq!(cache.main_len(), 2);
+        assert_eq!(cache.small_len(), 2);
+        assert_eq!(cache.ghost_len(), 2);
+    }
+
+    #[test]
+    fn test_s3fifo_cache_ghost_hit() {
+        let mut cache = S3FifoCacheBuilder::new(4)
+            .set_small_ratio(0.5)
+            .finalize()
+            .unwrap();
+        (1..=4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        cache.get(&2);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.ghost_len(), 2);
+
+        // 1 is in the ghost FIFO, so it is put to the main FIFO, the ghost FIFO only keeps
+        // the key of the new eviction from the small FIFO.
+        assert_eq!(cache.put(1, 11), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(list_of(&cache, 1), MAIN);
+        assert_eq!(cache.main_len(), 2);
+        assert_eq!(cache.ghost_len(), 2);
+        assert_eq!(cache.get(&1), Some(&11));
+
+        // removing a key forgets its ghost entry as well, so it is put to the small FIFO again
+        assert_eq!(cache.remove(&3), None);
+        assert_eq!(cache.ghost_len(), 1);
+        assert_eq!(cache.put(3, 3), PutResult::Evicted { key: 5, value: 5 });
+        assert_eq!(list_of(&cache, 3), SMALL);
+        assert_eq!(cache.ghost_len(), 2);
+    }
+
+    #[test]
+    fn test_s3fifo_cache_ghosts_compare_keys() {
+        let mut cache = S3FifoCacheBuilder::new(4)
+            .set_small_ratio(0.25)
+            .set_hasher(BuildHasherDefault::<CollidingHasher>::default())
+            .finalize()
+            .unwrap();
+        (1..=4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.ghost_keys().collect::<Vec<_>>(), vec![&1]);
+
+        // 6 has the same hash as the ghost key 1, but it is a new key
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(cache.main_len(), 0);
+        assert_eq!(cache.ghost_keys().collect::<Vec<_>>(), vec![&2, &1]);
+
+        // 1 is in the ghost FIFO, so it is put to the main FIFO
+        assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(cache.main_len(), 1);
+        assert_eq!(cache.ghost_keys().collect::<Vec<_>>(), vec![&3, &2]);
+    }
+}
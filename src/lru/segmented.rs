@@ -1,16 +1,34 @@
+use crate::lru::raw::EntryNode;
 use crate::lru::{debox, swap_value, CacheError, RawLRU};
-use crate::{Cache, DefaultEvictCallback, DefaultHashBuilder, KeyRef, PutResult};
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::entry::sealed::EntryCache;
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, Entry, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use alloc::boxed::Box;
+use alloc::vec::Vec;
 use core::borrow::Borrow;
-use core::hash::{BuildHasher, Hash};
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
 
 /// `SegmentedCacheBuilder` is used to help build a [`SegmentedCache`] with custom configurations.
 ///
 /// [`SegmentedCache`]: struct.SegmentedCache.html
-pub struct SegmentedCacheBuilder<FH = DefaultHashBuilder, RH = DefaultHashBuilder> {
+pub struct SegmentedCacheBuilder<
+    FH = DefaultHashBuilder,
+    RH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
     probationary_size: usize,
     protected_size: usize,
     probationary_hasher: Option<RH>,
     protected_hasher: Option<FH>,
+    dynamic: bool,
+    on_evict: Option<E>,
 }
 
 impl Default for SegmentedCacheBuilder {
@@ -20,6 +38,8 @@ impl Default for SegmentedCacheBuilder {
             protected_size: 0,
             probationary_hasher: Some(DefaultHashBuilder::default()),
             protected_hasher: Some(DefaultHashBuilder::default()),
+            dynamic: false,
+            on_evict: None,
         }
     }
 }
@@ -32,11 +52,13 @@ impl SegmentedCacheBuilder {
             protected_size,
             probationary_hasher: Some(DefaultHashBuilder::default()),
             protected_hasher: Some(DefaultHashBuilder::default()),
+            dynamic: false,
+            on_evict: None,
         }
     }
 }
 
-impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
+impl<FH: BuildHasher, RH: BuildHasher, E: OnEvictCallback> SegmentedCacheBuilder<FH, RH, E> {
     /// Set the cache size
     pub fn set_probationary_size(self, size: usize) -> Self {
         SegmentedCacheBuilder {
@@ -44,6 +66,8 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
             protected_size: self.protected_size,
             probationary_hasher: self.probationary_hasher,
             protected_hasher: self.protected_hasher,
+            dynamic: self.dynamic,
+            on_evict: self.on_evict,
         }
     }
 
@@ -54,6 +78,8 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
             protected_size: size,
             probationary_hasher: self.probationary_hasher,
             protected_hasher: self.protected_hasher,
+            dynamic: self.dynamic,
+            on_evict: self.on_evict,
         }
     }
 
@@ -61,12 +87,14 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
     pub fn set_probationary_hasher<NRH: BuildHasher>(
         self,
         hasher: NRH,
-    ) -> SegmentedCacheBuilder<FH, NRH> {
+    ) -> SegmentedCacheBuilder<FH, NRH, E> {
         SegmentedCacheBuilder {
             probationary_size: self.probationary_size,
             protected_size: self.protected_size,
             probationary_hasher: Some(hasher),
             protected_hasher: self.protected_hasher,
+            dynamic: self.dynamic,
+            on_evict: self.on_evict,
         }
     }
 
@@ -74,19 +102,107 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
     pub fn set_protected_hasher<NFH: BuildHasher>(
         self,
         hasher: NFH,
-    ) -> SegmentedCacheBuilder<NFH, RH> {
+    ) -> SegmentedCacheBuilder<NFH, RH, E> {
         SegmentedCacheBuilder {
             probationary_size: self.probationary_size,
             protected_size: self.protected_size,
             probationary_hasher: self.probationary_hasher,
             protected_hasher: Some(hasher),
+            dynamic: self.dynamic,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set whether the cache is a dynamic segmented LRU (DSLRU) cache.
+    ///
+    /// A DSLRU cache remembers the keys recently evicted from the probationary LRU and
+    /// the keys recently demoted from the protected LRU. A hit on one of them shifts capacity
+    /// from the other segment to the segment that was too small, the sum of the capacities
+    /// is not changed. Default is `false`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, SegmentedCache};
+    ///
+    /// let mut cache = SegmentedCache::<u64, u64>::builder(2, 2)
+    ///     .set_dynamic(true)
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    ///
+    /// // 1 is evicted from the probationary LRU
+    /// cache.put(3, 3);
+    /// assert!(!cache.contains(&1));
+    ///
+    /// // 1 comes back, the probationary LRU takes one slot from the protected LRU
+    /// cache.put(1, 1);
+    /// assert_eq!(cache.probationary_cap(), 3);
+    /// assert_eq!(cache.protected_cap(), 1);
+    /// assert_eq!(cache.cap(), 4);
+    /// ```
+    pub fn set_dynamic(self, dynamic: bool) -> Self {
+        SegmentedCacheBuilder {
+            probationary_size: self.probationary_size,
+            protected_size: self.protected_size,
+            probationary_hasher: self.probationary_hasher,
+            protected_hasher: self.protected_hasher,
+            dynamic,
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the callback which is invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry moves between the probationary LRU and
+    /// the protected LRU, it is invoked once the entry is evicted, removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, OnEvictCallback, SegmentedCache, SegmentedCacheBuilder};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: SegmentedCache<u64, u64, _, _, _> = SegmentedCacheBuilder::new(1, 1)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// // 1 is moved to the protected LRU, the callback is not invoked
+    /// cache.get(&1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 0);
+    ///
+    /// // 2 is evicted from the probationary LRU
+    /// cache.put(3, 3);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> SegmentedCacheBuilder<FH, RH, NE> {
+        SegmentedCacheBuilder {
+            probationary_size: self.probationary_size,
+            protected_size: self.protected_size,
+            probationary_hasher: self.probationary_hasher,
+            protected_hasher: self.protected_hasher,
+            dynamic: self.dynamic,
+            on_evict: Some(cb),
         }
     }
 
     /// Finalize the builder to [`SegmentedCache`]
     ///
     /// [`SegmentedCache`]: struct.SegmentedCache.html
-    pub fn finalize<K: Hash + Eq, V>(self) -> Result<SegmentedCache<K, V, FH, RH>, CacheError> {
+    pub fn finalize<K: Hash + Eq, V>(self) -> Result<SegmentedCache<K, V, FH, RH, E>, CacheError> {
         if self.protected_size == 0 {
             return Err(CacheError::InvalidSize(0));
         }
@@ -95,6 +211,16 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
             return Err(CacheError::InvalidSize(0));
         }
 
+        let ghosts = if self.dynamic {
+            let size = self.probationary_size + self.protected_size;
+            Some(Ghosts {
+                probationary: RawLRU::new(size).unwrap(),
+                protected: RawLRU::new(size).unwrap(),
+            })
+        } else {
+            None
+        };
+
         Ok(SegmentedCache {
             probationary_size: self.probationary_size,
             probationary: RawLRU::with_hasher(
@@ -105,6 +231,10 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
             protected_size: self.protected_size,
             protected: RawLRU::with_hasher(self.protected_size, self.protected_hasher.unwrap())
                 .unwrap(),
+            ghosts,
+            init_sizes: (self.probationary_size, self.protected_size),
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
         })
     }
 }
@@ -148,13 +278,43 @@ impl<FH: BuildHasher, RH: BuildHasher> SegmentedCacheBuilder<FH, RH> {
 /// assert_eq!(cache.len(), 0);
 /// ```
 ///
+/// # Dynamic Segmented LRU
+/// The capacities of the two segments are fixed by default. When the cache is built with
+/// [`set_dynamic`], the cache becomes a dynamic segmented LRU (DSLRU) cache, the capacities
+/// are shifted between the two segments at runtime, and the current split can be observed by
+/// [`probationary_cap`] and [`protected_cap`].
+///
 /// [Segmented LRU Cache]: https://dl.acm.org/doi/10.1109/2.268884
-pub struct SegmentedCache<K, V, FH = DefaultHashBuilder, RH = DefaultHashBuilder> {
+/// [`set_dynamic`]: struct.SegmentedCacheBuilder.html#method.set_dynamic
+/// [`probationary_cap`]: struct.SegmentedCache.html#method.probationary_cap
+/// [`protected_cap`]: struct.SegmentedCache.html#method.protected_cap
+pub struct SegmentedCache<
+    K,
+    V,
+    FH = DefaultHashBuilder,
+    RH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
     probationary_size: usize,
     probationary: RawLRU<K, V, DefaultEvictCallback, RH>,
 
     protected_size: usize,
     protected: RawLRU<K, V, DefaultEvictCallback, FH>,
+
+    // only used by the dynamic mode
+    ghosts: Option<Ghosts>,
+    // the configured sizes, purge goes back to them in the dynamic mode.
+    init_sizes: (usize, usize),
+
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+// The hashes of the keys recently evicted from the probationary LRU,
+// and the hashes of the keys recently demoted from the protected LRU.
+struct Ghosts {
+    probationary: RawLRU<u64, ()>,
+    protected: RawLRU<u64, ()>,
 }
 
 impl<K: Hash + Eq, V> SegmentedCache<K, V> {
@@ -187,7 +347,9 @@ impl<K: Hash + Eq, V> SegmentedCache<K, V> {
     }
 }
 
-impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> SegmentedCache<K, V, FH, RH> {
+impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher, E: OnEvictCallback>
+    SegmentedCache<K, V, FH, RH, E>
+{
     /// Create a [`AdaptiveCache`] from [`SegmentedCacheBuilder`].
     ///
     /// # Example
@@ -204,13 +366,24 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> SegmentedCache<K, V, FH,
     ///
     /// [`SegmentedCacheBuilder`]: struct.SegmentedCacheBuilder.html
     /// [`SegmentedCache`]: struct.SegmentedCache.html
-    pub fn from_builder(builder: SegmentedCacheBuilder<FH, RH>) -> Result<Self, CacheError> {
+    pub fn from_builder(builder: SegmentedCacheBuilder<FH, RH, E>) -> Result<Self, CacheError> {
         builder.finalize()
     }
 
     /// `put_protected` will force to put an entry in protected LRU
     pub fn put_protected(&mut self, k: K, v: V) -> PutResult<K, V> {
-        self.protected.put(k, v)
+        if let Some(ref cb) = self.on_evict {
+            if let Some(old) = self.protected.peek(&KeyRef { k: &k }) {
+                cb.on_evict_with_reason(&k, old, EvictionReason::Replaced);
+            }
+        }
+
+        let rst = self.protected.put(k, v);
+        if let PutResult::Evicted { key, value } = &rst {
+            self.cb(key, value, EvictionReason::Capacity);
+        }
+        self.stats.record_put(&rst);
+        rst
     }
 
     /// Returns the value corresponding to the least recently used item or `None` if the
@@ -272,13 +445,17 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> SegmentedCache<K, V, FH,
     /// Removes and returns the key and value corresponding to the least recently
     /// used item or `None` if the probationary cache is empty.
     pub fn remove_lru_from_probationary(&mut self) -> Option<(K, V)> {
-        self.probationary.remove_lru()
+        let (k, v) = self.probationary.remove_lru()?;
+        self.cb(&k, &v, EvictionReason::Removed);
+        Some((k, v))
     }
 
     /// Removes and returns the key and value corresponding to the least recently
     /// used item or `None` if the protected cache is empty.
     pub fn remove_lru_from_protected(&mut self) -> Option<(K, V)> {
-        self.protected.remove_lru()
+        let (k, v) = self.protected.remove_lru()?;
+        self.cb(&k, &v, EvictionReason::Removed);
+        Some((k, v))
     }
 
     /// Returns the number of key-value pairs that are currently in the protected LRU.
@@ -301,7 +478,72 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> SegmentedCache<K, V, FH,
         self.protected_size
     }
 
-    fn move_to_protected<T, Q>(&mut self, k: &Q, v: T) -> Option<T>
+    /// Gets the given key's corresponding [`Entry`] in the cache for in-place manipulation.
+    /// Looking up the entry promotes the key just like `get`, and a vacant entry is
+    /// inserted into the probationary LRU just like `put`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SegmentedCache};
+    /// let mut cache = SegmentedCache::new(2, 2).unwrap();
+    ///
+    /// cache.entry(1).or_insert(1);
+    /// assert_eq!(cache.probationary_len(), 1);
+    ///
+    /// *cache.entry(1).or_insert(1) += 1;
+    /// assert_eq!(cache.protected_len(), 1);
+    /// assert_eq!(cache.peek(&1), Some(&2));
+    /// ```
+    ///
+    /// [`Entry`]: ../enum.Entry.html
+    pub fn entry(&mut self, k: K) -> Entry<'_, K, V, Self> {
+        Entry::new(self, k)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, SegmentedCache};
+        /// let mut cache = SegmentedCache::new(1, 1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 2);
+        /// assert_eq!(stats.evictions(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, SegmentedCache};
+        /// let mut cache = SegmentedCache::new(1, 1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn move_to_protected<Q>(&mut self, k: &Q)
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -309,21 +551,156 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> SegmentedCache<K, V, FH,
         // remove the element from the probationary LRU
         // and put it in protected LRU.
         if let Some(ent) = self.probationary.remove_and_return_ent(k) {
-            match self.protected.put_or_evict_box(ent) {
-                None => Some(v),
-                Some(old_ent) => {
-                    self.probationary.put_box(old_ent);
-                    Some(v)
+            self.promote(ent);
+        }
+    }
+
+    // Puts an entry removed from the probationary LRU in the protected LRU,
+    // the least recently used entry of the protected LRU is demoted to the probationary LRU
+    // if the protected LRU is full.
+    fn promote(&mut self, ent: Box<EntryNode<K, V>>) {
+        if self.ghosts.is_some() {
+            let hash = self.hash_key(unsafe { &*ent.key.as_ptr() });
+            if let Some(ghosts) = self.ghosts.as_mut() {
+                if ghosts.protected.remove(&hash).is_some() {
+                    // the key was demoted recently, the protected LRU is too small.
+                    self.stats.record_ghost_hit();
+                    self.grow_protected();
                 }
             }
-        } else {
-            None
         }
+
+        if let Some(demoted) = self.protected.put_or_evict_box(ent) {
+            if self.ghosts.is_some() {
+                let hash = self.hash_key(unsafe { &*demoted.key.as_ptr() });
+                if let Some(ghosts) = self.ghosts.as_mut() {
+                    ghosts.protected.put(hash, ());
+                }
+            }
+            // the probationary LRU just lost an entry, so nothing is evicted here.
+            self.probationary.put_box(demoted);
+        }
+    }
+
+    // Moves capacity from the protected LRU to the probationary LRU,
+    // the entries out of the new protected capacity are demoted.
+    fn grow_probationary(&mut self) {
+        let delta = match self.ghosts.as_ref() {
+            Some(ghosts) => adaptive_delta(ghosts.protected.len(), ghosts.probationary.len()),
+            None => return,
+        };
+        self.shift_cap_to_probationary(delta.min(self.protected_size - 1));
+    }
+
+    // Moves capacity from the probationary LRU to the protected LRU, only the free
+    // slots of the probationary LRU are moved, so no entry is evicted.
+    fn grow_protected(&mut self) {
+        let delta = match self.ghosts.as_ref() {
+            Some(ghosts) => adaptive_delta(ghosts.probationary.len(), ghosts.protected.len()),
+            None => return,
+        };
+        self.shift_cap_to_protected(delta);
+    }
+
+    /// Moves `delta` of the capacity of the protected LRU to the probationary LRU,
+    /// the entries out of the new protected capacity are demoted.
+    pub(crate) fn shift_cap_to_probationary(&mut self, delta: usize) {
+        if delta == 0 {
+            return;
+        }
+
+        self.probationary_size += delta;
+        self.probationary.set_cap(self.probationary_size);
+        self.protected_size -= delta;
+        while self.protected.len() > self.protected_size {
+            let ent = self.protected.remove_lru_in().unwrap();
+            if self.ghosts.is_some() {
+                let hash = self.hash_key(unsafe { &*ent.key.as_ptr() });
+                if let Some(ghosts) = self.ghosts.as_mut() {
+                    ghosts.protected.put(hash, ());
+                }
+            }
+            self.probationary.put_box(ent);
+        }
+        self.protected.set_cap(self.protected_size);
+    }
+
+    /// Moves at most `delta` of the capacity of the probationary LRU to the protected LRU,
+    /// only the free slots of the probationary LRU are moved, so no entry is evicted.
+    pub(crate) fn shift_cap_to_protected(&mut self, delta: usize) {
+        let delta = delta.min(self.probationary_size - self.probationary.len().max(1));
+        if delta == 0 {
+            return;
+        }
+
+        self.probationary_size -= delta;
+        self.probationary.set_cap(self.probationary_size);
+        self.protected_size += delta;
+        self.protected.set_cap(self.protected_size);
+    }
+
+    /// Shrinks the probationary LRU by `delta`, the entries out of the new capacity are
+    /// removed without invoking the callback and returned, the least recently used one first.
+    pub(crate) fn shrink_probationary(&mut self, delta: usize) -> Vec<Box<EntryNode<K, V>>> {
+        self.probationary_size -= delta;
+        let mut ents = Vec::new();
+        while self.probationary.len() > self.probationary_size {
+            ents.push(self.probationary.remove_lru_in().unwrap());
+        }
+        self.probationary.set_cap(self.probationary_size);
+        ents
+    }
+
+    /// Grows the probationary LRU by `delta`, and puts the entries in it, the last one is the
+    /// most recently used. The caller makes sure that the entries fit in the new capacity.
+    pub(crate) fn grow_probationary_with(&mut self, delta: usize, ents: Vec<Box<EntryNode<K, V>>>) {
+        self.probationary_size += delta;
+        self.probationary.set_cap(self.probationary_size);
+        for ent in ents {
+            self.probationary.put_box(ent);
+        }
+    }
+
+    /// Removes the entry without invoking the callback.
+    pub(crate) fn remove_entry<Q>(&mut self, k: &Q) -> Option<(K, V)>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.probationary
+            .remove_entry(k)
+            .or_else(|| self.protected.remove_entry(k))
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key(&self, k: &K) -> u64 {
+        let mut hasher = self.probationary.map.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
     }
 }
 
-impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
-    for SegmentedCache<K, V, FH, RH>
+// The same as the adaptation of the partition in ARC: the step is the ratio between
+// the lengths of the two ghost lists, and at least one.
+#[inline]
+fn adaptive_delta(other_ghosts: usize, hit_ghosts: usize) -> usize {
+    if other_ghosts > hit_ghosts {
+        other_ghosts / hit_ghosts.max(1)
+    } else {
+        1
+    }
+}
+
+impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for SegmentedCache<K, V, FH, RH, E>
 {
     /// Puts a key-value pair into cache, returns a [`PutResult`].
     ///
@@ -355,28 +732,46 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
             .map(|bks| debox::<K, V>(bks))
         {
             self.protected.update(&mut v, ent_ptr);
+            self.cb(&k, &v, EvictionReason::Replaced);
+            self.stats.record_update();
             return PutResult::Update(v);
         }
 
         // check if the value is already in probationary segment and move it to protected segment
-        if self.probationary.contains(&key_ref) {
-            return self
-                .probationary
-                .remove_and_return_ent(&key_ref)
-                .and_then(|mut ent| {
-                    let ent_ptr = ent.as_mut();
-                    unsafe {
-                        swap_value(&mut v, ent_ptr);
-                    }
-                    self.protected
-                        .put_or_evict_box(ent)
-                        .map(|evicted_ent| self.probationary.put_box(evicted_ent))
-                })
-                .unwrap_or(PutResult::<K, V>::Update(v));
+        if let Some(mut ent) = self.probationary.remove_and_return_ent(&key_ref) {
+            unsafe {
+                swap_value(&mut v, ent.as_mut());
+            }
+            self.promote(ent);
+            self.cb(&k, &v, EvictionReason::Replaced);
+            self.stats.record_update();
+            return PutResult::Update(v);
         }
 
         // this is a new entry
-        self.probationary.put(k, v)
+        if self.ghosts.is_some() {
+            let hash = self.hash_key(&k);
+            if let Some(ghosts) = self.ghosts.as_mut() {
+                if ghosts.probationary.remove(&hash).is_some() {
+                    // the key was evicted recently, the probationary LRU is too small.
+                    self.stats.record_ghost_hit();
+                    self.grow_probationary();
+                }
+            }
+        }
+
+        let rst = self.probationary.put(k, v);
+        if let PutResult::Evicted { key, value } = &rst {
+            if self.ghosts.is_some() {
+                let hash = self.hash_key(key);
+                if let Some(ghosts) = self.ghosts.as_mut() {
+                    ghosts.probationary.put(hash, ());
+                }
+            }
+            self.cb(key, value, EvictionReason::Capacity);
+        }
+        self.stats.record_put(&rst);
+        rst
     }
 
     /// Returns a reference to the value of the key in the cache or `None` if it
@@ -395,24 +790,22 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
     ///
     /// assert_eq!(cache.get(&"banana"), Some(&6));
     /// ```
-    fn get<'a, Q>(&mut self, k: &'a Q) -> Option<&'a V>
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.protected
-            // already in protected LRU, we move it to the front
-            .get(k)
-            // does not in protected LRU, we try to find it in
-            // probationary LRU
-            .or_else(|| {
-                self.probationary
-                    .peek(k)
-                    // we find the element in probationary LRU
-                    // remove the element from the probationary LRU
-                    // and put it in protected LRU.
-                    .and_then(|v| self.move_to_protected(k, v))
-            })
+        // does not in protected LRU, we try to find it in
+        // probationary LRU, if we find the element in probationary LRU,
+        // remove the element from the probationary LRU
+        // and put it in protected LRU.
+        if !self.protected.contains(k) {
+            self.move_to_protected(k);
+        }
+        // already in protected LRU, we move it to the front
+        let v = self.protected.get(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a mutable reference to the value of the key in the cache or `None` if it
@@ -431,24 +824,22 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
     ///
     /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
     /// ```
-    fn get_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.protected
-            // already in protected LRU, we move it to the front
-            .get_mut(k)
-            // does not in protected LRU, we try to find it in
-            // probationary LRU
-            .or_else(|| {
-                self.probationary
-                    .peek_mut(k)
-                    // we find the element in probationary LRU
-                    // remove the element from the probationary LRU
-                    // and put it in protected LRU.
-                    .and_then(|v| self.move_to_protected(k, v))
-            })
+        // does not in protected LRU, we try to find it in
+        // probationary LRU, if we find the element in probationary LRU,
+        // remove the element from the probationary LRU
+        // and put it in protected LRU.
+        if !self.protected.contains(k) {
+            self.move_to_protected(k);
+        }
+        // already in protected LRU, we move it to the front
+        let v = self.protected.get_mut(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
@@ -467,7 +858,7 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
     /// assert_eq!(cache.peek(&1), Some(&"a"));
     /// assert_eq!(cache.peek(&2), Some(&"b"));
     /// ```
-    fn peek<'a, Q>(&self, k: &'a Q) -> Option<&'a V>
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -491,14 +882,16 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
     /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
     /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
     /// ```
-    fn peek_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.protected
-            .peek_mut(k)
-            .or_else(|| self.probationary.peek_mut(k))
+        if self.protected.contains(k) {
+            self.protected.peek_mut(k)
+        } else {
+            self.probationary.peek_mut(k)
+        }
     }
 
     /// Returns a bool indicating whether the given key is in the cache.
@@ -548,12 +941,13 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.probationary
-            .remove(k)
-            .or_else(|| self.protected.remove(k))
+        let (key, val) = self.remove_entry(k)?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
     }
 
-    /// Clears the contents of the cache.
+    /// Clears the contents of the cache. A dynamic cache also forgets its ghost keys and goes
+    /// back to the configured segment sizes.
     ///
     /// # Example
     ///
@@ -573,8 +967,21 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
     /// assert_eq!(cache.len(), 0);
     /// ```
     fn purge(&mut self) {
-        self.probationary.purge();
-        self.protected.purge();
+        while let Some((k, v)) = self.probationary.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.protected.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        if let Some(ghosts) = self.ghosts.as_mut() {
+            ghosts.probationary.purge();
+            ghosts.protected.purge();
+            let (probationary_size, protected_size) = self.init_sizes;
+            self.probationary_size = probationary_size;
+            self.probationary.set_cap(probationary_size);
+            self.protected_size = protected_size;
+            self.protected.set_cap(protected_size);
+        }
     }
 
     /// Returns the number of key-value pairs that are currently in the the cache.
@@ -627,3 +1034,197 @@ impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher> Cache<K, V>
         self.protected.is_empty() && self.probationary.is_empty()
     }
 }
+
+impl<K: Hash + Eq, V, FH: BuildHasher, RH: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for SegmentedCache<K, V, FH, RH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        let rst = self.put(k, v);
+        let v = self
+            .probationary
+            .peek_mru_mut()
+            .map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::{SegmentedCache, SegmentedCacheBuilder};
+    use crate::{Cache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+
+    #[test]
+    fn test_segmented_cache_dynamic_random_ops() {
+        let size = 128;
+        let mut rng = thread_rng();
+        let mut cache = SegmentedCache::<u64, u64>::builder(size / 2, size / 2)
+            .set_dynamic(true)
+            .finalize()
+            .unwrap();
+
+        (0..200_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => {
+                    let _ = cache.put(k, k);
+                }
+                1 => {
+                    if let Some(v) = cache.get(&k) {
+                        assert_eq!(*v, k);
+                    }
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+
+            assert_eq!(cache.cap(), size);
+            assert!(cache.probationary_cap() >= 1 && cache.protected_cap() >= 1);
+            assert!(cache.probationary_len() <= cache.probationary_cap());
+            assert!(cache.protected_len() <= cache.protected_cap());
+        })
+    }
+
+    #[test]
+    fn test_segmented_cache_dynamic_grow_protected() {
+        let mut cache = SegmentedCache::<u64, u64>::builder(4, 2)
+            .set_dynamic(true)
+            .finalize()
+            .unwrap();
+
+        (1..=3).for_each(|i| {
+            assert_eq!(cache.put(i, i), PutResult::Put);
+            assert_eq!(cache.get(&i), Some(&i));
+        });
+        // 1 is demoted to the probationary LRU
+        assert_eq!(cache.protected_len(), 2);
+        assert_eq!(cache.probationary_len(), 1);
+
+        // 1 is accessed again, the protected LRU takes a free slot
+        assert_eq!(cache.get(&1), Some(&1));
+        assert_eq!(cache.probationary_cap(), 3);
+        assert_eq!(cache.protected_cap(), 3);
+        assert_eq!(cache.protected_len(), 3);
+        assert_eq!(cache.probationary_len(), 0);
+
+        cache.purge();
+        assert_eq!(cache.len(), 0);
+        assert_eq!(cache.cap(), 6);
+    }
+
+    #[test]
+    fn test_segmented_cache_dynamic_purge() {
+        let mut cache = SegmentedCache::<u64, u64>::builder(2, 2)
+            .set_dynamic(true)
+            .finalize()
+            .unwrap();
+
+        (1..=3).for_each(|i| {
+            cache.put(i, i);
+        });
+        // 1 is evicted, and takes a slot from the protected LRU when it comes back
+        cache.put(1, 1);
+        assert_eq!(cache.probationary_cap(), 3);
+        assert_eq!(cache.protected_cap(), 1);
+
+        // purge goes back to the configured sizes
+        cache.purge();
+        assert_eq!(cache.probationary_cap(), 2);
+        assert_eq!(cache.protected_cap(), 2);
+
+        // and the ghost keys are cleared, so 2 is not a ghost hit after the purge
+        (3..=5).for_each(|i| {
+            cache.put(i, i);
+        });
+        cache.put(2, 2);
+        assert_eq!(cache.probationary_cap(), 2);
+        assert_eq!(cache.protected_cap(), 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    #[cfg(feature = "stats")]
+    fn test_segmented_cache_stats() {
+        let mut cache = SegmentedCache::<u64, u64>::builder(4, 2)
+            .set_dynamic(true)
+            .finalize()
+            .unwrap();
+
+        (1..=3).for_each(|i| {
+            cache.put(i, i);
+            cache.get(&i);
+        });
+        // 1 was demoted from the protected LRU
+        cache.get(&1);
+        cache.get(&4);
+        cache.put(2, 2);
+
+        let stats = cache.stats();
+        assert_eq!(stats.inserts(), 3);
+        assert_eq!(stats.updates(), 1);
+        assert_eq!(stats.hits(), 4);
+        assert_eq!(stats.misses(), 1);
+        assert_eq!(stats.ghost_hits(), 1);
+        assert_eq!(stats.evictions(), 0);
+
+        cache.reset_stats();
+        assert_eq!(cache.stats().hits(), 0);
+    }
+
+    #[test]
+    fn test_segmented_cache_fixed_caps() {
+        let mut cache = SegmentedCache::new(2, 2).unwrap();
+        (0..16u64).for_each(|i| {
+            cache.put(i, i);
+            cache.put(i % 3, i);
+        });
+        assert_eq!(cache.probationary_cap(), 2);
+        assert_eq!(cache.protected_cap(), 2);
+    }
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_segmented_cache_on_evict() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = SegmentedCacheBuilder::new(16, 48)
+            .set_dynamic(true)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64, u64>()
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 256;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Put | PutResult::Evicted { .. } => inserted += 1,
+                    _ => {}
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+}
@@ -0,0 +1,691 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::{Arena, ArenaIter};
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult, ResizableCache,
+};
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+// the queue is an arena list, the new entries are put to the front (the head of the queue)
+// and the hand moves from the back (the tail of the queue) to the front.
+const QUEUE: usize = 0;
+
+/// `SieveCache` is a fixed size cache based on the SIEVE algorithm.
+///
+/// The entries are kept in a FIFO queue, each of them with a visited bit. A hit only sets
+/// the bit of the entry, it never moves the entry in the queue. The new entries are put to
+/// the head of the queue, and when the cache is full the hand moves from the tail to the head:
+/// the visited entries are kept in place with their bit cleared, the first entry which is not
+/// visited is evicted. Unlike CLOCK, the hand stays where it stopped, so the retained entries
+/// are not moved back to the head and the new entries, which are between the hand and the head,
+/// are sifted out quickly if they are not used again.
+///
+/// Like [`ClockCache`], a hit is cheaper than in [`LRUCache`], and SIEVE has been shown to
+/// have a better hit ratio than LRU on web cache workloads.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, PutResult, SieveCache};
+///
+/// let mut cache = SieveCache::new(3).unwrap();
+/// cache.put(1, 1);
+/// cache.put(2, 2);
+/// cache.put(3, 3);
+///
+/// // 1 is visited, so the hand keeps it and evicts 2
+/// assert_eq!(cache.get(&1), Some(&1));
+/// assert_eq!(cache.put(4, 4), PutResult::Evicted { key: 2, value: 2 });
+///
+/// // the hand stays where it stopped, the next eviction starts from 3, not from 1
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+/// assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec![5, 4, 1]);
+/// ```
+///
+/// [`ClockCache`]: struct.ClockCache.html
+/// [`LRUCache`]: type.LRUCache.html
+pub struct SieveCache<K, V, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    arena: Arena<K, V, S>,
+    hand: Option<u32>,
+    cap: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> SieveCache<K, V> {
+    /// Creates a new `SieveCache` that holds at most `cap` items.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::SieveCache;
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::new(10).unwrap();
+    /// ```
+    pub fn new(cap: usize) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher> SieveCache<K, V, DefaultEvictCallback, S> {
+    /// Creates a new `SieveCache` that holds at most `cap` items and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{SieveCache, DefaultHashBuilder};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::with_hasher(10, s).unwrap();
+    /// ```
+    pub fn with_hasher(cap: usize, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback> SieveCache<K, V, E> {
+    /// Creates a new `SieveCache` that holds at most `cap` items and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, OnEvictCallback, SieveCache};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache = SieveCache::with_on_evict_cb(1, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(cap: usize, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), DefaultHashBuilder::default())
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> SieveCache<K, V, E, S> {
+    /// Creates a new `SieveCache` that holds at most `cap` items,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{SieveCache, DefaultEvictCallback, DefaultHashBuilder};
+    ///
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(cap: usize, cb: E, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(cap, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        cap: usize,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        if cap == 0 || cap >= u32::MAX as usize {
+            return Err(CacheError::InvalidSize(cap));
+        }
+
+        Ok(Self {
+            arena: Arena::with_hasher(cap, 1, hasher),
+            hand: None,
+            cap,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// An iterator visiting all entries in queue order, from the newest entry at the head
+    /// of the queue to the oldest one. The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    ///
+    /// let mut cache = SieveCache::new(3).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.put("c", 3);
+    ///
+    /// // a hit does not move the entry
+    /// cache.get(&"a");
+    ///
+    /// assert_eq!(cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(), vec!["c", "b", "a"]);
+    /// ```
+    pub fn iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(QUEUE)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, SieveCache};
+        /// let mut cache = SieveCache::new(1).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        /// cache.get(&2);
+        ///
+        /// assert_eq!(cache.stats().inserts(), 2);
+        /// assert_eq!(cache.stats().evictions(), 1);
+        /// assert_eq!(cache.stats().hits(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, SieveCache};
+        /// let mut cache = SieveCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    // Returns the index of the key and sets its visited bit if it is in the cache.
+    fn hit<Q>(&mut self, k: &Q) -> Option<u32>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.set_referenced(idx, true);
+        Some(idx)
+    }
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(idx) = self.arena.index(&k) {
+            self.arena.set_referenced(idx, true);
+            let old = self.arena.replace_val(idx, Some(v)).unwrap();
+            self.cb(&k, &old, EvictionReason::Replaced);
+            return PutResult::Update(old);
+        }
+
+        let evicted = if self.arena.len() >= self.cap {
+            self.evict(EvictionReason::Capacity)
+        } else {
+            None
+        };
+        self.arena.push_front(QUEUE, k, Some(v));
+        match evicted {
+            Some((key, value)) => PutResult::Evicted { key, value },
+            None => PutResult::Put,
+        }
+    }
+
+    // Moves the hand to the first entry which is not visited, clearing the visited bits
+    // on its way, and evicts that entry.
+    fn evict(&mut self, reason: EvictionReason) -> Option<(K, V)> {
+        let mut idx = self.hand.or_else(|| self.arena.back(QUEUE))?;
+        while self.arena.referenced(idx) {
+            self.arena.set_referenced(idx, false);
+            idx = self
+                .arena
+                .prev(idx)
+                .or_else(|| self.arena.back(QUEUE))
+                .unwrap();
+        }
+        self.hand = Some(idx);
+        Some(self.remove_at(idx, reason))
+    }
+
+    fn remove_at(&mut self, idx: u32, reason: EvictionReason) -> (K, V) {
+        if self.hand == Some(idx) {
+            self.hand = self.arena.prev(idx);
+        }
+        let (k, v) = self.arena.remove(idx);
+        let v = v.unwrap();
+        self.cb(&k, &v, reason);
+        (k, v)
+    }
+
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> Cache<K, V> for SieveCache<K, V, E, S> {
+    /// Puts a key-value pair to the head of the queue. If the cache is full, the hand
+    /// evicts an entry which is returned in `PutResult::Evicted`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PutResult, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(2, "b"), PutResult::Put);
+    /// assert_eq!(cache.put(2, "beta"), PutResult::Update("b"));
+    /// assert_eq!(cache.put(3, "c"), PutResult::Evicted { key: 1, value: "a" });
+    /// ```
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the visited bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val(idx)
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Sets the visited bit of the entry if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.hit(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not set the visited bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.val(self.arena.index(k)?)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not set the
+    /// visited bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena.val_mut(idx)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not set the
+    /// visited bit.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        Some(self.remove_at(idx, EvictionReason::Removed).1)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        while let Some(idx) = self.arena.back(QUEUE) {
+            let (k, v) = self.arena.remove(idx);
+            self.cb(&k, &v.unwrap(), EvictionReason::Purged);
+        }
+        self.hand = None;
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache = SieveCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.arena.len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, SieveCache};
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.cap
+    }
+
+    fn is_empty(&self) -> bool {
+        self.arena.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for SieveCache<K, V, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // the new entry is put to the head of the queue
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(QUEUE)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> ResizableCache
+    for SieveCache<K, V, E, S>
+{
+    /// Resizes the cache. If the new capacity is smaller than the size of the current
+    /// cache, the hand evicts entries until the cache fits. Growing the cache
+    /// reallocates the arena to the new capacity.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ResizableCache, SieveCache};
+    /// let mut cache: SieveCache<isize, &str> = SieveCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.resize(4);
+    /// cache.put(3, "c");
+    /// cache.put(4, "d");
+    /// cache.get(&1);
+    ///
+    /// assert_eq!(cache.len(), 4);
+    /// assert_eq!(cache.resize(1), 3);
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// ```
+    fn resize(&mut self, cap: usize) -> u64 {
+        let mut evicted = 0u64;
+        while self.arena.len() > cap {
+            self.evict(EvictionReason::Resized);
+            evicted += 1;
+        }
+        self.arena.reserve(cap);
+        self.cap = cap;
+        evicted
+    }
+}
+
+impl<K: Hash + Eq, V, E: OnEvictCallback, S: BuildHasher> fmt::Debug for SieveCache<K, V, E, S> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("SieveCache")
+            .field("len", &self.len())
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, E: Send, S: Send> Send for SieveCache<K, V, E, S> {}
+unsafe impl<K: Sync, V: Sync, E: Sync, S: Sync> Sync for SieveCache<K, V, E, S> {}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::SieveCache;
+    use crate::{Cache, PutResult, ResizableCache};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use std::collections::HashMap;
+
+    fn hand(cache: &SieveCache<u64, u64>) -> Option<u64> {
+        cache.hand.map(|idx| *cache.arena.key(idx))
+    }
+
+    #[test]
+    #[cfg(feature = "nightly")]
+    fn test_sieve_cache_get_with_borrow() {
+        use alloc::string::String;
+
+        let mut cache = SieveCache::new(2).unwrap();
+        cache.put(String::from("apple"), 1);
+        cache.put(String::from("banana"), 2);
+
+        assert_eq!(cache.get("apple"), Some(&1));
+        assert_eq!(cache.peek("banana"), Some(&2));
+        assert!(cache.contains("apple"));
+        assert_eq!(cache.remove("banana"), Some(2));
+        assert!(!cache.contains("banana"));
+    }
+
+    #[test]
+    fn test_sieve_cache_hand() {
+        let mut cache = SieveCache::new(4).unwrap();
+        (1..=4u64).for_each(|i| {
+            cache.put(i, i);
+        });
+        assert_eq!(hand(&cache), None);
+
+        // the hand starts from the tail, clears the visited bits of 1 and 2 and stops at 3
+        cache.get(&1);
+        cache.get(&2);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 3, value: 3 });
+        assert_eq!(hand(&cache), Some(4));
+
+        // 1 is visited again, but the hand goes on from 4 and does not go back to the tail
+        cache.get(&1);
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 4, value: 4 });
+        assert_eq!(hand(&cache), Some(5));
+
+        // the hand keeps the visited 6 and evicts 7, which is the head, so the hand moves
+        // past the head
+        cache.get(&6);
+        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 5, value: 5 });
+        assert_eq!(hand(&cache), Some(6));
+        assert_eq!(cache.put(8, 8), PutResult::Evicted { key: 7, value: 7 });
+        assert_eq!(hand(&cache), None);
+
+        // past the head, the hand wraps around to the tail, where 1 is still visited
+        assert_eq!(cache.put(9, 9), PutResult::Evicted { key: 2, value: 2 });
+        assert_eq!(hand(&cache), Some(6));
+        assert_eq!(cache.put(10, 10), PutResult::Evicted { key: 6, value: 6 });
+        assert_eq!(
+            cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![10, 9, 8, 1]
+        );
+
+        // removing the entry under the hand moves the hand to the next entry
+        assert_eq!(hand(&cache), Some(8));
+        assert_eq!(cache.remove(&8), Some(8));
+        assert_eq!(hand(&cache), Some(9));
+        assert_eq!(cache.put(11, 11), PutResult::Put);
+        assert_eq!(cache.put(12, 12), PutResult::Evicted { key: 9, value: 9 });
+        assert_eq!(
+            cache.iter().map(|(k, _)| *k).collect::<Vec<_>>(),
+            vec![12, 11, 10, 1]
+        );
+    }
+
+    #[test]
+    fn test_sieve_cache_resize() {
+        let mut cache = SieveCache::new(8).unwrap();
+        let mut model = HashMap::new();
+        (0..8u64).for_each(|i| {
+            cache.put(i, i);
+            model.insert(i, i);
+        });
+        (0..8u64).step_by(2).for_each(|i| {
+            cache.get(&i);
+        });
+
+        // the hand keeps the visited entries
+        assert_eq!(cache.resize(4), 4);
+        assert_eq!(cache.len(), 4);
+        (0..8u64)
+            .step_by(2)
+            .for_each(|i| assert!(cache.contains(&i)));
+
+        // the arena is reallocated when the cache grows
+        assert_eq!(cache.resize(64), 0);
+        (8..64u64).for_each(|i| {
+            assert_eq!(cache.put(i, i), PutResult::Put);
+            model.insert(i, i);
+        });
+        for (k, v) in cache.iter() {
+            assert_eq!(model.get(k), Some(v));
+        }
+        assert_eq!(cache.get(&0), Some(&0));
+        assert_eq!(cache.get(&63), Some(&63));
+    }
+}
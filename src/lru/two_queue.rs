@@ -1,14 +1,24 @@
+use crate::entry::sealed::EntryCache;
 use crate::lru::raw::EntryNode;
 use crate::lru::{
     swap_value, CacheError, DefaultEvictCallback, KeysLRUIter, KeysMRUIter, LRUIter, LRUIterMut,
     MRUIter, MRUIterMut, RawLRU, ValuesLRUIter, ValuesLRUIterMut, ValuesMRUIter, ValuesMRUIterMut,
 };
-use crate::{Cache, DefaultHashBuilder, KeyRef, PutResult};
-use alloc::boxed::Box;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultHashBuilder, EvictionReason, KeyRef, OnEvictCallback, PeekVictim,
+    PutResult,
+};
 use alloc::fmt;
 use core::borrow::Borrow;
-use core::hash::{BuildHasher, Hash};
-use core::mem;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
+
+mod arena;
+pub use arena::ArenaTwoQueueCache;
 
 /// `DEFAULT_2Q_RECENT_RATIO` is the ratio of the [`TwoQueueCache`] dedicated
 /// to recently added entries that have only been accessed once.
@@ -29,6 +39,7 @@ pub struct TwoQueueCacheBuilder<
     RH = DefaultHashBuilder,
     FH = DefaultHashBuilder,
     GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     size: usize,
     ghost_ratio: Option<f64>,
@@ -36,6 +47,7 @@ pub struct TwoQueueCacheBuilder<
     recent_hasher: Option<RH>,
     freq_hasher: Option<FH>,
     ghost_hasher: Option<GH>,
+    on_evict: Option<E>,
 }
 
 impl Default for TwoQueueCacheBuilder {
@@ -59,6 +71,7 @@ impl Default for TwoQueueCacheBuilder {
             recent_hasher: Some(DefaultHashBuilder::default()),
             freq_hasher: Some(DefaultHashBuilder::default()),
             ghost_hasher: Some(DefaultHashBuilder::default()),
+            on_evict: None,
         }
     }
 }
@@ -90,7 +103,9 @@ impl TwoQueueCacheBuilder {
     }
 }
 
-impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH, FH, GH> {
+impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    TwoQueueCacheBuilder<RH, FH, GH, E>
+{
     /// Set the ghost LRU size ratio
     pub fn set_ghost_ratio(self, ratio: f64) -> Self {
         TwoQueueCacheBuilder {
@@ -100,6 +115,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: self.recent_hasher,
             freq_hasher: self.freq_hasher,
             ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -112,6 +128,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: self.recent_hasher,
             freq_hasher: self.freq_hasher,
             ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -124,6 +141,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: self.recent_hasher,
             freq_hasher: self.freq_hasher,
             ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -131,7 +149,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
     pub fn set_recent_hasher<NRH: BuildHasher>(
         self,
         hasher: NRH,
-    ) -> TwoQueueCacheBuilder<NRH, FH, GH> {
+    ) -> TwoQueueCacheBuilder<NRH, FH, GH, E> {
         TwoQueueCacheBuilder {
             size: self.size,
             ghost_ratio: self.ghost_ratio,
@@ -139,6 +157,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: Some(hasher),
             freq_hasher: self.freq_hasher,
             ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -146,7 +165,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
     pub fn set_frequent_hasher<NFH: BuildHasher>(
         self,
         hasher: NFH,
-    ) -> TwoQueueCacheBuilder<RH, NFH, GH> {
+    ) -> TwoQueueCacheBuilder<RH, NFH, GH, E> {
         TwoQueueCacheBuilder {
             size: self.size,
             ghost_ratio: self.ghost_ratio,
@@ -154,6 +173,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: self.recent_hasher,
             freq_hasher: Some(hasher),
             ghost_hasher: self.ghost_hasher,
+            on_evict: self.on_evict,
         }
     }
 
@@ -161,7 +181,7 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
     pub fn set_ghost_hasher<NGH: BuildHasher>(
         self,
         hasher: NGH,
-    ) -> TwoQueueCacheBuilder<RH, FH, NGH> {
+    ) -> TwoQueueCacheBuilder<RH, FH, NGH, E> {
         TwoQueueCacheBuilder {
             size: self.size,
             ghost_ratio: self.ghost_ratio,
@@ -169,13 +189,65 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent_hasher: self.recent_hasher,
             freq_hasher: self.freq_hasher,
             ghost_hasher: Some(hasher),
+            on_evict: self.on_evict,
+        }
+    }
+
+    /// Set the callback which is invoked when an entry leaves the cache.
+    ///
+    /// The callback is not invoked when an entry moves between the inner LRUs. It is invoked
+    /// when an entry is evicted to the ghost LRU (the ghost LRU only remembers the key hash,
+    /// the entry itself is returned by `put`), removed, or purged.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, OnEvictCallback, TwoQueueCache, TwoQueueCacheBuilder};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: TwoQueueCache<u64, u64, _, _, _, _> = TwoQueueCacheBuilder::new(4)
+    ///     .set_on_evict(EvictedCounter(counter.clone()))
+    ///     .finalize()
+    ///     .unwrap();
+    ///
+    /// (1..=4).for_each(|i| {
+    ///     cache.put(i, i);
+    /// });
+    ///
+    /// // 1 is evicted to the ghost LRU, only the hash of its key is kept
+    /// cache.put(5, 5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    ///
+    /// cache.remove(&5);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 2);
+    /// ```
+    pub fn set_on_evict<NE: OnEvictCallback>(self, cb: NE) -> TwoQueueCacheBuilder<RH, FH, GH, NE> {
+        TwoQueueCacheBuilder {
+            size: self.size,
+            ghost_ratio: self.ghost_ratio,
+            recent_ratio: self.recent_ratio,
+            recent_hasher: self.recent_hasher,
+            freq_hasher: self.freq_hasher,
+            ghost_hasher: self.ghost_hasher,
+            on_evict: Some(cb),
         }
     }
 
     /// Finalize the builder to [`TwoQueueCache`]
     ///
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
-    pub fn finalize<K: Hash + Eq, V>(self) -> Result<TwoQueueCache<K, V, RH, FH, GH>, CacheError> {
+    pub fn finalize<K: Hash + Eq, V>(
+        self,
+    ) -> Result<TwoQueueCache<K, V, RH, FH, GH, E>, CacheError> {
         let size = self.size;
         if size == 0 {
             return Err(CacheError::InvalidSize(0));
@@ -208,6 +280,8 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
             recent,
             frequent: freq,
             ghost,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
         })
     }
 }
@@ -235,33 +309,28 @@ impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> TwoQueueCacheBuilder<RH,
 ///     assert_eq!(cache.put(i, i), PutResult::Put);
 /// });
 ///
-/// // Add 5 -> Evict 1 to ghost LRU
-/// assert_eq!(cache.put(5, 5), PutResult::Put);
+/// // Add 5 -> Evict 1 to ghost LRU, only the hash of 1 is kept
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+/// assert!(!cache.contains(&1));
 ///
-/// // Pull in the recently evicted
-/// assert_eq!(cache.put(1, 1), PutResult::Update(1));
+/// // Pull in the recently evicted key, it goes to freq LRU, and 2 is evicted
+/// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.frequent_len(), 1);
 ///
 /// // Add 6, should cause another recent evict
-/// assert_eq!(cache.put(6, 6), PutResult::<i32, i32>::Put);
-///
-/// // Add 7, should evict an entry from ghost LRU.
-/// assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 3, value: 3 });
 ///
-/// // Add 2, should evict an entry from ghost LRU
-/// assert_eq!(cache.put(2, 11), PutResult::Evicted { key: 3, value: 3 });
+/// // Add 7, should forget the least recent key of ghost LRU.
+/// assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 4, value: 4 });
 ///
-/// // Add 4, should put the entry from ghost LRU to freq LRU
-/// assert_eq!(cache.put(4, 11), PutResult::Update(4));
+/// // Add 4, should put the key from ghost LRU to freq LRU
+/// assert_eq!(cache.put(4, 11), PutResult::Evicted { key: 5, value: 5 });
 ///
 /// // move all entry in recent to freq.
-/// assert_eq!(cache.put(2, 22), PutResult::Update(11));
+/// assert_eq!(cache.put(6, 66), PutResult::Update(6));
 /// assert_eq!(cache.put(7, 77), PutResult::<i32, i32>::Update(7));
-///
-/// // Add 6, should put the entry from ghost LRU to freq LRU, and evicted one
-/// // entry
-/// assert_eq!(cache.put(6, 66), PutResult::EvictedAndUpdate { evicted: (5, 5), update: 6});
 /// assert_eq!(cache.recent_len(), 0);
-/// assert_eq!(cache.ghost_len(), 1);
+/// assert_eq!(cache.ghost_len(), 2);
 /// assert_eq!(cache.frequent_len(), 4);
 /// ```
 ///
@@ -273,12 +342,16 @@ pub struct TwoQueueCache<
     RH = DefaultHashBuilder,
     FH = DefaultHashBuilder,
     GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
 > {
     size: usize,
     recent_size: usize,
     recent: RawLRU<K, V, DefaultEvictCallback, RH>,
     frequent: RawLRU<K, V, DefaultEvictCallback, FH>,
-    ghost: RawLRU<K, V, DefaultEvictCallback, GH>,
+    // the ghost LRU only remembers the hashes of the evicted keys
+    ghost: RawLRU<u64, (), DefaultEvictCallback, GH>,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
 }
 
 impl<K: Hash + Eq, V> TwoQueueCache<K, V> {
@@ -377,12 +450,14 @@ impl<K: Hash + Eq, V> TwoQueueCache<K, V> {
             recent,
             frequent: freq,
             ghost,
+            on_evict: None,
+            stats: StatsRecorder::default(),
         })
     }
 }
 
-impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K, V>
-    for TwoQueueCache<K, V, RH, FH, GH>
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    Cache<K, V> for TwoQueueCache<K, V, RH, FH, GH, E>
 {
     /// Puts a key-value pair to the cache.
     ///
@@ -390,6 +465,8 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// - [`TwoQueueCache`] guarantees that the size of the recent LRU plus the size of the freq LRU
     /// is less or equal to the [`TwoQueueCache`]'s size.
     /// - The ghost LRU has its own size.
+    /// - The ghost LRU only remembers the key hashes, the entry evicted to the ghost LRU is
+    ///   returned in [`PutResult::Evicted`].
     ///
     /// # Example
     /// ```rust
@@ -403,141 +480,33 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// cache.put(4, 4);
     /// cache.put(5, 5);
     ///
-    /// // Pull in the recently evicted
-    /// assert_eq!(cache.put(1, 1), PutResult::Update(1));
+    /// // Pull in the recently evicted key, the least recent entry of the recent LRU is evicted
+    /// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
     ///
     /// // Add 6, should cause another recent evict
-    /// assert_eq!(cache.put(6, 6), PutResult::Put);
-    ///
-    /// // Add 7, should evict an entry from ghost LRU.
-    /// assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 2, value: 2});
-    ///
-    /// // Add 2, should evict an entry from ghost LRU
-    /// assert_eq!(cache.put(2, 11), PutResult::Evicted { key: 3, value: 3});
-    ///
-    /// // Add 4, should put the entry from ghost LRU to freq LRU
-    /// assert_eq!(cache.put(4, 11), PutResult::Update(4));
-    ///
-    /// // move all entry in recent to freq.
-    /// assert_eq!(cache.put(2, 22), PutResult::Update(11));
-    /// assert_eq!(cache.put(7, 77), PutResult::Update(7));
+    /// assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 3, value: 3 });
     ///
-    /// // Add 6, should put the entry from ghost LRU to freq LRU, and evicted one
-    /// // entry
-    /// assert_eq!(cache.put(6, 66), PutResult::EvictedAndUpdate { evicted: (5, 5), update: 6 });
+    /// // Update 6, should move it to freq LRU
+    /// assert_eq!(cache.put(6, 66), PutResult::Update(6));
     /// ```
     ///
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
-    fn put(&mut self, k: K, mut v: V) -> PutResult<K, V> {
-        let key_ref = KeyRef { k: &k };
-
-        // Check if the value is frequently used already,
-        // and just update the value
-        if let Some(ent_ptr) = self.frequent.map.get_mut(&key_ref).map(|node| {
-            let node_ptr: *mut EntryNode<K, V> = &mut **node;
-            node_ptr
-        }) {
-            self.frequent.update(&mut v, ent_ptr);
-            return PutResult::Update(v);
-        }
-
-        // Check if the value is recently used, and promote
-        // the value into the frequent list
-        if let Some(_) = self
-            .recent
-            // here we remove an entry from recent LRU if key exists
-            .remove_and_return_ent(&key_ref)
-            .map(|mut ent| {
-                unsafe {
-                    swap_value(&mut v, ent.as_mut());
-                }
-                // here we add the entry to frequent LRU,
-                // the result will always be PutResult::Put
-                // because we have removed this entry from recent LRU
-                self.frequent.put_box(ent)
-            })
-        {
-            return PutResult::Update(v);
-        }
-
-        // if we have space, nothing to do
-        let recent_len = self.recent.len();
-        let freq_len = self.frequent.len();
-
-        // If the value was recently evicted, add it to the
-        // frequently used list
-        if self.ghost.contains(&key_ref) {
-            return if recent_len + freq_len >= self.size {
-                let ent = if recent_len > self.recent_size {
-                    self.recent.remove_lru_in().unwrap()
-                } else {
-                    self.frequent.remove_lru_in().unwrap()
-                };
-
-                let rst = self.ghost.put_or_evict_box(ent);
-                match self.ghost.map.remove(&key_ref) {
-                    None => match rst {
-                        None => PutResult::Put,
-                        Some(mut ent) => {
-                            let ent_ptr = ent.as_mut();
-                            unsafe {
-                                mem::swap(&mut v, &mut (*(*ent_ptr).val.as_mut_ptr()) as &mut V);
-                            }
-                            self.frequent.put_box(ent);
-                            PutResult::Update(v)
-                        }
-                    },
-                    Some(mut ent) => {
-                        let ent_ptr = ent.as_mut();
-                        self.ghost.detach(ent_ptr);
-
-                        unsafe {
-                            mem::swap(&mut v, &mut (*(*ent_ptr).val.as_mut_ptr()) as &mut V);
-                            self.frequent.put_box(ent);
-                            match rst {
-                                None => PutResult::Update(v),
-                                Some(ent) => PutResult::EvictedAndUpdate {
-                                    evicted: (ent.key.assume_init(), ent.val.assume_init()),
-                                    update: v,
-                                },
-                            }
-                        }
-                    }
-                }
-            } else {
-                let mut ent = self.ghost.map.remove(&key_ref).unwrap();
-                let ent_ptr = ent.as_mut();
-                self.ghost.detach(ent_ptr);
-                unsafe {
-                    mem::swap(&mut v, &mut (*(*ent_ptr).val.as_mut_ptr()) as &mut V);
-                }
-                self.frequent.put_box(ent);
-                PutResult::Update(v)
-            };
-        }
-
-        // Add to the recently seen list.
-        let bks = Box::new(EntryNode::new(k, v));
-        // if we have enough space, we add entry to recent LRU directly
-        if freq_len + recent_len < self.size {
-            return match self.recent.put_or_evict_box(bks) {
-                None => PutResult::Put,
-                Some(evicted) => self.ghost.put_box(evicted),
-            };
+    /// [`PutResult::Evicted`]: enum.PutResult.html#variant.Evicted
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(ref cb) = self.on_evict {
+            let key_ref = KeyRef { k: &k };
+            let old = self
+                .frequent
+                .peek(&key_ref)
+                .or_else(|| self.recent.peek(&key_ref));
+            if let Some(old) = old {
+                cb.on_evict_with_reason(&k, old, EvictionReason::Replaced);
+            }
         }
 
-        // The cache does not have enough space, so we remove one entry from freq LRU or recent
-        // LRU. Then, put the removed entry to the front of the ghost LRU,
-        // if ghost LRU is also full, the cache will evict the less recent used entry of
-        // ghost LRU.
-        let ent = if recent_len >= self.recent_size {
-            self.recent.remove_lru_in().unwrap()
-        } else {
-            self.frequent.remove_lru_in().unwrap()
-        };
-
-        self.recent.put_box(bks);
-        self.ghost.put_box(ent)
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
     }
 
     /// Returns a mutable reference to the value of the key in the cache or `None` if it
@@ -556,17 +525,19 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     ///
     /// assert_eq!(cache.get(&"banana"), Some(&6));
     /// ```
-    fn get<'a, Q>(&mut self, k: &'a Q) -> Option<&'a V>
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        // Check if this is a frequent value
-        self.frequent.get(k).or_else(|| {
-            self.recent
-                .peek(k)
-                .and_then(|v| self.move_to_frequent(k, v))
-        })
+        // Check if this is a frequent value, otherwise promote
+        // the recent value to the frequent LRU
+        if !self.frequent.contains(k) {
+            self.move_to_frequent(k);
+        }
+        let v = self.frequent.get(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a mutable reference to the value of the key in the cache or `None` if it
@@ -587,17 +558,19 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
     /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
     /// ```
-    fn get_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        // Check if this is a frequent value
-        self.frequent.get_mut(k).or_else(|| {
-            self.recent
-                .peek_mut(k)
-                .and_then(|v| self.move_to_frequent(k, v))
-        })
+        // Check if this is a frequent value, otherwise promote
+        // the recent value to the frequent LRU
+        if !self.frequent.contains(k) {
+            self.move_to_frequent(k);
+        }
+        let v = self.frequent.get_mut(k);
+        self.stats.record_get(v.is_some());
+        v
     }
 
     /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
@@ -616,7 +589,7 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// assert_eq!(cache.peek(&1), Some(&"a"));
     /// assert_eq!(cache.peek(&2), Some(&"b"));
     /// ```
-    fn peek<'a, Q>(&self, k: &'a Q) -> Option<&'a V>
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -640,14 +613,16 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
     /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
     /// ```
-    fn peek_mut<'a, Q>(&mut self, k: &'a Q) -> Option<&'a mut V>
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.frequent
-            .peek_mut(k)
-            .or_else(|| self.recent.peek_mut(k))
+        if self.frequent.contains(k) {
+            self.frequent.peek_mut(k)
+        } else {
+            self.recent.peek_mut(k)
+        }
     }
 
     /// Removes and returns the value corresponding to the key from the cache or
@@ -671,10 +646,14 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
     {
-        self.frequent
-            .remove(k)
-            .or_else(|| self.recent.remove(k))
-            .or_else(|| self.ghost.remove(k))
+        let hash = self.hash_key(k);
+        self.ghost.remove(&hash);
+        let (key, val) = self
+            .frequent
+            .remove_entry(k)
+            .or_else(|| self.recent.remove_entry(k))?;
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
     }
 
     /// Returns a bool indicating whether the given key is in the cache. Does not update the
@@ -721,8 +700,12 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     /// assert_eq!(cache.len(), 0);
     /// ```
     fn purge(&mut self) {
-        self.frequent.purge();
-        self.recent.purge();
+        while let Some((k, v)) = self.frequent.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
+        while let Some((k, v)) = self.recent.remove_lru() {
+            self.cb(&k, &v, EvictionReason::Purged);
+        }
         self.ghost.purge();
     }
 
@@ -768,8 +751,57 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> Cache<K
     }
 }
 
-impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
-    TwoQueueCache<K, V, RH, FH, GH>
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    PeekVictim<K, V> for TwoQueueCache<K, V, RH, FH, GH, E>
+{
+    /// Returns the key of the entry which would be demoted to the ghost LRU if `k` was put
+    /// into the cache now.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PeekVictim, TwoQueueCache};
+    /// let mut cache = TwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.get(&2);
+    ///
+    /// // the recent LRU is over its share of the cache, so 1 is evicted from it
+    /// assert_eq!(cache.peek_victim(&3), Some(&1));
+    /// assert_eq!(cache.peek_victim(&1), None);
+    /// ```
+    fn peek_victim<Q>(&self, k: &Q) -> Option<&K>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Eq + Hash + ?Sized,
+    {
+        let recent_len = self.recent.len();
+        if recent_len + self.frequent.len() < self.size
+            || self.recent.contains(k)
+            || self.frequent.contains(k)
+        {
+            return None;
+        }
+
+        // keep consistent with `put_in` and `demote`
+        let from_recent = if self.ghost.contains(&self.hash_key(k)) {
+            recent_len > self.recent_size
+        } else {
+            recent_len >= self.recent_size
+        };
+
+        let victim = if from_recent {
+            self.recent.peek_lru().or_else(|| self.frequent.peek_lru())
+        } else {
+            self.frequent.peek_lru().or_else(|| self.recent.peek_lru())
+        };
+        victim.map(|(k, _)| k)
+    }
+}
+
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    TwoQueueCache<K, V, RH, FH, GH, E>
 {
     /// Create a [`TwoQueueCache`] from [`TwoQueueCacheBuilder`].
     ///
@@ -792,7 +824,7 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
     ///
     /// [`TwoQueueCacheBuilder`]: struct.TwoQueueCacheBuilder.html
     /// [`TwoQueueCache`]: struct.TwoQueueCache.html
-    pub fn from_builder(builder: TwoQueueCacheBuilder<RH, FH, GH>) -> Result<Self, CacheError> {
+    pub fn from_builder(builder: TwoQueueCacheBuilder<RH, FH, GH, E>) -> Result<Self, CacheError> {
         builder.finalize()
     }
 
@@ -806,11 +838,60 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
         self.frequent.len()
     }
 
-    /// Returns the number of key-value pairs that are currently in the the ghost LRU.
+    /// Returns the number of keys that are currently in the the ghost LRU.
     pub fn ghost_len(&self) -> usize {
         self.ghost.len()
     }
 
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key<Q: Hash + ?Sized>(&self, k: &Q) -> u64 {
+        let mut hasher = self.recent.map.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, TwoQueueCache};
+        /// let mut cache = TwoQueueCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(1, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 1);
+        /// assert_eq!(stats.updates(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, TwoQueueCache};
+        /// let mut cache = TwoQueueCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
     /// An iterator visiting all keys of recent LRU in most-recently used order. The iterator element type is
     /// `&'a K`.
     ///
@@ -1057,257 +1138,6 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
         self.recent.iter_lru_mut()
     }
 
-    /// An iterator visiting all keys of ghost LRU in most-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.ghost_keys() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn ghost_keys(&self) -> KeysMRUIter<'_, K, V> {
-        self.ghost.keys()
-    }
-
-    /// An iterator visiting all keys of ghost LRU in less-recently used order. The iterator element type is
-    /// `&'a K`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for key in cache.ghost_keys_lru() {
-    ///     println!("key: {}", key);
-    /// }
-    /// ```
-    pub fn ghost_keys_lru(&self) -> KeysLRUIter<'_, K, V> {
-        self.ghost.keys_lru()
-    }
-
-    /// An iterator visiting all values of ghost LRU in most-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.ghost_values() {
-    ///     println!("val: {}",  val);
-    /// }
-    /// ```
-    pub fn ghost_values(&self) -> ValuesMRUIter<'_, K, V> {
-        self.ghost.values()
-    }
-
-    /// An iterator visiting all values in less-recently used order. The iterator element type is
-    /// `&'a V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.ghost_values_lru() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn ghost_values_lru(&self) -> ValuesLRUIter<'_, K, V> {
-        self.ghost.values_lru()
-    }
-
-    /// An iterator visiting all values of ghost LRU in most-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.ghost_values_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn ghost_values_mut(&mut self) -> ValuesMRUIterMut<'_, K, V> {
-        self.ghost.values_mut()
-    }
-
-    /// An iterator visiting all values of ghost LRU in less-recently used order. The iterator element type is
-    /// `&'a mut V`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for val in cache.ghost_values_lru_mut() {
-    ///     println!("val: {}", val);
-    /// }
-    /// ```
-    pub fn ghost_values_lru_mut(&mut self) -> ValuesLRUIterMut<'_, K, V> {
-        self.ghost.values_lru_mut()
-    }
-
-    /// An iterator visiting all entries of ghost LRU in most-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.ghost_iter() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn ghost_iter(&self) -> MRUIter<'_, K, V> {
-        self.ghost.iter()
-    }
-
-    /// An iterator visiting all entries of ghost LRU in less-recently used order. The iterator element type is
-    /// `(&'a K, &'a V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    /// cache.put("a", 1);
-    /// cache.put("b", 2);
-    /// cache.put("c", 3);
-    ///
-    /// for (key, val) in cache.ghost_iter_lru() {
-    ///     println!("key: {} val: {}", key, val);
-    /// }
-    /// ```
-    pub fn ghost_iter_lru(&self) -> LRUIter<'_, K, V> {
-        self.ghost.iter_lru()
-    }
-
-    /// An iterator visiting all entries of ghost LRU in most-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 2i32;
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.ghost_iter_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr -= 1;
-    /// }
-    /// ```
-    pub fn ghost_iter_mut(&mut self) -> MRUIterMut<'_, K, V> {
-        self.ghost.iter_mut()
-    }
-
-    /// An iterator visiting all entries of ghost LRU in less-recently-used order, giving a mutable reference on
-    /// V.  The iterator element type is `(&'a K, &'a mut V)`.
-    ///
-    /// # Examples
-    ///
-    /// ```
-    /// use caches::{Cache, TwoQueueCache};
-    ///
-    /// struct HddBlock {
-    ///     idx: u64,
-    ///     dirty: bool,
-    ///     data: [u8; 512]
-    /// }
-    ///
-    /// let mut cache = TwoQueueCache::new(3).unwrap();
-    ///
-    /// // put in recent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// // upgrade to frequent list
-    /// cache.put(0, HddBlock { idx: 0, dirty: false, data: [0x00; 512]});
-    /// cache.put(1, HddBlock { idx: 1, dirty: true,  data: [0x55; 512]});
-    /// cache.put(2, HddBlock { idx: 2, dirty: true,  data: [0x77; 512]});
-    ///
-    /// let mut ctr = 0i32;
-    ///
-    /// // write dirty blocks to disk.
-    /// for (block_id, block) in cache.frequent_iter_lru_mut() {
-    ///     if block.dirty {
-    ///         // write block to disk
-    ///         block.dirty = false
-    ///     }
-    ///     assert_eq!(*block_id, ctr);
-    ///     ctr += 1;
-    /// }
-    /// ```
-    pub fn ghost_iter_lru_mut(&mut self) -> LRUIterMut<'_, K, V> {
-        self.ghost.iter_lru_mut()
-    }
-
     /// An iterator visiting all keys of frequent LRU in most-recently used order. The iterator element type is
     /// `&'a K`.
     ///
@@ -1559,7 +1389,15 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
         self.frequent.iter_lru_mut()
     }
 
-    fn move_to_frequent<T, Q>(&mut self, k: &Q, v: T) -> Option<T>
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    fn move_to_frequent<Q>(&mut self, k: &Q)
     where
         KeyRef<K>: Borrow<Q>,
         Q: Hash + Eq + ?Sized,
@@ -1567,39 +1405,156 @@ impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher>
         // remove the element from the recent LRU
         // and put it in frequent LRU.
         if let Some(ent) = self.recent.remove_and_return_ent(k) {
-            match self.frequent.put_or_evict_box(ent) {
-                None => Some(v),
-                // the Some branch will not reach, because we remove one from
-                // recent LRU, and add this one to frequent LRU, the total size
-                // of the cache is not changed. We still keep this for good measure.
-                Some(_) => Some(v),
-            }
+            // nothing is evicted here, because we remove one from
+            // recent LRU, and add this one to frequent LRU, the total size
+            // of the cache is not changed.
+            self.frequent.put_or_evict_box(ent);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    TwoQueueCache<K, V, RH, FH, GH, E>
+{
+    fn put_in(&mut self, k: K, mut v: V) -> PutResult<K, V> {
+        let key_ref = KeyRef { k: &k };
+
+        // Check if the value is frequently used already,
+        // and just update the value
+        if let Some(ent_ptr) = self.frequent.map.get_mut(&key_ref).map(|node| {
+            let node_ptr: *mut EntryNode<K, V> = &mut **node;
+            node_ptr
+        }) {
+            self.frequent.update(&mut v, ent_ptr);
+            return PutResult::Update(v);
+        }
+
+        // Check if the value is recently used, and promote
+        // the value into the frequent list
+        if let Some(_) = self
+            .recent
+            // here we remove an entry from recent LRU if key exists
+            .remove_and_return_ent(&key_ref)
+            .map(|mut ent| {
+                unsafe {
+                    swap_value(&mut v, ent.as_mut());
+                }
+                // here we add the entry to frequent LRU,
+                // the result will always be PutResult::Put
+                // because we have removed this entry from recent LRU
+                self.frequent.put_box(ent)
+            })
+        {
+            return PutResult::Update(v);
+        }
+
+        // if we have space, nothing to do
+        let recent_len = self.recent.len();
+        let freq_len = self.frequent.len();
+
+        // If the value was recently evicted, add it to the
+        // frequently used list
+        if self.ghost.remove(&self.hash_key(&k)).is_some() {
+            self.stats.record_ghost_hit();
+            let evicted = if recent_len + freq_len >= self.size {
+                self.demote(recent_len > self.recent_size)
+            } else {
+                None
+            };
+            self.frequent.put(k, v);
+            return match evicted {
+                Some((key, value)) => PutResult::Evicted { key, value },
+                None => PutResult::Put,
+            };
+        }
+
+        // The cache does not have enough space, so we remove one entry from freq LRU or recent
+        // LRU. Only the key of the removed entry is remembered by the ghost LRU, if ghost LRU
+        // is also full, the less recent used key of ghost LRU is forgotten.
+        let evicted = if freq_len + recent_len >= self.size {
+            self.demote(recent_len >= self.recent_size)
         } else {
             None
+        };
+
+        // Add to the recently seen list.
+        match self.recent.put(k, v) {
+            PutResult::Evicted { key, value } => {
+                self.evict_to_ghost(&key, &value);
+                PutResult::Evicted { key, value }
+            }
+            _ => match evicted {
+                Some((key, value)) => PutResult::Evicted { key, value },
+                None => PutResult::Put,
+            },
         }
     }
+
+    /// Removes the least recently used entry from the recent LRU (or the frequent LRU),
+    /// remembers the hash of its key in the ghost LRU and returns it. Falls back to the other LRU if
+    /// the chosen one is empty.
+    fn demote(&mut self, from_recent: bool) -> Option<(K, V)> {
+        let (k, v) = if from_recent {
+            self.recent
+                .remove_lru()
+                .or_else(|| self.frequent.remove_lru())
+        } else {
+            self.frequent
+                .remove_lru()
+                .or_else(|| self.recent.remove_lru())
+        }?;
+
+        self.evict_to_ghost(&k, &v);
+        Some((k, v))
+    }
+
+    #[inline]
+    fn evict_to_ghost(&mut self, k: &K, v: &V) {
+        let hash = self.hash_key(k);
+        self.ghost.put(hash, ());
+        self.cb(k, v, EvictionReason::Capacity);
+    }
+}
+
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    EntryCache<K, V> for TwoQueueCache<K, V, RH, FH, GH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a recently evicted key is put into the frequent LRU, other keys into the recent LRU
+        let to_frequent = self.ghost.contains(&self.hash_key(&k));
+        let rst = self.put(k, v);
+        let v = if to_frequent {
+            self.frequent.peek_mru_mut()
+        } else {
+            self.recent.peek_mru_mut()
+        };
+        let v = v.map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
 }
 
-impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher> fmt::Debug
-    for TwoQueueCache<K, V, RH, FH, GH>
+impl<K: Hash + Eq, V, RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    fmt::Debug for TwoQueueCache<K, V, RH, FH, GH, E>
 {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("TwoQueueCache")
-            .field("len", &self.len())
-            .field("cap", &self.cap())
+            .field("len", &(self.recent.len() + self.frequent.len()))
+            .field("cap", &self.size)
             .finish()
     }
 }
 
 #[cfg(test)]
 mod test {
-    use crate::lru::two_queue::TwoQueueCache;
+    use crate::lru::two_queue::{TwoQueueCache, TwoQueueCacheBuilder};
     use crate::lru::CacheError;
-    use crate::{Cache, PutResult};
+    use crate::{Cache, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use alloc::vec;
     use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
     use rand::seq::SliceRandom;
     use rand::{thread_rng, Rng};
-    use std::format;
 
     #[test]
     fn test_2q_cache_random_ops() {
@@ -1682,6 +1637,11 @@ mod test {
         assert_eq!(cache.frequent.len(), 1);
     }
 
+    // the key hashes in the ghost LRU, the most recent one first
+    fn ghost_keys<V>(cache: &TwoQueueCache<u64, V>) -> Vec<u64> {
+        cache.ghost.keys().copied().collect()
+    }
+
     #[test]
     fn test_2q_cache_put() {
         let mut cache = TwoQueueCache::new(4).unwrap();
@@ -1691,75 +1651,61 @@ mod test {
         cache.put(2, 2);
         cache.put(3, 3);
         cache.put(4, 4);
-        cache.put(5, 5);
+        assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
         assert_eq!(cache.recent.len(), 4);
         assert_eq!(cache.ghost.len(), 1,);
         assert_eq!(cache.frequent.len(), 0);
 
-        // Pull in the recently evicted
-        assert_eq!(cache.put(1, 1), PutResult::Update(1));
+        // Pull in the recently evicted, 1 is a new entry, and 2 is evicted
+        assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
         assert_eq!(cache.recent.len(), 3);
         assert_eq!(cache.ghost.len(), 1,);
         assert_eq!(cache.frequent.len(), 1);
 
         // Add 6, should cause another recent evict
-        assert_eq!(
-            format!("{:?}", cache.put(6, 6).clone()),
-            format!("{:?}", PutResult::<i32, i32>::Put)
-        );
+        assert_eq!(cache.put(6, 6), PutResult::Evicted { key: 3, value: 3 });
         assert_eq!(cache.recent.len(), 3);
         assert_eq!(cache.ghost.len(), 2,);
         assert_eq!(cache.frequent.len(), 1);
 
-        // Add 7, should evict an entry from ghost LRU.
-        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 2, value: 2 });
+        // Add 7, should forget the least recent key of ghost LRU.
+        assert_eq!(cache.put(7, 7), PutResult::Evicted { key: 4, value: 4 });
         assert_eq!(cache.recent.len(), 3);
         assert_eq!(cache.ghost.len(), 2,);
         assert_eq!(cache.frequent.len(), 1);
+        assert!(!cache.ghost.contains(&cache.hash_key(&2u64)));
 
-        // Add 2, should evict an entry from ghost LRU
-        assert_eq!(
-            format!("{:?}", cache.put(2, 11).clone()),
-            format!("{:?}", PutResult::Evicted { key: 3, value: 3 })
-        );
+        // Add 2, 2 has been forgotten by ghost LRU, so it is added to recent LRU
+        assert_eq!(cache.put(2, 11), PutResult::Evicted { key: 5, value: 5 });
         assert_eq!(cache.recent.len(), 3);
         assert_eq!(cache.ghost.len(), 2,);
         assert_eq!(cache.frequent.len(), 1);
 
-        // Add 4, should put the entry from ghost LRU to freq LRU
-        assert_eq!(cache.put(4, 11), PutResult::Update(4));
+        // Add 4, should put the key from ghost LRU to freq LRU
+        assert_eq!(cache.put(4, 11), PutResult::Evicted { key: 6, value: 6 });
         assert_eq!(cache.recent.len(), 2);
         assert_eq!(cache.ghost.len(), 2,);
         assert_eq!(cache.frequent.len(), 2);
 
         // move all entry in recent to freq.
-        assert_eq!(cache.put(2, 22).clone(), PutResult::Update(11));
+        assert_eq!(cache.put(2, 22), PutResult::Update(11));
         assert_eq!(cache.recent.len(), 1);
         assert_eq!(cache.ghost.len(), 2,);
         assert_eq!(cache.frequent.len(), 3);
 
-        assert_eq!(
-            format!("{:?}", cache.put(7, 77)),
-            format!("{:?}", PutResult::<i32, i32>::Update(7))
-        );
+        assert_eq!(cache.put(7, 77), PutResult::Update(7));
         assert_eq!(cache.recent.len(), 0);
         assert_eq!(cache.ghost.len(), 2);
         assert_eq!(cache.frequent.len(), 4);
 
-        // Add 6, should put the entry from ghost LRU to freq LRU, and evicted one
-        // entry
+        // Add 6, should put the key from ghost LRU to freq LRU, and move the least recent
+        // entry of freq LRU to ghost LRU
+        assert_eq!(cache.put(6, 66), PutResult::Evicted { key: 1, value: 1 });
+        assert_eq!(cache.recent.len(), 0);
         assert_eq!(
-            format!("{:?}", cache.put(6, 66).clone()),
-            format!(
-                "{:?}",
-                PutResult::EvictedAndUpdate {
-                    evicted: (5, 5),
-                    update: 6
-                }
-            )
+            ghost_keys(&cache),
+            vec![cache.hash_key(&1u64), cache.hash_key(&5u64)]
         );
-        assert_eq!(cache.recent.len(), 0);
-        assert_eq!(cache.ghost.len(), 1);
         assert_eq!(cache.frequent.len(), 4);
     }
 
@@ -1828,4 +1774,85 @@ mod test {
         cache.put(3, 3);
         assert!(!cache.contains(&1));
     }
+
+    struct DropCounter(Arc<AtomicUsize>);
+
+    impl Drop for DropCounter {
+        fn drop(&mut self) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_2q_cache_drops_values_at_eviction() {
+        let drops = Arc::new(AtomicUsize::new(0));
+        let mut cache = TwoQueueCache::new(8).unwrap();
+
+        let mut created = 0;
+        for k in (0..32u64).chain(0..32).chain(16..48) {
+            created += 1;
+            // the evicted value is handed back to the caller and dropped here
+            cache.put(k, DropCounter(drops.clone()));
+            // the ghost LRU holds no values, so every value not in the cache has been dropped
+            assert_eq!(drops.load(Ordering::SeqCst), created - cache.len());
+        }
+        assert_eq!(cache.ghost_len(), 4);
+        assert_eq!(
+            ghost_keys(&cache),
+            [39u64, 38, 37, 36]
+                .iter()
+                .map(|k| cache.hash_key(k))
+                .collect::<Vec<_>>()
+        );
+
+        // the demoted entry is returned, only the hash of its key is left in the ghost LRU
+        let rst = cache.put(100, DropCounter(drops.clone()));
+        assert!(matches!(rst, PutResult::Evicted { key: 40, .. }));
+        assert_eq!(drops.load(Ordering::SeqCst), created - cache.len());
+        drop(rst);
+        assert_eq!(drops.load(Ordering::SeqCst), created + 1 - cache.len());
+        assert_eq!(ghost_keys(&cache)[0], cache.hash_key(&40u64));
+    }
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    #[test]
+    fn test_2q_cache_on_evict() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = TwoQueueCacheBuilder::new(64)
+            .set_on_evict(EvictedCounter { ctr: ctr.clone() })
+            .finalize::<u64, u64>()
+            .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 256;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Put | PutResult::Evicted { .. } => inserted += 1,
+                    _ => {}
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
 }
@@ -0,0 +1,749 @@
+use super::TwoQueueCacheBuilder;
+use crate::entry::sealed::EntryCache;
+use crate::lru::arena::{Arena, ArenaIter};
+use crate::lru::CacheError;
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult,
+};
+use alloc::fmt;
+use core::borrow::Borrow;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::ptr::NonNull;
+
+const RECENT: usize = 0;
+const FREQUENT: usize = 1;
+// the only list of the ghost arena
+const GHOST: usize = 0;
+
+impl<RH: BuildHasher, FH: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    TwoQueueCacheBuilder<RH, FH, GH, E>
+{
+    /// Finalize the builder to [`ArenaTwoQueueCache`].
+    ///
+    /// The recent and frequent LRUs share one map, which uses the recent hasher. The ghost LRU
+    /// remembers the key hashes in its own arena, which uses the ghost hasher. The frequent
+    /// hasher is not used.
+    ///
+    /// # Example
+    ///
+    /// ```rust
+    /// use caches::{Cache, TwoQueueCacheBuilder};
+    ///
+    /// let mut cache = TwoQueueCacheBuilder::new(4)
+    ///     .set_recent_ratio(0.5)
+    ///     .finalize_arena()
+    ///     .unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.get(&1), Some(&"a"));
+    /// ```
+    ///
+    /// [`ArenaTwoQueueCache`]: struct.ArenaTwoQueueCache.html
+    pub fn finalize_arena<K: Hash + Eq, V>(
+        self,
+    ) -> Result<ArenaTwoQueueCache<K, V, RH, GH, E>, CacheError> {
+        let size = self.size;
+        if size == 0 || size >= u32::MAX as usize {
+            return Err(CacheError::InvalidSize(size));
+        }
+
+        let rr = self.recent_ratio.unwrap();
+        if !(0.0..=1.0).contains(&rr) {
+            return Err(CacheError::InvalidRecentRatio(rr));
+        }
+
+        let gr = self.ghost_ratio.unwrap();
+        if !(0.0..=1.0).contains(&gr) {
+            return Err(CacheError::InvalidGhostRatio(gr));
+        }
+
+        // Determine the sub-sizes
+        let rs = ((size as f64) * rr).floor() as usize;
+        let es = ((size as f64) * gr).floor() as usize;
+
+        Ok(ArenaTwoQueueCache {
+            arena: Arena::with_hasher(size, 2, self.recent_hasher.unwrap()),
+            ghost: Arena::with_hasher(es, 1, self.ghost_hasher.unwrap()),
+            size,
+            recent_size: rs,
+            ghost_size: es,
+            on_evict: self.on_evict,
+            stats: StatsRecorder::default(),
+        })
+    }
+}
+
+/// `ArenaTwoQueueCache` is a [`TwoQueueCache`] which stores its recent and frequent LRUs in
+/// one arena, like [`ArenaLRU`], and the key hashes of its ghost LRU in another one.
+///
+/// The arenas are preallocated to the size of the cache and the size of the ghost LRU,
+/// moving an entry between the recent and frequent LRUs only relinks it, so the cache does
+/// not allocate in the steady state. It behaves the same as a [`TwoQueueCache`] with the same
+/// parameters.
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{ArenaTwoQueueCache, Cache, PutResult};
+///
+/// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+///
+/// // Add 1,2,3,4,
+/// (1..=4).for_each(|i| {
+///     assert_eq!(cache.put(i, i), PutResult::Put);
+/// });
+///
+/// // Add 5 -> Evict 1 to ghost LRU, only the key hash of 1 is kept
+/// assert_eq!(cache.put(5, 5), PutResult::Evicted { key: 1, value: 1 });
+/// assert!(!cache.contains(&1));
+///
+/// // Pull in the recently evicted key, it goes to freq LRU, and 2 is evicted
+/// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+/// assert_eq!(cache.frequent_len(), 1);
+///
+/// // Update 5, should move it to freq LRU
+/// assert_eq!(cache.put(5, 55), PutResult::Update(5));
+/// assert_eq!(cache.frequent_len(), 2);
+/// ```
+///
+/// [`TwoQueueCache`]: struct.TwoQueueCache.html
+/// [`ArenaLRU`]: struct.ArenaLRU.html
+pub struct ArenaTwoQueueCache<
+    K,
+    V,
+    S = DefaultHashBuilder,
+    GH = DefaultHashBuilder,
+    E = DefaultEvictCallback,
+> {
+    arena: Arena<K, V, S>,
+    // the ghost LRU only keeps the hashes of the evicted keys
+    ghost: Arena<u64, (), GH>,
+    size: usize,
+    recent_size: usize,
+    ghost_size: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V> ArenaTwoQueueCache<K, V> {
+    /// Create an `ArenaTwoQueueCache` with size and default configurations.
+    pub fn new(size: usize) -> Result<Self, CacheError> {
+        TwoQueueCacheBuilder::new(size).finalize_arena()
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> Cache<K, V>
+    for ArenaTwoQueueCache<K, V, S, GH, E>
+{
+    /// Puts a key-value pair to the cache.
+    ///
+    /// # Note
+    /// - [`ArenaTwoQueueCache`] guarantees that the size of the recent LRU plus the size of the
+    ///   freq LRU is less or equal to the [`ArenaTwoQueueCache`]'s size.
+    /// - The ghost LRU has its own size.
+    /// - The ghost LRU only remembers key hashes, the entry evicted to the ghost LRU is returned
+    ///   in [`PutResult::Evicted`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{ArenaTwoQueueCache, Cache, PutResult};
+    ///
+    /// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+    /// // Add 1,2,3,4,5 -> Evict 1
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// cache.put(3, 3);
+    /// cache.put(4, 4);
+    /// cache.put(5, 5);
+    ///
+    /// // Pull in the recently evicted key, the least recent entry of the recent LRU is evicted
+    /// assert_eq!(cache.put(1, 1), PutResult::Evicted { key: 2, value: 2 });
+    ///
+    /// // Update 5, should move it to freq LRU
+    /// assert_eq!(cache.put(5, 55), PutResult::Update(5));
+    /// ```
+    ///
+    /// [`ArenaTwoQueueCache`]: struct.ArenaTwoQueueCache.html
+    /// [`PutResult::Evicted`]: enum.PutResult.html#variant.Evicted
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the frequent LRU if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get(&"banana"), Some(&6));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, FREQUENT);
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the frequent LRU if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// cache.put("banana", 4);
+    /// cache.put("banana", 6);
+    /// cache.put("pear", 2);
+    ///
+    /// assert_eq!(cache.get_mut(&"apple"), None);
+    /// assert_eq!(cache.get_mut(&"banana"), Some(&mut 6));
+    /// assert_eq!(cache.get_mut(&"pear"), Some(&mut 2));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k);
+        self.stats.record_get(idx.is_some());
+        let idx = idx?;
+        self.arena.move_to_front(idx, FREQUENT);
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LRU list so the key's
+    /// position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena.val(idx).map(|v| unsafe { &*(v as *const V) })
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the LRU
+    /// list so the key's position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_mut(&1), Some(&mut "a"));
+    /// assert_eq!(cache.peek_mut(&2), Some(&mut "b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = self.arena.index(k)?;
+        self.arena
+            .val_mut(idx)
+            .map(|v| unsafe { &mut *(v as *mut V) })
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not update the
+    /// LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.arena.index(k).is_some()
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    ///
+    /// cache.put(2, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("a"));
+    /// assert_eq!(cache.remove(&2), None);
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let idx = match self.arena.index(k) {
+            Some(idx) => idx,
+            None => {
+                let hash = self.hash_key(k);
+                if let Some(idx) = self.ghost.index(&hash) {
+                    self.ghost.remove(idx);
+                }
+                return None;
+            }
+        };
+        let (key, val) = self.arena.remove(idx);
+        let val = val.unwrap();
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache: ArenaTwoQueueCache<isize, &str> = ArenaTwoQueueCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// assert_eq!(cache.len(), 2);
+    ///
+    /// cache.purge();
+    /// assert_eq!(cache.len(), 0);
+    /// ```
+    fn purge(&mut self) {
+        for &list in &[FREQUENT, RECENT] {
+            while let Some(idx) = self.arena.back(list) {
+                let (k, v) = self.arena.remove(idx);
+                self.cb(&k, &v.unwrap(), EvictionReason::Purged);
+            }
+        }
+        while let Some(idx) = self.ghost.back(GHOST) {
+            self.ghost.remove(idx);
+        }
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache
+    /// (excluding the length of inner ghost LRU).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache = ArenaTwoQueueCache::new(2).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    fn len(&self) -> usize {
+        self.recent_len() + self.frequent_len()
+    }
+
+    /// Returns the maximum number of key-value pairs the cache can hold
+    /// (excluding the capacity of inner ghost LRU).
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    /// let mut cache: ArenaTwoQueueCache<isize, &str> = ArenaTwoQueueCache::new(2).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.size
+    }
+
+    fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback>
+    ArenaTwoQueueCache<K, V, S, GH, E>
+{
+    /// Returns the number of key-value pairs that are currently in the the recent LRU.
+    pub fn recent_len(&self) -> usize {
+        self.arena.list_len(RECENT)
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the frequent LRU.
+    pub fn frequent_len(&self) -> usize {
+        self.arena.list_len(FREQUENT)
+    }
+
+    /// Returns the number of key hashes that are currently in the the ghost LRU.
+    pub fn ghost_len(&self) -> usize {
+        self.ghost.list_len(GHOST)
+    }
+
+    /// An iterator visiting all entries of recent LRU in most-recently used order.
+    /// The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    ///
+    /// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.get(&"a");
+    ///
+    /// assert_eq!(cache.recent_iter().collect::<Vec<_>>(), vec![(&"b", &2)]);
+    /// ```
+    pub fn recent_iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(RECENT)
+    }
+
+    /// An iterator visiting all entries of frequent LRU in most-recently used order.
+    /// The iterator element type is `(&'a K, &'a V)`.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{ArenaTwoQueueCache, Cache};
+    ///
+    /// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.get(&"a");
+    ///
+    /// assert_eq!(cache.frequent_iter().collect::<Vec<_>>(), vec![(&"a", &1)]);
+    /// ```
+    pub fn frequent_iter(&self) -> ArenaIter<'_, K, V> {
+        self.arena.iter(FREQUENT)
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaTwoQueueCache, Cache};
+        /// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(1, "b");
+        /// cache.get(&1);
+        /// cache.get(&2);
+        ///
+        /// let stats = cache.stats();
+        /// assert_eq!(stats.inserts(), 1);
+        /// assert_eq!(stats.updates(), 1);
+        /// assert_eq!(stats.hits(), 1);
+        /// assert_eq!(stats.misses(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{ArenaTwoQueueCache, Cache, CacheStats};
+        /// let mut cache = ArenaTwoQueueCache::new(4).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    #[allow(clippy::manual_hash_one)]
+    fn hash_key<Q: Hash + ?Sized>(&self, k: &Q) -> u64 {
+        let mut hasher = self.arena.hasher().build_hasher();
+        k.hash(&mut hasher);
+        hasher.finish()
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        if let Some(idx) = self.arena.index(&KeyRef { k: &k }) {
+            // The value is frequently or recently used, update the value
+            // and promote it into the frequent list
+            let old = self.arena.replace_val(idx, Some(v)).unwrap();
+            self.arena.move_to_front(idx, FREQUENT);
+            self.cb(&k, &old, EvictionReason::Replaced);
+            return PutResult::Update(old);
+        }
+
+        let recent_len = self.recent_len();
+        let freq_len = self.frequent_len();
+        let full = recent_len + freq_len >= self.size;
+
+        // If the value was recently evicted, add it to the frequently used list. Otherwise,
+        // add it to the recently seen list. The cache does not have enough space, so we move
+        // one entry from freq LRU or recent LRU to the ghost LRU.
+        let (list, evicted) = match self.ghost.index(&self.hash_key(&k)) {
+            Some(idx) => {
+                self.stats.record_ghost_hit();
+                self.ghost.remove(idx);
+                let evicted = if full {
+                    self.demote(recent_len > self.recent_size)
+                } else {
+                    None
+                };
+                (FREQUENT, evicted)
+            }
+            None => {
+                let evicted = if full {
+                    self.demote(recent_len >= self.recent_size)
+                } else {
+                    None
+                };
+                (RECENT, evicted)
+            }
+        };
+
+        self.arena.push_front(list, k, Some(v));
+        match evicted {
+            Some((key, value)) => {
+                // the cache is consistent before the callback runs, even if it panics
+                self.cb(&key, &value, EvictionReason::Capacity);
+                PutResult::Evicted { key, value }
+            }
+            None => PutResult::Put,
+        }
+    }
+
+    /// Takes the least recently used entry of the recent LRU (or the frequent LRU) out of the
+    /// arena and returns it, only the key hash is kept in the ghost LRU.
+    fn demote(&mut self, from_recent: bool) -> Option<(K, V)> {
+        let (list, other) = if from_recent {
+            (RECENT, FREQUENT)
+        } else {
+            (FREQUENT, RECENT)
+        };
+        let idx = self.arena.back(list).or_else(|| self.arena.back(other))?;
+
+        let hash = self.hash_key(self.arena.key(idx));
+        let (key, val) = self.arena.remove(idx);
+
+        match self.ghost.index(&hash) {
+            Some(idx) => self.ghost.move_to_front(idx, GHOST),
+            None if self.ghost_size > 0 => {
+                if self.ghost.list_len(GHOST) >= self.ghost_size {
+                    let lru = self.ghost.back(GHOST).unwrap();
+                    self.ghost.remove(lru);
+                }
+                self.ghost.push_front(GHOST, hash, None);
+            }
+            None => {}
+        }
+        Some((key, val.unwrap()))
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> EntryCache<K, V>
+    for ArenaTwoQueueCache<K, V, S, GH, E>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        // a recently evicted key is put into the frequent LRU, other keys into the recent LRU
+        let list = match self.ghost.index(&self.hash_key(&k)) {
+            Some(_) => FREQUENT,
+            None => RECENT,
+        };
+        let rst = self.put(k, v);
+        let v = self
+            .arena
+            .front(list)
+            .and_then(|idx| self.arena.val_mut(idx))
+            .map(NonNull::from);
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, S: BuildHasher, GH: BuildHasher, E: OnEvictCallback> fmt::Debug
+    for ArenaTwoQueueCache<K, V, S, GH, E>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("ArenaTwoQueueCache")
+            .field("len", &(self.recent_len() + self.frequent_len()))
+            .field("cap", &self.size)
+            .finish()
+    }
+}
+
+unsafe impl<K: Send, V: Send, S: Send, GH: Send, E: Send> Send
+    for ArenaTwoQueueCache<K, V, S, GH, E>
+{
+}
+unsafe impl<K: Sync, V: Sync, S: Sync, GH: Sync, E: Sync> Sync
+    for ArenaTwoQueueCache<K, V, S, GH, E>
+{
+}
+
+#[cfg(test)]
+mod test {
+    use super::GHOST;
+    use crate::{ArenaTwoQueueCache, Cache, OnEvictCallback, TwoQueueCache, TwoQueueCacheBuilder};
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use rand::{rngs::StdRng, thread_rng, Rng, SeedableRng};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+
+    // The caches hash the keys with different seeds, so the ghost hashes are compared by
+    // the keys below `n` they belong to.
+    fn arena_ghost_keys(cache: &ArenaTwoQueueCache<u64, u64>, n: u64) -> Vec<u64> {
+        cache
+            .ghost
+            .keys(GHOST)
+            .map(|h| (0..n).find(|k| cache.hash_key(k) == *h).unwrap())
+            .collect()
+    }
+
+    fn ghost_keys(cache: &TwoQueueCache<u64, u64>, n: u64) -> Vec<u64> {
+        cache
+            .ghost
+            .keys()
+            .map(|h| (0..n).find(|k| cache.hash_key(k) == *h).unwrap())
+            .collect()
+    }
+
+    #[test]
+    fn test_arena_2q_same_as_2q() {
+        let mut arena = ArenaTwoQueueCache::new(128).unwrap();
+        let mut cache = TwoQueueCache::new(128).unwrap();
+        let mut rng = thread_rng();
+
+        (0..200_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 4 {
+                0 | 1 => assert_eq!(arena.put(k, k), cache.put(k, k)),
+                2 => assert_eq!(arena.get(&k), cache.get(&k)),
+                _ => assert_eq!(arena.remove(&k), cache.remove(&k)),
+            }
+            assert_eq!(arena.recent_len(), cache.recent_len());
+            assert_eq!(arena.frequent_len(), cache.frequent_len());
+            assert_eq!(arena.ghost_len(), cache.ghost_len());
+        });
+
+        assert!(arena.recent_iter().eq(cache.recent_iter()));
+        assert!(arena.frequent_iter().eq(cache.frequent_iter()));
+        assert_eq!(arena_ghost_keys(&arena, 512), ghost_keys(&cache, 512));
+    }
+
+    #[test]
+    fn test_arena_2q_same_as_2q_small() {
+        let mut arena = ArenaTwoQueueCache::new(8).unwrap();
+        let mut cache = TwoQueueCache::new(8).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        (0..20_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 32;
+            match rng.gen::<u64>() % 4 {
+                0 | 1 => assert_eq!(arena.put(k, k), cache.put(k, k)),
+                2 => assert_eq!(arena.get(&k), cache.get(&k)),
+                _ => assert_eq!(arena.remove(&k), cache.remove(&k)),
+            }
+            assert!(arena.len() <= arena.cap());
+            assert!(arena.recent_iter().eq(cache.recent_iter()));
+            assert!(arena.frequent_iter().eq(cache.frequent_iter()));
+            assert_eq!(arena_ghost_keys(&arena, 32), ghost_keys(&cache, 32));
+        });
+    }
+
+    #[test]
+    fn test_arena_2q_panicking_callback() {
+        struct PanicOnEvict;
+
+        impl OnEvictCallback for PanicOnEvict {
+            fn on_evict<K, V>(&self, _: &K, _: &V) {
+                panic!("on_evict");
+            }
+        }
+
+        let mut cache = TwoQueueCacheBuilder::new(2)
+            .set_on_evict(PanicOnEvict)
+            .finalize_arena()
+            .unwrap();
+        cache.put(1, 1);
+        cache.put(2, 2);
+
+        // 1 is evicted to the ghost LRU and 3 is added before the callback panics
+        assert!(catch_unwind(AssertUnwindSafe(|| cache.put(3, 3))).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.ghost_len(), 1);
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.peek(&3), Some(&3));
+
+        // 1 comes back from the ghost LRU, 2 is evicted
+        assert!(catch_unwind(AssertUnwindSafe(|| cache.put(1, 1))).is_err());
+        assert_eq!(cache.len(), 2);
+        assert_eq!(cache.ghost_len(), 1);
+        assert_eq!(cache.frequent_iter().collect::<Vec<_>>(), vec![(&1, &1)]);
+        assert_eq!(cache.recent_iter().collect::<Vec<_>>(), vec![(&3, &3)]);
+    }
+}
@@ -0,0 +1,827 @@
+use crate::entry::sealed::EntryCache;
+use crate::lru::{CacheError, LRUIter, MRUIter, RawLRU};
+use crate::stats::StatsRecorder;
+cfg_stats!(
+    use crate::CacheStats;
+);
+use crate::{
+    cfg_stats, Cache, DefaultEvictCallback, DefaultHashBuilder, EvictionReason, KeyRef,
+    OnEvictCallback, PutResult, ResizableCache, Weigher,
+};
+use alloc::vec::Vec;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash};
+use core::ptr::NonNull;
+
+/// `WeightedLRU` is a LRU cache whose capacity is a total weight instead of an entry count.
+///
+/// The weight of each entry is given by a [`Weigher`]. `put` evicts entries from the LRU end
+/// until the new entry fits, and rejects the entries which are heavier than the whole cache.
+/// All the evicted entries are returned in the [`PutResult`].
+///
+/// # Example
+///
+/// ```rust
+/// use caches::{Cache, PutResult, WeightedLRU};
+///
+/// let mut cache = WeightedLRU::new(10, |_: &u64, v: &Vec<u8>| v.len()).unwrap();
+///
+/// assert_eq!(cache.put(1, vec![0; 4]), PutResult::Put);
+/// assert_eq!(cache.put(2, vec![0; 4]), PutResult::Put);
+/// assert_eq!(cache.weight(), 8);
+///
+/// // both 1 and 2 have to be evicted to make room for 3
+/// assert_eq!(
+///     cache.put(3, vec![0; 8]),
+///     PutResult::EvictedMany { evicted: vec![(1, vec![0; 4]), (2, vec![0; 4])], update: None },
+/// );
+/// assert_eq!(cache.len(), 1);
+///
+/// // 4 is heavier than the whole cache
+/// assert_eq!(cache.put(4, vec![0; 11]), PutResult::Rejected { key: 4, value: vec![0; 11] });
+/// assert!(cache.contains(&3));
+/// ```
+///
+/// [`Weigher`]: trait.Weigher.html
+/// [`PutResult`]: enum.PutResult.html
+pub struct WeightedLRU<K, V, W, E = DefaultEvictCallback, S = DefaultHashBuilder> {
+    lru: RawLRU<K, V, DefaultEvictCallback, S>,
+    weigher: W,
+    weight: usize,
+    max_weight: usize,
+    on_evict: Option<E>,
+    stats: StatsRecorder,
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>> WeightedLRU<K, V, W> {
+    /// Creates a new `WeightedLRU` whose entries weigh at most `max_weight` in total.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{UnitWeigher, WeightedLRU};
+    /// let mut cache: WeightedLRU<u64, u64, UnitWeigher> = WeightedLRU::new(10, UnitWeigher).unwrap();
+    /// ```
+    pub fn new(max_weight: usize, weigher: W) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(
+            max_weight,
+            weigher,
+            None,
+            DefaultHashBuilder::default(),
+        )
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, S: BuildHasher>
+    WeightedLRU<K, V, W, DefaultEvictCallback, S>
+{
+    /// Creates a new `WeightedLRU` whose entries weigh at most `max_weight` in total and
+    /// uses the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{DefaultHashBuilder, UnitWeigher, WeightedLRU};
+    ///
+    /// let s = DefaultHashBuilder::default();
+    /// let mut cache: WeightedLRU<u64, u64, UnitWeigher> = WeightedLRU::with_hasher(10, UnitWeigher, s).unwrap();
+    /// ```
+    pub fn with_hasher(max_weight: usize, weigher: W, hasher: S) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(max_weight, weigher, None, hasher)
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback> WeightedLRU<K, V, W, E> {
+    /// Creates a new `WeightedLRU` whose entries weigh at most `max_weight` in total and
+    /// uses the provided evict callback.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, OnEvictCallback, UnitWeigher, WeightedLRU};
+    /// use std::sync::atomic::{AtomicU64, Ordering};
+    /// use std::sync::Arc;
+    ///
+    /// struct EvictedCounter(Arc<AtomicU64>);
+    ///
+    /// impl OnEvictCallback for EvictedCounter {
+    ///     fn on_evict<K, V>(&self, _: &K, _: &V) {
+    ///         self.0.fetch_add(1, Ordering::SeqCst);
+    ///     }
+    /// }
+    ///
+    /// let counter = Arc::new(AtomicU64::new(0));
+    /// let mut cache: WeightedLRU<u64, u64, _, _> =
+    ///     WeightedLRU::with_on_evict_cb(1, UnitWeigher, EvictedCounter(counter.clone())).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// assert_eq!(counter.load(Ordering::SeqCst), 1);
+    /// ```
+    pub fn with_on_evict_cb(max_weight: usize, weigher: W, cb: E) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(
+            max_weight,
+            weigher,
+            Some(cb),
+            DefaultHashBuilder::default(),
+        )
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback, S: BuildHasher>
+    WeightedLRU<K, V, W, E, S>
+{
+    /// Creates a new `WeightedLRU` whose entries weigh at most `max_weight` in total,
+    /// uses the provided evict callback and the provided hash builder to hash keys.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{DefaultEvictCallback, DefaultHashBuilder, UnitWeigher, WeightedLRU};
+    ///
+    /// let mut cache: WeightedLRU<u64, u64, UnitWeigher> = WeightedLRU::with_on_evict_cb_and_hasher(
+    ///     10,
+    ///     UnitWeigher,
+    ///     DefaultEvictCallback,
+    ///     DefaultHashBuilder::default(),
+    /// ).unwrap();
+    /// ```
+    pub fn with_on_evict_cb_and_hasher(
+        max_weight: usize,
+        weigher: W,
+        cb: E,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        Self::with_on_evict_cb_and_hasher_in(max_weight, weigher, Some(cb), hasher)
+    }
+
+    fn with_on_evict_cb_and_hasher_in(
+        max_weight: usize,
+        weigher: W,
+        cb: Option<E>,
+        hasher: S,
+    ) -> Result<Self, CacheError> {
+        if max_weight == 0 {
+            return Err(CacheError::InvalidSize(0));
+        }
+
+        Ok(Self {
+            lru: RawLRU::unbounded_with_hasher(hasher),
+            weigher,
+            weight: 0,
+            max_weight,
+            on_evict: cb,
+            stats: StatsRecorder::default(),
+        })
+    }
+
+    /// Returns the total weight of the entries in the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(10, |_: &u64, v: &&str| v.len()).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "bcd");
+    /// assert_eq!(cache.weight(), 4);
+    /// ```
+    pub fn weight(&self) -> usize {
+        self.weight
+    }
+
+    /// Returns the least recent used entry(&K, &V) in the cache or `None` if the cache is empty.
+    /// Does not update the LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek_lru(), Some((&1, &"a")));
+    /// ```
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.lru.peek_lru()
+    }
+
+    /// Removes and returns the least recent used entry in the cache or `None` if the cache is empty.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.remove_lru(), Some((1, "a")));
+    /// assert_eq!(cache.weight(), 1);
+    /// ```
+    pub fn remove_lru(&mut self) -> Option<(K, V)> {
+        self.remove_lru_with(EvictionReason::Removed)
+    }
+
+    /// An iterator visiting all entries in most-recently used order. The iterator element type is
+    /// `(&'a K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    ///
+    /// let mut cache = WeightedLRU::new(3, UnitWeigher).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.put("c", 3);
+    ///
+    /// for (key, val) in cache.iter() {
+    ///     println!("key: {} val: {}", key, val);
+    /// }
+    /// ```
+    pub fn iter(&self) -> MRUIter<'_, K, V> {
+        self.lru.iter()
+    }
+
+    /// An iterator visiting all entries in less-recently used order. The iterator element type is
+    /// `(&'a K, &'a V)`.
+    ///
+    /// # Examples
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    ///
+    /// let mut cache = WeightedLRU::new(3, UnitWeigher).unwrap();
+    /// cache.put("a", 1);
+    /// cache.put("b", 2);
+    /// cache.put("c", 3);
+    ///
+    /// for (key, val) in cache.iter_lru() {
+    ///     println!("key: {} val: {}", key, val);
+    /// }
+    /// ```
+    pub fn iter_lru(&self) -> LRUIter<'_, K, V> {
+        self.lru.iter_lru()
+    }
+
+    cfg_stats!(
+        /// Returns the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, UnitWeigher, WeightedLRU};
+        /// let mut cache = WeightedLRU::new(1, UnitWeigher).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.put(2, "b");
+        ///
+        /// assert_eq!(cache.stats().inserts(), 2);
+        /// assert_eq!(cache.stats().evictions(), 1);
+        /// ```
+        pub fn stats(&self) -> CacheStats {
+            self.stats.stats()
+        }
+
+        /// Resets the statistics of the cache.
+        ///
+        /// # Example
+        ///
+        /// ```
+        /// use caches::{Cache, CacheStats, UnitWeigher, WeightedLRU};
+        /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+        ///
+        /// cache.put(1, "a");
+        /// cache.reset_stats();
+        ///
+        /// assert_eq!(cache.stats(), CacheStats::default());
+        /// ```
+        pub fn reset_stats(&mut self) {
+            self.stats.reset();
+        }
+    );
+
+    fn put_in(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let weight = self.weigher.weight(&k, &v);
+        if weight > self.max_weight {
+            // the old value is replaced even if the new one is rejected, so the cache never
+            // keeps a stale value of the key.
+            if let Some((old_k, old_v)) = self.lru.remove_entry(&KeyRef { k: &k }) {
+                self.weight -= self.weigher.weight(&old_k, &old_v);
+                if let Some(ref cb) = self.on_evict {
+                    cb.on_evict_with_reason(&old_k, &old_v, EvictionReason::Replaced);
+                }
+            }
+            return PutResult::Rejected { key: k, value: v };
+        }
+
+        let old_weight = self.lru.peek(&KeyRef { k: &k }).map(|old| {
+            if let Some(ref cb) = self.on_evict {
+                cb.on_evict_with_reason(&k, old, EvictionReason::Replaced);
+            }
+            self.weigher.weight(&k, old)
+        });
+
+        let update = match self.lru.put(k, v) {
+            PutResult::Update(old) => Some(old),
+            _ => None,
+        };
+        self.weight = self.weight - old_weight.unwrap_or(0) + weight;
+
+        // the new entry is the most recently used one and it is not heavier than the cache,
+        // so it is never evicted here.
+        let mut evicted = Vec::new();
+        while self.weight > self.max_weight {
+            match self.remove_lru_with(EvictionReason::Capacity) {
+                Some(ent) => evicted.push(ent),
+                None => break,
+            }
+        }
+        PutResult::from_evicted(evicted, update)
+    }
+
+    fn remove_lru_with(&mut self, reason: EvictionReason) -> Option<(K, V)> {
+        let (k, v) = self.lru.remove_lru()?;
+        self.weight -= self.weigher.weight(&k, &v);
+        self.cb(&k, &v, reason);
+        Some((k, v))
+    }
+
+    #[inline]
+    fn cb(&mut self, k: &K, v: &V, reason: EvictionReason) {
+        self.stats.record_eviction(reason);
+        if let Some(ref cb) = self.on_evict {
+            cb.on_evict_with_reason(k, v, reason);
+        }
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback, S: BuildHasher> Cache<K, V>
+    for WeightedLRU<K, V, W, E, S>
+{
+    /// Puts a key-value pair into cache, returns a [`PutResult`].
+    ///
+    /// The entries are evicted from the LRU end until the new entry fits. If more than one entry
+    /// is evicted, they are returned in [`PutResult::EvictedMany`]. The entry is rejected and
+    /// returned in [`PutResult::Rejected`] if it is heavier than the whole cache, and the old
+    /// entry of the key, if any, is removed from the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, PutResult, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(3, |_: &u64, v: &&str| v.len()).unwrap();
+    ///
+    /// assert_eq!(PutResult::Put, cache.put(1, "a"));
+    /// assert_eq!(PutResult::Put, cache.put(2, "b"));
+    /// assert_eq!(PutResult::Update("b"), cache.put(2, "bc"));
+    /// assert_eq!(PutResult::Evicted { key: 1, value: "a" }, cache.put(3, "d"));
+    /// assert_eq!(PutResult::Rejected { key: 4, value: "efgh" }, cache.put(4, "efgh"));
+    ///
+    /// assert_eq!(cache.get(&2), Some(&"bc"));
+    /// assert_eq!(cache.weight(), 3);
+    /// ```
+    ///
+    /// [`PutResult`]: enum.PutResult.html
+    /// [`PutResult::EvictedMany`]: enum.PutResult.html#variant.EvictedMany
+    /// [`PutResult::Rejected`]: enum.PutResult.html#variant.Rejected
+    fn put(&mut self, k: K, v: V) -> PutResult<K, V> {
+        let rst = self.put_in(k, v);
+        self.stats.record_put(&rst);
+        rst
+    }
+
+    /// Returns a reference to the value of the key in the cache or `None` if it is not
+    /// present in the cache. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&2), Some(&"b"));
+    /// ```
+    fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let v = self.lru.get(k);
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Returns a mutable reference to the value of the key in the cache or `None` if it
+    /// is not present in the cache. Moves the key to the head of the LRU list if it exists.
+    ///
+    /// The value must keep its weight, the cache does not weigh it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    /// *cache.get_mut(&"apple").unwrap() = 9;
+    ///
+    /// assert_eq!(cache.get(&"apple"), Some(&9));
+    /// ```
+    fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let v = self.lru.get_mut(k);
+        self.stats.record_get(v.is_some());
+        v
+    }
+
+    /// Returns a reference to the value corresponding to the key in the cache or `None` if it is
+    /// not present in the cache. Unlike `get`, `peek` does not update the LRU list so the key's
+    /// position will be unchanged.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"a"));
+    /// assert_eq!(cache.peek(&2), Some(&"b"));
+    /// ```
+    fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lru.peek(k)
+    }
+
+    /// Returns a mutable reference to the value corresponding to the key in the cache or `None`
+    /// if it is not present in the cache. Unlike `get_mut`, `peek_mut` does not update the LRU
+    /// list so the key's position will be unchanged.
+    ///
+    /// The value must keep its weight, the cache does not weigh it again.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// *cache.peek_mut(&1).unwrap() = "b";
+    ///
+    /// assert_eq!(cache.peek(&1), Some(&"b"));
+    /// ```
+    fn peek_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lru.peek_mut(k)
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache. Does not update the
+    /// LRU list.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.put(3, "c");
+    ///
+    /// assert!(!cache.contains(&1));
+    /// assert!(cache.contains(&2));
+    /// assert!(cache.contains(&3));
+    /// ```
+    fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lru.contains(k)
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(4, |_: &u64, v: &&str| v.len()).unwrap();
+    ///
+    /// cache.put(2, "ab");
+    ///
+    /// assert_eq!(cache.remove(&1), None);
+    /// assert_eq!(cache.remove(&2), Some("ab"));
+    /// assert_eq!(cache.weight(), 0);
+    /// ```
+    fn remove<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let (key, val) = self.lru.remove_entry(k)?;
+        self.weight -= self.weigher.weight(&key, &val);
+        self.cb(&key, &val, EvictionReason::Removed);
+        Some(val)
+    }
+
+    /// Clears the contents of the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache: WeightedLRU<isize, &str, _> = WeightedLRU::new(2, UnitWeigher).unwrap();
+    ///
+    /// cache.put(1, "a");
+    /// cache.put(2, "b");
+    /// cache.purge();
+    ///
+    /// assert_eq!(cache.len(), 0);
+    /// assert_eq!(cache.weight(), 0);
+    /// ```
+    fn purge(&mut self) {
+        while self.remove_lru_with(EvictionReason::Purged).is_some() {}
+    }
+
+    /// Returns the number of key-value pairs that are currently in the the cache.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    /// assert_eq!(cache.len(), 0);
+    ///
+    /// cache.put(1, "a");
+    /// assert_eq!(cache.len(), 1);
+    /// ```
+    fn len(&self) -> usize {
+        self.lru.len()
+    }
+
+    /// Returns the maximum total weight of the entries the cache can hold.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache: WeightedLRU<isize, &str, _> = WeightedLRU::new(2, UnitWeigher).unwrap();
+    /// assert_eq!(cache.cap(), 2);
+    /// ```
+    fn cap(&self) -> usize {
+        self.max_weight
+    }
+
+    /// Returns a bool indicating whether the cache is empty or not.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(2, UnitWeigher).unwrap();
+    /// assert!(cache.is_empty());
+    ///
+    /// cache.put(1, "a");
+    /// assert!(!cache.is_empty());
+    /// ```
+    fn is_empty(&self) -> bool {
+        self.lru.is_empty()
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback, S: BuildHasher> EntryCache<K, V>
+    for WeightedLRU<K, V, W, E, S>
+{
+    fn put_vacant(&mut self, k: K, v: V) -> (PutResult<K, V>, Option<NonNull<V>>) {
+        let rst = self.put(k, v);
+        if let PutResult::Rejected { .. } = rst {
+            return (rst, None);
+        }
+        // the new entry is the most recently used one, it is never evicted by its own put
+        let v = self.lru.peek_mru_mut().map(|(_, v)| NonNull::from(v));
+        (rst, v)
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback, S: BuildHasher> ResizableCache
+    for WeightedLRU<K, V, W, E, S>
+{
+    /// Resizes the maximum total weight of the cache. If the new maximum is smaller than
+    /// the total weight of the current cache, entries are discarded from the LRU end until
+    /// the cache fits.
+    ///
+    /// # Example
+    ///
+    /// ```
+    /// use caches::{Cache, ResizableCache, UnitWeigher, WeightedLRU};
+    /// let mut cache = WeightedLRU::new(4, UnitWeigher).unwrap();
+    ///
+    /// (1..=4).for_each(|i| {
+    ///     cache.put(i, i);
+    /// });
+    ///
+    /// assert_eq!(cache.resize(2), 2);
+    /// assert_eq!(cache.get(&1), None);
+    /// assert_eq!(cache.get(&4), Some(&4));
+    /// ```
+    fn resize(&mut self, cap: usize) -> u64 {
+        let mut evicted = 0u64;
+        while self.weight > cap {
+            if self.remove_lru_with(EvictionReason::Resized).is_none() {
+                break;
+            }
+            evicted += 1;
+        }
+        self.max_weight = cap;
+        evicted
+    }
+}
+
+impl<K: Hash + Eq, V, W: Weigher<K, V>, E: OnEvictCallback, S: BuildHasher> fmt::Debug
+    for WeightedLRU<K, V, W, E, S>
+{
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("WeightedLRU")
+            .field("len", &self.len())
+            .field("weight", &self.weight)
+            .field("cap", &self.cap())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::lru::WeightedLRU;
+    use crate::{Cache, EvictionReason, OnEvictCallback, PutResult};
+    use alloc::sync::Arc;
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use rand::{thread_rng, Rng};
+    use std::sync::Mutex;
+
+    struct EvictedCounter {
+        ctr: Arc<AtomicUsize>,
+    }
+
+    impl OnEvictCallback for EvictedCounter {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {
+            self.ctr.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    struct Reasons(Arc<Mutex<Vec<EvictionReason>>>);
+
+    impl OnEvictCallback for Reasons {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {}
+
+        fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+            self.0.lock().unwrap().push(reason);
+        }
+    }
+
+    #[test]
+    fn test_weighted_lru_update_weight() {
+        let mut cache = WeightedLRU::new(10, |_: &u64, v: &Vec<u8>| v.len()).unwrap();
+
+        cache.put(1, vec![0; 3]);
+        cache.put(2, vec![0; 3]);
+        cache.put(3, vec![0; 3]);
+        assert_eq!(cache.weight(), 9);
+
+        // growing 3 evicts 1 and 2
+        assert_eq!(
+            cache.put(3, vec![0; 9]),
+            PutResult::EvictedMany {
+                evicted: vec![(1, vec![0; 3]), (2, vec![0; 3])],
+                update: Some(vec![0; 3]),
+            }
+        );
+        assert_eq!(cache.weight(), 9);
+
+        // shrinking 3 evicts nothing
+        assert_eq!(cache.put(3, vec![0; 1]), PutResult::Update(vec![0; 9]));
+        assert_eq!(cache.weight(), 1);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_weighted_lru_reject_update() {
+        let reasons = Arc::new(Mutex::new(Vec::new()));
+        let mut cache = WeightedLRU::with_on_evict_cb(
+            10,
+            |_: &u64, v: &Vec<u8>| v.len(),
+            Reasons(reasons.clone()),
+        )
+        .unwrap();
+
+        cache.put(1, vec![0; 3]);
+        cache.put(2, vec![0; 3]);
+
+        // the update heavier than the cache is rejected, and the old value is replaced
+        assert_eq!(
+            cache.put(1, vec![1; 11]),
+            PutResult::Rejected {
+                key: 1,
+                value: vec![1; 11]
+            }
+        );
+        assert_eq!(*reasons.lock().unwrap(), vec![EvictionReason::Replaced]);
+        assert!(!cache.contains(&1));
+        assert_eq!(cache.weight(), 3);
+        assert_eq!(cache.len(), 1);
+    }
+
+    #[test]
+    fn test_weighted_lru_get_or_insert_with() {
+        let mut cache = WeightedLRU::new(10, |_: &u64, v: &Vec<u8>| v.len()).unwrap();
+
+        assert_eq!(
+            cache.get_or_insert_with(1, || vec![1; 3]),
+            Some(&vec![1; 3])
+        );
+        assert_eq!(
+            cache.get_or_insert_with(1, || vec![2; 3]),
+            Some(&vec![1; 3])
+        );
+
+        // the value heavier than the capacity is rejected
+        assert_eq!(cache.get_or_insert_with(2, || vec![0; 11]), None);
+        assert_eq!(
+            cache.try_get_or_insert_with(2, || Ok::<_, ()>(vec![0; 11])),
+            Ok(None)
+        );
+        assert_eq!(cache.try_get_or_insert_with(2, || Err(())), Err(()));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.weight(), 3);
+    }
+
+    #[test]
+    fn test_weighted_lru_random_ops() {
+        let ctr = Arc::new(AtomicUsize::new(0));
+        let mut cache = WeightedLRU::with_on_evict_cb(
+            256,
+            |k: &u64, _: &u64| (*k % 16) as usize,
+            EvictedCounter { ctr: ctr.clone() },
+        )
+        .unwrap();
+        let mut rng = thread_rng();
+
+        // every inserted entry is still in the cache, or it has been passed to the callback once.
+        let mut inserted = 0;
+        (0..100_000).for_each(|_| {
+            let k = rng.gen::<u64>() % 512;
+            match rng.gen::<u64>() % 3 {
+                0 => match cache.put(k, k) {
+                    PutResult::Update(_) | PutResult::EvictedAndUpdate { .. } => {}
+                    PutResult::EvictedMany {
+                        update: Some(_), ..
+                    } => {}
+                    PutResult::Rejected { .. } => {}
+                    _ => inserted += 1,
+                },
+                1 => {
+                    let _ = cache.get(&k);
+                }
+                _ => {
+                    let _ = cache.remove(&k);
+                }
+            }
+            assert!(cache.weight() <= cache.cap());
+            assert_eq!(
+                cache.weight(),
+                cache.iter().map(|(k, _)| (*k % 16) as usize).sum()
+            );
+            assert_eq!(inserted, ctr.load(Ordering::SeqCst) + cache.len());
+        });
+
+        cache.purge();
+        assert_eq!(cache.weight(), 0);
+        assert_eq!(inserted, ctr.load(Ordering::SeqCst));
+    }
+}
@@ -99,3 +99,15 @@ macro_rules! cfg_not_hashbrown {
         )*
     }
 }
+
+#[macro_export]
+#[doc(hidden)]
+macro_rules! cfg_stats {
+    ($($item:item)*) => {
+        $(
+            #[cfg(feature = "stats")]
+            #[cfg_attr(docsrs, doc(cfg(feature = "stats")))]
+            $item
+        )*
+    }
+}
@@ -0,0 +1,515 @@
+//! A thread-safe cache which partitions the keys across several independently locked caches.
+use crate::lru::CacheError;
+use crate::{Cache, DefaultHashBuilder, KeyRef, PutResult};
+use alloc::boxed::Box;
+use core::borrow::Borrow;
+use core::fmt;
+use core::hash::{BuildHasher, Hash, Hasher};
+use core::marker::PhantomData;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+
+/// `ShardedCache` is a thread-safe wrapper over any [`Cache`].
+///
+/// The keys are partitioned by their hash across `N` shards, every shard is an inner cache
+/// built by a factory closure and guarded by its own [`Mutex`], so the threads working on
+/// different shards do not block each other. All of the methods take `&self`, so a
+/// `ShardedCache` can be shared between threads with an [`Arc`].
+///
+/// The eviction policy is applied per shard, so the cache as a whole only approximates
+/// the policy of the inner caches.
+///
+/// The lock of a shard is recovered by the next thread if a thread panicked while it was
+/// holding it. The loaders of [`get_or_insert_with`] run before the inner cache is changed, so
+/// a failed load leaves the shard as it was. But a panic in the eviction callback leaves the
+/// shard consistent only if the inner cache is panic safe, i.e. it finishes its bookkeeping
+/// before it invokes the callback. Otherwise, the eviction callback must not panic.
+///
+/// # Example
+/// ```rust
+/// use caches::{RawLRU, ShardedCache};
+/// use std::sync::Arc;
+/// use std::thread;
+///
+/// let cache = Arc::new(ShardedCache::new(4, |_| RawLRU::<u64, u64>::new(64).unwrap()).unwrap());
+///
+/// let handles: Vec<_> = (0..4u64)
+///     .map(|t| {
+///         let cache = cache.clone();
+///         thread::spawn(move || {
+///             for k in t * 16..(t + 1) * 16 {
+///                 cache.put(k, k);
+///             }
+///         })
+///     })
+///     .collect();
+/// handles.into_iter().for_each(|h| h.join().unwrap());
+///
+/// assert_eq!(cache.len(), 64);
+/// assert_eq!(cache.get(&42), Some(42));
+/// ```
+///
+/// [`Cache`]: trait.Cache.html
+/// [`Mutex`]: https://doc.rust-lang.org/std/sync/struct.Mutex.html
+/// [`Arc`]: https://doc.rust-lang.org/std/sync/struct.Arc.html
+/// [`get_or_insert_with`]: struct.ShardedCache.html#method.get_or_insert_with
+pub struct ShardedCache<K, V, C, S = DefaultHashBuilder> {
+    shards: Box<[Mutex<C>]>,
+    hasher: S,
+    marker: PhantomData<(K, V)>,
+}
+
+impl<K: Hash + Eq, V, C: Cache<K, V>> ShardedCache<K, V, C> {
+    /// Creates a `ShardedCache` with `shards` shards, the `i`th shard is built by `factory(i)`.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::lru::CacheError;
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(8, |_| RawLRU::<u64, u64>::new(16).unwrap()).unwrap();
+    /// assert_eq!(cache.shards(), 8);
+    /// assert_eq!(cache.cap(), 128);
+    ///
+    /// let rst = ShardedCache::new(0, |_| RawLRU::<u64, u64>::new(16).unwrap());
+    /// assert_eq!(rst.unwrap_err(), CacheError::InvalidSize(0));
+    /// ```
+    pub fn new<F>(shards: usize, factory: F) -> Result<Self, CacheError>
+    where
+        F: FnMut(usize) -> C,
+    {
+        Self::with_hasher(shards, DefaultHashBuilder::default(), factory)
+    }
+}
+
+impl<K: Hash + Eq, V, C: Cache<K, V>, S: BuildHasher> ShardedCache<K, V, C, S> {
+    /// Creates a `ShardedCache` with `shards` shards, which uses `hasher` to pick the shard of a key.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, RawLRU, ShardedCache};
+    /// use rustc_hash::FxHasher;
+    /// use std::hash::BuildHasherDefault;
+    ///
+    /// let cache = ShardedCache::with_hasher(
+    ///     4,
+    ///     BuildHasherDefault::<FxHasher>::default(),
+    ///     |_| RawLRU::<u64, u64>::new(16).unwrap(),
+    /// )
+    /// .unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// assert_eq!(cache.get(&1), Some(1));
+    /// ```
+    pub fn with_hasher<F>(shards: usize, hasher: S, mut factory: F) -> Result<Self, CacheError>
+    where
+        F: FnMut(usize) -> C,
+    {
+        if shards == 0 {
+            return Err(CacheError::InvalidSize(0));
+        }
+
+        Ok(Self {
+            shards: (0..shards).map(|i| Mutex::new(factory(i))).collect(),
+            hasher,
+            marker: PhantomData,
+        })
+    }
+
+    /// Puts a key-value pair into the shard of the key, returns a [`PutResult`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, PutResult, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, &str>::new(2).unwrap()).unwrap();
+    ///
+    /// assert_eq!(cache.put(1, "a"), PutResult::Put);
+    /// assert_eq!(cache.put(1, "b"), PutResult::Update("a"));
+    /// ```
+    ///
+    /// [`PutResult`]: struct.PutResult.html
+    pub fn put(&self, k: K, v: V) -> PutResult<K, V> {
+        self.lock(&k).put(k, v)
+    }
+
+    /// Returns a clone of the value of the key or `None` if it is not present in the cache.
+    /// Updates the inner cache like [`Cache::get`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<&str, u64>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put("apple", 8);
+    ///
+    /// assert_eq!(cache.get(&"apple"), Some(8));
+    /// assert_eq!(cache.get(&"banana"), None);
+    /// ```
+    ///
+    /// [`Cache::get`]: trait.Cache.html#tymethod.get
+    pub fn get<Q>(&self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.lock(k).get(k).cloned()
+    }
+
+    /// Calls `f` with the value of the key while the shard is locked, returns the result of `f`
+    /// or `None` if the key is not present in the cache. Updates the inner cache like [`Cache::get_mut`].
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, Vec<u64>>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, vec![1]);
+    ///
+    /// assert_eq!(cache.get_with(&1, |v| { v.push(2); v.len() }), Some(2));
+    /// assert_eq!(cache.get(&1), Some(vec![1, 2]));
+    /// ```
+    ///
+    /// [`Cache::get_mut`]: trait.Cache.html#tymethod.get_mut
+    pub fn get_with<Q, R, F>(&self, k: &Q, f: F) -> Option<R>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        F: FnOnce(&mut V) -> R,
+    {
+        self.lock(k).get_mut(k).map(f)
+    }
+
+    /// Returns a clone of the value of the key, if the key is not present, calls `f` to build
+    /// the value and puts it into the cache. Updates the inner cache like [`Cache::get`].
+    ///
+    /// `f` is called while the shard is locked, so the other threads loading the keys of
+    /// the same shard wait for it instead of building the value again. `f` must not access
+    /// the cache, or it deadlocks.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, u64>::new(2).unwrap()).unwrap();
+    ///
+    /// assert_eq!(cache.get_or_insert_with(1, || 10), 10);
+    /// assert_eq!(cache.get_or_insert_with(1, || unreachable!()), 10);
+    /// ```
+    ///
+    /// [`Cache::get`]: trait.Cache.html#tymethod.get
+    pub fn get_or_insert_with<F>(&self, k: K, f: F) -> V
+    where
+        V: Clone,
+        F: FnOnce() -> V,
+    {
+        let mut shard = self.lock(&k);
+        if let Some(v) = shard.get(&KeyRef { k: &k }) {
+            return v.clone();
+        }
+
+        let v = f();
+        shard.put(k, v.clone());
+        v
+    }
+
+    /// Like [`get_or_insert_with`], but `f` may fail. If `f` returns an error, nothing is put
+    /// into the cache and the error is returned.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, u64>::new(2).unwrap()).unwrap();
+    ///
+    /// assert!(cache.try_get_or_insert_with(1, || "a".parse::<u64>()).is_err());
+    /// assert!(!cache.contains(&1));
+    /// assert_eq!(cache.try_get_or_insert_with(1, || "10".parse::<u64>()), Ok(10));
+    /// ```
+    ///
+    /// [`get_or_insert_with`]: struct.ShardedCache.html#method.get_or_insert_with
+    pub fn try_get_or_insert_with<F, E>(&self, k: K, f: F) -> Result<V, E>
+    where
+        V: Clone,
+        F: FnOnce() -> Result<V, E>,
+    {
+        let mut shard = self.lock(&k);
+        if let Some(v) = shard.get(&KeyRef { k: &k }) {
+            return Ok(v.clone());
+        }
+
+        let v = f()?;
+        shard.put(k, v.clone());
+        Ok(v)
+    }
+
+    /// Returns a clone of the value of the key or `None` if it is not present in the cache.
+    /// Unlike `get`, `peek` does not update the inner cache.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, &str>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, "a");
+    ///
+    /// assert_eq!(cache.peek(&1), Some("a"));
+    /// assert_eq!(cache.peek(&2), None);
+    /// ```
+    pub fn peek<Q>(&self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+        V: Clone,
+    {
+        self.lock(k).peek(k).cloned()
+    }
+
+    /// Returns a bool indicating whether the given key is in the cache.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, &str>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, "a");
+    ///
+    /// assert!(cache.contains(&1));
+    /// assert!(!cache.contains(&2));
+    /// ```
+    pub fn contains<Q>(&self, k: &Q) -> bool
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock(k).contains(k)
+    }
+
+    /// Removes and returns the value corresponding to the key from the cache or
+    /// `None` if it does not exist.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, &str>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, "a");
+    ///
+    /// assert_eq!(cache.remove(&1), Some("a"));
+    /// assert_eq!(cache.remove(&1), None);
+    /// ```
+    pub fn remove<Q>(&self, k: &Q) -> Option<V>
+    where
+        KeyRef<K>: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.lock(k).remove(k)
+    }
+
+    /// Clears the contents of all of the shards, one shard at a time.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, u64>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    /// cache.purge();
+    ///
+    /// assert!(cache.is_empty());
+    /// ```
+    pub fn purge(&self) {
+        self.shards.iter().for_each(|shard| lock(shard).purge());
+    }
+
+    /// Returns the number of key-value pairs in the cache. The shards are visited
+    /// one at a time, so the result may be stale if other threads are writing to the cache.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{RawLRU, ShardedCache};
+    ///
+    /// let cache = ShardedCache::new(2, |_| RawLRU::<u64, u64>::new(2).unwrap()).unwrap();
+    ///
+    /// cache.put(1, 1);
+    /// cache.put(2, 2);
+    ///
+    /// assert_eq!(cache.len(), 2);
+    /// ```
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).len()).sum()
+    }
+
+    /// Returns a bool indicating whether all of the shards are empty.
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| lock(shard).is_empty())
+    }
+
+    /// Returns the sum of the capacities of the shards.
+    pub fn cap(&self) -> usize {
+        self.shards.iter().map(|shard| lock(shard).cap()).sum()
+    }
+
+    /// Returns the number of shards.
+    pub fn shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    #[allow(clippy::manual_hash_one)]
+    fn lock<Q>(&self, k: &Q) -> MutexGuard<'_, C>
+    where
+        Q: Hash + ?Sized,
+    {
+        let mut hasher = self.hasher.build_hasher();
+        k.hash(&mut hasher);
+        // the inner maps of the shards use the low bits of the same hash, so the shard is
+        // picked by the high bits of the mixed hash.
+        let hash = hasher.finish().wrapping_mul(0x9e37_79b9_7f4a_7c15) >> 32;
+        let idx = (hash % self.shards.len() as u64) as usize;
+        lock(&self.shards[idx])
+    }
+}
+
+// Locks the shard, the lock poisoned by a panicking thread is recovered, which relies on the
+// inner cache being panic safe.
+fn lock<C>(shard: &Mutex<C>) -> MutexGuard<'_, C> {
+    shard.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+impl<K, V, C, S> fmt::Debug for ShardedCache<K, V, C, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("ShardedCache")
+            .field("shards", &self.shards.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{EvictionReason, OnEvictCallback, RawLRU, ShardedCache, TwoQueueCache};
+    use alloc::sync::Arc;
+    use alloc::vec::Vec;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::panic::{catch_unwind, AssertUnwindSafe};
+    use std::thread;
+
+    #[test]
+    fn test_sharded_cache_multiple_threads() {
+        let cache =
+            Arc::new(ShardedCache::new(8, |_| RawLRU::<u64, u64>::new(1024).unwrap()).unwrap());
+
+        let handles: Vec<_> = (0..8u64)
+            .map(|t| {
+                let cache = cache.clone();
+                thread::spawn(move || {
+                    for k in t * 100..(t + 1) * 100 {
+                        cache.put(k, k * 2);
+                        assert_eq!(cache.get(&k), Some(k * 2));
+                    }
+                    for k in t * 100..t * 100 + 50 {
+                        assert_eq!(cache.remove(&k), Some(k * 2));
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        assert_eq!(cache.len(), 400);
+        (0..800u64).for_each(|k| assert_eq!(cache.contains(&k), k % 100 >= 50));
+    }
+
+    #[test]
+    fn test_sharded_cache_get_or_insert_with() {
+        let cache =
+            Arc::new(ShardedCache::new(4, |_| RawLRU::<u64, u64>::new(64).unwrap()).unwrap());
+        let loads = Arc::new(AtomicUsize::new(0));
+
+        let handles: Vec<_> = (0..8)
+            .map(|_| {
+                let cache = cache.clone();
+                let loads = loads.clone();
+                thread::spawn(move || {
+                    for k in 0..64u64 {
+                        let v = cache.get_or_insert_with(k, || {
+                            loads.fetch_add(1, Ordering::SeqCst);
+                            k * 2
+                        });
+                        assert_eq!(v, k * 2);
+                    }
+                })
+            })
+            .collect();
+        handles.into_iter().for_each(|h| h.join().unwrap());
+
+        // every key is loaded once, the loaders of the same key wait for the shard lock
+        assert_eq!(loads.load(Ordering::SeqCst), 64);
+        assert_eq!(cache.len(), 64);
+    }
+
+    #[test]
+    fn test_sharded_cache_shard_capacity() {
+        let cache = ShardedCache::new(4, |_| TwoQueueCache::<u64, u64>::new(8).unwrap()).unwrap();
+        (0..1000u64).for_each(|k| {
+            cache.put(k, k);
+        });
+
+        assert_eq!(cache.cap(), 32);
+        assert!(cache.len() <= cache.cap());
+        cache.purge();
+        assert!(cache.is_empty());
+    }
+
+    struct PanicOnReplace;
+
+    impl OnEvictCallback for PanicOnReplace {
+        fn on_evict<K, V>(&self, _: &K, _: &V) {}
+
+        fn on_evict_with_reason<K, V>(&self, _: &K, _: &V, reason: EvictionReason) {
+            if reason == EvictionReason::Replaced {
+                panic!("failed to handle the replaced value");
+            }
+        }
+    }
+
+    #[test]
+    fn test_sharded_cache_poisoned_shard() {
+        let cache = ShardedCache::new(1, |_| {
+            RawLRU::<u64, u64, _>::with_on_evict_cb(4, PanicOnReplace).unwrap()
+        })
+        .unwrap();
+        cache.put(1, 1);
+
+        let rst = catch_unwind(AssertUnwindSafe(|| cache.put(1, 2)));
+        assert!(rst.is_err());
+
+        // RawLRU replaces the value before it invokes the callback, so the shard is still
+        // usable after the callback panicked while it was locked
+        assert_eq!(cache.get(&1), Some(2));
+        cache.put(2, 2);
+        assert_eq!(cache.len(), 2);
+    }
+
+    #[test]
+    fn test_sharded_cache_panicking_loader() {
+        let cache = ShardedCache::new(1, |_| RawLRU::<u64, u64>::new(4).unwrap()).unwrap();
+        cache.put(1, 1);
+
+        let rst = catch_unwind(AssertUnwindSafe(|| {
+            cache.get_or_insert_with(2, || panic!("failed to load 2"))
+        }));
+        assert!(rst.is_err());
+
+        // the shard is still usable, and the failed load did not put anything
+        assert_eq!(cache.get(&1), Some(1));
+        assert!(!cache.contains(&2));
+        assert_eq!(cache.get_or_insert_with(2, || 2), 2);
+        assert_eq!(cache.len(), 2);
+    }
+}
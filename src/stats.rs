@@ -0,0 +1,179 @@
+//! Statistics of the caches.
+//!
+//! The statistics are only tracked when the `stats` feature is enabled, otherwise
+//! the recorder embedded in the caches is zero sized and all of its methods are no-ops.
+use crate::{cfg_stats, EvictionReason, PutResult};
+
+cfg_stats!(
+    /// `CacheStats` is a snapshot of the statistics of a cache.
+    ///
+    /// Only `get` and `get_mut` are counted as hits or misses, `peek` and `contains`
+    /// do not change the statistics.
+    ///
+    /// # Example
+    /// ```rust
+    /// use caches::{Cache, RawLRU};
+    ///
+    /// let mut cache = RawLRU::<u64, u64>::new(1).unwrap();
+    /// cache.put(1, 1);
+    /// cache.put(1, 2);
+    /// cache.put(2, 2);
+    /// cache.get(&1);
+    /// cache.get(&2);
+    ///
+    /// let stats = cache.stats();
+    /// assert_eq!(stats.inserts(), 2);
+    /// assert_eq!(stats.updates(), 1);
+    /// assert_eq!(stats.evictions(), 1);
+    /// assert_eq!(stats.hits(), 1);
+    /// assert_eq!(stats.misses(), 1);
+    /// assert_eq!(stats.hit_ratio(), 0.5);
+    /// ```
+    #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+    pub struct CacheStats {
+        hits: u64,
+        misses: u64,
+        inserts: u64,
+        updates: u64,
+        evictions: u64,
+        rejections: u64,
+        ghost_hits: u64,
+    }
+
+    impl CacheStats {
+        /// Returns the number of lookups which found the key.
+        #[inline]
+        pub fn hits(&self) -> u64 {
+            self.hits
+        }
+
+        /// Returns the number of lookups which did not find the key.
+        #[inline]
+        pub fn misses(&self) -> u64 {
+            self.misses
+        }
+
+        /// Returns the number of puts which inserted a new key.
+        #[inline]
+        pub fn inserts(&self) -> u64 {
+            self.inserts
+        }
+
+        /// Returns the number of puts which updated the value of an existing key.
+        #[inline]
+        pub fn updates(&self) -> u64 {
+            self.updates
+        }
+
+        /// Returns the number of entries evicted because the cache was full or was shrunk.
+        #[inline]
+        pub fn evictions(&self) -> u64 {
+            self.evictions
+        }
+
+        /// Returns the number of entries rejected by the admission policy.
+        #[inline]
+        pub fn rejections(&self) -> u64 {
+            self.rejections
+        }
+
+        /// Returns the number of accesses which found the key in a ghost list.
+        #[inline]
+        pub fn ghost_hits(&self) -> u64 {
+            self.ghost_hits
+        }
+
+        /// Returns the ratio of hits to lookups, or `0.0` if there is no lookup.
+        pub fn hit_ratio(&self) -> f64 {
+            let lookups = self.hits + self.misses;
+            if lookups == 0 {
+                0.0
+            } else {
+                self.hits as f64 / lookups as f64
+            }
+        }
+    }
+);
+
+/// `StatsRecorder` records the statistics of a cache.
+#[derive(Debug, Default, Clone, Copy)]
+pub(crate) struct StatsRecorder {
+    #[cfg(feature = "stats")]
+    stats: CacheStats,
+}
+
+#[cfg_attr(not(feature = "stats"), allow(unused_variables))]
+impl StatsRecorder {
+    #[inline(always)]
+    pub(crate) fn record_get(&mut self, hit: bool) {
+        #[cfg(feature = "stats")]
+        {
+            if hit {
+                self.stats.hits += 1;
+            } else {
+                self.stats.misses += 1;
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_put<K, V>(&mut self, rst: &PutResult<K, V>) {
+        match rst {
+            PutResult::Put | PutResult::Evicted { .. } => self.record_insert(),
+            PutResult::Update(_) | PutResult::EvictedAndUpdate { .. } => self.record_update(),
+            PutResult::EvictedMany { update: None, .. } => self.record_insert(),
+            PutResult::EvictedMany {
+                update: Some(_), ..
+            } => self.record_update(),
+            PutResult::Rejected { .. } => self.record_eviction(EvictionReason::Rejected),
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_insert(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.inserts += 1;
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_update(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.updates += 1;
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_eviction(&mut self, reason: EvictionReason) {
+        #[cfg(feature = "stats")]
+        {
+            match reason {
+                EvictionReason::Capacity | EvictionReason::Resized => self.stats.evictions += 1,
+                EvictionReason::Rejected => self.stats.rejections += 1,
+                _ => {}
+            }
+        }
+    }
+
+    #[inline(always)]
+    pub(crate) fn record_ghost_hit(&mut self) {
+        #[cfg(feature = "stats")]
+        {
+            self.stats.ghost_hits += 1;
+        }
+    }
+
+    cfg_stats!(
+        #[inline]
+        pub(crate) fn stats(&self) -> CacheStats {
+            self.stats
+        }
+
+        #[inline]
+        pub(crate) fn reset(&mut self) {
+            self.stats = CacheStats::default();
+        }
+    );
+}
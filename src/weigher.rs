@@ -0,0 +1,55 @@
+//! Weighers for the weighted caches.
+//!
+//! A [`Weigher`] tells a weighted cache how much of its capacity an entry takes, e.g. the
+//! size of the value in bytes. The capacity of a weighted cache is a total weight instead of
+//! an entry count.
+//!
+//! - [`UnitWeigher`] weighs every entry as `1`, so the capacity is an entry count again.
+//! - Any `Fn(&K, &V) -> usize` closure is also a [`Weigher`].
+//!
+//! [`Weigher`]: trait.Weigher.html
+//! [`UnitWeigher`]: struct.UnitWeigher.html
+
+/// `Weigher` returns the weight of an entry.
+///
+/// The weight of an entry must not change while it is in the cache,
+/// values which are mutated in place through `get_mut` or `peek_mut` are not weighed again.
+///
+/// # Example
+/// ```rust
+/// use caches::Weigher;
+///
+/// struct ByteWeigher;
+///
+/// impl Weigher<u64, Vec<u8>> for ByteWeigher {
+///     fn weight(&self, _: &u64, val: &Vec<u8>) -> usize {
+///         val.len()
+///     }
+/// }
+///
+/// assert_eq!(ByteWeigher.weight(&1, &vec![0; 100]), 100);
+///
+/// // closures are weighers too
+/// let weigher = |_: &u64, val: &Vec<u8>| val.len();
+/// assert_eq!(weigher.weight(&1, &vec![0; 10]), 10);
+/// ```
+pub trait Weigher<K, V> {
+    /// Returns the weight of the entry.
+    fn weight(&self, key: &K, val: &V) -> usize;
+}
+
+impl<K, V, F: Fn(&K, &V) -> usize> Weigher<K, V> for F {
+    fn weight(&self, key: &K, val: &V) -> usize {
+        self(key, val)
+    }
+}
+
+/// `UnitWeigher` weighs every entry as `1`.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct UnitWeigher;
+
+impl<K, V> Weigher<K, V> for UnitWeigher {
+    fn weight(&self, _: &K, _: &V) -> usize {
+        1
+    }
+}